@@ -0,0 +1,43 @@
+use labgen::{
+    image::{render_gif, AnimationOptions, ImageOptions},
+    maze::{generate_maze, MazeType, MazeWrap},
+};
+use std::{hint::black_box, time::Instant};
+
+// sizes of the mazes animated, the largest writing one frame per cell with a batch size of 1
+const SIZES: [(u32, u32); 2] = [(50, 50), (200, 200)];
+
+const BATCH_SIZES: [u16; 4] = [1, 10, 100, 1000];
+
+fn main() {
+    let opts = ImageOptions::default();
+    for (width, height) in SIZES {
+        let (maze, history) =
+            generate_maze(width, height, MazeType::Backtrack, MazeWrap::None, 1).unwrap();
+        for batch_size in BATCH_SIZES {
+            let ani_opts = AnimationOptions {
+                batch_size,
+                ..AnimationOptions::default()
+            };
+            let mut gif = Vec::new();
+            let start = Instant::now();
+            black_box(render_gif(
+                &maze,
+                &history.steps,
+                &opts,
+                &ani_opts,
+                &mut gif,
+            ))
+            .unwrap();
+            let elapsed = start.elapsed();
+            println!(
+                "{:>3}x{:<3} batch {:>4} {:>8.2?}  {:>8} KB",
+                width,
+                height,
+                batch_size,
+                elapsed,
+                gif.len() / 1000
+            );
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use labgen::maze::{generate_maze, MazeType, MazeWrap, Tile};
+use std::{hint::black_box, time::Instant};
+
+// sizes of the mazes generated, the largest taking over half a gigabyte of history
+const SIZES: [(u32, u32); 3] = [(1000, 1000), (4000, 4000), (10000, 10000)];
+
+const METHODS: [MazeType; 3] = [MazeType::Backtrack, MazeType::Prim, MazeType::Kruskal];
+
+fn main() {
+    println!("{} bytes per tile", std::mem::size_of::<Tile>());
+    for (width, height) in SIZES {
+        for method in METHODS {
+            let start = Instant::now();
+            let (maze, history) =
+                black_box(generate_maze(width, height, method, MazeWrap::None, 1).unwrap());
+            let elapsed = start.elapsed();
+            let tiles = std::mem::size_of_val(&maze.tiles[..]);
+            let steps = std::mem::size_of_val(&history.steps[..]);
+            println!(
+                "{:>5}x{:<5} {:<10} {:>8.2?}  tiles {:>6} MB  history {:>6} MB",
+                width,
+                height,
+                method.to_string(),
+                elapsed,
+                tiles / 1_000_000,
+                steps / 1_000_000
+            );
+        }
+    }
+}
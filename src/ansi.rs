@@ -0,0 +1,399 @@
+use crate::{
+    error::LabgenError,
+    image::{
+        add_rulers, draw_maze, image_size, play_frames, write_output, AnimationOptions,
+        ImageOptions,
+    },
+    maze::{Grid, MazeAction},
+};
+use std::{
+    io::Write,
+    thread,
+    time::{Duration, Instant},
+};
+
+// colors of the two pixels shown by a character, None where the pixel is transparent
+type Cell = (Option<[u8; 3]>, Option<[u8; 3]>);
+
+// writes the pixels as lines of half block characters, each showing two rows of pixels with the
+// upper one in the foreground color and the lower one in the background color so pixels come out
+// roughly square. transparent pixels keep the colors of the terminal. returns the number of lines
+fn write_pixels(
+    out: &mut dyn Write,
+    pixels: &[u8],
+    width: u16,
+    palette: &[u8],
+    transparent: Option<u8>,
+) -> Result<usize, LabgenError> {
+    let color = |i: Option<&u8>| {
+        i.filter(|i| Some(**i) != transparent)
+            .map(|i| [0, 1, 2].map(|c| palette[*i as usize * 3 + c]))
+    };
+    let rows: Vec<&[u8]> = pixels.chunks(width as usize).collect();
+    for pair in rows.chunks(2) {
+        let mut last: Option<Cell> = None;
+        for x in 0..width as usize {
+            let cell = (
+                color(pair[0].get(x)),
+                color(pair.get(1).and_then(|r| r.get(x))),
+            );
+            if last != Some(cell) {
+                match cell {
+                    (Some(top), Some(bottom)) => write!(
+                        out,
+                        "\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m",
+                        top[0], top[1], top[2], bottom[0], bottom[1], bottom[2]
+                    )?,
+                    (Some(only), None) | (None, Some(only)) => write!(
+                        out,
+                        "\x1b[49m\x1b[38;2;{};{};{}m",
+                        only[0], only[1], only[2]
+                    )?,
+                    (None, None) => write!(out, "\x1b[0m")?,
+                }
+                last = Some(cell);
+            }
+            match cell {
+                (Some(_), _) => write!(out, "\u{2580}")?,
+                (None, Some(_)) => write!(out, "\u{2584}")?,
+                (None, None) => write!(out, " ")?,
+            }
+        }
+        writeln!(out, "\x1b[0m")?;
+    }
+    Ok(rows.len().div_ceil(2))
+}
+
+// the png image drawn with ansi colors, to be printed in a terminal with 24 bit color support
+pub fn render_ansi(
+    maze: &Grid,
+    opts: &ImageOptions,
+    out: &mut dyn Write,
+) -> Result<(), LabgenError> {
+    let (width, height) = image_size(maze, &opts.layout())?;
+    let pixels = draw_maze(maze, opts, |_| 1)?;
+    let (pixels, width, _) = add_rulers(pixels, width, height, maze, opts)?;
+    write_pixels(out, &pixels, width, &opts.palette(), opts.transparent())?;
+    Ok(())
+}
+
+// draws the pixels over the lines drawn before them by moving the cursor back up, returning the
+// number of lines drawn. a terminal in raw mode needs its lines ended with a carriage return
+fn redraw(
+    out: &mut dyn Write,
+    pixels: &[u8],
+    width: u16,
+    opts: &ImageOptions,
+    lines: usize,
+    raw: bool,
+) -> Result<usize, LabgenError> {
+    if lines > 0 {
+        write!(out, "\x1b[{}A", lines)?;
+    }
+    let mut text = Vec::new();
+    let lines = write_pixels(
+        &mut text,
+        pixels,
+        width,
+        &opts.palette(),
+        opts.transparent(),
+    )?;
+    if raw {
+        text = String::from_utf8_lossy(&text)
+            .replace('\n', "\r\n")
+            .into_bytes();
+    }
+    out.write_all(&text)?;
+    out.flush()?;
+    Ok(lines)
+}
+
+// plays the generation in the terminal with the timing of the gif, every frame is drawn over the
+// previous one by moving the cursor back up. the cursor is hidden while playing
+pub fn render_ansi_animation(
+    maze: &Grid,
+    history: &[MazeAction],
+    opts: &ImageOptions,
+    ani_opts: &AnimationOptions,
+    out: &mut dyn Write,
+) -> Result<(), LabgenError> {
+    let (width, _) = image_size(maze, &opts.layout())?;
+    let mut lines = 0;
+    write!(out, "\x1b[?25l")?;
+    let played = play_frames(maze, history, opts, ani_opts, |pixels, delay, _| {
+        lines = redraw(out, pixels, width, opts, lines, false)?;
+        thread::sleep(Duration::from_millis(delay as u64 * 10));
+        Ok(())
+    });
+    write!(out, "\x1b[?25h")?;
+    played
+}
+
+// keys that control an animation played in the terminal
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Key {
+    Pause,
+    Back,
+    Forward,
+    Slower,
+    Faster,
+    Quit,
+}
+
+// the speed can be halved or doubled this many times
+#[cfg(not(target_arch = "wasm32"))]
+const MAX_SPEED: i32 = 3;
+
+// frames shown so far and how they are played: space pauses and resumes, the left and right arrows
+// step one frame back or forward while paused, and [ and ] halve or double the speed. every frame
+// is kept to step back to, which is fine for mazes small enough to fit in a terminal
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Default)]
+struct Playback {
+    frames: Vec<Vec<u8>>,
+    paused: bool,
+    // the delays of the frames are divided by two to this power
+    speed: i32,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Playback {
+    fn delay(&self, delay: u16) -> Duration {
+        Duration::from_millis(delay as u64 * 10).mul_f64(2f64.powi(-self.speed))
+    }
+
+    // draws the next frame and waits out its delay, or until it is stepped past while paused.
+    // read_key waits at most the given time for a key, or until one is pressed without a time.
+    // frames stepped back to are played again before returning
+    fn play(
+        &mut self,
+        pixels: &[u8],
+        delay: u16,
+        draw: &mut dyn FnMut(&[u8]) -> Result<(), LabgenError>,
+        read_key: &mut dyn FnMut(Option<Duration>) -> Result<Option<Key>, LabgenError>,
+    ) -> Result<(), LabgenError> {
+        self.frames.push(pixels.to_vec());
+        let last = self.frames.len() - 1;
+        let mut shown = last;
+        draw(&self.frames[shown])?;
+        let mut until = Instant::now() + self.delay(delay);
+        loop {
+            let wait = until.saturating_duration_since(Instant::now());
+            if !self.paused && wait.is_zero() {
+                if shown == last {
+                    return Ok(());
+                }
+                shown += 1;
+                draw(&self.frames[shown])?;
+                until = Instant::now() + self.delay(delay);
+                continue;
+            }
+            match read_key((!self.paused).then_some(wait))? {
+                Some(Key::Pause) => {
+                    self.paused = !self.paused;
+                    until = Instant::now() + self.delay(delay);
+                }
+                Some(Key::Back) if self.paused && shown > 0 => {
+                    shown -= 1;
+                    draw(&self.frames[shown])?;
+                }
+                Some(Key::Forward) if self.paused => {
+                    if shown == last {
+                        return Ok(());
+                    }
+                    shown += 1;
+                    draw(&self.frames[shown])?;
+                }
+                Some(Key::Slower) => self.speed = (self.speed - 1).max(-MAX_SPEED),
+                Some(Key::Faster) => self.speed = (self.speed + 1).min(MAX_SPEED),
+                Some(Key::Quit) => return Err(LabgenError::Cancelled),
+                _ => (),
+            }
+        }
+    }
+}
+
+// waits at most the given time for a key that controls the animation, ctrl-c and q or escape quit
+// it since the terminal is in raw mode and sends no interrupt
+#[cfg(not(target_arch = "wasm32"))]
+fn read_key(wait: Option<Duration>) -> Result<Option<Key>, LabgenError> {
+    use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+
+    let start = Instant::now();
+    loop {
+        if let Some(wait) = wait {
+            if !event::poll(wait.saturating_sub(start.elapsed()))? {
+                return Ok(None);
+            }
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind == KeyEventKind::Release {
+            continue;
+        }
+        let key = match key.code {
+            KeyCode::Char(' ') => Key::Pause,
+            KeyCode::Left => Key::Back,
+            KeyCode::Right => Key::Forward,
+            KeyCode::Char('[') => Key::Slower,
+            KeyCode::Char(']') => Key::Faster,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Key::Quit,
+            KeyCode::Char('q') | KeyCode::Esc => Key::Quit,
+            _ => continue,
+        };
+        return Ok(Some(key));
+    }
+}
+
+// render_ansi_animation with the keys of Playback read from the terminal, which is put in raw mode
+// while playing
+#[cfg(not(target_arch = "wasm32"))]
+fn render_ansi_preview(
+    maze: &Grid,
+    history: &[MazeAction],
+    opts: &ImageOptions,
+    ani_opts: &AnimationOptions,
+    out: &mut dyn Write,
+) -> Result<(), LabgenError> {
+    use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+
+    let (width, _) = image_size(maze, &opts.layout())?;
+    let mut lines = 0;
+    let mut playback = Playback::default();
+    enable_raw_mode()?;
+    write!(out, "\x1b[?25l")?;
+    let played = play_frames(maze, history, opts, ani_opts, |pixels, delay, _| {
+        let mut draw = |pixels: &[u8]| {
+            lines = redraw(out, pixels, width, opts, lines, true)?;
+            Ok(())
+        };
+        playback.play(pixels, delay, &mut draw, &mut read_key)
+    });
+    let restored = disable_raw_mode();
+    write!(out, "\x1b[?25h")?;
+    played.and(restored.map_err(LabgenError::from))
+}
+
+pub fn generate_ansi(maze: &Grid, opts: &ImageOptions) -> Result<(), LabgenError> {
+    write_output(&opts.file_path, "ans", |out| render_ansi(maze, opts, out))
+}
+
+pub fn generate_ansi_animation(
+    maze: &Grid,
+    history: &[MazeAction],
+    opts: &ImageOptions,
+    ani_opts: &AnimationOptions,
+) -> Result<(), LabgenError> {
+    write_output(&opts.file_path, "ans", |out| {
+        // the animation can only be controlled when it is watched
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            use std::io::{stdin, stdout, IsTerminal};
+            if opts.file_path == "-" && stdin().is_terminal() && stdout().is_terminal() {
+                return render_ansi_preview(maze, history, opts, ani_opts, out);
+            }
+        }
+        render_ansi_animation(maze, history, opts, ani_opts, out)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{generate_maze, MazeType, MazeWrap};
+
+    const PALETTE: [u8; 6] = [0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF];
+
+    #[test]
+    fn characters_show_two_rows_of_pixels() {
+        let mut out = Vec::new();
+        // the last line has no row below it, its characters only show their top half
+        let lines = write_pixels(&mut out, &[0, 1, 1, 0, 0, 0], 2, &PALETTE, None).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(lines, 2);
+        assert_eq!(text.lines().count(), 2);
+        assert_eq!(text.matches('\u{2580}').count(), 4);
+    }
+
+    #[test]
+    fn transparent_pixels_keep_the_terminal_colors() {
+        let mut out = Vec::new();
+        write_pixels(&mut out, &[1, 1, 0, 1], 2, &PALETTE, Some(1)).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(text, "\x1b[49m\x1b[38;2;0;0;0m\u{2584}\x1b[0m \x1b[0m\n");
+    }
+
+    // plays the frames with the keys pressed during each, recording the frames drawn. once the keys
+    // of a frame run out it is stepped past
+    fn play(playback: &mut Playback, keys: &[&[Key]]) -> (Vec<u8>, Result<(), LabgenError>) {
+        let mut drawn = Vec::new();
+        for keys in keys {
+            let mut keys = keys.iter();
+            let played = playback.play(
+                &[playback.frames.len() as u8],
+                0,
+                &mut |pixels| {
+                    drawn.push(pixels[0]);
+                    Ok(())
+                },
+                &mut |_| Ok(Some(keys.next().copied().unwrap_or(Key::Forward))),
+            );
+            if played.is_err() {
+                return (drawn, played);
+            }
+        }
+        (drawn, Ok(()))
+    }
+
+    #[test]
+    fn paused_animations_step_through_the_frames() {
+        let mut playback = Playback::default();
+        assert_eq!(play(&mut playback, &[&[], &[]]).0, [0, 1]);
+
+        playback.paused = true;
+        let keys = [Key::Back, Key::Back, Key::Back, Key::Forward];
+        assert_eq!(play(&mut playback, &[&keys]).0, [2, 1, 0, 1, 2]);
+        assert!(playback.paused);
+
+        // resuming plays the frames stepped back to again
+        let (drawn, _) = play(&mut playback, &[&[Key::Back, Key::Back, Key::Pause]]);
+        assert_eq!(drawn, [3, 2, 1, 2, 3]);
+        assert!(!playback.paused);
+    }
+
+    #[test]
+    fn animations_play_faster_and_slower() {
+        let mut playback = Playback {
+            paused: true,
+            ..Playback::default()
+        };
+        play(&mut playback, &[&[Key::Faster; 5]]).1.unwrap();
+        assert_eq!(playback.delay(8), Duration::from_millis(10));
+        play(&mut playback, &[&[Key::Slower; 8]]).1.unwrap();
+        assert_eq!(playback.delay(8), Duration::from_millis(640));
+    }
+
+    #[test]
+    fn quitting_cancels_the_animation() {
+        let mut playback = Playback {
+            paused: true,
+            ..Playback::default()
+        };
+        let (drawn, played) = play(&mut playback, &[&[Key::Quit], &[]]);
+        assert_eq!(drawn, [0]);
+        assert!(matches!(played, Err(LabgenError::Cancelled)));
+    }
+
+    #[test]
+    fn mazes_take_half_their_pixel_rows() {
+        let (maze, _) = generate_maze(5, 3, MazeType::Wilson, MazeWrap::None, 1).unwrap();
+        let opts = ImageOptions::default();
+        let (_, height) = image_size(&maze, &opts.layout()).unwrap();
+        let mut out = Vec::new();
+        render_ansi(&maze, &opts, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(text.lines().count(), (height as usize).div_ceil(2));
+    }
+}
@@ -0,0 +1,311 @@
+// proptest strategies for the core types, available to tests and behind the proptest feature so
+// extensions can be fuzzed with the same generators
+
+use crate::{
+    image::{AnimationOptions, Color, ExcludeStyle, ImageOptions},
+    maze::{
+        carve_maze, generate_maze, BorderSide, Grid, GrowingTreeBias, MazeHistory, MazeType,
+        MazeWrap, Opening, OpeningPosition, Point, Rect,
+    },
+    pattern::{carve_pattern, Pattern},
+    progress::Progress,
+    rooms::RoomShape,
+};
+use clap::ValueEnum;
+use proptest::{prelude::*, sample::select};
+
+// mazes are kept small so each case generates and renders quickly
+pub const MAX_SIZE: u32 = 24;
+
+impl Arbitrary for MazeType {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        select(MazeType::value_variants()).boxed()
+    }
+}
+
+impl Arbitrary for MazeWrap {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        select(MazeWrap::value_variants()).boxed()
+    }
+}
+
+// any rect of up to MAX_SIZE cells from the origin, use rect_within for one that fits a maze
+impl Arbitrary for Rect {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (0..MAX_SIZE, 0..MAX_SIZE, 0..=MAX_SIZE, 0..=MAX_SIZE)
+            .prop_map(|(x, y, width, height)| Rect {
+                x,
+                y,
+                width,
+                height,
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for RoomShape {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        let coord = || 0..MAX_SIZE as i32;
+        prop_oneof![
+            any::<Rect>().prop_map(RoomShape::Rect),
+            (coord(), coord(), 0..MAX_SIZE / 2).prop_map(|(x, y, radius)| RoomShape::Circle {
+                center: Point::new(x, y),
+                radius,
+            }),
+            prop::collection::vec((coord(), coord()), 3..8).prop_map(|corners| {
+                RoomShape::Polygon(corners.into_iter().map(|(x, y)| Point::new(x, y)).collect())
+            }),
+        ]
+        .boxed()
+    }
+}
+
+// a finished maze generated with an arbitrary method, wrap and seed
+impl Arbitrary for Grid {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        maze_with_history().prop_map(|(maze, _)| maze).boxed()
+    }
+}
+
+impl Arbitrary for ImageOptions {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (
+            1..=8u16,
+            0..=4u16,
+            any::<[u8; 6]>(),
+            select(ExcludeStyle::value_variants()),
+            any::<[u8; 3]>(),
+            any::<bool>(),
+        )
+            .prop_map(
+                |(passage_width, wall_width, color_map, exclude_style, exclude_color, rulers)| {
+                    ImageOptions {
+                        file_path: String::new(),
+                        passage_width,
+                        wall_width,
+                        color_map,
+                        exclude_style,
+                        exclude_color: Color(exclude_color),
+                        solution: None,
+                        labels: Vec::new(),
+                        rulers,
+                        ..ImageOptions::default()
+                    }
+                },
+            )
+            .boxed()
+    }
+}
+
+impl Arbitrary for AnimationOptions {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (0..=10u16, 0..=200u16, 1..=16u16, any::<bool>())
+            .prop_map(
+                |(frame_time, pause_time, batch_size, skip_unchanged)| AnimationOptions {
+                    frame_time,
+                    pause_time,
+                    batch_size,
+                    progress: false,
+                    on_progress: None,
+                    cancel: None,
+                    skip_unchanged,
+                },
+            )
+            .boxed()
+    }
+}
+
+// a finished maze together with the history it was generated with
+pub fn maze_with_history() -> impl Strategy<Value = (Grid, MazeHistory)> {
+    (
+        1..=MAX_SIZE,
+        1..=MAX_SIZE,
+        any::<MazeType>(),
+        any::<MazeWrap>(),
+        any::<u64>(),
+    )
+        .prop_map(|(width, height, method, wrap, seed)| {
+            generate_maze(width, height, method, wrap, seed).unwrap()
+        })
+}
+
+// a maze carved around an arbitrary rect of removed cells, with a method that supports them
+pub fn maze_with_exclusion() -> impl Strategy<Value = (Grid, MazeHistory, Rect)> {
+    (
+        1..=MAX_SIZE,
+        1..=MAX_SIZE,
+        select(
+            MazeType::value_variants()
+                .iter()
+                .copied()
+                .filter(|m| m.supports_exclusions())
+                .collect::<Vec<MazeType>>(),
+        ),
+        any::<MazeWrap>(),
+        any::<u64>(),
+        any::<Rect>(),
+    )
+        .prop_map(|(width, height, method, wrap, seed, rect)| {
+            let mut maze = Grid::new(width, height, wrap, seed, method);
+            maze.exclude(rect);
+            let (maze, history) = carve_maze(maze, &Progress::hidden());
+            (maze, history, rect)
+        })
+}
+
+impl Arbitrary for Pattern {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        prop_oneof![
+            Just(Pattern::Spiral),
+            Just(Pattern::Hilbert),
+            ("[F+\\-X]{1,6}", "[F+\\-X]{0,6}", "[F+\\-X]{0,6}", 0..6u32).prop_map(
+                |(axiom, f, x, iterations)| Pattern::LSystem {
+                    axiom,
+                    rules: vec![('F', f), ('X', x)],
+                    iterations,
+                }
+            ),
+        ]
+        .boxed()
+    }
+}
+
+// a maze grown around an arbitrary pattern, with a method that supports them
+pub fn maze_with_pattern() -> impl Strategy<Value = (Grid, MazeHistory)> {
+    (
+        1..=MAX_SIZE,
+        1..=MAX_SIZE,
+        select(
+            MazeType::value_variants()
+                .iter()
+                .copied()
+                .filter(|m| m.supports_patterns())
+                .collect::<Vec<MazeType>>(),
+        ),
+        any::<MazeWrap>(),
+        any::<u64>(),
+        any::<Pattern>(),
+    )
+        .prop_map(|(width, height, method, wrap, seed, pattern)| {
+            let mut maze = Grid::new(width, height, wrap, seed, method);
+            carve_pattern(&mut maze, &pattern);
+            carve_maze(maze, &Progress::hidden())
+        })
+}
+
+impl Arbitrary for Opening {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        let position = prop_oneof![
+            any::<u32>().prop_map(OpeningPosition::Cell),
+            Just(OpeningPosition::Center),
+            Just(OpeningPosition::Random),
+        ];
+        prop_oneof![
+            Just(Opening {
+                side: None,
+                position: OpeningPosition::Random,
+            }),
+            (select(BorderSide::value_variants()), position).prop_map(|(side, position)| {
+                Opening {
+                    side: Some(side),
+                    position,
+                }
+            }),
+        ]
+        .boxed()
+    }
+}
+
+impl Arbitrary for GrowingTreeBias {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        let single = prop_oneof![
+            Just(GrowingTreeBias::Oldest),
+            Just(GrowingTreeBias::Newest),
+            Just(GrowingTreeBias::Random),
+            (1..=100u8).prop_map(GrowingTreeBias::Percent),
+        ];
+        prop_oneof![
+            single.clone(),
+            prop::collection::vec((single, 1..=u8::MAX), 1..4).prop_map(GrowingTreeBias::Mix),
+        ]
+        .boxed()
+    }
+}
+
+// a maze and a non empty rect that lies inside of it
+pub fn rect_within() -> impl Strategy<Value = (Grid, Rect)> {
+    any::<Grid>().prop_flat_map(|maze| {
+        let (width, height) = (maze.width, maze.height);
+        (0..width, 0..height)
+            .prop_flat_map(move |(x, y)| (Just((x, y)), 1..=width - x, 1..=height - y))
+            .prop_map(move |((x, y), w, h)| {
+                (
+                    maze.clone(),
+                    Rect {
+                        x,
+                        y,
+                        width: w,
+                        height: h,
+                    },
+                )
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{Direction, Point};
+
+    proptest! {
+        #[test]
+        fn carves_are_reciprocal(maze in any::<Grid>()) {
+            for (i, tile) in maze.tiles.iter().enumerate() {
+                let pt = Point::new(
+                    (i % maze.width as usize) as i32,
+                    (i / maze.width as usize) as i32,
+                );
+                for dir in Direction::ALL.into_iter().filter(|d| tile.connected(*d)) {
+                    let next = maze.travel(pt, dir);
+                    prop_assert!(maze.contains(next), "{:?} connects {:?} off the grid", pt, dir);
+                    prop_assert!(maze.get_tile(next).connected(dir.opposite()));
+                }
+            }
+        }
+
+        #[test]
+        fn history_replay_reproduces_grid((maze, history) in maze_with_history()) {
+            prop_assert!(history.agrees_with(&maze));
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use crate::error::LabgenError;
+use crate::image::{solve_phases, write_output, AnimationOptions, ImageOptions};
+use crate::maze::{Grid, MazeAction, Point};
+use std::f32::consts::{FRAC_PI_2, TAU};
+use std::io::Write;
+
+const SAMPLE_RATE: u32 = 44100;
+// lowest pitch, given to the left column. every column to the right is higher up to two
+// octaves above it
+const BASE_FREQUENCY: f32 = 220.0;
+const OCTAVES: f32 = 2.0;
+const VOLUME: f32 = 0.3;
+// samples faded in and out at both ends of a tone so consecutive tones do not click
+const FADE: usize = 64;
+
+// tone of a step, higher further right in the maze and panned from left at the top to right at
+// the bottom. steps just outside the grid, like openings, sound like the edge they cross
+fn tone(maze: &Grid, pt: Point) -> (f32, f32) {
+    let fraction = |v: i32, size: u32| match size {
+        1 => 0.5,
+        _ => v.clamp(0, size as i32 - 1) as f32 / (size - 1) as f32,
+    };
+    let frequency = BASE_FREQUENCY * 2f32.powf(OCTAVES * fraction(pt.x, maze.width));
+    (frequency, fraction(pt.y, maze.height))
+}
+
+// appends a sine tone as interleaved left and right samples
+fn write_tone(samples: &mut Vec<i16>, length: usize, frequency: f32, pan: f32) {
+    let (left, right) = ((pan * FRAC_PI_2).cos(), (pan * FRAC_PI_2).sin());
+    let fade = FADE.min(length / 2).max(1);
+    for i in 0..length {
+        let envelope = (i.min(length - 1 - i) as f32 / fade as f32).min(1.0);
+        let v = (TAU * frequency * i as f32 / SAMPLE_RATE as f32).sin() * envelope * VOLUME;
+        samples.push((v * left * i16::MAX as f32) as i16);
+        samples.push((v * right * i16::MAX as f32) as i16);
+    }
+}
+
+// samples played during a gif delay given in hundredths of a second
+fn delay_samples(delay: u16) -> usize {
+    SAMPLE_RATE as usize * delay as usize / 100
+}
+
+// plays every carving step, and the solver after it, as a short tone lasting exactly as long as
+// the gif written with the same options shows it, followed by silence for the final pause. the
+// steps of a batch share the delay of their frame and the steps of an unfinished last batch only
+// appear in the final frame
+pub fn render_wav(
+    maze: &Grid,
+    history: &[MazeAction],
+    opts: &ImageOptions,
+    ani_opts: &AnimationOptions,
+    out: &mut dyn Write,
+) -> Result<(), LabgenError> {
+    let solve = opts
+        .solution
+        .as_ref()
+        .map_or(Vec::new(), |s| solve_phases(maze, s));
+    let steps: Vec<Point> = history
+        .iter()
+        .map(|step| step.cell())
+        .chain(solve.iter().map(|(pt, _, _)| *pt))
+        .collect();
+
+    let batch_size = ani_opts.batch_size as usize;
+    let frame_length = delay_samples(ani_opts.frame_time);
+    let mut samples: Vec<i16> = Vec::new();
+    for batch in steps.chunks_exact(batch_size) {
+        for (i, pt) in batch.iter().enumerate() {
+            let length = frame_length * (i + 1) / batch_size - frame_length * i / batch_size;
+            let (frequency, pan) = tone(maze, *pt);
+            write_tone(&mut samples, length, frequency, pan);
+        }
+    }
+    samples.resize(samples.len() + delay_samples(ani_opts.pause_time) * 2, 0);
+
+    // the maze metadata as comment of an info list, with the software that wrote it
+    let mut info: Vec<u8> = b"INFO".to_vec();
+    let metadata = maze.metadata();
+    for (id, text) in [
+        (b"ICMT", metadata.comment()),
+        (b"ISFT", format!("labgen {}", metadata.version)),
+    ] {
+        let mut text = text.into_bytes();
+        text.push(0);
+        info.extend_from_slice(id);
+        info.extend_from_slice(&(text.len() as u32).to_le_bytes());
+        info.extend_from_slice(&text);
+        // chunks start on even offsets
+        if text.len() % 2 == 1 {
+            info.push(0);
+        }
+    }
+
+    // 16 bit stereo pcm
+    let data_size = samples.len() as u32 * 2;
+    out.write_all(b"RIFF")?;
+    out.write_all(&(36 + 8 + info.len() as u32 + data_size).to_le_bytes())?;
+    out.write_all(b"WAVEfmt ")?;
+    out.write_all(&16u32.to_le_bytes())?;
+    out.write_all(&1u16.to_le_bytes())?;
+    out.write_all(&2u16.to_le_bytes())?;
+    out.write_all(&SAMPLE_RATE.to_le_bytes())?;
+    out.write_all(&(SAMPLE_RATE * 4).to_le_bytes())?;
+    out.write_all(&4u16.to_le_bytes())?;
+    out.write_all(&16u16.to_le_bytes())?;
+    out.write_all(b"LIST")?;
+    out.write_all(&(info.len() as u32).to_le_bytes())?;
+    out.write_all(&info)?;
+    out.write_all(b"data")?;
+    out.write_all(&data_size.to_le_bytes())?;
+    let bytes: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+    out.write_all(&bytes)?;
+    Ok(())
+}
+
+pub fn generate_wav(
+    maze: &Grid,
+    history: &[MazeAction],
+    opts: &ImageOptions,
+    ani_opts: &AnimationOptions,
+) -> Result<(), LabgenError> {
+    write_output(&opts.file_path, "wav", |out| {
+        render_wav(maze, history, opts, ani_opts, out)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{generate_maze, MazeType, MazeWrap};
+
+    #[test]
+    fn tones_rise_to_the_right_and_pan_to_the_bottom() {
+        let (maze, _) = generate_maze(3, 5, MazeType::Wilson, MazeWrap::None, 0).unwrap();
+        assert_eq!(tone(&maze, Point::new(0, 0)), (BASE_FREQUENCY, 0.0));
+        assert_eq!(tone(&maze, Point::new(2, 4)), (BASE_FREQUENCY * 4.0, 1.0));
+        // openings sound like the edge they leave
+        assert_eq!(
+            tone(&maze, Point::new(-1, 5)),
+            tone(&maze, Point::new(0, 4))
+        );
+    }
+
+    #[test]
+    fn sound_lasts_as_long_as_the_gif() {
+        let (maze, history) = generate_maze(4, 3, MazeType::Wilson, MazeWrap::None, 0).unwrap();
+        let ani_opts = AnimationOptions::default();
+        let mut wav = Vec::new();
+        render_wav(
+            &maze,
+            &history.steps,
+            &ImageOptions::default(),
+            &ani_opts,
+            &mut wav,
+        )
+        .unwrap();
+        assert_eq!(&wav[..4], b"RIFF");
+
+        // the data chunk follows the format chunk and the info list
+        let size_at = |i: usize| u32::from_le_bytes(wav[i..i + 4].try_into().unwrap()) as usize;
+        assert_eq!(size_at(4), wav.len() - 8);
+        assert_eq!(&wav[36..40], b"LIST");
+        let data = 44 + size_at(40);
+        assert_eq!(&wav[data..data + 4], b"data");
+        let size = size_at(data + 4);
+        let frames = history.steps.len() * delay_samples(ani_opts.frame_time)
+            + delay_samples(ani_opts.pause_time);
+        // two channels of two bytes
+        assert_eq!(size, frames * 4);
+        assert_eq!(wav.len(), data + 8 + size);
+    }
+}
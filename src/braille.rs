@@ -0,0 +1,86 @@
+use crate::{
+    error::LabgenError,
+    image::{add_rulers, draw_maze, image_size, write_output, ImageOptions},
+    maze::Grid,
+};
+use std::io::Write;
+
+// bit of the dot showing the pixel at x, y of the 2 by 4 pixels covered by a braille character
+const DOTS: [[u8; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+
+// the png image as plain text of braille characters, each covering 2 by 4 pixels with a raised
+// dot for every pixel in the wall color. a maze drawn with a passage and wall width of 1 packs a
+// column and two rows of cells into every character. labels and rulers are drawn in the wall color
+// as well, excluded cells show as blank, dotted or solid depending on their style
+pub fn render_braille(
+    maze: &Grid,
+    opts: &ImageOptions,
+    out: &mut dyn Write,
+) -> Result<(), LabgenError> {
+    let (width, height) = image_size(maze, &opts.layout())?;
+    let pixels = draw_maze(maze, opts, |_| 1)?;
+    let (pixels, width, height) = add_rulers(pixels, width, height, maze, opts)?;
+
+    let (width, height) = (width as usize, height as usize);
+    for top in (0..height).step_by(4) {
+        let line: String = (0..width)
+            .step_by(2)
+            .map(|left| {
+                let mut dots = 0;
+                for (dx, column) in DOTS.iter().enumerate() {
+                    for (dy, dot) in column.iter().enumerate() {
+                        let (x, y) = (left + dx, top + dy);
+                        if x < width && y < height && pixels[x + y * width] == 0 {
+                            dots |= dot;
+                        }
+                    }
+                }
+                char::from_u32(0x2800 + dots as u32).unwrap()
+            })
+            .collect();
+        writeln!(out, "{}", line)?;
+    }
+    Ok(())
+}
+
+pub fn generate_braille(maze: &Grid, opts: &ImageOptions) -> Result<(), LabgenError> {
+    write_output(&opts.file_path, "txt", |out| {
+        render_braille(maze, opts, out)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{generate_maze, MazeType, MazeWrap};
+
+    #[test]
+    fn characters_cover_two_by_four_pixels() {
+        let (maze, _) = generate_maze(6, 5, MazeType::Wilson, MazeWrap::None, 1).unwrap();
+        let opts = ImageOptions::default();
+        let (width, height) = image_size(&maze, &opts.layout()).unwrap();
+        let mut out = Vec::new();
+        render_braille(&maze, &opts, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), (height as usize).div_ceil(4));
+        for line in lines {
+            assert_eq!(line.chars().count(), (width as usize).div_ceil(2));
+            assert!(line.chars().all(|c| ('\u{2800}'..='\u{28FF}').contains(&c)));
+        }
+    }
+
+    #[test]
+    fn walls_raise_dots() {
+        // a single cell 2 pixels wide inside walls of 1 pixel, a 4 by 4 image split into a left
+        // and a right character
+        let (maze, _) = generate_maze(1, 1, MazeType::Wilson, MazeWrap::None, 1).unwrap();
+        let opts = ImageOptions {
+            passage_width: 2,
+            ..ImageOptions::default()
+        };
+        let mut out = Vec::new();
+        render_braille(&maze, &opts, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "\u{28CF}\u{28F9}\n");
+    }
+}
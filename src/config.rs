@@ -0,0 +1,229 @@
+use labgen::{
+    error::LabgenError,
+    image::{AnimationFormat, Color, ColorBy, ExcludeStyle, ImageFormat},
+    maze::{
+        BorderShape, CellLabel, GrowingTreeBias, MazeType, MazeWrap, Opening, Orientation, Point,
+        Rect, ScanOrder, UncarveStrategy, WeightSource,
+    },
+    moves::SolutionFormat,
+    pattern::Pattern,
+    pdf::PageSize,
+    polar::CellShape,
+    rooms::Room,
+    solver::SolverType,
+    stats::{Entrances, StatsFormat, StatsPredicate},
+    template::RoomDoors,
+    text::TileMap,
+};
+use serde::{Deserialize, Serialize};
+use std::{fs, io};
+
+// options of the plain invocation as stored in a toml file. keys use the long flag names and
+// every key is optional, options missing from the file keep their command line default
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub method: Option<MazeType>,
+    pub wrap: Option<MazeWrap>,
+    pub cells: Option<CellShape>,
+    #[serde(rename = "scan-order")]
+    pub scan_order: Option<ScanOrder>,
+    pub bias: Option<GrowingTreeBias>,
+    pub orientation: Option<Orientation>,
+    pub weights: Option<WeightSource>,
+    pub out: Option<String>,
+    pub animate: Option<bool>,
+    pub format: Option<ImageFormat>,
+    #[serde(rename = "wall-height")]
+    pub wall_height: Option<f32>,
+    #[serde(rename = "base-thickness")]
+    pub base_thickness: Option<f32>,
+    pub page: Option<PageSize>,
+    pub margin: Option<f32>,
+    pub title: Option<String>,
+    #[serde(rename = "print-check")]
+    pub print_check: Option<bool>,
+    pub dpi: Option<u16>,
+    #[serde(rename = "text-style")]
+    pub text_style: Option<TileMap>,
+    pub load: Option<String>,
+    pub replay: Option<String>,
+    #[serde(rename = "save-history")]
+    pub save_history: Option<String>,
+    pub viewport: Option<Rect>,
+    pub exclude: Option<Vec<Rect>>,
+    pub border: Option<BorderShape>,
+    pub mask: Option<String>,
+    pub pattern: Option<Pattern>,
+    pub template: Option<String>,
+    #[serde(rename = "template-scale")]
+    pub template_scale: Option<u32>,
+    #[serde(rename = "rooms-file")]
+    pub rooms_file: Option<String>,
+    #[serde(rename = "room-doors")]
+    pub room_doors: Option<RoomDoors>,
+    pub overlay: Option<u16>,
+    #[serde(rename = "overlay-wall")]
+    pub overlay_wall: Option<u16>,
+    #[serde(rename = "overlay-method")]
+    pub overlay_method: Option<MazeType>,
+    #[serde(rename = "exclude-style")]
+    pub exclude_style: Option<ExcludeStyle>,
+    #[serde(rename = "exclude-color")]
+    pub exclude_color: Option<Color>,
+    pub invert: Option<bool>,
+    #[serde(rename = "random-rotate")]
+    pub random_rotate: Option<bool>,
+    pub uncarve: Option<u32>,
+    #[serde(rename = "uncarve-strategy")]
+    pub uncarve_strategy: Option<UncarveStrategy>,
+    pub braid: Option<u8>,
+    pub corridors: Option<u8>,
+    #[serde(rename = "noise-frequency")]
+    pub noise_frequency: Option<u16>,
+    #[serde(rename = "noise-threshold")]
+    pub noise_threshold: Option<i8>,
+    #[serde(rename = "noise-map")]
+    pub noise_map: Option<bool>,
+    #[serde(rename = "color-by")]
+    pub color_by: Option<ColorBy>,
+    #[serde(rename = "distance-from")]
+    pub distance_from: Option<Point>,
+    #[serde(rename = "gradient-start")]
+    pub gradient_start: Option<Color>,
+    #[serde(rename = "gradient-end")]
+    pub gradient_end: Option<Color>,
+    pub taper: Option<u16>,
+    pub rulers: Option<bool>,
+    pub tiles: Option<u16>,
+    #[serde(rename = "tile-overlap")]
+    pub tile_overlap: Option<u16>,
+    pub entrance: Option<Opening>,
+    pub entrances: Option<Entrances>,
+    pub exit: Option<Opening>,
+    pub label: Option<Vec<CellLabel>>,
+    pub solve: Option<SolverType>,
+    #[serde(rename = "solution-format")]
+    pub solution_format: Option<SolutionFormat>,
+    #[serde(rename = "animate-solve")]
+    pub animate_solve: Option<SolverType>,
+    pub compress: Option<bool>,
+    #[serde(rename = "animation-format")]
+    pub animation_format: Option<AnimationFormat>,
+    pub audio: Option<bool>,
+    pub keyframes: Option<u16>,
+    pub batch: Option<u16>,
+    #[serde(rename = "skip-unchanged")]
+    pub skip_unchanged: Option<bool>,
+    pub seed: Option<u64>,
+    #[serde(rename = "seed-uncarve")]
+    pub seed_uncarve: Option<u64>,
+    #[serde(rename = "seed-braid")]
+    pub seed_braid: Option<u64>,
+    #[serde(rename = "seed-symmetry")]
+    pub seed_symmetry: Option<u64>,
+    #[serde(rename = "seed-openings")]
+    pub seed_openings: Option<u64>,
+    #[serde(rename = "seed-doors")]
+    pub seed_doors: Option<u64>,
+    #[serde(rename = "seed-corridors")]
+    pub seed_corridors: Option<u64>,
+    #[serde(rename = "seed-overlay")]
+    pub seed_overlay: Option<u64>,
+    pub daily: Option<String>,
+    #[serde(rename = "reroll-until")]
+    pub reroll_until: Option<StatsPredicate>,
+    #[serde(rename = "reroll-limit")]
+    pub reroll_limit: Option<u32>,
+    pub stats: Option<StatsFormat>,
+    pub count: Option<u32>,
+    #[serde(rename = "no-progress")]
+    pub no_progress: Option<bool>,
+    pub passagewidth: Option<u16>,
+    pub wallwidth: Option<u16>,
+    pub frametime: Option<u16>,
+    pub pausetime: Option<u16>,
+    pub exec: Option<String>,
+    // rooms as [[room]] tables like in a rooms file, kept last as toml writes tables after keys
+    #[serde(rename = "room")]
+    pub rooms: Option<Vec<Room>>,
+}
+
+impl Config {
+    pub fn load(path: &str) -> Result<Self, LabgenError> {
+        let text = fs::read_to_string(path).map_err(|e| {
+            io::Error::new(e.kind(), format!("could not read config {}: {}", path, e))
+        })?;
+        // the values are checked against the limits of their flags by check_config in main
+        toml::from_str(&text)
+            .map_err(|e| LabgenError::Usage(format!("invalid config {}: {}", path, e)))
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, path::PathBuf};
+
+    // a config file in the temp directory, removed again when dropped
+    struct TempConfig(PathBuf);
+
+    impl TempConfig {
+        fn new(name: &str, text: &str) -> Self {
+            let path = env::temp_dir().join(format!("labgen-{}-{}.toml", name, std::process::id()));
+            fs::write(&path, text).unwrap();
+            TempConfig(path)
+        }
+
+        fn load(&self) -> Result<Config, LabgenError> {
+            Config::load(self.0.to_str().unwrap())
+        }
+    }
+
+    impl Drop for TempConfig {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn printed_configs_load_back() {
+        let config = Config {
+            width: Some(30),
+            method: Some(MazeType::Kruskal),
+            scan_order: Some(ScanOrder::Hilbert),
+            label: Some(vec!["1,2,a".parse().unwrap()]),
+            rooms: Some(vec![Room {
+                name: Some("hall".to_string()),
+                shape: "rect:2,2,6,4".parse().unwrap(),
+            }]),
+            ..Config::default()
+        };
+        let text = config.to_toml();
+        assert!(text.contains("scan-order = \"hilbert\""));
+        let file = TempConfig::new("round-trip", &text);
+        assert_eq!(file.load().unwrap().to_toml(), text);
+    }
+
+    #[test]
+    fn unknown_keys_are_usage_errors() {
+        let file = TempConfig::new("unknown", "width = 5\nscan_order = \"row\"\n");
+        match file.load() {
+            Err(LabgenError::Usage(message)) => assert!(message.contains("scan_order")),
+            other => panic!("loaded {:?}", other),
+        }
+    }
+
+    #[test]
+    fn missing_files_are_io_errors() {
+        let path = env::temp_dir().join("labgen-missing-config.toml");
+        let loaded = Config::load(path.to_str().unwrap());
+        assert!(matches!(loaded, Err(LabgenError::Io(_))));
+    }
+}
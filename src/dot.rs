@@ -0,0 +1,95 @@
+use crate::{
+    error::LabgenError,
+    image::write_output,
+    maze::{Direction, Grid, Point},
+};
+use std::io::Write;
+
+// the maze as an undirected graphviz graph with a node for every cell that is not removed and an
+// edge for every passage between two cells. nodes are named x,y and pinned at their cell with y
+// growing upwards, so neato or fdp lay the graph out as the maze. passages across a wrapped edge
+// are dashed, openings leading out of the grid are left out
+pub fn render_dot(maze: &Grid, out: &mut dyn Write) -> Result<(), LabgenError> {
+    for line in maze.metadata().comment().lines() {
+        writeln!(out, "// {}", line)?;
+    }
+    writeln!(out, "graph maze {{")?;
+    writeln!(out, "    node [shape=point];")?;
+
+    let cells = (0..maze.height as i32).flat_map(|y| (0..maze.width as i32).map(move |x| (x, y)));
+    for (x, y) in cells.clone() {
+        if !maze.is_removed(Point::new(x, y)) {
+            writeln!(out, "    \"{0},{1}\" [pos=\"{0},{2}!\"];", x, y, -y)?;
+        }
+    }
+
+    // every passage is written once, from the cell on its west or north side
+    for (x, y) in cells {
+        let pt = Point::new(x, y);
+        for dir in [Direction::East, Direction::South] {
+            let next = maze.travel(pt, dir);
+            if !maze.get_tile(pt).connected(dir) || !maze.contains(next) || next == pt {
+                continue;
+            }
+            let style = if next == pt.travel(dir) {
+                ""
+            } else {
+                " [style=dashed]"
+            };
+            writeln!(
+                out,
+                "    \"{},{}\" -- \"{},{}\"{};",
+                x, y, next.x, next.y, style
+            )?;
+        }
+    }
+
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+pub fn generate_dot(maze: &Grid, file_path: &str) -> Result<(), LabgenError> {
+    write_output(file_path, "dot", |out| render_dot(maze, out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{generate_maze, ConnectionStatus, MazeType, MazeWrap};
+
+    fn dot(maze: &Grid) -> String {
+        let mut out = Vec::new();
+        render_dot(maze, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn perfect_mazes_have_an_edge_per_passage() {
+        let (maze, _) = generate_maze(5, 4, MazeType::Wilson, MazeWrap::None, 3).unwrap();
+        let text = dot(&maze);
+        assert_eq!(text.matches("[pos=").count(), 20);
+        assert_eq!(text.matches(" -- ").count(), 19);
+        assert!(!text.contains("dashed"));
+        assert!(text.trim_end().ends_with('}'));
+    }
+
+    #[test]
+    fn wrapped_passages_are_dashed() {
+        let mut maze = Grid::new(3, 1, MazeWrap::Horizontal, 0, MazeType::default());
+        maze.carve(Point::new(0, 0), Direction::East).unwrap();
+        maze.carve(Point::new(2, 0), Direction::East).unwrap();
+        let text = dot(&maze);
+        assert!(text.contains("    \"0,0\" -- \"1,0\";\n"));
+        assert!(text.contains("    \"2,0\" -- \"0,0\" [style=dashed];\n"));
+    }
+
+    #[test]
+    fn removed_cells_have_no_node() {
+        let mut maze = Grid::new(2, 1, MazeWrap::None, 0, MazeType::default());
+        maze.get_tile_mut(Point::new(1, 0))
+            .set_status(ConnectionStatus::Removed);
+        let text = dot(&maze);
+        assert!(text.contains("\"0,0\" [pos=\"0,0!\"]"));
+        assert!(!text.contains("\"1,0\""));
+    }
+}
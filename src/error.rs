@@ -0,0 +1,112 @@
+use std::{error, fmt, io};
+
+// every failure is reported through one of these so the process exit code tells scripts what
+// kind of problem occurred
+#[derive(Debug)]
+pub enum LabgenError {
+    // arguments that parse but can not be used together
+    Usage(String),
+    // requested areas that do not fit the maze
+    Geometry(String),
+    Io(io::Error),
+    // the image is too large for the output format or was rejected by the encoder
+    Render(String),
+    // no maze satisfied the reroll predicate within the retry limit
+    NoMatch(String),
+    // the work was stopped early through its cancel token
+    Cancelled,
+}
+
+impl LabgenError {
+    // 1 is left for panics and 2 matches the code clap uses for its own usage errors
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            LabgenError::Usage(_) => 2,
+            LabgenError::Geometry(_) => 3,
+            LabgenError::Io(_) => 4,
+            LabgenError::Render(_) => 5,
+            LabgenError::NoMatch(_) => 6,
+            // the code shells give processes ended by ctrl-c
+            LabgenError::Cancelled => 130,
+        }
+    }
+}
+
+impl fmt::Display for LabgenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LabgenError::Usage(msg) => write!(f, "{}", msg),
+            LabgenError::Geometry(msg) => write!(f, "{}", msg),
+            LabgenError::Io(e) => write!(f, "{}", e),
+            LabgenError::Render(msg) => write!(f, "could not render image: {}", msg),
+            LabgenError::NoMatch(msg) => write!(f, "{}", msg),
+            LabgenError::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+impl error::Error for LabgenError {}
+
+// sizes generate_maze can not carve a maze of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MazeGenError {
+    // a side without any cells
+    Empty { width: u32, height: u32 },
+    // a side longer than points can address, or more cells than fit in memory
+    TooLarge { width: u32, height: u32 },
+    // the progress was cancelled before the maze was finished
+    Cancelled,
+}
+
+impl fmt::Display for MazeGenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MazeGenError::Empty { width, height } => {
+                write!(f, "a {}x{} maze has no cells", width, height)
+            }
+            MazeGenError::TooLarge { width, height } => write!(
+                f,
+                "a {}x{} maze is too large, sides can be at most {} cells",
+                width,
+                height,
+                i32::MAX
+            ),
+            MazeGenError::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+impl error::Error for MazeGenError {}
+
+impl From<MazeGenError> for LabgenError {
+    fn from(e: MazeGenError) -> Self {
+        match e {
+            MazeGenError::Cancelled => LabgenError::Cancelled,
+            e => LabgenError::Usage(e.to_string()),
+        }
+    }
+}
+
+impl From<io::Error> for LabgenError {
+    fn from(e: io::Error) -> Self {
+        LabgenError::Io(e)
+    }
+}
+
+impl From<gif::EncodingError> for LabgenError {
+    fn from(e: gif::EncodingError) -> Self {
+        match e {
+            gif::EncodingError::Io(e) => LabgenError::Io(e),
+            e => LabgenError::Render(e.to_string()),
+        }
+    }
+}
+
+impl From<png::EncodingError> for LabgenError {
+    fn from(e: png::EncodingError) -> Self {
+        match e {
+            png::EncodingError::IoError(e) => LabgenError::Io(e),
+            e => LabgenError::Render(e.to_string()),
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{
+    error::LabgenError,
+    maze::{generate_maze, MazeType, MazeWrap},
+    text::{render_text, TileMap},
+};
+use std::io::Write;
+
+// size of the sample maze in cells, small enough to fit next to the description in a terminal
+const SAMPLE_WIDTH: u32 = 12;
+const SAMPLE_HEIGHT: u32 = 6;
+
+// how the method carves, the texture it leaves and what its animation shows
+fn description(method: MazeType) -> (&'static str, &'static str, &'static str) {
+    match method {
+        MazeType::Backtrack => (
+            "walks to a random unvisited neighbor until it is stuck, then backs up to the last \
+            cell with one left",
+            "long winding passages with few, short dead ends",
+            "a single path snaking through the grid",
+        ),
+        MazeType::Prim => (
+            "joins a random cell from the frontier of cells next to the maze",
+            "many short dead ends branching off everywhere",
+            "the maze growing outward from one cell in all directions at once",
+        ),
+        MazeType::BinaryTree => (
+            "joins every cell to its neighbor to the north or west",
+            "open corridors along the top and left edges, passages run diagonally toward the top \
+            left",
+            "cells joining in scan order",
+        ),
+        MazeType::Sidewinder => (
+            "carves runs of cells along each row and joins every run to the row above once",
+            "an open corridor along the top edge, passages run vertically",
+            "the maze filling in row by row",
+        ),
+        MazeType::Noise => (
+            "splits the grid by a noise threshold and floods one side with prim and the other \
+            with backtrack",
+            "regions of short dead ends next to regions of long corridors",
+            "both regions growing at once",
+        ),
+        MazeType::GrowingTree => (
+            "grows from a random one of the newest 10% of open cells, or from the cell picked by \
+            --bias",
+            "mostly long corridors like backtrack with some branching like prim",
+            "a winding path that now and then sprouts somewhere else",
+        ),
+        MazeType::Wilson => (
+            "joins random walks from unvisited cells to the maze once they reach it, erasing \
+            the loops they made",
+            "none, every maze is equally likely",
+            "slow at first, then filling in quickly as the maze grows",
+        ),
+        MazeType::Kruskal => (
+            "removes walls in random order wherever they separate two unjoined parts",
+            "many short dead ends, close to unbiased",
+            "scattered fragments merging into one maze",
+        ),
+        MazeType::Eller => (
+            "works one row at a time, joining cells in the row and carrying some down to the \
+            next",
+            "horizontal passages slightly favored",
+            "the maze filling in row by row",
+        ),
+        MazeType::HuntAndKill => (
+            "walks like backtrack, but when stuck hunts in scan order for a cell next to the maze",
+            "long winding passages with few dead ends",
+            "long walks, each starting where the hunt finds a cell",
+        ),
+        MazeType::RecursiveDivision => (
+            "divides the open grid with walls across chambers that keep a single gap",
+            "long straight walls and rectangular chambers",
+            "the finished maze replayed from the top left, walls are not added one by one",
+        ),
+    }
+}
+
+fn wrap_names(wrap: MazeWrap) -> &'static str {
+    match wrap {
+        MazeWrap::None => "no",
+        MazeWrap::Horizontal => "horizontal only",
+        MazeWrap::Vertical => "vertical only",
+        MazeWrap::Full => "horizontal, vertical and full",
+    }
+}
+
+fn yes(supported: bool) -> &'static str {
+    if supported {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+// describes a generation method with the options that affect it, followed by a small sample maze
+// drawn as text
+pub fn render_explain(method: MazeType, seed: u64, out: &mut dyn Write) -> Result<(), LabgenError> {
+    let (how, bias, animation) = description(method);
+    let mut options = vec!["--seed"];
+    if method.supported_wrap() != MazeWrap::None {
+        options.push("--wrap");
+    }
+    if method.uses_scan_order() {
+        options.push("--scan-order");
+    }
+    if method.uses_orientation() {
+        options.push("--orientation");
+    }
+    if method.uses_weights() {
+        options.push("--weights");
+    }
+    if method == MazeType::GrowingTree {
+        options.push("--bias");
+    }
+    if method == MazeType::Noise {
+        options.extend(["--noise-frequency", "--noise-threshold"]);
+    }
+
+    writeln!(out, "{}: {}", method, how)?;
+    for (name, value) in [
+        ("bias", bias),
+        ("animation", animation),
+        ("wrap", wrap_names(method.supported_wrap())),
+        ("exclusions", yes(method.supports_exclusions())),
+        ("patterns", yes(method.supports_patterns())),
+        ("rooms", yes(method.supports_rooms())),
+        ("options", &options.join(", ")),
+    ] {
+        writeln!(out, "{:<12}{}", format!("{}:", name), value)?;
+    }
+
+    writeln!(out)?;
+    let (maze, _) = generate_maze(SAMPLE_WIDTH, SAMPLE_HEIGHT, method, MazeWrap::None, seed)?;
+    render_text(&maze, &TileMap::default(), out)
+}
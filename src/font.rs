@@ -0,0 +1,102 @@
+// tiny 3x5 bitmap font used to stamp labels into rendered images
+// each glyph is 5 rows of 3 bits, most significant bit is the leftmost pixel
+
+pub const GLYPH_WIDTH: u16 = 3;
+pub const GLYPH_HEIGHT: u16 = 5;
+
+fn glyph(c: char) -> [u8; 5] {
+    match c.to_ascii_uppercase() {
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b110, 0b001, 0b010, 0b100, 0b111],
+        '3' => [0b110, 0b001, 0b010, 0b001, 0b110],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b110, 0b001, 0b110],
+        '6' => [0b011, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b110],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '(' => [0b001, 0b010, 0b010, 0b010, 0b001],
+        ')' => [0b100, 0b010, 0b010, 0b010, 0b100],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '%' => [0b101, 0b001, 0b010, 0b100, 0b101],
+        '=' => [0b000, 0b111, 0b000, 0b111, 0b000],
+        '?' => [0b110, 0b001, 0b010, 0b000, 0b010],
+        _ => [0b000; 5],
+    }
+}
+
+// width in pixels of a string drawn at the given scale, glyphs are separated by one column
+pub fn text_width(text: &str, scale: u16) -> u16 {
+    let len = text.chars().count() as u16;
+    if len == 0 {
+        0
+    } else {
+        (len * (GLYPH_WIDTH + 1) - 1) * scale
+    }
+}
+
+// draws text into an indexed pixel buffer, pixels falling outside of the image are skipped
+pub fn draw_text(
+    pixels: &mut [u8],
+    image_width: u16,
+    left: u16,
+    top: u16,
+    text: &str,
+    scale: u16,
+    color: u8,
+) {
+    let image_height = pixels.len() / image_width as usize;
+
+    for (i, c) in text.chars().enumerate() {
+        let glyph_left = left as usize + i * (GLYPH_WIDTH + 1) as usize * scale as usize;
+
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for col in 0..GLYPH_WIDTH as usize {
+                if bits & (0b100 >> col) == 0 {
+                    continue;
+                }
+
+                for dy in 0..scale as usize {
+                    for dx in 0..scale as usize {
+                        let x = glyph_left + col * scale as usize + dx;
+                        let y = top as usize + row * scale as usize + dy;
+                        if x < image_width as usize && y < image_height {
+                            pixels[x + y * image_width as usize] = color;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
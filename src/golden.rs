@@ -0,0 +1,620 @@
+// golden image regression tests. fixed seeds are rendered in memory with every renderer and a
+// hash of the encoded bytes is compared against testdata/goldens.txt. after an intentional
+// change to the output, regenerate the file with UPDATE_GOLDENS=1 cargo test goldens
+
+use crate::{
+    ansi::render_ansi,
+    audio::render_wav,
+    braille::render_braille,
+    dot::render_dot,
+    explain::render_explain,
+    heatmap::render_distance_map,
+    image::{
+        render_apng, render_gif, render_gif_compare, render_gif_solve, render_gif_uncompressed,
+        render_keyframes, render_png, render_png_gradient, render_png_noise, render_png_polar,
+        render_png_tapered, AnimationOptions, Color, ComparePanel, ExcludeStyle, ImageOptions,
+    },
+    mask::{apply_mask, Mask},
+    maze::{
+        carve_maze, generate_maze, noise_costs, BorderShape, Direction, Grid, MazeAction, MazeType,
+        MazeWrap, NoiseOptions, Orientation, Point, Rect, ScanOrder, UncarveStrategy, WeightSource,
+    },
+    moves::{render_moves, SolutionFormat},
+    overlay::{overlay_maze, OverlayScale},
+    pattern::{carve_pattern, Pattern},
+    pdf::{render_pdf, PageSize, PdfOptions},
+    polar::{carve_polar, PolarGrid},
+    progress::Progress,
+    solver::{solve, SolverType},
+    stats::{distances, generation_order, noise_levels},
+    stl::{render_stl, MeshOptions},
+    svg::render_svg,
+    template::{apply_template, place_doors, seal_rooms, Template},
+    text::{render_text, TileMap},
+};
+#[cfg(feature = "serde")]
+use crate::{html::render_html, json::render_json, rooms::Rooms, template::room_cells};
+use clap::ValueEnum;
+use rand::{rngs::StdRng, SeedableRng};
+use std::{collections::BTreeMap, fs, io::Write};
+
+const GOLDENS_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/goldens.txt");
+
+const METHODS: [MazeType; 11] = [
+    MazeType::Backtrack,
+    MazeType::Prim,
+    MazeType::BinaryTree,
+    MazeType::Sidewinder,
+    MazeType::Noise,
+    MazeType::GrowingTree,
+    MazeType::Wilson,
+    MazeType::Kruskal,
+    MazeType::Eller,
+    MazeType::HuntAndKill,
+    MazeType::RecursiveDivision,
+];
+
+// fnv-1a, unlike the std hashers its output is fixed across rust versions
+fn hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |h, b| {
+        (h ^ *b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn render(f: impl FnOnce(&mut dyn Write) -> Result<(), crate::error::LabgenError>) -> u64 {
+    let mut buffer: Vec<u8> = Vec::new();
+    f(&mut buffer).unwrap();
+    hash(&buffer)
+}
+
+fn image_options() -> ImageOptions {
+    ImageOptions {
+        file_path: String::new(),
+        passage_width: 3,
+        wall_width: 1,
+        color_map: [0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF],
+        exclude_style: ExcludeStyle::Color,
+        exclude_color: Color([0x80, 0x80, 0x80]),
+        solution: None,
+        labels: Vec::new(),
+        rulers: false,
+        gradient: [Color([0x20, 0x40, 0xFF]), Color([0xFF, 0x40, 0x20])],
+    }
+}
+
+fn animation_options() -> AnimationOptions {
+    AnimationOptions {
+        frame_time: 2,
+        pause_time: 100,
+        batch_size: 3,
+        progress: false,
+        on_progress: None,
+        cancel: None,
+        skip_unchanged: false,
+    }
+}
+
+fn corner(maze: &Grid) -> Point {
+    Point::new(maze.width as i32 - 1, maze.height as i32 - 1)
+}
+
+// hash of every rendered case by name
+fn render_cases() -> BTreeMap<String, u64> {
+    let opts = image_options();
+    let ani_opts = animation_options();
+    let mut cases = BTreeMap::new();
+
+    for method in METHODS {
+        cases.insert(
+            format!("explain-{}", method),
+            render(|out| render_explain(method, 1, out)),
+        );
+        for wrap in [MazeWrap::None, MazeWrap::Full] {
+            let (maze, history) = generate_maze(13, 9, method, wrap, 1).unwrap();
+            let name = format!("{}-{}", method, wrap);
+
+            cases.insert(
+                format!("png-{}", name),
+                render(|out| render_png(&maze, &opts, out)),
+            );
+            cases.insert(
+                format!("png-order-{}", name),
+                render(|out| {
+                    render_png_gradient(&maze, &generation_order(&maze, &history.steps), &opts, out)
+                }),
+            );
+            cases.insert(
+                format!("gif-{}", name),
+                render(|out| render_gif_uncompressed(&maze, &history.steps, &opts, &ani_opts, out)),
+            );
+            cases.insert(
+                format!("gif-compressed-{}", name),
+                render(|out| render_gif(&maze, &history.steps, &opts, &ani_opts, out)),
+            );
+        }
+    }
+
+    let (maze, _) = generate_maze(13, 9, MazeType::Backtrack, MazeWrap::None, 2).unwrap();
+    for solver in [SolverType::Bfs, SolverType::AStar, SolverType::Tremaux] {
+        let solution = solve(&maze, Point::new(0, 0), corner(&maze), solver).unwrap();
+        cases.insert(
+            format!("gif-solve-{:?}", solver).to_lowercase(),
+            render(|out| render_gif_solve(&maze, &solution, &opts, &ani_opts, out)),
+        );
+    }
+
+    let panels: Vec<ComparePanel> = [MazeType::Backtrack, MazeType::Prim, MazeType::Wilson]
+        .into_iter()
+        .map(|method| {
+            let (maze, history) = generate_maze(13, 9, method, MazeWrap::None, 3).unwrap();
+            ComparePanel {
+                label: method.to_string(),
+                maze,
+                history,
+            }
+        })
+        .collect();
+    cases.insert(
+        "gif-compare".to_string(),
+        render(|out| render_gif_compare(&panels, &opts, &ani_opts, out)),
+    );
+
+    let mut maze = Grid::new(13, 9, MazeWrap::None, 4, MazeType::Wilson);
+    maze.exclude(Rect {
+        x: 4,
+        y: 2,
+        width: 5,
+        height: 4,
+    });
+    let (maze, history) = carve_maze(maze, &Progress::hidden());
+    for style in ExcludeStyle::value_variants() {
+        let opts = ImageOptions {
+            exclude_style: *style,
+            ..image_options()
+        };
+        let name = format!("{:?}", style).to_lowercase();
+        cases.insert(
+            format!("png-exclude-{}", name),
+            render(|out| render_png(&maze, &opts, out)),
+        );
+        cases.insert(
+            format!("gif-exclude-{}", name),
+            render(|out| render_gif(&maze, &history.steps, &opts, &ani_opts, out)),
+        );
+        cases.insert(
+            format!("svg-exclude-{}", name),
+            render(|out| render_svg(&maze, &history.steps, &opts, &ani_opts, out)),
+        );
+        cases.insert(
+            format!("stl-exclude-{}", name),
+            render(|out| render_stl(&maze, &opts, &MeshOptions::default(), out)),
+        );
+    }
+
+    let (maze, history) = generate_maze(13, 9, MazeType::Prim, MazeWrap::Full, 6).unwrap();
+    let opts = ImageOptions {
+        solution: solve(&maze, Point::new(0, 0), corner(&maze), SolverType::AStar),
+        ..image_options()
+    };
+    cases.insert(
+        "png-solution".to_string(),
+        render(|out| render_png(&maze, &opts, out)),
+    );
+    cases.insert(
+        "gif-solution".to_string(),
+        render(|out| render_gif_uncompressed(&maze, &history.steps, &opts, &ani_opts, out)),
+    );
+    cases.insert(
+        "gif-compressed-solution".to_string(),
+        render(|out| render_gif(&maze, &history.steps, &opts, &ani_opts, out)),
+    );
+    cases.insert(
+        "moves-solution".to_string(),
+        render(|out| {
+            let solution = opts.solution.as_ref().unwrap();
+            render_moves(&maze, solution, SolutionFormat::Moves, out)
+        }),
+    );
+    cases.insert(
+        "json-moves-solution".to_string(),
+        render(|out| {
+            let solution = opts.solution.as_ref().unwrap();
+            render_moves(&maze, solution, SolutionFormat::Json, out)
+        }),
+    );
+    cases.insert(
+        "wav-solution".to_string(),
+        render(|out| render_wav(&maze, &history.steps, &opts, &ani_opts, out)),
+    );
+    cases.insert(
+        "apng-solution".to_string(),
+        render(|out| render_apng(&maze, &history.steps, &opts, &ani_opts, out)),
+    );
+    cases.insert(
+        "svg-solution".to_string(),
+        render(|out| render_svg(&maze, &history.steps, &opts, &ani_opts, out)),
+    );
+    cases.insert(
+        "png-keyframes-solution".to_string(),
+        render(|out| render_keyframes(&maze, &history.steps, 4, &opts, out)),
+    );
+    let pdf_opts = PdfOptions {
+        page: PageSize::Letter,
+        margin: 20.0,
+        title: Some("Maze (solved)".to_string()),
+    };
+    cases.insert(
+        "ansi-solution".to_string(),
+        render(|out| render_ansi(&maze, &opts, out)),
+    );
+    cases.insert(
+        "pdf-solution".to_string(),
+        render(|out| render_pdf(&maze, &opts, &pdf_opts, out)),
+    );
+    let opts = ImageOptions {
+        labels: ["0,0,S", "12,8,E", "6,4,42"]
+            .iter()
+            .map(|l| l.parse().unwrap())
+            .collect(),
+        passage_width: 7,
+        ..image_options()
+    };
+    cases.insert(
+        "png-labels".to_string(),
+        render(|out| render_png(&maze, &opts, out)),
+    );
+    cases.insert(
+        "gif-labels".to_string(),
+        render(|out| render_gif_uncompressed(&maze, &history.steps, &opts, &ani_opts, out)),
+    );
+    cases.insert(
+        "gif-compressed-labels".to_string(),
+        render(|out| render_gif(&maze, &history.steps, &opts, &ani_opts, out)),
+    );
+    cases.insert(
+        "svg-labels".to_string(),
+        render(|out| render_svg(&maze, &history.steps, &opts, &ani_opts, out)),
+    );
+    cases.insert(
+        "braille-labels".to_string(),
+        render(|out| render_braille(&maze, &opts, out)),
+    );
+    let opts = ImageOptions {
+        gradient: [Color([0xFF, 0xFF, 0x00]), Color([0x00, 0x80, 0x00])],
+        ..image_options()
+    };
+    cases.insert(
+        "png-distance".to_string(),
+        render(|out| render_png_gradient(&maze, &distances(&maze, Point::new(6, 4)), &opts, out)),
+    );
+    let mapped = ImageOptions {
+        rulers: true,
+        exclude_style: ExcludeStyle::Transparent,
+        ..image_options()
+    };
+    cases.insert(
+        "distance-map-rulers".to_string(),
+        render(|out| render_distance_map(&maze, &distances(&maze, Point::new(6, 4)), &mapped, out)),
+    );
+    let tapered = ImageOptions {
+        passage_width: 7,
+        solution: solve(&maze, Point::new(0, 0), Point::new(6, 4), SolverType::Bfs),
+        ..image_options()
+    };
+    cases.insert(
+        "png-taper".to_string(),
+        render(|out| {
+            render_png_tapered(&maze, &distances(&maze, Point::new(0, 0)), 2, &tapered, out)
+        }),
+    );
+    let mut noisy = Grid::new(17, 11, MazeWrap::None, 3, MazeType::Noise);
+    noisy.params.noise = NoiseOptions {
+        frequency: 3,
+        threshold: 20,
+    };
+    let (noisy, _) = carve_maze(noisy, &Progress::hidden());
+    cases.insert(
+        "png-noise-levels".to_string(),
+        render(|out| render_png_gradient(&noisy, &noise_levels(&noisy), &opts, out)),
+    );
+    cases.insert(
+        "png-noise-map".to_string(),
+        render(|out| render_png_noise(&noisy, &opts, out)),
+    );
+    let opts = ImageOptions {
+        rulers: true,
+        ..image_options()
+    };
+    cases.insert(
+        "png-rulers".to_string(),
+        render(|out| render_png(&maze, &opts, out)),
+    );
+    let (mut opened, _) = generate_maze(13, 9, MazeType::Backtrack, MazeWrap::None, 10).unwrap();
+    let mut rng = StdRng::seed_from_u64(0);
+    for opening in ["left:center", "bottom:random", "top:0", "random"] {
+        let (pt, dir) = opened
+            .opening_cell(opening.parse().unwrap(), &mut rng)
+            .unwrap();
+        opened.get_tile_mut(pt).connect(dir);
+    }
+    cases.insert(
+        "png-openings".to_string(),
+        render(|out| render_png(&opened, &image_options(), out)),
+    );
+    #[cfg(feature = "serde")]
+    cases.insert(
+        "json-openings".to_string(),
+        render(|out| render_json(&opened, out)),
+    );
+    let (wrapped, _) = generate_maze(7, 5, MazeType::Wilson, MazeWrap::Full, 2).unwrap();
+    cases.insert(
+        "dot-wrap".to_string(),
+        render(|out| render_dot(&wrapped, out)),
+    );
+    cases.insert(
+        "text-wrap".to_string(),
+        render(|out| render_text(&wrapped, &TileMap::default(), out)),
+    );
+    cases.insert(
+        "text-openings".to_string(),
+        render(|out| render_text(&opened, &"ascii".parse().unwrap(), out)),
+    );
+    #[cfg(feature = "serde")]
+    cases.insert(
+        "html-wrap".to_string(),
+        render(|out| {
+            render_html(
+                &wrapped,
+                &image_options(),
+                Point::new(0, 0),
+                corner(&wrapped),
+                out,
+            )
+        }),
+    );
+    let opts = image_options();
+    cases.insert(
+        "png-invert".to_string(),
+        render(|out| render_png(&maze.invert(), &opts, out)),
+    );
+
+    for shape in BorderShape::value_variants() {
+        let mut maze = Grid::new(15, 11, MazeWrap::None, 5, MazeType::Backtrack);
+        maze.exclude_outside(*shape);
+        let (maze, _) = carve_maze(maze, &Progress::hidden());
+        cases.insert(
+            format!("png-border-{:?}", shape).to_lowercase(),
+            render(|out| render_png(&maze, &opts, out)),
+        );
+    }
+
+    // the walls of a small maze image make a mask with thin black lines
+    let (small, _) = generate_maze(4, 3, MazeType::Prim, MazeWrap::None, 13).unwrap();
+    let mut mask_png: Vec<u8> = Vec::new();
+    render_png(&small, &opts, &mut mask_png).unwrap();
+    let mut maze = Grid::new(17, 13, MazeWrap::None, 13, MazeType::Backtrack);
+    apply_mask(&mut maze, &Mask::decode(mask_png.as_slice()).unwrap());
+    let (maze, _) = carve_maze(maze, &Progress::hidden());
+    cases.insert(
+        "png-mask".to_string(),
+        render(|out| render_png(&maze, &opts, out)),
+    );
+
+    for method in [MazeType::BinaryTree, MazeType::Kruskal] {
+        let mut maze = Grid::new(13, 9, MazeWrap::None, 8, method);
+        maze.params.scan_order = ScanOrder::Hilbert;
+        let (maze, _) = carve_maze(maze, &Progress::hidden());
+        cases.insert(
+            format!("png-hilbert-{}", method),
+            render(|out| render_png(&maze, &opts, out)),
+        );
+    }
+
+    for (name, bias) in [("oldest", "oldest"), ("mix", "newest:50,random:50")] {
+        let mut maze = Grid::new(13, 9, MazeWrap::None, 8, MazeType::GrowingTree);
+        maze.params.bias = bias.parse().unwrap();
+        let (maze, _) = carve_maze(maze, &Progress::hidden());
+        cases.insert(
+            format!("png-bias-{}", name),
+            render(|out| render_png(&maze, &opts, out)),
+        );
+    }
+
+    for (method, orientation) in [
+        (MazeType::BinaryTree, Orientation::Se),
+        (MazeType::Sidewinder, Orientation::East),
+    ] {
+        let mut maze = Grid::new(13, 9, MazeWrap::None, 8, method);
+        maze.params.orientation = Some(orientation);
+        let (maze, _) = carve_maze(maze, &Progress::hidden());
+        cases.insert(
+            format!("png-orientation-{}-{}", method, orientation),
+            render(|out| render_png(&maze, &opts, out)),
+        );
+    }
+
+    for method in [MazeType::Prim, MazeType::Kruskal] {
+        let mut maze = Grid::new(13, 9, MazeWrap::None, 8, method);
+        maze.params.weights = Some(WeightSource::Noise);
+        maze.costs = noise_costs(&maze);
+        let (maze, _) = carve_maze(maze, &Progress::hidden());
+        cases.insert(
+            format!("png-weights-{}", method),
+            render(|out| render_png(&maze, &opts, out)),
+        );
+    }
+
+    for strategy in UncarveStrategy::value_variants() {
+        let (mut maze, _) = generate_maze(13, 9, MazeType::Prim, MazeWrap::None, 9).unwrap();
+        maze.uncarve(40, *strategy, maze.params.seed);
+        cases.insert(
+            format!("png-uncarve-{:?}", strategy).to_lowercase(),
+            render(|out| render_png(&maze, &opts, out)),
+        );
+    }
+
+    // a history carving every passage twice, the second half changes nothing
+    let (maze, history) = generate_maze(13, 9, MazeType::Eller, MazeWrap::None, 15).unwrap();
+    let steps = [history.steps.clone(), history.steps.clone()].concat();
+    let skip_opts = AnimationOptions {
+        skip_unchanged: true,
+        ..animation_options()
+    };
+    cases.insert(
+        "gif-skip-unchanged".to_string(),
+        render(|out| render_gif_uncompressed(&maze, &steps, &opts, &skip_opts, out)),
+    );
+    cases.insert(
+        "gif-compressed-skip-unchanged".to_string(),
+        render(|out| render_gif(&maze, &steps, &opts, &skip_opts, out)),
+    );
+
+    let (mut maze, history) = generate_maze(13, 9, MazeType::Prim, MazeWrap::None, 14).unwrap();
+    let mut steps = history.steps.clone();
+    steps.extend(
+        maze.braid(60, maze.params.seed)
+            .into_iter()
+            .map(MazeAction::from),
+    );
+    cases.insert(
+        "png-braid".to_string(),
+        render(|out| render_png(&maze, &opts, out)),
+    );
+    cases.insert(
+        "gif-braid".to_string(),
+        render(|out| render_gif_uncompressed(&maze, &steps, &opts, &ani_opts, out)),
+    );
+
+    for method in [MazeType::Backtrack, MazeType::GrowingTree] {
+        let maze = carve_polar(PolarGrid::new(8, 11, method), &Progress::hidden());
+        cases.insert(
+            format!("png-polar-{}", method),
+            render(|out| render_png_polar(&maze, &opts, out)),
+        );
+    }
+
+    let template: Template = "..R\n#RR\n...".parse().unwrap();
+    for method in [MazeType::Wilson, MazeType::Kruskal] {
+        let mut maze = Grid::new(15, 11, MazeWrap::None, 12, method);
+        apply_template(&mut maze, &template, 4);
+        let (maze, _) = carve_maze(maze, &Progress::hidden());
+        cases.insert(
+            format!("png-template-{}", method),
+            render(|out| render_png(&maze, &opts, out)),
+        );
+    }
+    let mut maze = Grid::new(15, 11, MazeWrap::None, 12, MazeType::Wilson);
+    apply_template(&mut maze, &template, 4);
+    seal_rooms(&mut maze, &template, 4);
+    let (mut maze, _) = carve_maze(maze, &Progress::hidden());
+    let doors = [
+        (Point::new(8, 1), Direction::West),
+        (Point::new(6, 7), Direction::South),
+    ];
+    place_doors(&mut maze, &template, 4, &doors).unwrap();
+    cases.insert(
+        "png-template-doors".to_string(),
+        render(|out| render_png(&maze, &opts, out)),
+    );
+
+    // rooms files are toml, so they can only be read with serde
+    #[cfg(feature = "serde")]
+    {
+        let rooms: Rooms = "[[room]]\nname = \"hall\"\nshape = \"rect:1,1,5,3\"\n\n\
+            [[room]]\nshape = \"rect:9,5,4,4\"\n"
+            .parse()
+            .unwrap();
+        rooms.check(15, 11).unwrap();
+        let template = rooms.template(15, 11);
+        let mut maze = Grid::new(15, 11, MazeWrap::None, 12, MazeType::Kruskal);
+        apply_template(&mut maze, &template, 1);
+        let (mut maze, _) = carve_maze(maze, &Progress::hidden());
+        cases.insert(
+            "png-rooms-file".to_string(),
+            render(|out| render_png(&maze, &opts, out)),
+        );
+        maze.thin_corridors(30, &room_cells(&maze, &template, 1), maze.params.seed);
+        cases.insert(
+            "png-rooms-corridors".to_string(),
+            render(|out| render_png(&maze, &opts, out)),
+        );
+
+        let rooms: Rooms = "[[room]]\nshape = \"circle:4,4,2\"\n\n\
+            [[room]]\nshape = \"polygon:8,1;14,1;14,8\"\n"
+            .parse()
+            .unwrap();
+        rooms.check(15, 11).unwrap();
+        let template = rooms.template(15, 11);
+        let mut maze = Grid::new(15, 11, MazeWrap::None, 12, MazeType::Wilson);
+        apply_template(&mut maze, &template, 1);
+        seal_rooms(&mut maze, &template, 1);
+        let (mut maze, _) = carve_maze(maze, &Progress::hidden());
+        let doors = [
+            (Point::new(4, 2), Direction::North),
+            (Point::new(8, 1), Direction::West),
+        ];
+        place_doors(&mut maze, &template, 1, &doors).unwrap();
+        cases.insert(
+            "png-rooms-shapes".to_string(),
+            render(|out| render_png(&maze, &opts, out)),
+        );
+    }
+
+    let scale = OverlayScale { room: 3, wall: 2 };
+    let (coarse, _) = generate_maze(4, 3, MazeType::Kruskal, MazeWrap::None, 4).unwrap();
+    let mut maze = Grid::new(19, 14, MazeWrap::None, 4, MazeType::Wilson);
+    overlay_maze(&mut maze, &coarse, scale);
+    let (maze, _) = carve_maze(maze, &Progress::hidden());
+    cases.insert(
+        "png-overlay".to_string(),
+        render(|out| render_png(&maze, &opts, out)),
+    );
+
+    for pattern in ["spiral", "hilbert", "lsystem:F:F=F+F-F-F+F:3"] {
+        let pattern: Pattern = pattern.parse().unwrap();
+        let mut maze = Grid::new(15, 11, MazeWrap::None, 7, MazeType::Wilson);
+        carve_pattern(&mut maze, &pattern);
+        let (maze, _) = carve_maze(maze, &Progress::hidden());
+        let name = pattern.to_string();
+        let name = name.split(':').next().unwrap();
+        cases.insert(
+            format!("png-pattern-{}", name),
+            render(|out| render_png(&maze, &opts, out)),
+        );
+    }
+
+    cases
+}
+
+#[test]
+fn renders_match_goldens() {
+    let cases = render_cases();
+
+    if std::env::var_os("UPDATE_GOLDENS").is_some() {
+        let text: String = cases
+            .iter()
+            .map(|(name, hash)| format!("{} {:016x}\n", name, hash))
+            .collect();
+        fs::create_dir_all(concat!(env!("CARGO_MANIFEST_DIR"), "/testdata")).unwrap();
+        fs::write(GOLDENS_PATH, text).unwrap();
+        return;
+    }
+
+    let goldens: BTreeMap<String, u64> = fs::read_to_string(GOLDENS_PATH)
+        .expect("missing goldens, create them with UPDATE_GOLDENS=1 cargo test goldens")
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .map(|(name, hash)| (name.to_string(), u64::from_str_radix(hash, 16).unwrap()))
+        .collect();
+
+    let changed: Vec<&String> = cases
+        .iter()
+        .filter(|(name, hash)| goldens.get(*name) != Some(hash))
+        .map(|(name, _)| name)
+        .collect();
+    assert!(
+        changed.is_empty(),
+        "rendered output differs from the goldens for {:?}",
+        changed
+    );
+}
@@ -0,0 +1,132 @@
+use crate::{
+    error::LabgenError,
+    image::{add_rulers, draw_maze, image_size, png_text, write_output, ImageOptions},
+    maze::{Grid, Point},
+};
+use std::io::Write;
+
+// colors spread evenly from the lowest to the highest value, close to the viridis map so the
+// lightness rises steadily and reads the same for most kinds of color blindness
+const COLORMAP: [[u8; 3]; 5] = [
+    [68, 1, 84],
+    [59, 82, 139],
+    [33, 145, 140],
+    [94, 201, 98],
+    [253, 231, 37],
+];
+
+// color of t between 0 and 1, blended between the two nearest colors of the map
+pub fn colormap(t: f32) -> [u8; 3] {
+    let at = t.clamp(0.0, 1.0) * (COLORMAP.len() - 1) as f32;
+    let i = (at as usize).min(COLORMAP.len() - 2);
+    let s = at - i as f32;
+    [0, 1, 2].map(|c| {
+        let (a, b) = (COLORMAP[i][c] as f32, COLORMAP[i + 1][c] as f32);
+        (a + (b - a) * s).round() as u8
+    })
+}
+
+// value of every pixel of the image without rulers that belongs to a cell or to the passage
+// between two cells, which takes the average of both so the colors run smoothly along it.
+// pixels of the outer wall take the value of the cell next to them
+fn pixel_values(maze: &Grid, values: &[Option<u32>], opts: &ImageOptions) -> Vec<Option<f32>> {
+    let layout = opts.layout();
+    let (width, height) = layout.image_size(maze.width, maze.height).unwrap();
+    let cell_width = layout.cell_width().unwrap();
+    let value = |x: u32, y: u32| values[maze.get_index(Point::new(x as i32, y as i32))];
+    // cell covering a pixel along one axis, with the cell before it when the pixel is in the
+    // wall between them
+    let cells = |p: u16, cells: u32| {
+        let cell = (p / cell_width) as u32;
+        let before =
+            (p % cell_width < layout.wall_width && cell > 0 && cell < cells).then(|| cell - 1);
+        (cell.min(cells - 1), before)
+    };
+
+    let mut pixels = Vec::with_capacity(width as usize * height as usize);
+    for y in 0..height {
+        let (row, above) = cells(y, maze.height);
+        for x in 0..width {
+            let (column, left) = cells(x, maze.width);
+            let neighbor = match (left, above) {
+                (Some(left), _) => value(left, row),
+                (None, Some(above)) => value(column, above),
+                (None, None) => None,
+            };
+            pixels.push(match (value(column, row), neighbor) {
+                (Some(a), Some(b)) => Some((a + b) as f32 / 2.0),
+                (a, b) => a.or(b).map(|v| v as f32),
+            });
+        }
+    }
+    pixels
+}
+
+// rgb image of the maze with every passage colored by its value along COLORMAP, from the lowest
+// to the highest value of the maze. cells without a value, walls, labels and the solution keep
+// the colors of the png image. an alpha channel is only added when excluded cells are transparent
+pub fn render_distance_map(
+    maze: &Grid,
+    values: &[Option<u32>],
+    opts: &ImageOptions,
+    out: &mut dyn Write,
+) -> Result<(), LabgenError> {
+    let (width, height) = image_size(maze, &opts.layout())?;
+    let pixels = draw_maze(maze, opts, |_| 1)?;
+    let mut pixel_values = pixel_values(maze, values, opts);
+    let (pixels, new_width, new_height) = add_rulers(pixels, width, height, maze, opts)?;
+    if new_width != width {
+        // rulers move the maze to the bottom right of the larger image
+        let (left, top) = ((new_width - width) as usize, (new_height - height) as usize);
+        let mut moved = vec![None; new_width as usize * new_height as usize];
+        for (y, row) in pixel_values.chunks(width as usize).enumerate() {
+            let start = left + (y + top) * new_width as usize;
+            moved[start..start + width as usize].copy_from_slice(row);
+        }
+        pixel_values = moved;
+    }
+
+    let (min, max) = values
+        .iter()
+        .flatten()
+        .fold((u32::MAX, 0), |(lo, hi), v| (lo.min(*v), hi.max(*v)));
+    let span = max.saturating_sub(min).max(1) as f32;
+    let palette = opts.palette();
+    let transparent = opts.transparent();
+    let channels = if transparent.is_some() { 4 } else { 3 };
+    let mut data: Vec<u8> = Vec::with_capacity(pixels.len() * channels);
+    for (i, value) in pixels.iter().zip(&pixel_values) {
+        let rgb = match value {
+            Some(v) if *i == 1 => colormap((v - min as f32) / span),
+            _ => [0, 1, 2].map(|c| palette[*i as usize * 3 + c]),
+        };
+        data.extend(rgb);
+        if transparent.is_some() {
+            data.push(if Some(*i) == transparent { 0 } else { 0xFF });
+        }
+    }
+
+    let mut encoder = png::Encoder::new(out, new_width as u32, new_height as u32);
+    encoder.set_color(if transparent.is_some() {
+        png::ColorType::Rgba
+    } else {
+        png::ColorType::Rgb
+    });
+    for (key, value) in png_text(maze, opts) {
+        encoder.add_text_chunk(key.to_string(), value)?;
+    }
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&data)?;
+    writer.finish()?;
+    Ok(())
+}
+
+pub fn generate_distance_map(
+    maze: &Grid,
+    values: &[Option<u32>],
+    opts: &ImageOptions,
+) -> Result<(), LabgenError> {
+    write_output(&opts.file_path, "png", |out| {
+        render_distance_map(maze, values, opts, out)
+    })
+}
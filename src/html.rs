@@ -0,0 +1,392 @@
+use crate::{
+    error::LabgenError,
+    image::{accessible_text, write_output, ExcludeStyle, ImageOptions},
+    maze::{Grid, Point},
+};
+use serde::Serialize;
+use std::io::Write;
+
+// the maze as embedded in the page. cells holds one character per cell row by row from the top
+// left, the hex digit of its connections or - for removed cells. colors are css hex colors, the
+// excluded color is null when excluded cells are transparent
+#[derive(Debug, Serialize)]
+struct PageMaze {
+    width: u32,
+    height: u32,
+    wrap: [bool; 2],
+    passage: u16,
+    wall: u16,
+    start: [i32; 2],
+    end: [i32; 2],
+    colors: PageColors,
+    cells: String,
+}
+
+#[derive(Debug, Serialize)]
+struct PageColors {
+    wall: String,
+    passage: String,
+    excluded: Option<String>,
+    player: String,
+    end: String,
+}
+
+// text that can be placed in an element or in a quoted attribute
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn css_color(palette: &[u8], index: usize) -> String {
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        palette[index * 3],
+        palette[index * 3 + 1],
+        palette[index * 3 + 2]
+    )
+}
+
+// standalone page drawing the maze on a canvas, where a dot is walked from start to end with the
+// arrow or wasd keys. passages across wrapped edges can be walked through. hatched excluded cells
+// are filled with the exclude color. the canvas carries the title and description of the png
+// image for screen readers
+pub fn render_html(
+    maze: &Grid,
+    opts: &ImageOptions,
+    start: Point,
+    end: Point,
+    out: &mut dyn Write,
+) -> Result<(), LabgenError> {
+    let palette = opts.palette();
+    let excluded = match opts.exclude_style {
+        ExcludeStyle::Wall => Some(css_color(&palette, 0)),
+        ExcludeStyle::Color | ExcludeStyle::Hatch => Some(css_color(&palette, 2)),
+        ExcludeStyle::Transparent => None,
+    };
+    let cells = maze
+        .tiles
+        .iter()
+        .enumerate()
+        .map(|(i, tile)| {
+            let pt = Point::new(
+                (i % maze.width as usize) as i32,
+                (i / maze.width as usize) as i32,
+            );
+            if maze.is_removed(pt) {
+                '-'
+            } else {
+                char::from_digit(tile.connections() as u32 & 0b1111, 16).unwrap()
+            }
+        })
+        .collect();
+    let page = PageMaze {
+        width: maze.width,
+        height: maze.height,
+        wrap: [maze.wrap.horizontal(), maze.wrap.vertical()],
+        passage: opts.passage_width,
+        wall: opts.wall_width,
+        start: [start.x, start.y],
+        end: [end.x, end.y],
+        colors: PageColors {
+            wall: css_color(&palette, 0),
+            passage: css_color(&palette, 1),
+            excluded,
+            player: css_color(&palette, 3),
+            end: css_color(&palette, 4),
+        },
+        cells,
+    };
+
+    // the blob only holds numbers, booleans and strings of hex digits, it can not end the script
+    let blob = serde_json::to_string(&page).map_err(std::io::Error::from)?;
+    // a solution drawn into the other exports is kept out of sight here, where the path is walked,
+    // as a json array of x,y cells for programs checking the puzzle
+    let solution = match &opts.solution {
+        Some(solution) => {
+            let path: Vec<[i32; 2]> = solution.path.iter().map(|pt| [pt.x, pt.y]).collect();
+            format!(
+                "<script type=\"application/json\" id=\"solution\">{}</script>\n",
+                serde_json::to_string(&path).map_err(std::io::Error::from)?
+            )
+        }
+        None => String::new(),
+    };
+    let (title, description) = accessible_text(maze, opts);
+    write!(
+        out,
+        "{}",
+        PAGE.replace("{title}", &escape(&title))
+            .replace("{description}", &escape(&description))
+            .replace("{comment}", &maze.metadata().comment())
+            .replace("{solution}", &solution)
+            .replace("{maze}", &blob)
+    )?;
+    Ok(())
+}
+
+pub fn generate_html(
+    maze: &Grid,
+    opts: &ImageOptions,
+    start: Point,
+    end: Point,
+) -> Result<(), LabgenError> {
+    write_output(&opts.file_path, "html", |out| {
+        render_html(maze, opts, start, end, out)
+    })
+}
+
+// the canvas is drawn like the png image, zoomed by the largest whole factor that fits the window
+const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<meta name="description" content="{description}">
+<!--
+{comment}
+-->
+<style>
+body { margin: 0; padding: 16px; font-family: sans-serif; text-align: center; }
+canvas { image-rendering: pixelated; }
+</style>
+</head>
+<body>
+<canvas id="maze" role="img" aria-label="{title}" aria-describedby="description"></canvas>
+<p id="description" hidden>{description}</p>
+<p id="status"></p>
+{solution}<script>
+const maze = {maze};
+const canvas = document.getElementById("maze");
+const message = document.getElementById("status");
+const ctx = canvas.getContext("2d");
+const cell = maze.passage + maze.wall;
+const width = maze.width * cell + maze.wall;
+const height = maze.height * cell + maze.wall;
+const zoom = Math.max(
+  1,
+  Math.floor(Math.min((innerWidth - 32) / width, (innerHeight - 96) / height)),
+);
+canvas.width = width * zoom;
+canvas.height = height * zoom;
+
+const removed = (x, y) =>
+  x >= 0 && y >= 0 && x < maze.width && y < maze.height && maze.cells[x + y * maze.width] === "-";
+const connections = (x, y) => parseInt(maze.cells[x + y * maze.width], 16);
+
+function fill(color, left, top, w, h) {
+  if (color === null) {
+    ctx.clearRect(left * zoom, top * zoom, w * zoom, h * zoom);
+  } else {
+    ctx.fillStyle = color;
+    ctx.fillRect(left * zoom, top * zoom, w * zoom, h * zoom);
+  }
+}
+
+// the passage of a cell, or of a cell extended through its wall in a direction
+function cellRect(color, x, y, dx, dy) {
+  const left = x * cell + maze.wall;
+  const top = y * cell + maze.wall;
+  if (dx === 0 && dy === 0) fill(color, left, top, maze.passage, maze.passage);
+  if (dx === 1) fill(color, left + maze.passage, top, maze.wall, maze.passage);
+  if (dx === -1) fill(color, left - maze.wall, top, maze.wall, maze.passage);
+  if (dy === 1) fill(color, left, top + maze.passage, maze.passage, maze.wall);
+  if (dy === -1) fill(color, left, top - maze.wall, maze.passage, maze.wall);
+}
+
+function drawCell(x, y, color) {
+  const open = connections(x, y);
+  cellRect(color, x, y, 0, 0);
+  if (open & 2) cellRect(color, x, y, 1, 0);
+  if (open & 4) cellRect(color, x, y, 0, 1);
+  if (x === 0 && open & 8) cellRect(color, x, y, -1, 0);
+  if (y === 0 && open & 1) cellRect(color, x, y, 0, -1);
+}
+
+function drawMaze() {
+  fill(maze.colors.wall, 0, 0, width, height);
+  for (let y = 0; y < maze.height; y++) {
+    for (let x = 0; x < maze.width; x++) {
+      if (removed(x, y)) {
+        // excluded regions are one block, including the walls between their cells
+        const color = maze.colors.excluded;
+        cellRect(color, x, y, 0, 0);
+        if (removed(x + 1, y)) cellRect(color, x, y, 1, 0);
+        if (removed(x, y + 1)) cellRect(color, x, y, 0, 1);
+        if (removed(x + 1, y) && removed(x, y + 1) && removed(x + 1, y + 1)) {
+          fill(color, (x + 1) * cell, (y + 1) * cell, maze.wall, maze.wall);
+        }
+      } else {
+        drawCell(x, y, maze.colors.passage);
+      }
+    }
+  }
+  cellRect(maze.colors.end, maze.end[0], maze.end[1], 0, 0);
+}
+
+let player = [...maze.start];
+let moves = 0;
+
+function drawPlayer() {
+  const [x, y] = player;
+  const radius = (maze.passage * zoom) / 2;
+  ctx.fillStyle = maze.colors.player;
+  ctx.beginPath();
+  ctx.arc(
+    (x * cell + maze.wall) * zoom + radius,
+    (y * cell + maze.wall) * zoom + radius,
+    radius * 0.8,
+    0,
+    2 * Math.PI,
+  );
+  ctx.fill();
+}
+
+function showStatus() {
+  const solved = player[0] === maze.end[0] && player[1] === maze.end[1];
+  message.textContent = solved
+    ? `solved in ${moves} moves, press r to play again`
+    : "walk to the marked cell with the arrow keys or wasd";
+}
+
+// direction bits and steps of the keys
+const keys = {
+  ArrowUp: [1, 0, -1], w: [1, 0, -1],
+  ArrowRight: [2, 1, 0], d: [2, 1, 0],
+  ArrowDown: [4, 0, 1], s: [4, 0, 1],
+  ArrowLeft: [8, -1, 0], a: [8, -1, 0],
+};
+
+document.addEventListener("keydown", (e) => {
+  if (e.key === "r") {
+    player = [...maze.start];
+    moves = 0;
+    drawMaze();
+    drawPlayer();
+    showStatus();
+    return;
+  }
+  const key = keys[e.key];
+  if (!key) return;
+  e.preventDefault();
+  const [bit, dx, dy] = key;
+  let [x, y] = [player[0] + dx, player[1] + dy];
+  if (maze.wrap[0]) x = (x + maze.width) % maze.width;
+  if (maze.wrap[1]) y = (y + maze.height) % maze.height;
+  // openings lead out of the grid, but there is nothing to walk to
+  if (!(connections(...player) & bit) || x < 0 || y < 0 || x >= maze.width || y >= maze.height) {
+    return;
+  }
+
+  const [px, py] = player;
+  const atEnd = px === maze.end[0] && py === maze.end[1];
+  cellRect(atEnd ? maze.colors.end : maze.colors.passage, px, py, 0, 0);
+  player = [x, y];
+  moves++;
+  drawPlayer();
+  showStatus();
+});
+
+drawMaze();
+drawPlayer();
+showStatus();
+</script>
+</body>
+</html>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        maze::{generate_maze, ConnectionStatus, MazeType, MazeWrap},
+        solver::{solve, SolverType},
+    };
+
+    // the maze object the script of a page starts from
+    fn page_maze(page: &str) -> serde_json::Value {
+        let start = page.find("const maze = ").unwrap() + "const maze = ".len();
+        let end = start + page[start..].find(";\n").unwrap();
+        serde_json::from_str(&page[start..end]).unwrap()
+    }
+
+    #[test]
+    fn pages_hold_every_cell() {
+        let (mut maze, _) = generate_maze(4, 3, MazeType::Wilson, MazeWrap::None, 5).unwrap();
+        maze.get_tile_mut(Point::new(3, 2))
+            .set_status(ConnectionStatus::Removed);
+        let mut out = Vec::new();
+        let end = Point::new(3, 1);
+        render_html(
+            &maze,
+            &ImageOptions::default(),
+            Point::new(0, 0),
+            end,
+            &mut out,
+        )
+        .unwrap();
+        let page = String::from_utf8(out).unwrap();
+        assert!(page.contains("<title>4x3 wilson maze</title>"));
+
+        let blob = page_maze(&page);
+        assert_eq!(blob["width"], 4);
+        assert_eq!(blob["end"], serde_json::json!([3, 1]));
+        let cells = blob["cells"].as_str().unwrap();
+        assert_eq!(cells.len(), 12);
+        assert!(cells.ends_with('-'));
+        for (i, c) in cells.chars().take(11).enumerate() {
+            assert_eq!(c.to_digit(16), Some(maze.tiles[i].connections() as u32));
+        }
+    }
+
+    #[test]
+    fn solutions_are_hidden_in_the_page() {
+        let (maze, _) = generate_maze(3, 3, MazeType::Wilson, MazeWrap::None, 5).unwrap();
+        let (start, end) = (Point::new(0, 0), Point::new(2, 2));
+        let solution = solve(&maze, start, end, SolverType::Bfs).unwrap();
+        let opts = ImageOptions {
+            solution: Some(solution.clone()),
+            ..ImageOptions::default()
+        };
+        let mut out = Vec::new();
+        render_html(&maze, &opts, start, end, &mut out).unwrap();
+        let page = String::from_utf8(out).unwrap();
+        assert!(page.contains("<p id=\"description\" hidden>A maze of 3 columns and 3 rows"));
+        assert!(page.contains("from cell A1 to cell C3.</p>"));
+
+        let start = page
+            .find("<script type=\"application/json\" id=\"solution\">")
+            .unwrap();
+        let blob = &page[page[start..].find('>').unwrap() + start + 1..];
+        let blob = &blob[..blob.find("</script>").unwrap()];
+        let path: Vec<[i32; 2]> = serde_json::from_str(blob).unwrap();
+        let expected: Vec<[i32; 2]> = solution.path.iter().map(|pt| [pt.x, pt.y]).collect();
+        assert_eq!(path, expected);
+
+        let mut out = Vec::new();
+        render_html(
+            &maze,
+            &ImageOptions::default(),
+            Point::new(0, 0),
+            end,
+            &mut out,
+        )
+        .unwrap();
+        assert!(!String::from_utf8(out).unwrap().contains("id=\"solution\""));
+    }
+
+    #[test]
+    fn transparent_excluded_cells_have_no_color() {
+        let (maze, _) = generate_maze(2, 2, MazeType::Wilson, MazeWrap::None, 5).unwrap();
+        let opts = ImageOptions {
+            exclude_style: ExcludeStyle::Transparent,
+            ..ImageOptions::default()
+        };
+        let mut out = Vec::new();
+        render_html(&maze, &opts, Point::new(0, 0), Point::new(1, 1), &mut out).unwrap();
+        let blob = page_maze(&String::from_utf8(out).unwrap());
+        assert_eq!(blob["colors"]["excluded"], serde_json::Value::Null);
+        assert_eq!(blob["colors"]["wall"], "#000000");
+    }
+}
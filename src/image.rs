@@ -1,6 +1,25 @@
-use crate::maze::{ConnectionStatus, Direction, Grid, Point};
-use gif::{DisposalMethod, Encoder, Frame, Repeat};
-use std::{borrow::Cow, fs::File, io::BufWriter, path::Path};
+use crate::{
+    error::LabgenError,
+    font::{draw_text, text_width, GLYPH_HEIGHT},
+    layout::{PixelLayout, PixelRect},
+    maze::{noise_map, CellLabel, Direction, Grid, MazeAction, MazeHistory, MazeMetadata, Point},
+    polar::PolarGrid,
+    progress::{CancelToken, Progress, ProgressFn},
+    solver::{Solution, SolveStep},
+};
+use clap::ValueEnum;
+use gif::{AnyExtension, DisposalMethod, Encoder, Frame, Repeat};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{
+    borrow::Cow,
+    f32::consts::TAU,
+    fmt,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+    str::FromStr,
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct ImageOptions {
@@ -8,234 +27,1988 @@ pub struct ImageOptions {
     pub passage_width: u16,
     pub wall_width: u16,
     pub color_map: [u8; 6],
+    pub exclude_style: ExcludeStyle,
+    pub exclude_color: Color,
+    // solver run drawn over the maze, images show its path while animations first play back
+    // the search once the maze is finished
+    pub solution: Option<Solution>,
+    // text drawn on top of cells once the maze is finished
+    pub labels: Vec<CellLabel>,
+    // column letters and row numbers in a margin above and left of images
+    pub rulers: bool,
+    // colors of the lowest and highest value of gradients
+    pub gradient: [Color; 2],
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+// the defaults of the command line
+impl Default for ImageOptions {
+    fn default() -> Self {
+        Self {
+            file_path: String::new(),
+            passage_width: 4,
+            wall_width: 1,
+            color_map: [0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF],
+            exclude_style: ExcludeStyle::Color,
+            exclude_color: Color([0x80, 0x80, 0x80]),
+            solution: None,
+            labels: Vec::new(),
+            rulers: false,
+            gradient: [Color([0x20, 0x40, 0xFF]), Color([0xFF, 0x40, 0x20])],
+        }
+    }
+}
+
+impl ImageOptions {
+    pub fn layout(&self) -> PixelLayout {
+        PixelLayout::new(self.passage_width, self.wall_width)
+    }
+
+    // wall and passage colors followed by the colors of excluded cells and of the solver
+    pub(crate) fn palette(&self) -> Vec<u8> {
+        let mut palette = self.color_map.to_vec();
+        palette.extend_from_slice(&self.exclude_color.0);
+        palette.extend_from_slice(&SOLVE_COLORS);
+        palette
+    }
+
+    fn solution_path(&self) -> &[Point] {
+        self.solution.as_ref().map_or(&[], |s| &s.path)
+    }
+
+    // palette index that is drawn transparent, if any
+    pub(crate) fn transparent(&self) -> Option<u8> {
+        (self.exclude_style == ExcludeStyle::Transparent).then_some(EXCLUDED)
+    }
+}
+
+// file format of generation animations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum AnimationFormat {
+    Gif,
+    /// animated png, shown as the finished maze by viewers without animation support
+    Apng,
+    /// animated svg that scales without blurring, for web pages
+    Svg,
+    /// h.264 video encoded by ffmpeg, which has to be installed
+    Mp4,
+    /// vp9 video encoded by ffmpeg, which has to be installed
+    Webm,
+}
+
+impl AnimationFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            AnimationFormat::Gif => "gif",
+            AnimationFormat::Apng => "png",
+            AnimationFormat::Svg => "svg",
+            AnimationFormat::Mp4 => "mp4",
+            AnimationFormat::Webm => "webm",
+        }
+    }
+}
+
+// file format of still images
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum ImageFormat {
+    Png,
+    /// the cells and their connections, to be read back with --load or by other programs
+    Json,
+    /// graphviz graph of the passages between cells, pinned at their coordinates
+    Dot,
+    /// 3d mesh of the walls standing on a base plate, for printing
+    Stl,
+    /// pages to print, with the solution on a second page when solved
+    Pdf,
+    /// web page where the maze is walked with the arrow keys
+    Html,
+    /// colored text printed to the terminal, animated in place with --animate where space pauses,
+    /// the arrow keys step through the frames and [ and ] change the speed
+    Ansi,
+    /// plain text of braille characters, each showing 2 by 4 pixels
+    Braille,
+    /// plain text of the walls drawn with the characters of --text-style
+    Text,
+    /// rgb png of the passages colored by their distance from --distance-from or the entrance
+    DistanceMap,
+}
+
+impl ImageFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Png | ImageFormat::DistanceMap => "png",
+            ImageFormat::Json => "json",
+            ImageFormat::Dot => "dot",
+            ImageFormat::Stl => "stl",
+            ImageFormat::Pdf => "pdf",
+            ImageFormat::Html => "html",
+            ImageFormat::Ansi => "ans",
+            ImageFormat::Braille | ImageFormat::Text => "txt",
+        }
+    }
+}
+
+impl fmt::Display for ImageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.to_possible_value().unwrap().get_name().fmt(f)
+    }
+}
+
+// how cells removed from the maze are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum ExcludeStyle {
+    /// fill with the wall color
+    Wall,
+    /// fill with the exclude color
+    Color,
+    /// diagonal stripes of the exclude color over the wall color
+    Hatch,
+    /// leave a transparent hole
+    Transparent,
+}
+
+// rgb color written as a hex triplet such as 808080, an optional leading # is accepted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
+pub struct Color(pub [u8; 3]);
+
+impl FromStr for Color {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.strip_prefix('#').unwrap_or(s);
+        if hex.len() != 6 || !hex.is_ascii() {
+            return Err(format!("invalid color \"{}\": expected RRGGBB", s));
+        }
+
+        let mut color = [0; 3];
+        for (i, c) in color.iter_mut().enumerate() {
+            *c = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)
+                .map_err(|e| format!("invalid color \"{}\": {}", s, e))?;
+        }
+        Ok(Color(color))
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02x}{:02x}{:02x}", self.0[0], self.0[1], self.0[2])
+    }
+}
+
+impl TryFrom<String> for Color {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Color> for String {
+    fn from(color: Color) -> Self {
+        color.to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct AnimationOptions {
     pub frame_time: u16,
     pub pause_time: u16,
     pub batch_size: u16,
+    // show a bar while encoding long animations
+    pub progress: bool,
+    // told about the frames written while encoding, with or without the bar
+    pub on_progress: Option<ProgressFn>,
+    // ends the animation early on the frame it reached
+    pub cancel: Option<CancelToken>,
+    // frames that change no pixels lengthen the frame before them instead of being written
+    pub skip_unchanged: bool,
+}
+
+// the defaults of the command line
+impl Default for AnimationOptions {
+    fn default() -> Self {
+        Self {
+            frame_time: 2,
+            pause_time: 100,
+            batch_size: 1,
+            progress: false,
+            on_progress: None,
+            cancel: None,
+            skip_unchanged: false,
+        }
+    }
+}
+
+pub(crate) fn encoding_progress(ani_opts: &AnimationOptions, frames: u64) -> Progress {
+    let progress = match (ani_opts.progress, &ani_opts.on_progress) {
+        (true, Some(callback)) => Progress::new("encoding", frames).report_to(callback.clone()),
+        (true, None) => Progress::new("encoding", frames),
+        (false, Some(callback)) => Progress::with_callback(frames, callback.clone()),
+        (false, None) => Progress::hidden(),
+    };
+    match &ani_opts.cancel {
+        Some(cancel) => progress.cancel_on(cancel.clone()),
+        None => progress,
+    }
+}
+
+// opens the file the image is written to, a path of "-" writes the encoded image to stdout
+fn output_writer(file_path: &str, extension: &str) -> Result<Box<dyn Write>, LabgenError> {
+    if file_path == "-" {
+        Ok(Box::new(BufWriter::new(io::stdout().lock())))
+    } else {
+        let path = format!("{}.{}", file_path, extension);
+        // missing directories on the way to the file are created
+        if let Some(parent) = Path::new(&path).parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!("could not create directory {}: {}", parent.display(), e),
+                )
+            })?;
+        }
+        let file = File::create(&path)
+            .map_err(|e| io::Error::new(e.kind(), format!("could not create {}: {}", path, e)))?;
+        Ok(Box::new(BufWriter::new(file)))
+    }
+}
+
+// renders into the output file, the render_* functions can also be used with in memory buffers
+pub(crate) fn write_output(
+    file_path: &str,
+    extension: &str,
+    render: impl FnOnce(&mut dyn Write) -> Result<(), LabgenError>,
+) -> Result<(), LabgenError> {
+    let mut output = output_writer(file_path, extension)?;
+    render(&mut output)?;
+    output.flush()?;
+    Ok(())
 }
 
 pub fn generate_gif_uncompressed(
     maze: &Grid,
-    history: &[(Point, Direction)],
+    history: &[MazeAction],
     opts: &ImageOptions,
     ani_opts: &AnimationOptions,
-) {
-    let cell_width: u16 = opts.passage_width + opts.wall_width;
+) -> Result<(), LabgenError> {
+    write_output(&opts.file_path, "gif", |out| {
+        render_gif_uncompressed(maze, history, opts, ani_opts, out)
+    })
+}
+
+pub fn generate_gif_compare(
+    panels: &[ComparePanel],
+    opts: &ImageOptions,
+    ani_opts: &AnimationOptions,
+) -> Result<(), LabgenError> {
+    write_output(&opts.file_path, "gif", |out| {
+        render_gif_compare(panels, opts, ani_opts, out)
+    })
+}
+
+// written next to the animation, with -keyframes added to its name
+pub fn generate_keyframes(
+    maze: &Grid,
+    history: &[MazeAction],
+    count: u16,
+    opts: &ImageOptions,
+) -> Result<(), LabgenError> {
+    let file_path = format!("{}-keyframes", opts.file_path);
+    write_output(&file_path, "png", |out| {
+        render_keyframes(maze, history, count, opts, out)
+    })
+}
+
+pub fn generate_apng(
+    maze: &Grid,
+    history: &[MazeAction],
+    opts: &ImageOptions,
+    ani_opts: &AnimationOptions,
+) -> Result<(), LabgenError> {
+    write_output(&opts.file_path, "png", |out| {
+        render_apng(maze, history, opts, ani_opts, out)
+    })
+}
+
+pub fn generate_gif(
+    maze: &Grid,
+    history: &[MazeAction],
+    opts: &ImageOptions,
+    ani_opts: &AnimationOptions,
+) -> Result<(), LabgenError> {
+    write_output(&opts.file_path, "gif", |out| {
+        render_gif(maze, history, opts, ani_opts, out)
+    })
+}
 
-    let (width, height) = (
-        maze.width * cell_width + opts.wall_width,
-        maze.height * cell_width + opts.wall_width,
+pub fn generate_gif_solve(
+    maze: &Grid,
+    solution: &Solution,
+    opts: &ImageOptions,
+    ani_opts: &AnimationOptions,
+) -> Result<(), LabgenError> {
+    write_output(&opts.file_path, "gif", |out| {
+        render_gif_solve(maze, solution, opts, ani_opts, out)
+    })
+}
+
+pub fn generate_png(maze: &Grid, opts: &ImageOptions) -> Result<(), LabgenError> {
+    write_output(&opts.file_path, "png", |out| render_png(maze, opts, out))
+}
+
+pub fn generate_png_polar(maze: &PolarGrid, opts: &ImageOptions) -> Result<(), LabgenError> {
+    write_output(&opts.file_path, "png", |out| {
+        render_png_polar(maze, opts, out)
+    })
+}
+
+pub fn generate_png_gradient(
+    maze: &Grid,
+    values: &[Option<u32>],
+    opts: &ImageOptions,
+) -> Result<(), LabgenError> {
+    write_output(&opts.file_path, "png", |out| {
+        render_png_gradient(maze, values, opts, out)
+    })
+}
+
+pub fn generate_png_tapered(
+    maze: &Grid,
+    values: &[Option<u32>],
+    min_width: u16,
+    opts: &ImageOptions,
+) -> Result<(), LabgenError> {
+    write_output(&opts.file_path, "png", |out| {
+        render_png_tapered(maze, values, min_width, opts, out)
+    })
+}
+
+// stores the generation parameters in a gif comment extension
+fn write_gif_metadata<W: Write>(
+    encoder: &mut Encoder<W>,
+    metadata: &MazeMetadata,
+) -> Result<(), LabgenError> {
+    encoder.write_raw_extension(AnyExtension(0xFE), &[metadata.comment().as_bytes()])?;
+    Ok(())
+}
+
+pub(crate) fn image_size(maze: &Grid, layout: &PixelLayout) -> Result<(u16, u16), LabgenError> {
+    let cell_width = cell_width(layout)?;
+    layout.image_size(maze.width, maze.height).ok_or_else(|| {
+        LabgenError::Render(format!(
+            "a {}x{} maze with {} pixel cells is larger than {} pixels, png images can be \
+            split into tiles with --tiles",
+            maze.width,
+            maze.height,
+            cell_width,
+            u16::MAX
+        ))
+    })
+}
+
+// pixels of a cell with its wall, which must fit in a u16 whatever the size of the maze
+pub(crate) fn cell_width(layout: &PixelLayout) -> Result<u16, LabgenError> {
+    layout.cell_width().ok_or_else(|| {
+        LabgenError::Render(format!(
+            "passages of {} pixels with walls of {} pixels make cells larger than {} pixels",
+            layout.passage_width,
+            layout.wall_width,
+            u16::MAX
+        ))
+    })
+}
+
+// rect covered by a cell and its passage in the given direction, the cell is known to be inside
+// an image whose size was already checked by image_size
+pub(crate) fn cell_rect(layout: &PixelLayout, pt: Point, dir: Direction) -> PixelRect {
+    layout.cell_to_pixel_rect(pt, dir).unwrap()
+}
+
+// holds back the latest frame of an animation, so a following frame that changes nothing can add
+// its delay to it instead of being written
+struct PendingFrame(Option<Frame<'static>>);
+
+impl PendingFrame {
+    fn push(
+        &mut self,
+        encoder: &mut Encoder<&mut dyn Write>,
+        frame: Frame<'static>,
+        unchanged: bool,
+    ) -> Result<(), LabgenError> {
+        match &mut self.0 {
+            Some(pending) if unchanged => pending.delay = pending.delay.saturating_add(frame.delay),
+            _ => {
+                if let Some(pending) = self.0.replace(frame) {
+                    encoder.write_frame(&pending)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // whether frame shows the same pixels as the held back frame
+    fn shows(&self, pixels: &[u8]) -> bool {
+        self.0.as_ref().is_some_and(|f| f.buffer[..] == pixels[..])
+    }
+
+    fn flush(&mut self, encoder: &mut Encoder<&mut dyn Write>) -> Result<(), LabgenError> {
+        if let Some(pending) = self.0.take() {
+            encoder.write_frame(&pending)?;
+        }
+        Ok(())
+    }
+}
+
+fn region_pixels(pixels: &[u8], image_width: u16, rect: PixelRect) -> Vec<u8> {
+    (rect.top..rect.top + rect.height)
+        .flat_map(|y| {
+            let start = rect.left as usize + y as usize * image_width as usize;
+            pixels[start..start + rect.width as usize].iter().copied()
+        })
+        .collect()
+}
+
+// frame of the part of the image inside rect, kept on top of the frames before it
+fn region_frame(pixels: &[u8], image_width: u16, rect: PixelRect, delay: u16) -> Frame<'static> {
+    let buffer = region_pixels(pixels, image_width, rect);
+    Frame {
+        width: rect.width,
+        height: rect.height,
+        top: rect.top,
+        left: rect.left,
+        delay,
+        dispose: DisposalMethod::Keep,
+        buffer: Cow::Owned(buffer),
+        ..Frame::default()
+    }
+}
+
+// copies a buffer covering rect back into the image
+fn fill_region(pixels: &mut [u8], image_width: u16, rect: PixelRect, buffer: &[u8]) {
+    for (row, y) in (rect.top..rect.top + rect.height).enumerate() {
+        let start = rect.left as usize + y as usize * image_width as usize;
+        let line = &buffer[row * rect.width as usize..(row + 1) * rect.width as usize];
+        pixels[start..start + rect.width as usize].copy_from_slice(line);
+    }
+}
+
+pub(crate) fn fill_rect(pixels: &mut [u8], image_width: u16, rect: PixelRect, color: u8) {
+    for y in rect.top..(rect.top + rect.height) {
+        for x in rect.left..(rect.left + rect.width) {
+            pixels[x as usize + (y as usize * image_width as usize)] = color;
+        }
+    }
+}
+
+// palette indices of excluded cells and of the solver, following the wall and passage colors of
+// the color map. the path found by the solver is drawn in the solution color, the frontier and
+// visited colors are only used while playing back the search
+const EXCLUDED: u8 = 2;
+const SOLUTION: u8 = 3;
+const FRONTIER: u8 = 4;
+const VISITED: u8 = 5;
+const SOLVE_COLORS: [u8; 9] = [0xFF, 0x30, 0x30, 0x40, 0xC0, 0xFF, 0xA0, 0xA0, 0xA0];
+
+// each cell of a path with the direction toward the previous cell, so filling cell_rect of every
+// entry draws the path including the passages between its cells
+fn path_steps(maze: &Grid, path: &[Point]) -> Vec<(Point, Direction)> {
+    path.iter()
+        .enumerate()
+        .map(|(i, pt)| {
+            let dir = match i {
+                0 => Direction::NoDir,
+                _ => Direction::ALL
+                    .into_iter()
+                    .find(|d| maze.travel(*pt, *d) == path[i - 1])
+                    .unwrap_or(Direction::NoDir),
+            };
+            (*pt, dir)
+        })
+        .collect()
+}
+
+// playback of a solver in three phases: cells entering the frontier, cells being closed, and
+// finally the reconstructed path from start to end
+pub(crate) fn solve_phases(maze: &Grid, solution: &Solution) -> Vec<(Point, Direction, u8)> {
+    let search = solution.steps.iter().map(|step| match *step {
+        SolveStep::Open(pt, dir) => (pt, dir, FRONTIER),
+        SolveStep::Close(pt, dir) => (pt, dir, VISITED),
+    });
+    let path = path_steps(maze, &solution.path)
+        .into_iter()
+        .map(|(pt, dir)| (pt, dir, SOLUTION));
+    search.chain(path).collect()
+}
+
+// turns the actions of a history into the pixels they change. a carve opens the cell and the
+// passage toward its neighbor, a wall closes the passage between two cells again and a cleared
+// rect opens its cells together with the passages between them. a scan highlights the cells of
+// its rect that are not in the maze yet, until the next action takes the highlight back
+pub(crate) struct ActionFills<'a> {
+    maze: &'a Grid,
+    layout: PixelLayout,
+    added: Vec<bool>,
+    highlighted: Vec<PixelRect>,
+}
+
+impl<'a> ActionFills<'a> {
+    pub(crate) fn new(maze: &'a Grid, layout: PixelLayout) -> Self {
+        Self {
+            maze,
+            layout,
+            added: vec![false; maze.tiles.len()],
+            highlighted: Vec::new(),
+        }
+    }
+
+    pub(crate) fn fills(&mut self, action: MazeAction) -> Vec<(PixelRect, u8)> {
+        let mut fills = self.finish();
+        let layout = &self.layout;
+        match action {
+            MazeAction::Carve(pt, dir) => {
+                if self.maze.contains(pt) {
+                    self.added[self.maze.get_index(pt)] = true;
+                }
+                fills.push((cell_rect(layout, pt, dir), 1));
+            }
+            MazeAction::Wall(pt, dir) => {
+                fills.push((layout.wall_to_pixel_rect(pt, dir).unwrap(), 0));
+            }
+            MazeAction::Clear(rect) => {
+                for pt in rect.cells() {
+                    self.added[self.maze.get_index(pt)] = true;
+                    let east = pt.x as u32 + 1 < rect.x + rect.width;
+                    let south = pt.y as u32 + 1 < rect.y + rect.height;
+                    for (dir, open) in [
+                        (Direction::NoDir, true),
+                        (Direction::East, east),
+                        (Direction::South, south),
+                    ] {
+                        if open {
+                            fills.push((cell_rect(layout, pt, dir), 1));
+                        }
+                    }
+                }
+            }
+            MazeAction::Scan(rect) => {
+                for pt in rect.cells() {
+                    if !self.maze.is_removed(pt) && !self.added[self.maze.get_index(pt)] {
+                        let cell = cell_rect(layout, pt, Direction::NoDir);
+                        self.highlighted.push(cell);
+                        fills.push((cell, FRONTIER));
+                    }
+                }
+            }
+        }
+        fills
+    }
+
+    // takes back the highlight of the last scan
+    pub(crate) fn finish(&mut self) -> Vec<(PixelRect, u8)> {
+        self.highlighted.drain(..).map(|rect| (rect, 0)).collect()
+    }
+}
+
+// every step of an animation as the pixels it fills, the generation followed by the solver when
+// the maze is solved
+pub(crate) fn animation_steps(
+    maze: &Grid,
+    history: &[MazeAction],
+    opts: &ImageOptions,
+) -> Vec<Vec<(PixelRect, u8)>> {
+    let layout = opts.layout();
+    let mut actions = ActionFills::new(maze, layout);
+    let mut steps: Vec<Vec<(PixelRect, u8)>> = history
+        .iter()
+        .map(|action| actions.fills(*action))
+        .collect();
+    let rest = actions.finish();
+    if !rest.is_empty() {
+        steps.push(rest);
+    }
+    let solve = opts
+        .solution
+        .as_ref()
+        .map_or(Vec::new(), |s| solve_phases(maze, s));
+    steps.extend(
+        solve
+            .into_iter()
+            .map(|(pt, dir, color)| vec![(cell_rect(&layout, pt, dir), color)]),
     );
+    steps
+}
 
-    let mut state: Vec<u8> = vec![0; width as usize * height as usize];
-    let mut image =
-        BufWriter::new(File::create(format!("{}.gif", &opts.file_path).as_str()).unwrap());
-    let mut encoder = Encoder::new(&mut image, width, height, &opts.color_map).unwrap();
-    encoder.set_repeat(Repeat::Infinite).unwrap();
+// smallest rect covering all pixels of a step, None for walls without pixels
+fn fills_rect(fills: &[(PixelRect, u8)]) -> Option<PixelRect> {
+    fills
+        .iter()
+        .map(|(rect, _)| *rect)
+        .filter(|rect| rect.width > 0 && rect.height > 0)
+        .reduce(PixelRect::union)
+}
 
-    let mut frame_num = 0;
-    for (pt, dir) in history {
-        let area_top: u16;
-        let area_left: u16;
-        let area_width: u16;
-        let area_height: u16;
+// a frame without changes still needs a pixel to hold its delay
+const DELAY_PIXEL: PixelRect = PixelRect {
+    left: 0,
+    top: 0,
+    width: 1,
+    height: 1,
+};
 
-        frame_num += 1;
+// draws text in the wall color centered on a passage sized square at left, top, at the largest
+// scale that still fits. text too long for the passage at the smallest scale spills over
+fn draw_label(
+    pixels: &mut [u8],
+    image_width: u16,
+    left: u16,
+    top: u16,
+    passage_width: u16,
+    text: &str,
+) {
+    let scale = (passage_width / text_width(text, 1).max(GLYPH_HEIGHT)).max(1);
+    let left = left + passage_width.saturating_sub(text_width(text, scale)) / 2;
+    let top = top + passage_width.saturating_sub(GLYPH_HEIGHT * scale) / 2;
+    draw_text(pixels, image_width, left, top, text, scale, 0);
+}
 
-        match dir {
-            Direction::NoDir => {
-                area_width = opts.passage_width;
-                area_height = opts.passage_width;
-                area_top = pt.y as u16 * cell_width + opts.wall_width;
-                area_left = pt.x as u16 * cell_width + opts.wall_width;
+// opens the outer wall wherever a passage leaves the grid. a passage carved across a wrapped edge
+// only appears on one side in the history, while images of the finished maze open the border
+// next to both of its cells. only wall pixels are filled, so openings the solver already colored
+// keep their color. returns the rects of the openings that were drawn
+pub(crate) fn draw_openings(
+    pixels: &mut [u8],
+    image_width: u16,
+    maze: &Grid,
+    opts: &ImageOptions,
+) -> Vec<PixelRect> {
+    let layout = opts.layout();
+    let mut opened = Vec::new();
+    for y in 0..maze.height {
+        for x in 0..maze.width {
+            let pt = Point::new(x as i32, y as i32);
+            if maze.is_removed(pt) {
+                continue;
             }
-            Direction::North => {
-                area_width = opts.passage_width;
-                area_height = cell_width;
-                area_top = pt.y as u16 * cell_width + 0;
-                area_left = pt.x as u16 * cell_width + opts.wall_width;
+            for dir in Direction::ALL {
+                if !maze.get_tile(pt).connected(dir) || maze.contains(pt.travel(dir)) {
+                    continue;
+                }
+                let rect = cell_rect(&layout, pt, dir);
+                let mut changed = false;
+                for py in rect.top..rect.top + rect.height {
+                    for px in rect.left..rect.left + rect.width {
+                        let pixel = &mut pixels[px as usize + py as usize * image_width as usize];
+                        if *pixel == 0 {
+                            *pixel = 1;
+                            changed = true;
+                        }
+                    }
+                }
+                if changed {
+                    opened.push(rect);
+                }
             }
-            Direction::East => {
-                area_width = cell_width;
-                area_height = opts.passage_width;
-                area_top = pt.y as u16 * cell_width + opts.wall_width;
-                area_left = pt.x as u16 * cell_width + opts.wall_width;
+        }
+    }
+    opened
+}
+
+// draws every label that lies inside the maze onto its cell
+pub(crate) fn draw_labels(pixels: &mut [u8], image_width: u16, maze: &Grid, opts: &ImageOptions) {
+    let layout = opts.layout();
+    for label in opts.labels.iter().filter(|l| maze.contains(l.pt)) {
+        let cell = cell_rect(&layout, label.pt, Direction::NoDir);
+        draw_label(
+            pixels,
+            image_width,
+            cell.left,
+            cell.top,
+            layout.passage_width,
+            &label.text,
+        );
+    }
+}
+
+// fills the area of removed cells in the given style. walls between two removed cells are
+// filled as well, so excluded regions are drawn as one block outlined by regular walls. the maze
+// is drawn with its top left corner at left, top
+pub(crate) fn draw_exclusions(
+    pixels: &mut [u8],
+    image_width: u16,
+    left: u16,
+    top: u16,
+    maze: &Grid,
+    opts: &ImageOptions,
+) {
+    let layout = opts.layout();
+    let removed = |x: u32, y: u32| {
+        x < maze.width && y < maze.height && maze.is_removed(Point::new(x as i32, y as i32))
+    };
+
+    let mut area = Vec::new();
+    for y in 0..maze.height {
+        for x in 0..maze.width {
+            if !removed(x, y) {
+                continue;
+            }
+            let pt = Point::new(x as i32, y as i32);
+            area.push(cell_rect(&layout, pt, Direction::NoDir));
+            if removed(x + 1, y) {
+                area.push(cell_rect(&layout, pt, Direction::East));
             }
-            Direction::South => {
-                area_width = opts.passage_width;
-                area_height = cell_width;
-                area_top = pt.y as u16 * cell_width + opts.wall_width;
-                area_left = pt.x as u16 * cell_width + opts.wall_width;
+            if removed(x, y + 1) {
+                area.push(cell_rect(&layout, pt, Direction::South));
             }
-            Direction::West => {
-                area_width = cell_width;
-                area_height = opts.passage_width;
-                area_top = pt.y as u16 * cell_width + opts.wall_width;
-                area_left = pt.x as u16 * cell_width + 0;
+            if removed(x + 1, y) && removed(x, y + 1) && removed(x + 1, y + 1) {
+                let cell = cell_rect(&layout, pt, Direction::NoDir);
+                area.push(PixelRect {
+                    left: cell.left + cell.width,
+                    top: cell.top + cell.height,
+                    width: layout.wall_width,
+                    height: layout.wall_width,
+                });
             }
         }
+    }
 
-        for y in area_top..(area_top + area_height) {
-            for x in area_left..(area_left + area_width) {
-                state[x as usize + (y as usize * width as usize)] = 1;
+    for rect in area {
+        let rect = rect.offset(left, top);
+        match opts.exclude_style {
+            ExcludeStyle::Wall => fill_rect(pixels, image_width, rect, 0),
+            ExcludeStyle::Color | ExcludeStyle::Transparent => {
+                fill_rect(pixels, image_width, rect, EXCLUDED)
+            }
+            ExcludeStyle::Hatch => {
+                for y in rect.top..(rect.top + rect.height) {
+                    for x in rect.left..(rect.left + rect.width) {
+                        pixels[x as usize + (y as usize * image_width as usize)] =
+                            if (x + y) % 4 < 2 { EXCLUDED } else { 0 };
+                    }
+                }
             }
         }
+    }
+}
 
-        // generate and save frame
-        if frame_num % ani_opts.batch_size == 0 {
-            let mut frame = Frame::default();
-            frame.width = width;
-            frame.height = height;
-            frame.delay = ani_opts.frame_time;
-            frame.buffer = Cow::Borrowed(&state);
-            encoder.write_frame(&frame).unwrap();
+// draws the generation one batch at a time and hands every full frame to show together with its
+// delay, the last one being the finished maze shown for the pause. cells of an unfinished last
+// batch only appear in the last frame
+pub(crate) fn play_frames(
+    maze: &Grid,
+    history: &[MazeAction],
+    opts: &ImageOptions,
+    ani_opts: &AnimationOptions,
+    mut show: impl FnMut(&[u8], u16, bool) -> Result<(), LabgenError>,
+) -> Result<(), LabgenError> {
+    let layout = opts.layout();
+    let (width, height) = image_size(maze, &layout)?;
+
+    let mut state: Vec<u8> = vec![0; width as usize * height as usize];
+    draw_exclusions(&mut state, width, 0, 0, maze, opts);
+    // the solver is played back once the maze is finished
+    let steps = animation_steps(maze, history, opts);
+    let progress = encoding_progress(
+        ani_opts,
+        (steps.len() / ani_opts.batch_size as usize) as u64,
+    );
+
+    for (step, fills) in steps.iter().enumerate() {
+        if progress.cancelled() {
+            break;
+        }
+        for (rect, color) in fills {
+            fill_rect(&mut state, width, *rect, *color);
+        }
+
+        if (step + 1) % ani_opts.batch_size as usize == 0 {
+            show(&state, ani_opts.frame_time, false)?;
+            progress.inc();
         }
     }
 
     // final frame with a higher delay
-    let mut frame = Frame::default();
-    frame.width = width;
-    frame.height = height;
-    frame.delay = ani_opts.pause_time;
-    frame.buffer = Cow::Borrowed(&state);
-    encoder.write_frame(&frame).unwrap();
+    draw_openings(&mut state, width, maze, opts);
+    draw_labels(&mut state, width, maze, opts);
+    show(&state, ani_opts.pause_time, true)?;
+    progress.finish();
+    Ok(())
 }
 
-pub fn generate_gif(
+// the generation one batch at a time like play_frames, every frame after the first only covering
+// the pixels that changed since the frame before it
+pub fn render_gif_uncompressed(
     maze: &Grid,
-    history: &[(Point, Direction)],
+    history: &[MazeAction],
     opts: &ImageOptions,
     ani_opts: &AnimationOptions,
-) {
-    let cell_width: u16 = opts.passage_width + opts.wall_width;
+    out: &mut dyn Write,
+) -> Result<(), LabgenError> {
+    let (width, height) = image_size(maze, &opts.layout())?;
+    let mut encoder = Encoder::new(out, width, height, &opts.palette())?;
+    encoder.set_repeat(Repeat::Infinite)?;
+    write_gif_metadata(&mut encoder, &maze.metadata())?;
 
-    let (width, height) = (
-        maze.width * cell_width + opts.wall_width,
-        maze.height * cell_width + opts.wall_width,
-    );
+    let mut pending = PendingFrame(None);
+    // image as of the frames written so far
+    let mut shown: Option<Vec<u8>> = None;
+    play_frames(maze, history, opts, ani_opts, |pixels, delay, last| {
+        let changed = match &mut shown {
+            Some(shown) => {
+                let rect = changed_rect(shown, pixels, width);
+                if let Some(rect) = rect {
+                    fill_region(shown, width, rect, &region_pixels(pixels, width, rect));
+                }
+                rect
+            }
+            None => {
+                shown = Some(pixels.to_vec());
+                Some(PixelRect {
+                    left: 0,
+                    top: 0,
+                    width,
+                    height,
+                })
+            }
+        };
+        let rect = changed.unwrap_or(DELAY_PIXEL);
+        let frame = Frame {
+            transparent: opts.transparent(),
+            ..region_frame(pixels, width, rect, delay)
+        };
+        if last {
+            pending.flush(&mut encoder)?;
+            encoder.write_frame(&frame)?;
+        } else {
+            let unchanged = ani_opts.skip_unchanged && changed.is_none();
+            pending.push(&mut encoder, frame, unchanged)?;
+        }
+        Ok(())
+    })?;
+    encoder.into_inner()?.flush()?;
+    Ok(())
+}
+
+// smallest rect covering every pixel that differs between two images of the same size, None
+// when they are the same
+fn changed_rect(before: &[u8], after: &[u8], image_width: u16) -> Option<PixelRect> {
+    let rows = before
+        .chunks(image_width as usize)
+        .zip(after.chunks(image_width as usize));
+    let mut changed: Option<PixelRect> = None;
+    for (y, (a, b)) in rows.enumerate() {
+        let Some(first) = (0..a.len()).find(|x| a[*x] != b[*x]) else {
+            continue;
+        };
+        let last = (0..a.len()).rfind(|x| a[*x] != b[*x]).unwrap();
+        let row = PixelRect {
+            left: first as u16,
+            top: y as u16,
+            width: (last + 1 - first) as u16,
+            height: 1,
+        };
+        changed = Some(changed.map_or(row, |c| c.union(row)));
+    }
+    changed
+}
+
+pub struct ComparePanel {
+    pub label: String,
+    pub maze: Grid,
+    pub history: MazeHistory,
+}
+
+// animates several mazes of the same size next to each other, each panel labeled with its name.
+// all panels advance by one history entry per step so the algorithms can be compared directly
+pub fn render_gif_compare(
+    panels: &[ComparePanel],
+    opts: &ImageOptions,
+    ani_opts: &AnimationOptions,
+    out: &mut dyn Write,
+) -> Result<(), LabgenError> {
+    let layout = opts.layout();
+    let (panel_width, panel_height) = image_size(&panels[0].maze, &layout)?;
 
-    let empty_maze: Vec<u8> = vec![0; width as usize * height as usize];
-    let connected_cell: Vec<u8> = vec![1; (cell_width * cell_width) as usize];
+    let gap = layout.cell_width().unwrap();
+    let label_scale: u16 = 2;
+    let label_height = (GLYPH_HEIGHT + 2) * label_scale;
+    let width = panels.len() as u32 * (panel_width as u32 + gap as u32) - gap as u32;
+    let height = label_height as u32 + panel_height as u32;
+    if width > u16::MAX as u32 || height > u16::MAX as u32 {
+        return Err(LabgenError::Render(format!(
+            "{} panels of {} by {} pixels are larger than {} pixels",
+            panels.len(),
+            panel_width,
+            panel_height,
+            u16::MAX
+        )));
+    }
+    let (width, height) = (width as u16, height as u16);
+
+    let mut state: Vec<u8> = vec![0; width as usize * height as usize];
+
+    // draw labels, truncated to fit the width of their panel
+    for (i, panel) in panels.iter().enumerate() {
+        let mut label = panel.label.clone();
+        while text_width(&label, label_scale) > panel_width {
+            label.pop();
+        }
+        draw_text(
+            &mut state,
+            width,
+            i as u16 * (panel_width + gap),
+            label_scale,
+            &label,
+            label_scale,
+            1,
+        );
+    }
+
+    for (i, panel) in panels.iter().enumerate() {
+        let left = i as u16 * (panel_width + gap);
+        draw_exclusions(&mut state, width, left, label_height, &panel.maze, opts);
+    }
+
+    let mut encoder = Encoder::new(out, width, height, &opts.palette())?;
+    encoder.set_repeat(Repeat::Infinite)?;
+    for panel in panels {
+        write_gif_metadata(&mut encoder, &panel.maze.metadata())?;
+    }
+
+    let steps = panels
+        .iter()
+        .map(|p| p.history.steps.len())
+        .max()
+        .unwrap_or(0);
+    let progress = encoding_progress(ani_opts, (steps / ani_opts.batch_size as usize) as u64);
+    let mut actions: Vec<ActionFills> = panels
+        .iter()
+        .map(|panel| ActionFills::new(&panel.maze, layout))
+        .collect();
+    for step in 0..steps {
+        if progress.cancelled() {
+            break;
+        }
+        for (i, (panel, actions)) in panels.iter().zip(&mut actions).enumerate() {
+            // a finished panel only takes back the highlight of its last scan
+            let fills = match panel.history.steps.get(step) {
+                Some(action) => actions.fills(*action),
+                None => actions.finish(),
+            };
+            for (rect, color) in fills {
+                let rect = rect.offset(i as u16 * (panel_width + gap), label_height);
+                fill_rect(&mut state, width, rect, color);
+            }
+        }
+
+        if (step + 1) % ani_opts.batch_size as usize == 0 {
+            let frame = Frame {
+                width,
+                height,
+                delay: ani_opts.frame_time,
+                transparent: opts.transparent(),
+                buffer: Cow::Borrowed(&state),
+                ..Frame::default()
+            };
+            encoder.write_frame(&frame)?;
+            progress.inc();
+        }
+    }
+
+    // final frame with a higher delay
+    let frame = Frame {
+        width,
+        height,
+        delay: ani_opts.pause_time,
+        transparent: opts.transparent(),
+        buffer: Cow::Borrowed(&state),
+        ..Frame::default()
+    };
+    encoder.write_frame(&frame)?;
+    encoder.into_inner()?.flush()?;
+    progress.finish();
+    Ok(())
+}
+
+// a row of snapshots of the animation at evenly spaced fractions of its steps, from the empty
+// maze to the finished one, each labeled with its percentage like the panels of a comparison.
+// the solver is played back after the generation as in the gif
+pub fn render_keyframes(
+    maze: &Grid,
+    history: &[MazeAction],
+    count: u16,
+    opts: &ImageOptions,
+    out: &mut dyn Write,
+) -> Result<(), LabgenError> {
+    if count < 2 {
+        return Err(LabgenError::Render(
+            "keyframes need at least the first and the last frame".to_string(),
+        ));
+    }
+    let layout = opts.layout();
+    let (panel_width, panel_height) = image_size(maze, &layout)?;
+
+    let gap = layout.cell_width().unwrap() as u32;
+    let label_scale: u16 = 2;
+    let label_height = (GLYPH_HEIGHT + 2) * label_scale;
+    let width = count as u32 * (panel_width as u32 + gap) - gap;
+    let height = label_height as u32 + panel_height as u32;
+    if width > u16::MAX as u32 || height > u16::MAX as u32 {
+        return Err(LabgenError::Render(format!(
+            "{} keyframes of {} by {} pixels are larger than {} pixels",
+            count,
+            panel_width,
+            panel_height,
+            u16::MAX
+        )));
+    }
+    let (width, height) = (width as u16, height as u16);
+
+    let mut state: Vec<u8> = vec![0; panel_width as usize * panel_height as usize];
+    draw_exclusions(&mut state, panel_width, 0, 0, maze, opts);
+    let steps = animation_steps(maze, history, opts);
+
+    let mut pixels: Vec<u8> = vec![0; width as usize * height as usize];
+    let mut played = 0;
+    for i in 0..count {
+        let percent = i as usize * 100 / (count as usize - 1);
+        let until = steps.len() * i as usize / (count as usize - 1);
+        for (rect, color) in steps[played..until].iter().flatten() {
+            fill_rect(&mut state, panel_width, *rect, *color);
+        }
+        played = until;
+        if i == count - 1 {
+            draw_openings(&mut state, panel_width, maze, opts);
+            draw_labels(&mut state, panel_width, maze, opts);
+        }
+
+        let left = i * (panel_width + gap as u16);
+        let mut label = format!("{}%", percent);
+        while text_width(&label, label_scale) > panel_width {
+            label.pop();
+        }
+        draw_text(
+            &mut pixels,
+            width,
+            left,
+            label_scale,
+            &label,
+            label_scale,
+            1,
+        );
+        fill_region(
+            &mut pixels,
+            width,
+            PixelRect {
+                left,
+                top: label_height,
+                width: panel_width,
+                height: panel_height,
+            },
+            &state,
+        );
+    }
+
+    write_png(
+        &pixels,
+        width,
+        height,
+        &opts.palette(),
+        opts.transparent(),
+        png_text(maze, opts),
+        out,
+    )
+}
+
+// plays the generation like a compressed gif, each batch only updating the bounding box of its
+// cells. the finished maze is stored as a separate default image for viewers that do not animate
+pub fn render_apng(
+    maze: &Grid,
+    history: &[MazeAction],
+    opts: &ImageOptions,
+    ani_opts: &AnimationOptions,
+    out: &mut dyn Write,
+) -> Result<(), LabgenError> {
+    let layout = opts.layout();
+    let (width, height) = image_size(maze, &layout)?;
+
+    let mut state: Vec<u8> = vec![0; width as usize * height as usize];
+    draw_exclusions(&mut state, width, 0, 0, maze, opts);
+
+    // the solver is played back once the maze is finished
+    let steps = animation_steps(maze, history, opts);
+
+    let mut finished = state.clone();
+    for (rect, color) in steps.iter().flatten() {
+        fill_rect(&mut finished, width, *rect, *color);
+    }
+    draw_openings(&mut finished, width, maze, opts);
+    draw_labels(&mut finished, width, maze, opts);
+
+    let batch_size = ani_opts.batch_size as usize;
+    // the empty maze, every batch including an unfinished last one and the finished maze
+    let frames = 1 + steps.len().div_ceil(batch_size) + 1;
+
+    let palette = opts.palette();
+    let mut encoder = png_encoder(
+        width,
+        height,
+        &palette,
+        opts.transparent(),
+        png_text(maze, opts),
+        out,
+    )?;
+    encoder.set_animated(frames as u32, 0)?;
+    encoder.set_sep_def_img(true)?;
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&finished)?;
+    let progress = encoding_progress(ani_opts, (steps.len() / batch_size) as u64);
+
+    let mut write_frame = |pixels: &[u8], rect: PixelRect, delay: u16| {
+        // both are checked against the other, so the old position could push the new size out
+        writer.reset_frame_position()?;
+        writer.set_frame_dimension(rect.width as u32, rect.height as u32)?;
+        writer.set_frame_position(rect.left as u32, rect.top as u32)?;
+        writer.set_frame_delay(delay, 100)?;
+        writer.write_image_data(&region_pixels(pixels, width, rect))
+    };
+    let full = PixelRect {
+        left: 0,
+        top: 0,
+        width,
+        height,
+    };
+    write_frame(&state, full, 0)?;
+
+    let mut changed: Option<PixelRect> = None;
+    for (step, fills) in steps.iter().enumerate() {
+        if progress.cancelled() {
+            break;
+        }
+        for (rect, color) in fills {
+            fill_rect(&mut state, width, *rect, *color);
+        }
+        if let Some(rect) = fills_rect(fills) {
+            changed = Some(changed.map_or(rect, |c| c.union(rect)));
+        }
+
+        if (step + 1) % batch_size == 0 {
+            let rect = changed.take().unwrap_or(DELAY_PIXEL);
+            write_frame(&state, rect, ani_opts.frame_time)?;
+            progress.inc();
+        }
+    }
+    // cells of an unfinished last batch appear together with the final frame
+    if let Some(rect) = changed {
+        write_frame(&state, rect, 0)?;
+    }
+    write_frame(&finished, full, ani_opts.pause_time)?;
+
+    writer.finish()?;
+    progress.finish();
+    Ok(())
+}
+
+pub fn render_gif(
+    maze: &Grid,
+    history: &[MazeAction],
+    opts: &ImageOptions,
+    ani_opts: &AnimationOptions,
+    out: &mut dyn Write,
+) -> Result<(), LabgenError> {
+    let layout = opts.layout();
+    let (width, height) = image_size(maze, &layout)?;
 
-    let mut image =
-        BufWriter::new(File::create(format!("{}.gif", &opts.file_path).as_str()).unwrap());
-    let mut encoder = Encoder::new(&mut image, width, height, &opts.color_map).unwrap();
-    encoder.set_repeat(Repeat::Infinite).unwrap();
+    let mut state: Vec<u8> = vec![0; width as usize * height as usize];
+    draw_exclusions(&mut state, width, 0, 0, maze, opts);
+
+    // the solver is played back once the maze is finished
+    let steps = animation_steps(maze, history, opts);
+
+    let mut encoder = Encoder::new(out, width, height, &opts.palette())?;
+    encoder.set_repeat(Repeat::Infinite)?;
+    write_gif_metadata(&mut encoder, &maze.metadata())?;
+    let progress = encoding_progress(
+        ani_opts,
+        (steps.len() / ani_opts.batch_size as usize) as u64,
+    );
 
     // initial frame to set background
-    let mut frame = Frame::default();
-    frame.width = width;
-    frame.height = height;
-    frame.delay = 0;
-    frame.buffer = Cow::Borrowed(&empty_maze);
-    encoder.write_frame(&frame).unwrap();
-
-    for (pt, dir) in history {
-        let mut frame = Frame::default();
-        frame.delay = ani_opts.frame_time;
-
-        // set dimensions and position of frame
-        match dir {
-            Direction::NoDir => {
-                frame.width = opts.passage_width;
-                frame.height = opts.passage_width;
-                frame.top = pt.y as u16 * cell_width + opts.wall_width;
-                frame.left = pt.x as u16 * cell_width + opts.wall_width;
-            }
-            Direction::North => {
-                frame.width = opts.passage_width;
-                frame.height = cell_width;
-                frame.top = pt.y as u16 * cell_width + 0;
-                frame.left = pt.x as u16 * cell_width + opts.wall_width;
-            }
-            Direction::East => {
-                frame.width = cell_width;
-                frame.height = opts.passage_width;
-                frame.top = pt.y as u16 * cell_width + opts.wall_width;
-                frame.left = pt.x as u16 * cell_width + opts.wall_width;
-            }
-            Direction::South => {
-                frame.width = opts.passage_width;
-                frame.height = cell_width;
-                frame.top = pt.y as u16 * cell_width + opts.wall_width;
-                frame.left = pt.x as u16 * cell_width + opts.wall_width;
-            }
-            Direction::West => {
-                frame.width = cell_width;
-                frame.height = opts.passage_width;
-                frame.top = pt.y as u16 * cell_width + opts.wall_width;
-                frame.left = pt.x as u16 * cell_width + 0;
-            }
-        }
-
-        frame.buffer = Cow::Borrowed(&connected_cell);
-        frame.dispose = DisposalMethod::Keep;
-        encoder.write_frame(&frame).unwrap();
-    }
-
-    // final empty frame with a higher delay
-    let mut frame = Frame::default();
-    frame.width = 1;
-    frame.height = 1;
-    frame.dispose = DisposalMethod::Keep;
-    frame.delay = ani_opts.pause_time;
-    frame.buffer = Cow::Borrowed(&[0]);
-    encoder.write_frame(&frame).unwrap();
-}
-
-pub fn generate_png(maze: &Grid, opts: &ImageOptions) {
-    let cell_width: u16 = opts.passage_width + opts.wall_width;
-    let (width, height) = (
-        maze.width * cell_width + opts.wall_width,
-        maze.height * cell_width + opts.wall_width,
+    let mut pending = PendingFrame(None);
+    let frame = Frame {
+        width,
+        height,
+        delay: 0,
+        transparent: opts.transparent(),
+        buffer: Cow::Owned(state.clone()),
+        ..Frame::default()
+    };
+    pending.push(&mut encoder, frame, false)?;
+    // image as of the last frame, to tell whether a batch changed anything
+    let mut shown = state.clone();
+
+    // each frame only covers the bounding box of the cells drawn since the previous one
+    let mut changed: Option<PixelRect> = None;
+    for (step, fills) in steps.iter().enumerate() {
+        if progress.cancelled() {
+            break;
+        }
+        for (rect, color) in fills {
+            fill_rect(&mut state, width, *rect, *color);
+        }
+        if let Some(rect) = fills_rect(fills) {
+            changed = Some(changed.map_or(rect, |c| c.union(rect)));
+        }
+
+        if (step + 1) % ani_opts.batch_size as usize == 0 {
+            let rect = changed.take().unwrap_or(DELAY_PIXEL);
+            let frame = Frame {
+                transparent: opts.transparent(),
+                ..region_frame(&state, width, rect, ani_opts.frame_time)
+            };
+            let unchanged = ani_opts.skip_unchanged
+                && frame.buffer[..] == region_pixels(&shown, width, rect)[..];
+            if ani_opts.skip_unchanged && !unchanged {
+                fill_region(&mut shown, width, rect, &frame.buffer);
+            }
+            pending.push(&mut encoder, frame, unchanged)?;
+            progress.inc();
+        }
+    }
+    pending.flush(&mut encoder)?;
+    // excluded cells inside a frame stay transparent like in the first one
+    let last_frame = |state: &[u8], rect: PixelRect| Frame {
+        transparent: opts.transparent(),
+        ..region_frame(state, width, rect, 0)
+    };
+    // cells of an unfinished last batch appear together with the final frame
+    if let Some(rect) = changed {
+        encoder.write_frame(&last_frame(&state, rect))?;
+    }
+    for rect in draw_openings(&mut state, width, maze, opts) {
+        encoder.write_frame(&last_frame(&state, rect))?;
+    }
+
+    // labels are drawn over the finished maze, the passage color is left transparent so only
+    // the text replaces what is already there
+    for label in opts.labels.iter().filter(|l| maze.contains(l.pt)) {
+        let rect = cell_rect(&layout, label.pt, Direction::NoDir);
+        let mut buffer: Vec<u8> = vec![1; rect.width as usize * rect.height as usize];
+        draw_label(&mut buffer, rect.width, 0, 0, rect.width, &label.text);
+        let frame = Frame {
+            width: rect.width,
+            height: rect.height,
+            top: rect.top,
+            left: rect.left,
+            dispose: DisposalMethod::Keep,
+            transparent: Some(1),
+            buffer: Cow::Owned(buffer),
+            ..Frame::default()
+        };
+        encoder.write_frame(&frame)?;
+    }
+
+    // final empty frame with a higher delay, its pixel is transparent so it leaves the image as
+    // it is even where a maze without walls has a passage in the corner
+    let frame = Frame {
+        width: 1,
+        height: 1,
+        dispose: DisposalMethod::Keep,
+        delay: ani_opts.pause_time,
+        transparent: Some(0),
+        buffer: Cow::Borrowed(&[0]),
+        ..Frame::default()
+    };
+    encoder.write_frame(&frame)?;
+    encoder.into_inner()?.flush()?;
+    progress.finish();
+    Ok(())
+}
+
+// animates a solver on a finished maze, see solve_phases
+pub fn render_gif_solve(
+    maze: &Grid,
+    solution: &Solution,
+    opts: &ImageOptions,
+    ani_opts: &AnimationOptions,
+    out: &mut dyn Write,
+) -> Result<(), LabgenError> {
+    let layout = opts.layout();
+    let (width, height) = image_size(maze, &layout)?;
+
+    let mut state = draw_maze(maze, opts, |_| 1)?;
+    let mut encoder = Encoder::new(out, width, height, &opts.palette())?;
+    encoder.set_repeat(Repeat::Infinite)?;
+    write_gif_metadata(&mut encoder, &maze.metadata())?;
+
+    let progress = encoding_progress(
+        ani_opts,
+        ((solution.steps.len() + solution.path.len()) / ani_opts.batch_size as usize) as u64,
     );
+    let mut pending = PendingFrame(None);
+    let mut frame_num = 0;
+    for (pt, dir, color) in solve_phases(maze, solution) {
+        if progress.cancelled() {
+            break;
+        }
+        frame_num += 1;
 
-    let file = File::create(format!("{}.png", &opts.file_path).as_str()).unwrap();
-    let ref mut writer = BufWriter::new(file);
+        fill_rect(&mut state, width, cell_rect(&layout, pt, dir), color);
 
-    let mut encoder = png::Encoder::new(writer, width as u32, height as u32);
-    encoder.set_color(png::ColorType::Indexed);
-    encoder.set_palette(&opts.color_map);
+        if frame_num % ani_opts.batch_size == 0 {
+            let unchanged = ani_opts.skip_unchanged && pending.shows(&state);
+            let frame = Frame {
+                width,
+                height,
+                delay: ani_opts.frame_time,
+                transparent: opts.transparent(),
+                buffer: Cow::Owned(state.clone()),
+                ..Frame::default()
+            };
+            pending.push(&mut encoder, frame, unchanged)?;
+            progress.inc();
+        }
+    }
+    pending.flush(&mut encoder)?;
 
-    let mut writer = encoder.write_header().unwrap();
+    // final frame with a higher delay
+    draw_labels(&mut state, width, maze, opts);
+    let frame = Frame {
+        width,
+        height,
+        delay: ani_opts.pause_time,
+        transparent: opts.transparent(),
+        buffer: Cow::Borrowed(&state),
+        ..Frame::default()
+    };
+    encoder.write_frame(&frame)?;
+    encoder.into_inner()?.flush()?;
+    progress.finish();
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum ColorBy {
+    /// order in which cells joined the maze during generation
+    Order,
+    /// length of the path from the entrance, or the top left cell, to each cell
+    Distance,
+    /// value of the noise that split a noise maze into regions
+    Noise,
+}
+
+// first palette index used by gradients, indices below are the colors of ImageOptions::palette
+const GRADIENT_START: u8 = 6;
+
+// draws every cell and its east/south connections, colored by the provided function, the area of
+// removed cells, the solution and the labels
+pub(crate) fn draw_maze(
+    maze: &Grid,
+    opts: &ImageOptions,
+    color: impl Fn(Point) -> u8,
+) -> Result<Vec<u8>, LabgenError> {
+    let layout = opts.layout();
+    draw_maze_with(maze, opts, color, |pt, dir| cell_rect(&layout, pt, dir))
+}
+
+// draw_maze with the passages and the solution filling the rects given by rect instead of
+// cell_rect
+fn draw_maze_with(
+    maze: &Grid,
+    opts: &ImageOptions,
+    color: impl Fn(Point) -> u8,
+    rect: impl Fn(Point, Direction) -> PixelRect,
+) -> Result<Vec<u8>, LabgenError> {
+    let (width, height) = image_size(maze, &opts.layout())?;
 
     let mut pixels: Vec<u8> = vec![0; width as usize * height as usize];
 
     for py in 0..maze.height {
         for px in 0..maze.width {
-            let top: u16 = py as u16 * cell_width + opts.wall_width;
-            let left: u16 = px as u16 * cell_width + opts.wall_width;
-            let connections = maze
-                .get_tile(Point {
-                    x: px as i16,
-                    y: py as i16,
-                })
-                .connections;
-
-            for y in 0..opts.passage_width {
-                for x in 0..opts.passage_width {
-                    pixels[(x + left) as usize + ((y + top) as usize * width as usize)] = 1;
-                }
+            let pt = Point::new(px as i32, py as i32);
+            if maze.is_removed(pt) {
+                continue;
             }
+            let cell_color = color(pt);
+            let connections = maze.get_tile(pt).connections();
+
+            fill_rect(&mut pixels, width, rect(pt, Direction::NoDir), cell_color);
             if connections & Direction::East as u8 != 0 {
-                for y in 0..opts.passage_width {
-                    for x in opts.passage_width..cell_width {
-                        pixels[(x + left) as usize + ((y + top) as usize * width as usize)] = 1;
-                    }
-                }
+                fill_rect(&mut pixels, width, rect(pt, Direction::East), cell_color);
             }
             if connections & Direction::South as u8 != 0 {
-                for y in opts.passage_width..cell_width {
-                    for x in 0..opts.passage_width {
-                        pixels[(x + left) as usize + ((y + top) as usize * width as usize)] = 1;
+                fill_rect(&mut pixels, width, rect(pt, Direction::South), cell_color);
+            }
+            // connections wrapping around the maze open the border on both sides
+            if px == 0 && connections & Direction::West as u8 != 0 {
+                fill_rect(&mut pixels, width, rect(pt, Direction::West), cell_color);
+            }
+            if py == 0 && connections & Direction::North as u8 != 0 {
+                fill_rect(&mut pixels, width, rect(pt, Direction::North), cell_color);
+            }
+        }
+    }
+    draw_exclusions(&mut pixels, width, 0, 0, maze, opts);
+    for (pt, dir) in path_steps(maze, opts.solution_path()) {
+        fill_rect(&mut pixels, width, rect(pt, dir), SOLUTION);
+    }
+    draw_labels(&mut pixels, width, maze, opts);
+
+    Ok(pixels)
+}
+
+// column name used by rulers, A to Z followed by AA, AB and so on
+fn column_name(mut x: u32) -> String {
+    let mut name = String::new();
+    loop {
+        name.insert(0, (b'A' + (x % 26) as u8) as char);
+        if x < 26 {
+            return name;
+        }
+        x = x / 26 - 1;
+    }
+}
+
+// places the image in a larger one with column letters along the top and row numbers, counted
+// from 1, along the left. labels that would touch their neighbor are skipped so dense mazes only
+// get every few columns or rows labeled
+pub(crate) fn add_rulers(
+    pixels: Vec<u8>,
+    width: u16,
+    height: u16,
+    maze: &Grid,
+    opts: &ImageOptions,
+) -> Result<(Vec<u8>, u16, u16), LabgenError> {
+    if !opts.rulers {
+        return Ok((pixels, width, height));
+    }
+
+    let layout = opts.layout();
+    let scale = (layout.passage_width / (GLYPH_HEIGHT * 2)).max(1);
+    let top = (GLYPH_HEIGHT + 2) * scale;
+    let left = text_width(&maze.height.to_string(), scale) + 2 * scale;
+    let (new_width, new_height) = match (width.checked_add(left), height.checked_add(top)) {
+        (Some(w), Some(h)) => (w, h),
+        _ => {
+            return Err(LabgenError::Render(format!(
+                "a maze with rulers is larger than {} pixels",
+                u16::MAX
+            )))
+        }
+    };
+
+    let mut canvas: Vec<u8> = vec![1; new_width as usize * new_height as usize];
+    for (y, row) in pixels.chunks(width as usize).enumerate() {
+        let start = left as usize + (y + top as usize) * new_width as usize;
+        canvas[start..start + width as usize].copy_from_slice(row);
+    }
+
+    let mut next_free = 0;
+    for x in 0..maze.width {
+        let name = column_name(x);
+        let cell = cell_rect(&layout, Point::new(x as i32, 0), Direction::NoDir);
+        let text_left =
+            (left + cell.left + cell.width / 2).saturating_sub(text_width(&name, scale) / 2);
+        if text_left >= next_free {
+            draw_text(&mut canvas, new_width, text_left, scale, &name, scale, 0);
+            next_free = text_left + text_width(&name, scale) + 2 * scale;
+        }
+    }
+
+    let mut next_free = 0;
+    for y in 0..maze.height {
+        let number = (y + 1).to_string();
+        let cell = cell_rect(&layout, Point::new(0, y as i32), Direction::NoDir);
+        let text_top = (top + cell.top + cell.height / 2).saturating_sub(GLYPH_HEIGHT * scale / 2);
+        if text_top >= next_free {
+            let text_left = left - scale - text_width(&number, scale);
+            draw_text(
+                &mut canvas,
+                new_width,
+                text_left,
+                text_top,
+                &number,
+                scale,
+                0,
+            );
+            next_free = text_top + (GLYPH_HEIGHT + 1) * scale;
+        }
+    }
+
+    Ok((canvas, new_width, new_height))
+}
+
+// title and description of a maze image for screen readers, shared by the png, svg and html
+// exports
+pub(crate) fn accessible_text(maze: &Grid, opts: &ImageOptions) -> (String, String) {
+    let title = format!("{}x{} {} maze", maze.width, maze.height, maze.params.method);
+    let mut description = format!(
+        "A maze of {} columns and {} rows generated with the {} method, columns are named \
+        with letters and rows numbered from 1.",
+        maze.width, maze.height, maze.params.method
+    );
+    if let (Some(first), Some(last)) = (opts.solution_path().first(), opts.solution_path().last()) {
+        description += &format!(
+            " Its solution is drawn from cell {}{} to cell {}{}.",
+            column_name(first.x as u32),
+            first.y + 1,
+            column_name(last.x as u32),
+            last.y + 1
+        );
+    }
+    (title, description)
+}
+
+// the solution path as x,y cells separated by spaces so puzzles can be checked by programs, None
+// when no solution is drawn
+pub(crate) fn solution_text(opts: &ImageOptions) -> Option<String> {
+    opts.solution.as_ref().map(|solution| {
+        let path: Vec<String> = solution
+            .path
+            .iter()
+            .map(|pt| format!("{},{}", pt.x, pt.y))
+            .collect();
+        path.join(" ")
+    })
+}
+
+// text chunks of png images, the generation parameters followed by the title, description and
+// solution path
+pub(crate) fn png_text(maze: &Grid, opts: &ImageOptions) -> Vec<(&'static str, String)> {
+    let mut text = maze.metadata().entries();
+    let (title, description) = accessible_text(maze, opts);
+    text.push(("Title", title));
+    text.push(("Description", description));
+    if let Some(path) = solution_text(opts) {
+        text.push(("solution", path));
+    }
+    text
+}
+
+fn png_encoder<'a>(
+    width: u16,
+    height: u16,
+    palette: &'a [u8],
+    transparent: Option<u8>,
+    text: Vec<(&'static str, String)>,
+    out: &'a mut dyn Write,
+) -> Result<png::Encoder<'a, &'a mut dyn Write>, LabgenError> {
+    let mut encoder = png::Encoder::new(out, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_palette(palette);
+    if let Some(index) = transparent {
+        // alpha of the palette entries up to and including the transparent one
+        let mut alpha = vec![0xFF; index as usize + 1];
+        alpha[index as usize] = 0;
+        encoder.set_trns(alpha);
+    }
+    for (key, value) in text {
+        encoder.add_text_chunk(key.to_string(), value)?;
+    }
+    Ok(encoder)
+}
+
+pub(crate) fn write_png(
+    pixels: &[u8],
+    width: u16,
+    height: u16,
+    palette: &[u8],
+    transparent: Option<u8>,
+    text: Vec<(&'static str, String)>,
+    out: &mut dyn Write,
+) -> Result<(), LabgenError> {
+    let encoder = png_encoder(width, height, palette, transparent, text, out)?;
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(pixels)?;
+    writer.finish()?;
+    Ok(())
+}
+
+pub fn render_png(
+    maze: &Grid,
+    opts: &ImageOptions,
+    out: &mut dyn Write,
+) -> Result<(), LabgenError> {
+    let layout = opts.layout();
+    let (width, height) = image_size(maze, &layout)?;
+
+    let pixels = draw_maze(maze, opts, |_| 1)?;
+    let (pixels, width, height) = add_rulers(pixels, width, height, maze, opts)?;
+    write_png(
+        &pixels,
+        width,
+        height,
+        &opts.palette(),
+        opts.transparent(),
+        png_text(maze, opts),
+        out,
+    )
+}
+
+// passage of the cell at pt narrowed to width, centered in its full passage area. passages to a
+// neighbor take the narrower width of the two and run through the wall up to the narrowed area of
+// the neighbor, passages leaving the image are cut at its border
+fn tapered_rect(
+    maze: &Grid,
+    layout: &PixelLayout,
+    widths: &[u16],
+    pt: Point,
+    dir: Direction,
+) -> PixelRect {
+    let (image_width, image_height) = layout.image_size(maze.width, maze.height).unwrap();
+    let next = maze.travel(pt, dir);
+    let width = match dir {
+        Direction::NoDir => widths[maze.get_index(pt)],
+        _ if maze.contains(next) => widths[maze.get_index(pt)].min(widths[maze.get_index(next)]),
+        _ => widths[maze.get_index(pt)],
+    } as i32;
+    let cell_width = layout.cell_width().unwrap() as i32;
+    let offset = (layout.passage_width as i32 - width) / 2 + layout.wall_width as i32;
+    let (left, top) = (pt.x * cell_width + offset, pt.y * cell_width + offset);
+
+    let (left, top, right, bottom) = match dir {
+        Direction::NoDir => (left, top, left + width, top + width),
+        Direction::North => (left, top - cell_width, left + width, top),
+        Direction::East => (left, top, left + cell_width, top + width),
+        Direction::South => (left, top, left + width, top + cell_width),
+        Direction::West => (left - cell_width, top, left, top + width),
+    };
+    let (left, top) = (left.max(0), top.max(0));
+    let (right, bottom) = (
+        right.min(image_width as i32),
+        bottom.min(image_height as i32),
+    );
+    PixelRect {
+        left: left as u16,
+        top: top as u16,
+        width: (right - left) as u16,
+        height: (bottom - top) as u16,
+    }
+}
+
+// narrows the passages of each cell according to its value, from the full passage width at 0
+// down to min_width at the largest value, while the walls widen to fill the space. cells without
+// a value keep the full width
+pub fn render_png_tapered(
+    maze: &Grid,
+    values: &[Option<u32>],
+    min_width: u16,
+    opts: &ImageOptions,
+    out: &mut dyn Write,
+) -> Result<(), LabgenError> {
+    let layout = opts.layout();
+    let (width, height) = image_size(maze, &layout)?;
+
+    let min_width = min_width.clamp(1, layout.passage_width);
+    let shrink = (layout.passage_width - min_width) as u64;
+    let max = values.iter().flatten().copied().max().unwrap_or(0).max(1) as u64;
+    let widths: Vec<u16> = values
+        .iter()
+        .map(|v| match v {
+            Some(v) => layout.passage_width - (*v as u64 * shrink / max) as u16,
+            None => layout.passage_width,
+        })
+        .collect();
+
+    let pixels = draw_maze_with(
+        maze,
+        opts,
+        |_| 1,
+        |pt, dir| tapered_rect(maze, &layout, &widths, pt, dir),
+    )?;
+    let (pixels, width, height) = add_rulers(pixels, width, height, maze, opts)?;
+    write_png(
+        &pixels,
+        width,
+        height,
+        &opts.palette(),
+        opts.transparent(),
+        png_text(maze, opts),
+        out,
+    )
+}
+
+// colors each cell along a gradient according to its value, cells without a value use the
+// passage color
+pub fn render_png_gradient(
+    maze: &Grid,
+    values: &[Option<u32>],
+    opts: &ImageOptions,
+    out: &mut dyn Write,
+) -> Result<(), LabgenError> {
+    let layout = opts.layout();
+    let (width, height) = image_size(maze, &layout)?;
+
+    let steps = (u8::MAX - GRADIENT_START) as u32 + 1;
+    let mut palette: Vec<u8> = opts.palette();
+    for i in 0..steps {
+        for (a, b) in opts.gradient[0].0.iter().zip(opts.gradient[1].0.iter()) {
+            palette.push(((*a as u32 * (steps - 1 - i) + *b as u32 * i) / (steps - 1)) as u8);
+        }
+    }
+
+    let max = values.iter().flatten().copied().max().unwrap_or(0).max(1);
+    let pixels = draw_maze(maze, opts, |pt| match values[maze.get_index(pt)] {
+        Some(v) => GRADIENT_START + (v as u64 * (steps - 1) as u64 / max as u64) as u8,
+        None => 1,
+    })?;
+    let (pixels, width, height) = add_rulers(pixels, width, height, maze, opts)?;
+    write_png(
+        &pixels,
+        width,
+        height,
+        &palette,
+        opts.transparent(),
+        png_text(maze, opts),
+        out,
+    )
+}
+
+// the noise a noise maze was carved along as an image of the same size as the maze, lighter
+// where the noise is higher. cells up to the threshold are tinted blue, the others red
+pub fn render_png_noise(
+    maze: &Grid,
+    opts: &ImageOptions,
+    out: &mut dyn Write,
+) -> Result<(), LabgenError> {
+    let layout = opts.layout();
+    let (width, height) = image_size(maze, &layout)?;
+
+    // 128 shades of each tint
+    let mut palette: Vec<u8> = Vec::new();
+    for i in 0..=u8::MAX {
+        let level = (64 + (i % 128) as u32 * 191 / 127) as u8;
+        if i < 128 {
+            palette.extend([level / 2, level / 2, level]);
+        } else {
+            palette.extend([level, level / 2, level / 2]);
+        }
+    }
+
+    let noise = noise_map(maze);
+    let threshold = maze.params.noise.threshold as f32 / 100.0;
+    let cell_width = layout.cell_width().unwrap();
+    let mut pixels: Vec<u8> = Vec::with_capacity(width as usize * height as usize);
+    for y in 0..height {
+        for x in 0..width {
+            // the walls right and below the last cells belong to them
+            let pt = Point::new(
+                ((x / cell_width) as u32).min(maze.width - 1) as i32,
+                ((y / cell_width) as u32).min(maze.height - 1) as i32,
+            );
+            let v = noise[maze.get_index(pt)];
+            let level = ((v.clamp(-1.0, 1.0) + 1.0) / 2.0 * 127.0) as u8;
+            pixels.push(if v <= threshold { level } else { 128 + level });
+        }
+    }
+
+    write_png(
+        &pixels,
+        width,
+        height,
+        &palette,
+        None,
+        maze.metadata().entries(),
+        out,
+    )
+}
+
+pub fn generate_png_noise(maze: &Grid, opts: &ImageOptions) -> Result<(), LabgenError> {
+    write_output(&opts.file_path, "png", |out| {
+        render_png_noise(maze, opts, out)
+    })
+}
+
+// color of a pixel at distance d from the center of a polar maze and at angle theta, measured
+// clockwise from the right. rings are cell_width wide with their wall along the inner edge, the
+// radial wall between two cells lies along the start of the later one
+fn polar_pixel(maze: &PolarGrid, d: f32, theta: f32, layout: &PixelLayout) -> u8 {
+    let cell_width = layout.cell_width().unwrap() as f32;
+    let wall_width = layout.wall_width as f32;
+    let rings = maze.rings() as f32;
+    if d >= rings * cell_width {
+        return if d < rings * cell_width + wall_width {
+            0
+        } else {
+            1
+        };
+    }
+
+    let ring = (d / cell_width) as usize;
+    if ring == 0 {
+        return 1;
+    }
+    let size = maze.ring_sizes[ring];
+    let step = TAU / size as f32;
+    let cell = ((theta / step) as u32).min(size - 1);
+
+    let previous = maze.index(ring, (cell + size - 1) % size);
+    if size > 1 && (theta - cell as f32 * step) * d < wall_width && !maze.tiles[previous].next {
+        return 0;
+    }
+    if d - ring as f32 * cell_width < wall_width && !maze.tiles[maze.index(ring, cell)].inward {
+        return 0;
+    }
+    1
+}
+
+// draws the rings of a polar maze inside a square image, the outside of the outer ring uses the
+// passage color
+pub fn render_png_polar(
+    maze: &PolarGrid,
+    opts: &ImageOptions,
+    out: &mut dyn Write,
+) -> Result<(), LabgenError> {
+    let layout = opts.layout();
+    let cell_width = cell_width(&layout)?;
+    let radius = maze.rings() as u32 * cell_width as u32 + layout.wall_width as u32;
+    let size = u16::try_from(radius * 2).map_err(|_| {
+        LabgenError::Render(format!(
+            "a polar maze of {} rings with {} pixel cells is larger than {} pixels",
+            maze.rings(),
+            cell_width,
+            u16::MAX
+        ))
+    })?;
+
+    let mut pixels: Vec<u8> = vec![1; size as usize * size as usize];
+    for y in 0..size {
+        for x in 0..size {
+            let dx = x as f32 + 0.5 - radius as f32;
+            let dy = y as f32 + 0.5 - radius as f32;
+            let theta = dy.atan2(dx).rem_euclid(TAU);
+            pixels[x as usize + y as usize * size as usize] =
+                polar_pixel(maze, dx.hypot(dy), theta, &layout);
+        }
+    }
+
+    let text = vec![
+        ("seed", maze.seed.to_string()),
+        ("method", maze.method.to_string()),
+        ("cells", "polar".to_string()),
+        ("rings", maze.rings().to_string()),
+        ("version", env!("CARGO_PKG_VERSION").to_string()),
+        (
+            "Title",
+            format!("{} ring polar {} maze", maze.rings(), maze.method),
+        ),
+    ];
+    write_png(
+        &pixels,
+        size,
+        size,
+        &opts.palette(),
+        opts.transparent(),
+        text,
+        out,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        arbitrary::maze_with_history,
+        progress::CancelToken,
+        solver::{solve, SolverType},
+    };
+    use proptest::prelude::*;
+
+    // hands the delay and the image shown by every frame of a gif to each, with transparent pixels
+    // of a frame keeping what is below them when transparent is set. pixels no frame covered yet
+    // are 0xFF
+    fn play_gif(gif: &[u8], transparent: bool, mut each: impl FnMut(u16, &[u8])) {
+        let mut decoder = gif::DecodeOptions::new();
+        decoder.set_color_output(gif::ColorOutput::Indexed);
+        let mut decoder = decoder.read_info(gif).unwrap();
+        let width = decoder.width() as usize;
+        let mut image = vec![0xFF; width * decoder.height() as usize];
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            for (row, line) in frame.buffer.chunks(frame.width as usize).enumerate() {
+                let start = frame.left as usize + (frame.top as usize + row) * width;
+                for (x, pixel) in line.iter().enumerate() {
+                    if !transparent || frame.transparent != Some(*pixel) {
+                        image[start + x] = *pixel;
                     }
                 }
             }
+            each(frame.delay, &image);
         }
     }
 
-    writer.write_image_data(&pixels).unwrap();
+    proptest! {
+        #[test]
+        fn renderers_accept_any_options(
+            (maze, history) in maze_with_history(),
+            mut opts in any::<ImageOptions>(),
+            ani_opts in any::<AnimationOptions>(),
+        ) {
+            let end = Point::new(maze.width as i32 - 1, maze.height as i32 - 1);
+            opts.solution = solve(&maze, Point::new(0, 0), end, SolverType::Bfs);
+            render_png(&maze, &opts, &mut Vec::new()).unwrap();
+            render_gif(&maze, &history.steps, &opts, &ani_opts, &mut Vec::new()).unwrap();
+        }
+
+        #[test]
+        fn gif_frames_add_up_to_played_frames(
+            (maze, history) in maze_with_history(),
+            opts in any::<ImageOptions>(),
+            ani_opts in any::<AnimationOptions>(),
+        ) {
+            let ani_opts = AnimationOptions { skip_unchanged: false, ..ani_opts };
+            let mut played = Vec::new();
+            play_frames(&maze, &history.steps, &opts, &ani_opts, |pixels, delay, _| {
+                played.push((delay, pixels.to_vec()));
+                Ok(())
+            })
+            .unwrap();
+
+            let mut gif = Vec::new();
+            render_gif_uncompressed(&maze, &history.steps, &opts, &ani_opts, &mut gif).unwrap();
+            let mut shown = Vec::new();
+            play_gif(&gif, false, |delay, image| shown.push((delay, image.to_vec())));
+            prop_assert!(shown == played);
+        }
+
+        #[test]
+        fn compressed_gifs_end_on_the_same_image(
+            (maze, history) in maze_with_history(),
+            opts in any::<ImageOptions>(),
+            ani_opts in any::<AnimationOptions>(),
+        ) {
+            let [compressed, uncompressed] = [render_gif, render_gif_uncompressed].map(|render| {
+                let mut gif = Vec::new();
+                render(&maze, &history.steps, &opts, &ani_opts, &mut gif).unwrap();
+                let mut last = Vec::new();
+                play_gif(&gif, true, |_, image| last = image.to_vec());
+                last
+            });
+            prop_assert!(compressed == uncompressed);
+        }
+
+        #[test]
+        fn cancelled_animations_end_on_the_last_frame(
+            (maze, history) in maze_with_history(),
+            opts in any::<ImageOptions>(),
+            ani_opts in any::<AnimationOptions>(),
+        ) {
+            // the animation ends right away on its last frame, still a readable gif
+            let cancel = CancelToken::new();
+            cancel.cancel();
+            let ani_opts = AnimationOptions { cancel: Some(cancel), ..ani_opts };
+            let mut gif = Vec::new();
+            render_gif_uncompressed(&maze, &history.steps, &opts, &ani_opts, &mut gif).unwrap();
+            let mut frames = 0;
+            play_gif(&gif, false, |_, _| frames += 1);
+            prop_assert_eq!(frames, 1);
+        }
+    }
 }
@@ -0,0 +1,169 @@
+use crate::{
+    error::LabgenError,
+    image::write_output,
+    maze::{
+        ConnectionStatus, Direction, GenParams, Grid, GrowingTreeBias, MazeType, MazeWrap,
+        NoiseOptions, Orientation, Point, ScanOrder, Tile, WeightSource,
+    },
+};
+use serde::{de::Error, Deserialize, Serialize};
+use std::io::{Read, Write};
+
+// a maze as stored in json. tiles are listed row by row from the top left, the connections of a
+// tile are the bits of the directions it is open to: 1 north, 2 east, 4 south and 8 west
+#[derive(Debug, Serialize, Deserialize)]
+struct MazeFile {
+    version: String,
+    width: u32,
+    height: u32,
+    wrap: MazeWrap,
+    seed: u64,
+    method: MazeType,
+    #[serde(rename = "scan-order")]
+    scan_order: ScanOrder,
+    // missing from files written before noise could be tuned
+    #[serde(default)]
+    noise: NoiseOptions,
+    // missing from files written before the growing tree bias could be picked
+    #[serde(default)]
+    bias: GrowingTreeBias,
+    // missing from files written before binary tree and sidewinder could be turned
+    #[serde(default)]
+    orientation: Option<Orientation>,
+    // missing from files written before prim and kruskal could be weighted
+    #[serde(default)]
+    weights: Option<WeightSource>,
+    tiles: Vec<JsonTile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct JsonTile {
+    status: ConnectionStatus,
+    connections: u8,
+}
+
+pub fn render_json(maze: &Grid, out: &mut dyn Write) -> Result<(), LabgenError> {
+    let file = MazeFile {
+        version: maze.metadata().version,
+        width: maze.width,
+        height: maze.height,
+        wrap: maze.wrap,
+        seed: maze.params.seed,
+        method: maze.params.method,
+        scan_order: maze.params.scan_order,
+        noise: maze.params.noise,
+        bias: maze.params.bias.clone(),
+        orientation: maze.params.orientation,
+        weights: maze.params.weights.clone(),
+        tiles: maze
+            .tiles
+            .iter()
+            .map(|tile| JsonTile {
+                status: tile.status(),
+                connections: tile.connections(),
+            })
+            .collect(),
+    };
+    serde_json::to_writer(&mut *out, &file).map_err(std::io::Error::from)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+pub fn generate_json(maze: &Grid, file_path: &str) -> Result<(), LabgenError> {
+    write_output(file_path, "json", |out| render_json(maze, out))
+}
+
+// reads a maze written by render_json. files of other versions are accepted as long as their
+// tiles fit the size and every passage between two cells is open from both sides. passages
+// leading out of the grid are kept, they are openings or the edges of a crop
+pub fn read_json(input: impl Read) -> Result<Grid, serde_json::Error> {
+    let file: MazeFile = serde_json::from_reader(input)?;
+    if file.width == 0 || file.height == 0 {
+        return Err(serde_json::Error::custom(format!(
+            "a {}x{} maze has no cells",
+            file.width, file.height
+        )));
+    }
+    if file.tiles.len() != file.width as usize * file.height as usize {
+        return Err(serde_json::Error::custom(format!(
+            "a {}x{} maze needs {} tiles but {} are given",
+            file.width,
+            file.height,
+            file.width as usize * file.height as usize,
+            file.tiles.len()
+        )));
+    }
+
+    for (i, tile) in file.tiles.iter().enumerate() {
+        if tile.connections > 0b1111 {
+            return Err(serde_json::Error::custom(format!(
+                "tile {},{} has invalid connections {}",
+                i % file.width as usize,
+                i / file.width as usize,
+                tile.connections
+            )));
+        }
+    }
+
+    let maze = Grid {
+        tiles: file
+            .tiles
+            .iter()
+            .map(|tile| Tile::new(tile.status, tile.connections))
+            .collect(),
+        width: file.width,
+        height: file.height,
+        wrap: file.wrap,
+        params: GenParams {
+            seed: file.seed,
+            method: file.method,
+            scan_order: file.scan_order,
+            noise: file.noise,
+            bias: file.bias,
+            orientation: file.orientation,
+            weights: file.weights,
+        },
+        costs: Vec::new(),
+    };
+    for (i, tile) in maze.tiles.iter().enumerate() {
+        let pt = Point::new(
+            (i % maze.width as usize) as i32,
+            (i / maze.width as usize) as i32,
+        );
+        for dir in Direction::ALL {
+            let next = maze.travel(pt, dir);
+            if tile.connected(dir)
+                && maze.contains(next)
+                && !maze.get_tile(next).connected(dir.opposite())
+            {
+                return Err(serde_json::Error::custom(format!(
+                    "tile {},{} is open to {},{} but not the other way around",
+                    pt.x, pt.y, next.x, next.y
+                )));
+            }
+        }
+    }
+
+    Ok(maze)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arbitrary::rect_within;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn json_reads_back_the_same_maze((maze, rect) in rect_within()) {
+            // crops keep connections leaving the grid, which have to survive as well
+            for maze in [maze.crop(rect).unwrap(), maze] {
+                let mut json = Vec::new();
+                render_json(&maze, &mut json).unwrap();
+                let read = read_json(json.as_slice()).unwrap();
+                prop_assert_eq!(&read.tiles, &maze.tiles);
+                prop_assert_eq!(read.metadata(), maze.metadata());
+            }
+        }
+    }
+}
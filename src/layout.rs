@@ -0,0 +1,193 @@
+use crate::maze::{Direction, Point};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixelRect {
+    pub left: u16,
+    pub top: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl PixelRect {
+    pub fn offset(self, left: u16, top: u16) -> Self {
+        Self {
+            left: self.left + left,
+            top: self.top + top,
+            ..self
+        }
+    }
+
+    // smallest rect covering both
+    pub fn union(self, other: Self) -> Self {
+        let left = self.left.min(other.left);
+        let top = self.top.min(other.top);
+        Self {
+            left,
+            top,
+            width: (self.left + self.width).max(other.left + other.width) - left,
+            height: (self.top + self.height).max(other.top + other.height) - top,
+        }
+    }
+}
+
+// maps maze cells onto image pixels. each cell is a square of passage_width pixels, separated
+// from its neighbors and from the image border by wall_width pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixelLayout {
+    pub passage_width: u16,
+    pub wall_width: u16,
+}
+
+impl PixelLayout {
+    pub fn new(passage_width: u16, wall_width: u16) -> Self {
+        Self {
+            passage_width,
+            wall_width,
+        }
+    }
+
+    // pixels of a cell with the wall before it, None if they do not fit in a u16
+    pub fn cell_width(&self) -> Option<u16> {
+        self.passage_width.checked_add(self.wall_width)
+    }
+
+    // pixel dimensions of a maze of the given size, None if either does not fit in a u16
+    pub fn image_size(&self, width: u32, height: u32) -> Option<(u16, u16)> {
+        let cell_width = self.cell_width()?;
+        let span = |cells: u32| {
+            let pixels = cells as u64 * cell_width as u64 + self.wall_width as u64;
+            u16::try_from(pixels).ok()
+        };
+        Some((span(width)?, span(height)?))
+    }
+
+    // area of a cell extended through the wall in the given direction, NoDir gives just the
+    // passage area of the cell. None for negative points or if the rect does not fit in a u16
+    pub fn cell_to_pixel_rect(&self, pt: Point, dir: Direction) -> Option<PixelRect> {
+        let cell_width = self.cell_width()?;
+        let cell_left = u16::try_from(pt.x).ok()?.checked_mul(cell_width)?;
+        let cell_top = u16::try_from(pt.y).ok()?.checked_mul(cell_width)?;
+        let inner_left = cell_left.checked_add(self.wall_width)?;
+        let inner_top = cell_top.checked_add(self.wall_width)?;
+
+        let (left, top, width, height) = match dir {
+            Direction::NoDir => (
+                inner_left,
+                inner_top,
+                self.passage_width,
+                self.passage_width,
+            ),
+            Direction::North => (inner_left, cell_top, self.passage_width, cell_width),
+            Direction::East => (inner_left, inner_top, cell_width, self.passage_width),
+            Direction::South => (inner_left, inner_top, self.passage_width, cell_width),
+            Direction::West => (cell_left, inner_top, cell_width, self.passage_width),
+        };
+        left.checked_add(width)?;
+        top.checked_add(height)?;
+
+        Some(PixelRect {
+            left,
+            top,
+            width,
+            height,
+        })
+    }
+
+    // the part of the wall in the given direction that a passage from the cell goes through,
+    // NoDir gives the passage area of the cell like cell_to_pixel_rect
+    pub fn wall_to_pixel_rect(&self, pt: Point, dir: Direction) -> Option<PixelRect> {
+        let rect = self.cell_to_pixel_rect(pt, dir)?;
+        let (passage, wall) = (self.passage_width, self.wall_width);
+        Some(match dir {
+            Direction::NoDir => rect,
+            Direction::North => PixelRect {
+                height: wall,
+                ..rect
+            },
+            Direction::West => PixelRect {
+                width: wall,
+                ..rect
+            },
+            Direction::East => PixelRect {
+                left: rect.left + passage,
+                width: wall,
+                ..rect
+            },
+            Direction::South => PixelRect {
+                top: rect.top + passage,
+                height: wall,
+                ..rect
+            },
+        })
+    }
+
+    // cell whose passage area contains the pixel, None if the pixel lies on a wall line.
+    // the result is not checked against the maze bounds
+    pub fn pixel_to_cell(&self, x: u16, y: u16) -> Option<Point> {
+        let cell_width = self.cell_width()?;
+        if x % cell_width < self.wall_width || y % cell_width < self.wall_width {
+            return None;
+        }
+
+        Some(Point::new((x / cell_width) as i32, (y / cell_width) as i32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oversized_cells_have_no_layout() {
+        let layout = PixelLayout::new(u16::MAX, u16::MAX);
+        assert_eq!(layout.cell_width(), None);
+        assert_eq!(layout.image_size(1, 1), None);
+        assert_eq!(
+            layout.cell_to_pixel_rect(Point::new(0, 0), Direction::NoDir),
+            None
+        );
+        assert_eq!(layout.pixel_to_cell(0, 0), None);
+    }
+
+    #[test]
+    fn largest_cells_fit() {
+        let layout = PixelLayout::new(u16::MAX - 1, 1);
+        assert_eq!(layout.cell_width(), Some(u16::MAX));
+        assert_eq!(layout.image_size(1, 1), None);
+        assert_eq!(PixelLayout::new(10, 1).image_size(3, 2), Some((34, 23)));
+    }
+
+    #[test]
+    fn walls_lie_between_the_passages() {
+        let layout = PixelLayout::new(3, 1);
+        let pt = Point::new(1, 1);
+        let rect = |left, top, width, height| {
+            Some(PixelRect {
+                left,
+                top,
+                width,
+                height,
+            })
+        };
+        assert_eq!(
+            layout.wall_to_pixel_rect(pt, Direction::North),
+            rect(5, 4, 3, 1)
+        );
+        assert_eq!(
+            layout.wall_to_pixel_rect(pt, Direction::East),
+            rect(8, 5, 1, 3)
+        );
+        assert_eq!(
+            layout.wall_to_pixel_rect(pt, Direction::South),
+            rect(5, 8, 3, 1)
+        );
+        assert_eq!(
+            layout.wall_to_pixel_rect(pt, Direction::West),
+            rect(4, 5, 1, 3)
+        );
+        assert_eq!(
+            layout.wall_to_pixel_rect(pt, Direction::East),
+            layout.wall_to_pixel_rect(Point::new(2, 1), Direction::West)
+        );
+    }
+}
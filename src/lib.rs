@@ -0,0 +1,101 @@
+//! Maze generation and rendering.
+//!
+//! Mazes are carved into a [`Grid`] by one of the [`MazeType`] methods, or by any other
+//! [`MazeGenerator`] through [`carve_maze_with`], together with the [`MazeHistory`] of carving
+//! steps that animations play back. The [`render`] functions encode
+//! a maze as png, distance map png, json, dot, stl, pdf, html, ansi, braille or box drawing text,
+//! gif, svg, video or wav into any writer. [`generate`] runs every stage the command line does,
+//! from carving through braiding, cropping and openings to the solution, as set by [`Options`].
+//!
+//! The `serde` feature, on by default, derives `Serialize` and `Deserialize` for the maze types
+//! and adds the json, html and history formats that are built on them.
+//!
+//! ```
+//! use labgen::{generate_maze, render, ImageOptions, MazeType, MazeWrap};
+//!
+//! let (maze, _history) = generate_maze(20, 10, MazeType::Wilson, MazeWrap::None, 7).unwrap();
+//!
+//! let mut png: Vec<u8> = Vec::new();
+//! render::png(&maze, &ImageOptions::default(), &mut png).unwrap();
+//! ```
+
+pub mod ansi;
+#[cfg(any(test, feature = "proptest"))]
+pub mod arbitrary;
+pub mod audio;
+pub mod braille;
+pub mod dot;
+pub mod error;
+pub mod explain;
+mod font;
+#[cfg(test)]
+mod golden;
+pub mod heatmap;
+#[cfg(feature = "serde")]
+pub mod html;
+pub mod image;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod layout;
+pub mod mask;
+pub mod maze;
+pub mod moves;
+pub mod overlay;
+pub mod pattern;
+pub mod pdf;
+pub mod pipeline;
+pub mod polar;
+pub mod progress;
+#[cfg(feature = "serde")]
+pub mod replay;
+pub mod rooms;
+pub mod solver;
+pub mod stats;
+pub mod stl;
+pub mod svg;
+pub mod template;
+pub mod text;
+pub mod tiles;
+pub mod video;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use error::{LabgenError, MazeGenError};
+pub use image::ImageOptions;
+#[cfg(feature = "serde")]
+pub use json::read_json;
+pub use maze::{
+    carve_maze, carve_maze_with, generate_maze, generate_maze_with_progress, EditJournal, Grid,
+    MazeGenerator, MazeHistory, MazeType, MazeWrap, Point, SeedSchedule, WallEdit,
+};
+pub use pipeline::{generate, Options, Output};
+pub use solver::{
+    distance_field, next_step_toward, solve, solve_maze, DistanceField, Solution, SolverType,
+};
+
+/// Encoders writing a maze as an image, animation or sound.
+pub mod render {
+    pub use crate::ansi::{render_ansi as ansi, render_ansi_animation as ansi_animation};
+    pub use crate::audio::render_wav as wav;
+    pub use crate::braille::render_braille as braille;
+    pub use crate::dot::render_dot as dot;
+    pub use crate::heatmap::render_distance_map as distance_map;
+    #[cfg(feature = "serde")]
+    pub use crate::html::render_html as html;
+    pub use crate::image::{
+        render_apng as apng, render_gif as gif, render_gif_compare as gif_compare,
+        render_gif_solve as gif_solve, render_gif_uncompressed as gif_uncompressed,
+        render_keyframes as keyframes, render_png as png, render_png_gradient as png_gradient,
+        render_png_polar as png_polar, render_png_tapered as png_tapered, AnimationOptions,
+        ComparePanel, ImageOptions,
+    };
+    #[cfg(feature = "serde")]
+    pub use crate::json::render_json as json;
+    pub use crate::moves::{render_moves as moves, SolutionFormat};
+    pub use crate::pdf::{print_report, render_pdf as pdf, PageSize, PdfOptions, PrintReport};
+    pub use crate::stl::{render_stl as stl, MeshOptions};
+    pub use crate::svg::render_svg as svg;
+    pub use crate::text::{render_text as text, TileMap};
+    pub use crate::tiles::{render_tile as tile, TileOptions};
+    pub use crate::video::{render_video as video, VideoFormat};
+}
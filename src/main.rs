@@ -1,32 +1,345 @@
-use crate::{
+use crate::config::Config;
+use clap::{parser::ValueSource, ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand};
+use labgen::{
+    ansi::{generate_ansi, generate_ansi_animation},
+    audio::generate_wav,
+    braille::generate_braille,
+    dot::generate_dot,
+    error::LabgenError,
+    explain::render_explain,
+    heatmap::generate_distance_map,
+    html::generate_html,
     image::{
-        generate_gif, generate_gif_uncompressed, generate_png, AnimationOptions, ImageOptions,
+        generate_apng, generate_gif, generate_gif_compare, generate_gif_solve,
+        generate_gif_uncompressed, generate_keyframes, generate_png, generate_png_gradient,
+        generate_png_noise, generate_png_polar, generate_png_tapered, AnimationFormat,
+        AnimationOptions, Color, ColorBy, ComparePanel, ExcludeStyle, ImageFormat, ImageOptions,
     },
-    maze::{generate_maze, MazeType},
+    json::{generate_json, read_json},
+    layout::PixelLayout,
+    mask::{CostImage, Mask},
+    maze::{
+        generate_maze, BorderShape, BorderSide, CellLabel, Grid, GrowingTreeBias, MazeAction,
+        MazeHistory, MazeType, MazeWrap, NoiseOptions, Opening, Orientation, Point, Rect,
+        ScanOrder, SeedSchedule, UncarveStrategy, WeightSource,
+    },
+    moves::{generate_moves, SolutionFormat},
+    overlay::OverlayScale,
+    pattern::Pattern,
+    pdf::{generate_pdf, print_report, PageSize, PdfOptions},
+    pipeline::{generate, generate_matching, generate_polar, Options, Output},
+    polar::{supports_polar, CellShape},
+    progress::{CancelToken, Progress},
+    replay::{generate_history, is_history_file, load_history},
+    rooms::Rooms,
+    solver::SolverType,
+    stats::{
+        self, distances, generation_order, noise_levels, Entrances, MazeStats, StatsFormat,
+        StatsPredicate,
+    },
+    stl::{generate_stl, MeshOptions},
+    svg::generate_svg,
+    template::{RoomDoors, Template},
+    text::{generate_text, TileMap},
+    tiles::{generate_tiles, TileOptions},
+    video::{generate_video, VideoFormat},
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rayon::prelude::*;
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+    process,
+    sync::{atomic::Ordering, OnceLock},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
-use clap::Parser;
-use rand::{rngs::StdRng, SeedableRng};
-use std::time::Instant;
 
-mod image;
-mod maze;
+mod config;
 
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
+#[command(
+    version,
+    about,
+    long_about = None,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    args: Args,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// generate and render a maze, the same as running labgen without a subcommand
+    Generate(Box<Args>),
+    /// draw a maze file again with other image options, animating it if it is a history
+    Render(FileArgs),
+    /// draw a maze file with its solution, found with --solve=bfs unless another solver is given
+    Solve(FileArgs),
+    /// animate several generation methods side by side using the same seed
+    Compare(CompareArgs),
+    /// generate a maze and report statistics about it instead of an image
+    Stats(StatsArgs),
+    /// generate two mazes of the same size and report the fraction of walls they share
+    Similarity(SimilarityArgs),
+    /// describe a generation method, the options affecting it and a small sample of its mazes
+    Explain(ExplainArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct FileArgs {
+    /// maze written with --format json or history written with --save-history
+    #[arg(value_name = "file")]
+    file: String,
+
+    #[command(flatten)]
+    args: Box<Args>,
+}
+
+#[derive(clap::Args, Debug)]
+struct ExplainArgs {
+    /// generation method to describe
+    #[arg(value_name = "method")]
+    method: MazeType,
+
+    /// rng seed of the sample maze
+    #[arg(short = 's', long = "seed")]
+    seed: Option<u64>,
+}
+
+#[derive(clap::Args, Debug)]
+struct SimilarityArgs {
+    /// width of the mazes in cells
+    #[arg(value_name = "width", value_parser = clap::value_parser!(u32).range(1..=i32::MAX as i64))]
+    width: u32,
+
+    /// height of the mazes in cells
+    #[arg(value_name = "height", value_parser = clap::value_parser!(u32).range(1..=i32::MAX as i64))]
+    height: u32,
+
+    /// generation methods of the two mazes, a single method is used for both
+    #[arg(
+        short = 'm',
+        long = "methods",
+        value_delimiter = ',',
+        num_args = 1..=2,
+        default_value = "backtrack"
+    )]
+    methods: Vec<MazeType>,
+
+    /// wrap the mazes around their edges
+    #[arg(short = 'w', long = "wrap", default_value = "none")]
+    wrap: MazeWrap,
+
+    /// rng seeds of the two mazes, a single seed is used for both
+    #[arg(
+        short = 's',
+        long = "seeds",
+        value_delimiter = ',',
+        num_args = 1..=2,
+        required = true
+    )]
+    seeds: Vec<u64>,
+}
+
+#[derive(clap::Args, Debug)]
+struct StatsArgs {
+    /// width of the maze in cells
+    #[arg(
+        value_name = "width",
+        required_unless_present = "load",
+        value_parser = clap::value_parser!(u32).range(1..=i32::MAX as i64)
+    )]
+    width: Option<u32>,
+
+    /// height of the maze in cells
+    #[arg(
+        value_name = "height",
+        required_unless_present = "load",
+        value_parser = clap::value_parser!(u32).range(1..=i32::MAX as i64)
+    )]
+    height: Option<u32>,
+
+    /// measure a maze saved with --format json or --save-history instead of generating one
+    #[arg(
+        long = "load",
+        value_name = "file",
+        conflicts_with_all = [
+            "width", "height", "method", "wrap", "seed", "braid", "reroll_until"
+        ]
+    )]
+    load: Option<String>,
+
+    /// generation method used for the maze
+    #[arg(short = 'm', long = "method", default_value = "backtrack")]
+    method: MazeType,
+
+    /// wrap the maze around its edges
+    #[arg(short = 'w', long = "wrap", default_value = "none")]
+    wrap: MazeWrap,
+
+    /// rng seed
+    #[arg(short = 's', long = "seed")]
+    seed: Option<u64>,
+
+    /// open a wall at this percentage of dead ends before measuring, adding loops and with them
+    /// further solutions
+    #[arg(
+        long = "braid",
+        value_name = "percent",
+        value_parser = clap::value_parser!(u8).range(0..=100)
+    )]
+    braid: Option<u8>,
+
+    /// regenerate with seeds derived from the first one until the maze statistics satisfy a
+    /// condition such as "deadends<50 && solution>200"
+    #[arg(long = "reroll-until", value_name = "condition")]
+    reroll_until: Option<StatsPredicate>,
+
+    /// maximum number of mazes generated while looking for one matching --reroll-until
+    #[arg(
+        long = "reroll-limit",
+        default_value = "1000",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    reroll_limit: u32,
+
+    /// attach text to the cell at x,y, included in the json output. can be given several times
+    #[arg(long = "label", value_name = "x,y,text")]
+    labels: Vec<CellLabel>,
+
+    /// print statistics as json, including per-cell data such as generation order
+    #[arg(long = "json")]
+    json: bool,
+}
+
+#[derive(clap::Args, Debug)]
+struct CompareArgs {
+    /// width of the maze in cells
+    #[arg(value_name = "width", value_parser = clap::value_parser!(u32).range(1..=i32::MAX as i64))]
+    width: u32,
+
+    /// height of the maze in cells
+    #[arg(value_name = "height", value_parser = clap::value_parser!(u32).range(1..=i32::MAX as i64))]
+    height: u32,
+
+    /// comma separated list of generation methods to compare
+    #[arg(
+        short = 'm',
+        long = "methods",
+        value_delimiter = ',',
+        default_value = "backtrack,prim,wilson"
+    )]
+    methods: Vec<MazeType>,
+
+    /// wrap the maze around its edges
+    #[arg(short = 'w', long = "wrap", default_value = "none")]
+    wrap: MazeWrap,
+
+    /// file to save animation to, the extension is optional. a directory gets a file named after
+    /// the seed. "-" writes the animation to stdout
+    #[arg(
+        short = 'o',
+        long = "out",
+        value_name = "file",
+        default_value = "./compare"
+    )]
+    file_path: String,
+
+    /// number of new cells to draw per frame of animation
+    #[arg(
+        short = 'b',
+        long = "batch",
+        value_name = "batch size",
+        default_value = "1",
+        value_parser = clap::value_parser!(u16).range(1..)
+    )]
+    batch_size: u16,
+
+    /// rng seed
+    #[arg(short = 's', long = "seed")]
+    seed: Option<u64>,
+
+    /// pixel dimension of passages
+    #[arg(
+        long = "passagewidth",
+        default_value = "4",
+        value_parser = clap::value_parser!(u16).range(1..)
+    )]
+    passage_width: u16,
+
+    /// pixel dimension of walls
+    #[arg(long = "wallwidth", default_value = "1")]
+    wall_width: u16,
+
+    /// length of time between frames (units of 10ms)
+    #[arg(short = 'f', long = "frametime", default_value = "2")]
+    frame_time: u16,
+
+    /// length of time for final frame (units of 10ms)
+    #[arg(short = 'p', long = "pausetime", default_value = "100")]
+    pause_time: u16,
+
+    /// never draw a progress bar while encoding
+    #[arg(long = "no-progress")]
+    no_progress: bool,
+}
+
+#[derive(clap::Args, Debug)]
 struct Args {
     /// width of the maze in cells
-    #[arg(value_name = "width")]
-    width: u16,
+    #[arg(
+        value_name = "width",
+        value_parser = clap::value_parser!(u32).range(1..=i32::MAX as i64)
+    )]
+    width: Option<u32>,
 
     /// height of the maze in cells
-    #[arg(value_name = "height")]
-    height: u16,
+    #[arg(
+        value_name = "height",
+        value_parser = clap::value_parser!(u32).range(1..=i32::MAX as i64)
+    )]
+    height: Option<u32>,
 
     /// generation method used for the maze
     #[arg(short = 'm', long = "method", default_value = "backtrack")]
     method: MazeType,
 
-    /// file to save image to
+    /// wrap the maze around its edges
+    #[arg(short = 'w', long = "wrap", default_value = "none")]
+    wrap: MazeWrap,
+
+    /// shape of the cells, polar mazes have as many rings as the height and ignore the width
+    #[arg(long = "cells", value_name = "shape", default_value = "square")]
+    cells: CellShape,
+
+    /// order binary-tree and kruskal visit the cells in, ignored by the other methods
+    #[arg(long = "scan-order", value_name = "order", default_value = "row")]
+    scan_order: ScanOrder,
+
+    /// cell growing-tree grows from next: oldest, newest, random, a random one of the newest
+    /// percent of cells, or a weighted mix like newest:50,random:50. ignored by the other methods
+    #[arg(long = "bias", value_name = "bias", default_value = "10")]
+    bias: GrowingTreeBias,
+
+    /// corner binary-tree passages lead toward or side sidewinder runs lie along, turning their
+    /// bias from the north west
+    #[arg(long = "orientation", value_name = "way")]
+    orientation: Option<Orientation>,
+
+    /// cost of carving into each cell for --method prim or kruskal, from the noise of a noise
+    /// maze or the brightness of a png with image:<file>. cheap areas get long passages along
+    /// lines of equal cost
+    #[arg(long = "weights", value_name = "weights")]
+    weights: Option<WeightSource>,
+
+    /// file to save image to, the extension is optional. a directory gets a file named after the
+    /// method and seed. "-" writes the image to stdout
     #[arg(
         short = 'o',
         long = "out",
@@ -39,25 +352,457 @@ struct Args {
     #[arg(short = 'a', long = "animate")]
     animate: bool,
 
+    /// file format of the image
+    #[arg(long = "format", value_name = "format", default_value = "png")]
+    format: ImageFormat,
+
+    /// height of the walls above the base in millimeters for --format stl, where every pixel is
+    /// a millimeter
+    #[arg(long = "wall-height", value_name = "mm", default_value = "5")]
+    wall_height: f32,
+
+    /// thickness of the base plate under the maze in millimeters for --format stl
+    #[arg(long = "base-thickness", value_name = "mm", default_value = "2")]
+    base_thickness: f32,
+
+    /// paper size of the pages for --format pdf
+    #[arg(long = "page", value_name = "size", default_value = "a4")]
+    page: PageSize,
+
+    /// blank space around the maze on every side of the page in millimeters for --format pdf
+    #[arg(long = "margin", value_name = "mm", default_value = "15")]
+    margin: f32,
+
+    /// text printed above the maze for --format pdf
+    #[arg(long = "title", value_name = "text")]
+    title: Option<String>,
+
+    /// report the printed width of walls and passages and the ink used for --format pdf, with a
+    /// warning and suggested widths when the maze will not print well
+    #[arg(long = "print-check")]
+    print_check: bool,
+
+    /// resolution of the printer in dots per inch for --print-check
+    #[arg(
+        long = "dpi",
+        default_value = "300",
+        value_parser = clap::value_parser!(u16).range(1..)
+    )]
+    dpi: u16,
+
+    /// glyphs of the walls for --format text: ascii, light, heavy, double, rounded, block, or 16
+    /// characters for the corners where no, north, east, north and east, ... walls meet, counting
+    /// north 1, east 2, south 4 and west 8
+    #[arg(long = "text-style", value_name = "style", default_value = "light")]
+    text_style: TileMap,
+
+    /// render a maze saved with --format json instead of generating one
+    #[arg(
+        long = "load",
+        value_name = "file",
+        conflicts_with_all = [
+            "width", "height", "method", "wrap", "scan_order", "bias", "orientation", "seed", "daily"
+        ]
+    )]
+    load: Option<String>,
+
+    /// render a maze and its history saved with --save-history instead of generating one, the
+    /// history can be animated again with other image options
+    #[arg(
+        long = "replay",
+        value_name = "file",
+        conflicts_with_all = [
+            "load", "width", "height", "method", "wrap", "scan_order", "bias", "orientation",
+            "seed", "daily"
+        ]
+    )]
+    replay: Option<String>,
+
+    /// also save the finished maze with the history that carved it to a file, to be rendered
+    /// again with --replay
+    #[arg(long = "save-history", value_name = "file")]
+    save_history: Option<String>,
+
+    // read by run from --load or --replay
+    #[arg(skip)]
+    loaded: Option<Grid>,
+
+    // read by run from --replay
+    #[arg(skip)]
+    loaded_history: Option<MazeHistory>,
+
+    // rooms written into the config instead of a rooms file
+    #[arg(skip)]
+    rooms: Option<Rooms>,
+
+    // ids of the options taken from the config by apply_config
+    #[arg(skip)]
+    from_config: Vec<&'static str>,
+
+    /// only render the cells inside x,y,width,height
+    #[arg(long = "viewport", value_name = "x,y,w,h")]
+    viewport: Option<Rect>,
+
+    /// leave the cells inside x,y,width,height out of the maze, can be given several times
+    #[arg(long = "exclude", value_name = "x,y,w,h")]
+    exclude: Vec<Rect>,
+
+    /// shape of the maze outline, cells outside of it are excluded
+    #[arg(long = "border", value_name = "shape")]
+    border: Option<BorderShape>,
+
+    /// png stretched over the maze, cells on black pixels are excluded
+    #[arg(long = "mask", value_name = "file")]
+    mask: Option<String>,
+
+    /// carve spiral, hilbert or lsystem:axiom:X=rule,...:iterations first and grow the maze around it
+    #[arg(long = "pattern", value_name = "pattern")]
+    pattern: Option<Pattern>,
+
+    /// text file laying out the maze with . for maze, R for room and # for excluded cells
+    #[arg(long = "template", value_name = "file")]
+    template: Option<String>,
+
+    /// number of cells across each character of the template covers
+    #[arg(
+        long = "template-scale",
+        value_name = "cells",
+        default_value = "1",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    template_scale: u32,
+
+    /// toml file with a [[room]] table for every room, each with a shape of
+    /// "rect:x,y,width,height", "circle:x,y,radius" or "polygon:x,y;x,y;x,y;..." and an optional
+    /// name
+    #[arg(long = "rooms-file", value_name = "file", conflicts_with = "template")]
+    rooms_file: Option<String>,
+
+    /// how template or --rooms-file rooms are joined to the maze: auto where the maze grows into
+    /// them, none to leave them sealed, even:N for N doors per room spread over its sides (or
+    /// even:N:north,south,... for only those sides), or only at the walls listed as
+    /// x,y,direction;... from a room cell
+    #[arg(long = "room-doors", value_name = "doors", default_value = "auto")]
+    room_doors: RoomDoors,
+
+    /// lay a coarse maze over the maze with every coarse cell covering cells by cells of it, the
+    /// walls of the coarse maze remove the cells beneath them so the maze follows it
+    #[arg(
+        long = "overlay",
+        value_name = "cells",
+        value_parser = clap::value_parser!(u16).range(1..)
+    )]
+    overlay: Option<u16>,
+
+    /// thickness in cells of the walls of the --overlay maze
+    #[arg(
+        long = "overlay-wall",
+        value_name = "cells",
+        default_value = "1",
+        value_parser = clap::value_parser!(u16).range(1..)
+    )]
+    overlay_wall: u16,
+
+    /// method carving the --overlay maze
+    #[arg(
+        long = "overlay-method",
+        value_name = "method",
+        default_value = "backtrack"
+    )]
+    overlay_method: MazeType,
+
+    /// how excluded cells are drawn
+    #[arg(long = "exclude-style", value_name = "style", default_value = "color")]
+    exclude_style: ExcludeStyle,
+
+    /// color of excluded cells as RRGGBB
+    #[arg(long = "exclude-color", value_name = "color", default_value = "808080")]
+    exclude_color: Color,
+
+    /// swap walls and passages of the finished maze, can not be animated
+    #[arg(long = "invert", conflicts_with = "animate")]
+    invert: bool,
+
+    /// rotate or mirror the finished maze by a symmetry picked with the seed, mazes that are not
+    /// square keep their size
+    #[arg(long = "random-rotate")]
+    random_rotate: bool,
+
+    /// remove up to this many dead end cells from the finished maze, can not be animated
+    #[arg(long = "uncarve", value_name = "cells", conflicts_with = "animate")]
+    uncarve: Option<u32>,
+
+    /// which dead end --uncarve removes next
+    #[arg(
+        long = "uncarve-strategy",
+        value_name = "strategy",
+        default_value = "uniform"
+    )]
+    uncarve_strategy: UncarveStrategy,
+
+    /// open a wall at this percentage of dead ends, adding loops to the finished maze
+    #[arg(
+        long = "braid",
+        value_name = "percent",
+        value_parser = clap::value_parser!(u8).range(0..=100)
+    )]
+    braid: Option<u8>,
+
+    /// keep passages on only this percentage of the cells outside of rooms, removing dead ends
+    /// and then the short stubs left branching off the corridors, for dungeons of rooms joined by
+    /// winding corridors. can not be animated
+    #[arg(
+        long = "corridors",
+        value_name = "percent",
+        value_parser = clap::value_parser!(u8).range(0..=100),
+        conflicts_with = "animate"
+    )]
+    corridors: Option<u8>,
+
+    /// number of noise features across the maze for --method noise or --weights noise, more give
+    /// smaller regions
+    #[arg(
+        long = "noise-frequency",
+        value_name = "features",
+        value_parser = clap::value_parser!(u16).range(1..=1024)
+    )]
+    noise_frequency: Option<u16>,
+
+    /// noise level in hundredths from -100 to 100 dividing the cells --method noise floods
+    /// differently
+    #[arg(
+        long = "noise-threshold",
+        value_name = "level",
+        allow_hyphen_values = true,
+        value_parser = clap::value_parser!(i8).range(-100..=100)
+    )]
+    noise_threshold: Option<i8>,
+
+    /// also write the noise a --method noise maze was carved along as <out>-noise.png
+    #[arg(long = "noise-map")]
+    noise_map: bool,
+
+    /// color passages along a gradient by a per-cell metric
+    #[arg(long = "color-by", value_name = "metric")]
+    color_by: Option<ColorBy>,
+
+    /// cell at x,y distances are measured from for --color-by distance and --format distance-map,
+    /// by default the entrance or the top left cell
+    #[arg(long = "distance-from", value_name = "x,y")]
+    distance_from: Option<Point>,
+
+    /// color of the lowest value of --color-by
+    #[arg(
+        long = "gradient-start",
+        value_name = "color",
+        default_value = "2040ff"
+    )]
+    gradient_start: Color,
+
+    /// color of the highest value of --color-by
+    #[arg(long = "gradient-end", value_name = "color", default_value = "ff4020")]
+    gradient_end: Color,
+
+    /// narrow the passages with the distance from the entrance, from the full --passagewidth down
+    /// to min pixels at the farthest cell
+    #[arg(
+        long = "taper",
+        value_name = "min",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "1",
+        value_parser = clap::value_parser!(u16).range(1..)
+    )]
+    taper: Option<u16>,
+
+    /// label columns with letters and rows with numbers along the edges of images
+    #[arg(long = "rulers")]
+    rulers: bool,
+
+    /// write the png as square tiles of this many pixels into a directory, together with an
+    /// index.html to browse them, for mazes too large to render as a single image
+    #[arg(
+        long = "tiles",
+        value_name = "size",
+        value_parser = clap::value_parser!(u16).range(1..)
+    )]
+    tiles: Option<u16>,
+
+    /// pixels of the neighboring tiles also drawn along every edge of a tile
+    #[arg(long = "tile-overlap", value_name = "pixels", default_value = "0")]
+    tile_overlap: u16,
+
+    /// open the outer wall as side:cell, side:center, side:random or random, with cells counted
+    /// from 0 at the top or left end of the side
+    #[arg(long = "entrance", value_name = "opening")]
+    entrance: Option<Opening>,
+
+    /// open the outer wall for the exit, written like --entrance
+    #[arg(long = "exit", value_name = "opening")]
+    exit: Option<Opening>,
+
+    /// place the entrance and exit inside the maze instead of opening the outer wall, marked S and
+    /// E in the image
+    #[arg(long = "entrances", value_name = "placement", conflicts_with_all = ["entrance", "exit"])]
+    entrances: Option<Entrances>,
+
+    /// draw text on the cell at x,y, can be given several times
+    #[arg(long = "label", value_name = "x,y,text")]
+    labels: Vec<CellLabel>,
+
+    /// solve the maze from the entrance to the exit, by default the top left and bottom right
+    /// cells, and draw the path. animations play back the solver's search after generation
+    #[arg(
+        long = "solve",
+        value_name = "solver",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "bfs",
+        conflicts_with = "animate_solve"
+    )]
+    solve: Option<SolverType>,
+
+    /// also write the path found by --solve as moves for programs walking the maze
+    #[arg(long = "solution-format", value_name = "format")]
+    solution_format: Option<SolutionFormat>,
+
+    /// animate a solver from the entrance to the exit instead of the generation
+    #[arg(long = "animate-solve", value_name = "solver")]
+    animate_solve: Option<SolverType>,
+
     /// try to compress generated gif
     #[arg(short = 'c', long = "compress", default_value = "false")]
     compress: bool,
 
+    /// file format of the generation animation
+    #[arg(
+        long = "animation-format",
+        value_name = "format",
+        default_value = "gif"
+    )]
+    animation_format: AnimationFormat,
+
+    /// also write the generation as a wav file lasting as long as the animation, every carved
+    /// cell sounds higher further right and pans from left to right going down
+    #[arg(long = "audio")]
+    audio: bool,
+
+    /// also write a png of this many snapshots of the animation side by side, evenly spaced from
+    /// the empty to the finished maze
+    #[arg(
+        long = "keyframes",
+        value_name = "count",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "5",
+        value_parser = clap::value_parser!(u16).range(2..)
+    )]
+    keyframes: Option<u16>,
+
     /// number of new cells to draw per frame of animation
     #[arg(
         short = 'b',
         long = "batch",
         value_name = "batch size",
-        default_value = "1"
+        default_value = "1",
+        value_parser = clap::value_parser!(u16).range(1..)
     )]
     batch_size: u16,
 
+    /// fold frames that change nothing into the frame before them
+    #[arg(long = "skip-unchanged")]
+    skip_unchanged: bool,
+
     /// rng seed
     #[arg(short = 's', long = "seed")]
     seed: Option<u64>,
 
+    /// rng seed of --uncarve instead of one derived from --seed, so the removed dead ends
+    /// change without changing the maze
+    #[arg(long = "seed-uncarve", value_name = "seed")]
+    seed_uncarve: Option<u64>,
+
+    /// rng seed of --braid instead of one derived from --seed
+    #[arg(long = "seed-braid", value_name = "seed")]
+    seed_braid: Option<u64>,
+
+    /// rng seed of --random-rotate instead of one derived from --seed
+    #[arg(long = "seed-symmetry", value_name = "seed")]
+    seed_symmetry: Option<u64>,
+
+    /// rng seed of random --entrance and --exit positions instead of one derived from --seed
+    #[arg(long = "seed-openings", value_name = "seed")]
+    seed_openings: Option<u64>,
+
+    /// rng seed of the doors placed by --room-doors even instead of one derived from --seed
+    #[arg(long = "seed-doors", value_name = "seed")]
+    seed_doors: Option<u64>,
+
+    /// rng seed of --corridors instead of one derived from --seed
+    #[arg(long = "seed-corridors", value_name = "seed")]
+    seed_corridors: Option<u64>,
+
+    /// rng seed of the coarse maze of --overlay instead of one derived from --seed
+    #[arg(long = "seed-overlay", value_name = "seed")]
+    seed_overlay: Option<u64>,
+
+    /// derive the seed from the current utc date and an optional namespace, so everyone using
+    /// the same options and namespace gets the same maze of the day
+    #[arg(
+        long = "daily",
+        value_name = "namespace",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "",
+        conflicts_with = "seed"
+    )]
+    daily: Option<String>,
+
+    /// regenerate with seeds derived from the first one until the maze statistics satisfy a
+    /// condition such as "deadends<50 && solution>200"
+    #[arg(long = "reroll-until", value_name = "condition")]
+    reroll_until: Option<StatsPredicate>,
+
+    /// maximum number of mazes generated while looking for one matching --reroll-until
+    #[arg(
+        long = "reroll-limit",
+        default_value = "1000",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    reroll_limit: u32,
+
+    /// print statistics of the finished maze such as dead ends, junctions, corridor lengths and
+    /// the longest path, to compare the character of generation methods
+    #[arg(
+        long = "stats",
+        value_name = "format",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "table"
+    )]
+    stats: Option<StatsFormat>,
+
+    /// number of mazes to generate in parallel, each saved to its own numbered file with a
+    /// seed derived from --seed
+    #[arg(
+        short = 'n',
+        long = "count",
+        default_value = "1",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    count: u32,
+
+    /// never draw progress bars, which otherwise appear on stderr for work taking more than a
+    /// couple of seconds
+    #[arg(long = "no-progress")]
+    no_progress: bool,
+
     /// pixel dimension of passages
-    #[arg(long = "passagewidth", default_value = "4")]
+    #[arg(
+        long = "passagewidth",
+        default_value = "4",
+        value_parser = clap::value_parser!(u16).range(1..)
+    )]
     passage_width: u16,
 
     /// pixel dimension of walls
@@ -71,49 +816,1793 @@ struct Args {
     /// length of time for final frame (units of 10ms)
     #[arg(short = 'p', long = "pausetime", default_value = "100")]
     pause_time: u16,
+
+    /// run a shell command after each image is written, {file} is replaced by its path. the
+    /// path is appended if the command has no {file}
+    #[arg(long = "exec", value_name = "command")]
+    exec: Option<String>,
+
+    /// read options from a toml file, flags given on the command line take precedence
+    #[arg(long = "config", value_name = "file")]
+    config: Option<String>,
+
+    /// print the resolved options as toml that can be passed back with --config, then exit
+    #[arg(long = "print-config")]
+    print_config: bool,
+}
+
+impl Args {
+    // everything carving would decide comes from a loaded maze
+    fn take_loaded(&mut self, maze: Grid) {
+        self.width = Some(maze.width);
+        self.height = Some(maze.height);
+        self.method = maze.params.method;
+        self.wrap = maze.wrap;
+        self.scan_order = maze.params.scan_order;
+        self.bias = maze.params.bias.clone();
+        self.orientation = maze.params.orientation;
+        self.seed = Some(maze.params.seed);
+        self.loaded = Some(maze);
+    }
+
+    // takes every option that was not given on the command line from the config, except for
+    // those in ignored
+    fn apply_config(&mut self, config: Config, matches: &ArgMatches, ignored: &[String]) {
+        macro_rules! merge {
+            ($($field:ident <- $key:ident),* $(,)?) => {
+                $(
+                    let id = stringify!($field);
+                    if matches.value_source(id) != Some(ValueSource::CommandLine)
+                        && !ignored.iter().any(|ignored| ignored == id)
+                    {
+                        if let Some(value) = config.$key {
+                            self.$field = value.into();
+                            self.from_config.push(id);
+                        }
+                    }
+                )*
+            };
+        }
+
+        merge!(
+            width <- width,
+            height <- height,
+            method <- method,
+            wrap <- wrap,
+            cells <- cells,
+            scan_order <- scan_order,
+            bias <- bias,
+            orientation <- orientation,
+            weights <- weights,
+            file_path <- out,
+            animate <- animate,
+            format <- format,
+            wall_height <- wall_height,
+            base_thickness <- base_thickness,
+            page <- page,
+            margin <- margin,
+            title <- title,
+            print_check <- print_check,
+            dpi <- dpi,
+            text_style <- text_style,
+            load <- load,
+            replay <- replay,
+            save_history <- save_history,
+            viewport <- viewport,
+            exclude <- exclude,
+            border <- border,
+            mask <- mask,
+            pattern <- pattern,
+            template <- template,
+            template_scale <- template_scale,
+            rooms_file <- rooms_file,
+            room_doors <- room_doors,
+            overlay <- overlay,
+            overlay_wall <- overlay_wall,
+            overlay_method <- overlay_method,
+            exclude_style <- exclude_style,
+            exclude_color <- exclude_color,
+            invert <- invert,
+            random_rotate <- random_rotate,
+            uncarve <- uncarve,
+            uncarve_strategy <- uncarve_strategy,
+            braid <- braid,
+            corridors <- corridors,
+            noise_frequency <- noise_frequency,
+            noise_threshold <- noise_threshold,
+            noise_map <- noise_map,
+            color_by <- color_by,
+            distance_from <- distance_from,
+            gradient_start <- gradient_start,
+            gradient_end <- gradient_end,
+            taper <- taper,
+            rulers <- rulers,
+            tiles <- tiles,
+            tile_overlap <- tile_overlap,
+            entrance <- entrance,
+            entrances <- entrances,
+            exit <- exit,
+            labels <- label,
+            solve <- solve,
+            solution_format <- solution_format,
+            animate_solve <- animate_solve,
+            compress <- compress,
+            animation_format <- animation_format,
+            audio <- audio,
+            keyframes <- keyframes,
+            batch_size <- batch,
+            skip_unchanged <- skip_unchanged,
+            seed <- seed,
+            seed_uncarve <- seed_uncarve,
+            seed_braid <- seed_braid,
+            seed_symmetry <- seed_symmetry,
+            seed_openings <- seed_openings,
+            seed_doors <- seed_doors,
+            seed_corridors <- seed_corridors,
+            seed_overlay <- seed_overlay,
+            daily <- daily,
+            reroll_until <- reroll_until,
+            reroll_limit <- reroll_limit,
+            stats <- stats,
+            count <- count,
+            no_progress <- no_progress,
+            passage_width <- passagewidth,
+            wall_width <- wallwidth,
+            frame_time <- frametime,
+            pause_time <- pausetime,
+            exec <- exec,
+        );
+
+        // a rooms file on the command line replaces the rooms of the config
+        if matches.value_source("rooms_file") != Some(ValueSource::CommandLine) {
+            self.rooms = config.rooms.map(|rooms| Rooms { rooms });
+        }
+    }
+
+    fn to_config(&self) -> Config {
+        Config {
+            width: self.width,
+            height: self.height,
+            method: Some(self.method),
+            wrap: Some(self.wrap),
+            cells: Some(self.cells),
+            scan_order: Some(self.scan_order),
+            bias: Some(self.bias.clone()),
+            orientation: self.orientation,
+            weights: self.weights.clone(),
+            out: Some(self.file_path.clone()),
+            animate: Some(self.animate),
+            format: Some(self.format),
+            wall_height: Some(self.wall_height),
+            base_thickness: Some(self.base_thickness),
+            page: Some(self.page),
+            margin: Some(self.margin),
+            title: self.title.clone(),
+            print_check: Some(self.print_check),
+            dpi: Some(self.dpi),
+            text_style: Some(self.text_style),
+            load: self.load.clone(),
+            replay: self.replay.clone(),
+            save_history: self.save_history.clone(),
+            viewport: self.viewport,
+            exclude: Some(self.exclude.clone()),
+            border: self.border,
+            mask: self.mask.clone(),
+            pattern: self.pattern.clone(),
+            template: self.template.clone(),
+            template_scale: Some(self.template_scale),
+            rooms_file: self.rooms_file.clone(),
+            room_doors: Some(self.room_doors.clone()),
+            overlay: self.overlay,
+            overlay_wall: Some(self.overlay_wall),
+            overlay_method: Some(self.overlay_method),
+            exclude_style: Some(self.exclude_style),
+            exclude_color: Some(self.exclude_color),
+            invert: Some(self.invert),
+            random_rotate: Some(self.random_rotate),
+            uncarve: self.uncarve,
+            uncarve_strategy: Some(self.uncarve_strategy),
+            braid: self.braid,
+            corridors: self.corridors,
+            noise_frequency: self.noise_frequency,
+            noise_threshold: self.noise_threshold,
+            noise_map: Some(self.noise_map),
+            color_by: self.color_by,
+            distance_from: self.distance_from,
+            gradient_start: Some(self.gradient_start),
+            gradient_end: Some(self.gradient_end),
+            taper: self.taper,
+            rulers: Some(self.rulers),
+            tiles: self.tiles,
+            tile_overlap: Some(self.tile_overlap),
+            entrance: self.entrance,
+            entrances: self.entrances,
+            exit: self.exit,
+            label: Some(self.labels.clone()),
+            solve: self.solve,
+            solution_format: self.solution_format,
+            animate_solve: self.animate_solve,
+            compress: Some(self.compress),
+            animation_format: Some(self.animation_format),
+            audio: Some(self.audio),
+            keyframes: self.keyframes,
+            batch: Some(self.batch_size),
+            skip_unchanged: Some(self.skip_unchanged),
+            seed: self.seed,
+            seed_uncarve: self.seed_uncarve,
+            seed_braid: self.seed_braid,
+            seed_symmetry: self.seed_symmetry,
+            seed_openings: self.seed_openings,
+            seed_doors: self.seed_doors,
+            seed_corridors: self.seed_corridors,
+            seed_overlay: self.seed_overlay,
+            // already turned into the seed
+            daily: None,
+            reroll_until: self.reroll_until.clone(),
+            reroll_limit: Some(self.reroll_limit),
+            stats: self.stats,
+            count: Some(self.count),
+            no_progress: Some(self.no_progress),
+            passagewidth: Some(self.passage_width),
+            wallwidth: Some(self.wall_width),
+            frametime: Some(self.frame_time),
+            pausetime: Some(self.pause_time),
+            exec: self.exec.clone(),
+            rooms: self.rooms.as_ref().map(|rooms| rooms.rooms.clone()),
+        }
+    }
+}
+
+// cancelled by ctrl-c, which then stops carving and cuts animations short while still writing
+// them as complete files. a second ctrl-c exits right away
+fn cancel_token() -> CancelToken {
+    static CANCEL: OnceLock<CancelToken> = OnceLock::new();
+    CANCEL
+        .get_or_init(|| {
+            let cancel = CancelToken::new();
+            let handler = cancel.clone();
+            // without the handler ctrl-c simply ends the process
+            let _ = ctrlc::set_handler(move || {
+                if handler.0.swap(true, Ordering::Relaxed) {
+                    process::exit(LabgenError::Cancelled.exit_code());
+                }
+            });
+            cancel
+        })
+        .clone()
 }
 
 fn main() {
-    let args = Args::parse();
+    if let Err(e) = run() {
+        eprintln!("error: {}", e);
+        process::exit(e.exit_code());
+    }
+}
+
+fn run() -> Result<(), LabgenError> {
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
 
+    match cli.command {
+        Some(Command::Compare(args)) => compare(args),
+        Some(Command::Stats(args)) => stats(args),
+        Some(Command::Similarity(args)) => similarity(args),
+        Some(Command::Explain(args)) => render_explain(
+            args.method,
+            args.seed.unwrap_or(rand::random::<u64>()),
+            &mut io::stdout(),
+        ),
+        Some(Command::Generate(args)) => {
+            let matches = subcommand_matches(&matches);
+            plain(with_config(*args, matches)?, matches)
+        }
+        Some(Command::Render(render)) => {
+            let matches = subcommand_matches(&matches);
+            let args = with_config(*render.args, matches)?;
+            plain(open_file(args, &render.file, "render", matches)?, matches)
+        }
+        Some(Command::Solve(solve)) => {
+            let matches = subcommand_matches(&matches);
+            let args = with_config(*solve.args, matches)?;
+            let mut args = open_file(args, &solve.file, "solve", matches)?;
+            if args.solve.is_none() && args.animate_solve.is_none() {
+                args.solve = Some(SolverType::default());
+            }
+            plain(args, matches)
+        }
+        None => plain(with_config(cli.args, &matches)?, &matches),
+    }
+}
+
+fn subcommand_matches(matches: &ArgMatches) -> &ArgMatches {
+    matches.subcommand().unwrap().1
+}
+
+fn with_config(mut args: Args, matches: &ArgMatches) -> Result<Args, LabgenError> {
+    if let Some(path) = args.config.clone() {
+        let config = Config::load(&path)?;
+        let ignored = check_config(&config, &path, matches)?;
+        args.apply_config(config, matches, &ignored);
+    }
+    Ok(args)
+}
+
+// checks the options of the config the way clap checks the command line, parsing them again as
+// flags so every value meets the range of its flag and no two conflict. returns the ids of the
+// options of the config that conflict with the command line, which replaces them
+fn check_config(
+    config: &Config,
+    path: &str,
+    matches: &ArgMatches,
+) -> Result<Vec<String>, LabgenError> {
+    let mut cmd = Cli::command();
+    cmd.build();
+    let table = toml::Table::try_from(config).unwrap();
+    let on_command_line = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+    // flags set to false and empty lists count as missing, like options left off the command
+    // line. width and height are the only positional arguments
+    let mut given = Vec::new();
+    let mut positional = [None, None];
+    let mut flags = Vec::new();
+    for arg in cmd.get_arguments() {
+        let key = arg.get_long().unwrap_or(arg.get_id().as_str());
+        let values: Vec<String> = match table.get(key) {
+            None | Some(toml::Value::Boolean(false)) => continue,
+            Some(toml::Value::Array(values)) => values.iter().map(toml_text).collect(),
+            Some(value) => vec![toml_text(value)],
+        };
+        if values.is_empty() {
+            continue;
+        }
+        given.push(arg);
+        for value in values {
+            match (arg.get_index(), arg.get_action().takes_values()) {
+                (Some(index), _) => positional[index - 1] = Some(value),
+                (None, true) => flags.push(format!("--{}={}", key, value)),
+                (None, false) => flags.push(format!("--{}", key)),
+            }
+        }
+    }
+    // a height can not be given without a width before it
+    if positional[1].is_some() && positional[0].is_none() {
+        positional[0] = Some("1".to_string());
+    }
+    let argv = ["labgen".to_string()]
+        .into_iter()
+        .chain(positional.into_iter().flatten())
+        .chain(flags);
+    if let Err(e) = cmd.clone().try_get_matches_from(argv) {
+        let message = e.to_string();
+        let message = message.lines().next().unwrap();
+        return Err(LabgenError::Usage(format!(
+            "invalid config {}: {}",
+            path,
+            message.strip_prefix("error: ").unwrap_or(message)
+        )));
+    }
+
+    // options of the config conflicting with the command line, in either direction
+    let mut ignored = Vec::new();
+    for arg in &given {
+        let id = arg.get_id().as_str();
+        if on_command_line(id) {
+            continue;
+        }
+        let conflicts = cmd
+            .get_arg_conflicts_with(arg)
+            .into_iter()
+            .any(|other| on_command_line(other.get_id().as_str()))
+            || cmd.get_arguments().any(|other| {
+                cmd.get_arg_conflicts_with(other)
+                    .iter()
+                    .any(|c| c.get_id() == arg.get_id())
+                    && on_command_line(other.get_id().as_str())
+            });
+        if conflicts {
+            ignored.push(id.to_string());
+        }
+    }
+    Ok(ignored)
+}
+
+// value of a config key as it would be written on the command line
+fn toml_text(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+// arguments of render and solve, reading the maze from file as --load does, or as --replay does
+// for histories so they can be animated again. the file takes the place of a load or replay key
+// of the config
+fn open_file(
+    mut args: Args,
+    file: &str,
+    command: &str,
+    matches: &ArgMatches,
+) -> Result<Args, LabgenError> {
+    for id in ["width", "height", "load", "replay"] {
+        if matches.value_source(id) == Some(ValueSource::CommandLine) {
+            return Err(LabgenError::Usage(format!(
+                "{} takes the maze from {}, {} can not be given",
+                command,
+                file,
+                if matches!(id, "width" | "height") {
+                    "its size".to_string()
+                } else {
+                    format!("--{}", id)
+                }
+            )));
+        }
+    }
+    (args.load, args.replay) = if is_history_file(file)? {
+        (None, Some(file.to_string()))
+    } else {
+        (Some(file.to_string()), None)
+    };
+    Ok(args)
+}
+
+// the plain invocation, which generate runs as well
+fn plain(mut args: Args, matches: &ArgMatches) -> Result<(), LabgenError> {
+    if args.load.is_some() && args.replay.is_some() {
+        return Err(LabgenError::Usage(
+            "--replay can not be combined with --load".to_string(),
+        ));
+    }
+    if let Some(path) = args.load.clone() {
+        check_load(&args, "--load")?;
+        let maze = read_maze(&path)?;
+        args.take_loaded(maze);
+    }
+    if let Some(path) = args.replay.clone() {
+        check_load(&args, "--replay")?;
+        let (maze, history) = load_history(&path)?;
+        if args.animate && !history.agrees_with(&maze) {
+            return Err(LabgenError::Usage(format!(
+                "{}: the maze was changed after carving, its history can not be animated",
+                path
+            )));
+        }
+        args.take_loaded(maze);
+        args.loaded_history = Some(history);
+    }
+    if args.save_history.is_some() && args.count > 1 {
+        return Err(LabgenError::Usage(
+            "--save-history can not be combined with --count".to_string(),
+        ));
+    }
+    if args.width.is_none() || args.height.is_none() {
+        return Err(LabgenError::Usage(
+            "width and height must be given on the command line or in the config".to_string(),
+        ));
+    }
+    check_cell_width(args.passage_width, args.wall_width)?;
+    check_labels(&args.labels, args.width.unwrap(), args.height.unwrap())?;
+    check_exclusions(&args.exclude, args.width.unwrap(), args.height.unwrap())?;
+    // [[room]] tables are not options, check_config does not see them
+    if args.rooms.is_some() && args.rooms_file.is_some() {
+        return Err(LabgenError::Usage(
+            "[[room]] tables can not be combined with --rooms-file".to_string(),
+        ));
+    }
+    if args.rooms.is_some() && args.template.is_some() {
+        return Err(LabgenError::Usage(
+            "[[room]] tables can not be combined with --template".to_string(),
+        ));
+    }
+    for opening in [args.entrance, args.exit].into_iter().flatten() {
+        if let Some(side) = opening.side {
+            if !BorderSide::walled(args.wrap).contains(&side) {
+                return Err(LabgenError::Usage(format!(
+                    "opening {} lies on an edge the maze wraps around",
+                    opening
+                )));
+            }
+        }
+    }
+    if args.format == ImageFormat::Ansi {
+        // an output file in the config gives way to --format ansi on the command line
+        let from_config = |id| args.from_config.contains(&id);
+        if args.file_path != "-"
+            && (matches.value_source("file_path") == Some(ValueSource::CommandLine)
+                || from_config("file_path") && from_config("format"))
+        {
+            return Err(LabgenError::Usage(
+                "--format ansi prints to the terminal and can not be combined with --out"
+                    .to_string(),
+            ));
+        }
+        args.file_path = "-".to_string();
+    }
+    if args.exec.is_some() && args.file_path == "-" {
+        return Err(LabgenError::Usage(
+            "--exec can not be combined with writing to stdout".to_string(),
+        ));
+    }
+    // the history of an inverted or uncarved maze no longer matches its connections
+    if args.invert && args.animate {
+        return Err(LabgenError::Usage(
+            "--invert can not be combined with --animate".to_string(),
+        ));
+    }
+    if args.animation_format != AnimationFormat::Gif && !args.animate {
+        return Err(LabgenError::Usage(
+            "--animation-format can only be combined with --animate".to_string(),
+        ));
+    }
+    if args.animation_format != AnimationFormat::Gif && args.format == ImageFormat::Ansi {
+        return Err(LabgenError::Usage(
+            "--animation-format can not be combined with --format ansi".to_string(),
+        ));
+    }
+    if args.audio && !args.animate {
+        return Err(LabgenError::Usage(
+            "--audio can only be combined with --animate".to_string(),
+        ));
+    }
+    if args.audio && args.file_path == "-" {
+        return Err(LabgenError::Usage(
+            "--audio can not be combined with writing to stdout".to_string(),
+        ));
+    }
+    if args.solution_format.is_some() && args.solve.is_none() {
+        return Err(LabgenError::Usage(
+            "--solution-format can only be combined with --solve".to_string(),
+        ));
+    }
+    if args.solution_format.is_some() && args.file_path == "-" {
+        return Err(LabgenError::Usage(
+            "--solution-format can not be combined with writing to stdout".to_string(),
+        ));
+    }
+    // the statistics of every maze would interleave on the terminal
+    if args.stats.is_some() && args.count > 1 {
+        return Err(LabgenError::Usage(
+            "--stats can not be combined with --count".to_string(),
+        ));
+    }
+    if args.keyframes.is_some() && !args.animate {
+        return Err(LabgenError::Usage(
+            "--keyframes can only be combined with --animate".to_string(),
+        ));
+    }
+    if args.keyframes.is_some() && args.file_path == "-" {
+        return Err(LabgenError::Usage(
+            "--keyframes can not be combined with writing to stdout".to_string(),
+        ));
+    }
+    if args.tiles.is_some() {
+        // tiles only show the maze, the other options need the whole image
+        let whole_image = [
+            ("--animate", args.animate),
+            ("--animate-solve", args.animate_solve.is_some()),
+            ("--solve", args.solve.is_some()),
+            ("--color-by", args.color_by.is_some()),
+            ("--taper", args.taper.is_some()),
+            ("--label", !args.labels.is_empty()),
+            ("--rulers", args.rulers),
+            ("writing to stdout", args.file_path == "-"),
+        ];
+        if let Some((flag, _)) = whole_image.iter().find(|(_, used)| *used) {
+            return Err(LabgenError::Usage(format!(
+                "--tiles can not be combined with {}",
+                flag
+            )));
+        }
+    }
+    if args.taper.is_some() {
+        // passages are narrowed in the finished image only
+        let other_image = [
+            ("--animate", args.animate),
+            ("--animate-solve", args.animate_solve.is_some()),
+            ("--color-by", args.color_by.is_some()),
+        ];
+        if let Some((flag, _)) = other_image.iter().find(|(_, used)| *used) {
+            return Err(LabgenError::Usage(format!(
+                "--taper can not be combined with {}",
+                flag
+            )));
+        }
+    }
+    if args.format != ImageFormat::Png {
+        // the other formats only hold the maze, not what would be drawn over it. pdf
+        // pages show the image, with the solution on a page of its own, ansi prints the
+        // image or plays its animation in the terminal, braille text shows its walls and
+        // distance maps color the image
+        let maze_only = !matches!(
+            args.format,
+            ImageFormat::Pdf | ImageFormat::Ansi | ImageFormat::Braille | ImageFormat::DistanceMap
+        );
+        let colored = matches!(
+            args.format,
+            ImageFormat::Pdf | ImageFormat::Ansi | ImageFormat::DistanceMap
+        );
+        let drawn = [
+            (
+                "--animate",
+                args.animate && args.format != ImageFormat::Ansi,
+            ),
+            ("--animate-solve", args.animate_solve.is_some()),
+            // the path can still be written with --solution-format
+            (
+                "--solve",
+                !colored && args.solve.is_some() && args.solution_format.is_none(),
+            ),
+            ("--color-by", args.color_by.is_some()),
+            ("--taper", args.taper.is_some()),
+            ("--label", maze_only && !args.labels.is_empty()),
+            ("--rulers", maze_only && args.rulers),
+            ("--tiles", args.tiles.is_some()),
+            ("--cells polar", args.cells == CellShape::Polar),
+        ];
+        if let Some((flag, _)) = drawn.iter().find(|(_, used)| *used) {
+            return Err(LabgenError::Usage(format!(
+                "--format {} can not be combined with {}",
+                args.format, flag
+            )));
+        }
+    }
+    let positive = |mm: f32| mm.is_finite() && mm > 0.0;
+    for (flag, mm) in [
+        ("--wall-height", args.wall_height),
+        ("--base-thickness", args.base_thickness),
+    ] {
+        if !positive(mm) {
+            return Err(LabgenError::Usage(format!(
+                "{} must be a positive number of millimeters",
+                flag
+            )));
+        }
+    }
+    if args.distance_from.is_some()
+        && args.format != ImageFormat::DistanceMap
+        && args.color_by != Some(ColorBy::Distance)
+    {
+        return Err(LabgenError::Usage(
+            "--distance-from can only be combined with --format distance-map or \
+            --color-by distance"
+                .to_string(),
+        ));
+    }
+    let stage_seeds = [
+        (
+            "--seed-uncarve",
+            args.seed_uncarve,
+            "--uncarve",
+            args.uncarve.is_some(),
+        ),
+        (
+            "--seed-braid",
+            args.seed_braid,
+            "--braid",
+            args.braid.is_some(),
+        ),
+        (
+            "--seed-symmetry",
+            args.seed_symmetry,
+            "--random-rotate",
+            args.random_rotate,
+        ),
+        (
+            "--seed-openings",
+            args.seed_openings,
+            "--entrance or --exit",
+            args.entrance.is_some() || args.exit.is_some(),
+        ),
+        (
+            "--seed-doors",
+            args.seed_doors,
+            "--room-doors even",
+            matches!(args.room_doors, RoomDoors::Even { .. }),
+        ),
+        (
+            "--seed-corridors",
+            args.seed_corridors,
+            "--corridors",
+            args.corridors.is_some(),
+        ),
+        (
+            "--seed-overlay",
+            args.seed_overlay,
+            "--overlay",
+            args.overlay.is_some(),
+        ),
+    ];
+    for (flag, seed, stage, used) in stage_seeds {
+        if seed.is_some() && !used {
+            return Err(LabgenError::Usage(format!(
+                "{} can only be combined with {}",
+                flag, stage
+            )));
+        }
+    }
+    if args.print_check && args.format != ImageFormat::Pdf {
+        return Err(LabgenError::Usage(
+            "--print-check can only be combined with --format pdf".to_string(),
+        ));
+    }
+    if !pdf_options(&args).fits() {
+        return Err(LabgenError::Usage(
+            "--margin must leave room for the maze on the page".to_string(),
+        ));
+    }
+    let noise_used = [
+        ("--noise-frequency", args.noise_frequency.is_some()),
+        ("--noise-threshold", args.noise_threshold.is_some()),
+        ("--noise-map", args.noise_map),
+        ("--color-by noise", args.color_by == Some(ColorBy::Noise)),
+    ];
+    if let Some((flag, _)) = noise_used.iter().find(|(_, used)| *used) {
+        let weighted = *flag == "--noise-frequency" && args.weights == Some(WeightSource::Noise);
+        if args.method != MazeType::Noise && !weighted {
+            return Err(LabgenError::Usage(format!(
+                "{} only applies to --method noise",
+                flag
+            )));
+        }
+        // the noise covers the grid as it was carved
+        if args.viewport.is_some() || args.random_rotate {
+            return Err(LabgenError::Usage(format!(
+                "{} can not be combined with --viewport or --random-rotate",
+                flag
+            )));
+        }
+    }
+    if let Some(orientation) = args.orientation {
+        match args.method {
+            MazeType::BinaryTree if !orientation.is_corner() => {
+                return Err(LabgenError::Usage(
+                    "--orientation for binary-tree is one of nw, ne, sw or se".to_string(),
+                ));
+            }
+            MazeType::Sidewinder if orientation.is_corner() => {
+                return Err(LabgenError::Usage(
+                    "--orientation for sidewinder is one of north, east, south or west".to_string(),
+                ));
+            }
+            method if !method.uses_orientation() => {
+                return Err(LabgenError::Usage(
+                    "--orientation can only be combined with --method binary-tree or \
+                    sidewinder"
+                        .to_string(),
+                ));
+            }
+            _ => {}
+        }
+    }
+    if args.weights.is_some() && !args.method.uses_weights() {
+        return Err(LabgenError::Usage(
+            "--weights can only be combined with --method prim or kruskal".to_string(),
+        ));
+    }
+    if args.noise_map && args.file_path == "-" {
+        return Err(LabgenError::Usage(
+            "--noise-map can not be combined with writing to stdout".to_string(),
+        ));
+    }
+    if args.uncarve.is_some() && args.animate {
+        return Err(LabgenError::Usage(
+            "--uncarve can not be combined with --animate".to_string(),
+        ));
+    }
+    if args.corridors.is_some() && args.animate {
+        return Err(LabgenError::Usage(
+            "--corridors can not be combined with --animate".to_string(),
+        ));
+    }
+    if (!args.exclude.is_empty()
+        || args.border.is_some()
+        || args.mask.is_some()
+        || args.overlay.is_some())
+        && !args.method.supports_exclusions()
+    {
+        return Err(LabgenError::Usage(format!(
+            "{} can not generate mazes with excluded cells",
+            args.method
+        )));
+    }
+    // a loaded maze is not carved again, so its wrap is taken as it is
+    let supported = args.method.supported_wrap();
+    if args.loaded.is_none()
+        && ((args.wrap.horizontal() && !supported.horizontal())
+            || (args.wrap.vertical() && !supported.vertical()))
+    {
+        return Err(LabgenError::Usage(format!(
+            "{} can not generate mazes with --wrap {}",
+            args.method, args.wrap
+        )));
+    }
+    if args.overlay.is_some() && args.wrap != MazeWrap::None {
+        return Err(LabgenError::Usage(
+            "--overlay can not be combined with --wrap".to_string(),
+        ));
+    }
+    if args.pattern.is_some() && !args.method.supports_patterns() {
+        return Err(LabgenError::Usage(format!(
+            "{} can not grow a maze around a pattern",
+            args.method
+        )));
+    }
+    if args.cells == CellShape::Polar {
+        check_polar(&args)?;
+    }
+    // pick the seed now so a printed config reproduces this exact maze
+    if let Some(namespace) = &args.daily {
+        let (key, seed) = daily_seed(SystemTime::now(), namespace);
+        if !args.print_config {
+            writeln!(
+                info_output(&args.file_path),
+                "daily seed for {}: {}",
+                key,
+                seed
+            )?;
+        }
+        args.seed = Some(seed);
+    }
+    args.seed = Some(args.seed.unwrap_or(rand::random::<u64>()));
+
+    if args.print_config {
+        write!(io::stdout(), "{}", args.to_config().to_toml())?;
+        Ok(())
+    } else {
+        write_mazes(args)
+    }
+}
+
+// seed of the daily maze on the utc date of now, together with the date and namespace it was
+// derived from. hashed with 64 bit fnv-1a, which unlike the std hashers is stable across builds
+fn daily_seed(now: SystemTime, namespace: &str) -> (String, u64) {
+    let days = (now.duration_since(UNIX_EPOCH).unwrap().as_secs() / 86400) as i64;
+    // civil date of a day count, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+
+    let mut key = format!("{:04}-{:02}-{:02}", year, month, day);
+    if !namespace.is_empty() {
+        key += &format!("/{}", namespace);
+    }
+    let seed = key.bytes().fold(0xcbf29ce484222325u64, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    });
+    (key, seed)
+}
+
+// reads a png with the decoder of a mask or cost image
+fn read_png<T>(
+    path: &str,
+    decode: impl Fn(io::BufReader<fs::File>) -> Result<T, png::DecodingError>,
+) -> Result<T, LabgenError> {
+    let file = fs::File::open(path)
+        .map_err(|e| io::Error::new(e.kind(), format!("could not read {}: {}", path, e)))?;
+    decode(io::BufReader::new(file)).map_err(|e| match e {
+        png::DecodingError::IoError(e) => LabgenError::Io(e),
+        e => LabgenError::Usage(format!("{}: {}", path, e)),
+    })
+}
+
+fn read_maze(path: &str) -> Result<Grid, LabgenError> {
+    let file = fs::File::open(path)
+        .map_err(|e| io::Error::new(e.kind(), format!("could not read {}: {}", path, e)))?;
+    read_json(io::BufReader::new(file)).map_err(|e| {
+        if e.is_io() {
+            LabgenError::Io(e.into())
+        } else {
+            LabgenError::Usage(format!("{}: {}", path, e))
+        }
+    })
+}
+
+fn read_template(path: &str) -> Result<Template, LabgenError> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| io::Error::new(e.kind(), format!("could not read {}: {}", path, e)))?;
+    text.parse()
+        .map_err(|e| LabgenError::Usage(format!("{}: {}", path, e)))
+}
+
+fn read_rooms(path: &str) -> Result<Rooms, LabgenError> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| io::Error::new(e.kind(), format!("could not read {}: {}", path, e)))?;
+    text.parse()
+        .map_err(|e| LabgenError::Usage(format!("invalid rooms file {}: {}", path, e)))
+}
+
+// runs the --exec command through the shell with the path of a written file
+fn run_exec(command: &str, path: &str) -> Result<(), LabgenError> {
+    // quoted so paths with spaces or shell characters reach the command as a single argument
+    let quoted = if cfg!(windows) {
+        format!("\"{}\"", path)
+    } else {
+        format!("'{}'", path.replace('\'', "'\\''"))
+    };
+    let command = if command.contains("{file}") {
+        command.replace("{file}", &quoted)
+    } else {
+        format!("{} {}", command, quoted)
+    };
+
+    let status = if cfg!(windows) {
+        process::Command::new("cmd").args(["/C", &command]).status()
+    } else {
+        process::Command::new("sh").args(["-c", &command]).status()
+    }
+    .map_err(|e| io::Error::new(e.kind(), format!("could not run \"{}\": {}", command, e)))?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("\"{}\" failed with {}", command, status)).into())
+    }
+}
+
+// informational output goes to stderr when the image itself is written to stdout
+fn info_output(file_path: &str) -> Box<dyn Write> {
+    if file_path == "-" {
+        Box::new(io::stderr())
+    } else {
+        Box::new(io::stdout())
+    }
+}
+
+fn compare(args: CompareArgs) -> Result<(), LabgenError> {
+    check_cell_width(args.passage_width, args.wall_width)?;
     let seed: u64 = args.seed.unwrap_or(rand::random::<u64>());
-    let mut rng: StdRng = StdRng::seed_from_u64(seed);
+
+    let panels = args
+        .methods
+        .iter()
+        .map(|method| {
+            let (maze, history) = generate_maze(args.width, args.height, *method, args.wrap, seed)?;
+            Ok(ComparePanel {
+                label: method.to_string(),
+                maze,
+                history,
+            })
+        })
+        .collect::<Result<Vec<_>, LabgenError>>()?;
+
+    let opts = ImageOptions {
+        file_path: output_base(&args.file_path, &format!("compare-{}", seed), "gif")?,
+        passage_width: args.passage_width,
+        wall_width: args.wall_width,
+        ..ImageOptions::default()
+    };
+    let ani_opts = AnimationOptions {
+        frame_time: args.frame_time,
+        pause_time: args.pause_time,
+        batch_size: args.batch_size,
+        progress: !args.no_progress,
+        on_progress: None,
+        cancel: Some(cancel_token()),
+        skip_unchanged: false,
+    };
+
+    if panels.is_empty() {
+        return Err(LabgenError::Usage(
+            "compare needs at least one method".to_string(),
+        ));
+    }
+    generate_gif_compare(&panels, &opts, &ani_opts)?;
+
+    writeln!(info_output(&opts.file_path), "seed: {}", seed)?;
+    Ok(())
+}
+
+fn stats(args: StatsArgs) -> Result<(), LabgenError> {
+    // json mazes carry no history, their generation order is left empty
+    let (maze, steps) = match &args.load {
+        Some(path) if is_history_file(path)? => {
+            let (maze, history) = load_history(path)?;
+            (maze, history.steps)
+        }
+        Some(path) => (read_maze(path)?, Vec::new()),
+        None => {
+            let (width, height) = (args.width.unwrap(), args.height.unwrap());
+            let mut seed: u64 = args.seed.unwrap_or(rand::random::<u64>());
+            let (maze, history) = generate_matching(
+                |seed| {
+                    let (mut maze, mut history) =
+                        generate_maze(width, height, args.method, args.wrap, seed)?;
+                    if let Some(percent) = args.braid {
+                        let braided = maze.braid(percent, SeedSchedule::new(seed).braid);
+                        history
+                            .steps
+                            .extend(braided.into_iter().map(MazeAction::from));
+                    }
+                    Ok((maze, history))
+                },
+                &mut seed,
+                args.reroll_until.as_ref(),
+                args.reroll_limit,
+            )?;
+            (maze, history.steps)
+        }
+    };
+    check_labels(&args.labels, maze.width, maze.height)?;
+    let mut stats = MazeStats::new(&maze, &steps);
+    stats.labels = args.labels;
+
+    if args.json {
+        writeln!(io::stdout(), "{}", stats.to_json())?;
+    } else {
+        write!(io::stdout(), "{}", stats.to_table())?;
+    }
+    Ok(())
+}
+
+// generates and renders a single maze, returning the seed of the kept maze and the time spent
+// generating and rendering it
+fn similarity(args: SimilarityArgs) -> Result<(), LabgenError> {
+    if args.methods.len() > 2 || args.seeds.len() > 2 {
+        return Err(LabgenError::Usage(
+            "similarity compares exactly two mazes, give at most two methods and seeds".to_string(),
+        ));
+    }
+
+    let [a, b] = [0, 1].map(|i| {
+        let method = args.methods[i.min(args.methods.len() - 1)];
+        let seed = args.seeds[i.min(args.seeds.len() - 1)];
+        generate_maze(args.width, args.height, method, args.wrap, seed).map(|(maze, _)| maze)
+    });
+    let (a, b) = (a?, b?);
+
+    // both mazes are generated with the same size
+    writeln!(io::stdout(), "{:.3}", stats::similarity(&a, &b).unwrap())?;
+    Ok(())
+}
+
+// polar mazes are only carved and drawn as png, options that rely on rows and columns of cells
+// or on the generation history are refused
+fn check_polar(args: &Args) -> Result<(), LabgenError> {
+    if !supports_polar(args.method) {
+        return Err(LabgenError::Usage(format!(
+            "{} can not generate polar mazes",
+            args.method
+        )));
+    }
+    if args.height.is_some_and(|rings| rings > u16::MAX as u32) {
+        return Err(LabgenError::Usage(format!(
+            "polar mazes can have at most {} rings",
+            u16::MAX
+        )));
+    }
+    let square_only = [
+        ("--wrap", args.wrap != MazeWrap::None),
+        ("--animate", args.animate),
+        ("--animate-solve", args.animate_solve.is_some()),
+        ("--solve", args.solve.is_some()),
+        ("--color-by", args.color_by.is_some()),
+        ("--taper", args.taper.is_some()),
+        ("--viewport", args.viewport.is_some()),
+        ("--exclude", !args.exclude.is_empty()),
+        ("--border", args.border.is_some()),
+        ("--mask", args.mask.is_some()),
+        ("--weights", args.weights.is_some()),
+        ("--orientation", args.orientation.is_some()),
+        ("--pattern", args.pattern.is_some()),
+        ("--template", args.template.is_some()),
+        ("--rooms-file", args.rooms_file.is_some()),
+        ("[[room]] tables", args.rooms.is_some()),
+        ("--overlay", args.overlay.is_some()),
+        ("--label", !args.labels.is_empty()),
+        ("--rulers", args.rulers),
+        ("--tiles", args.tiles.is_some()),
+        ("--invert", args.invert),
+        ("--uncarve", args.uncarve.is_some()),
+        ("--braid", args.braid.is_some()),
+        ("--corridors", args.corridors.is_some()),
+        ("--entrance", args.entrance.is_some()),
+        ("--entrances", args.entrances.is_some()),
+        ("--exit", args.exit.is_some()),
+        ("--random-rotate", args.random_rotate),
+        ("--reroll-until", args.reroll_until.is_some()),
+        ("--stats", args.stats.is_some()),
+        ("--save-history", args.save_history.is_some()),
+    ];
+    match square_only.iter().find(|(_, used)| *used) {
+        Some((flag, _)) => Err(LabgenError::Usage(format!(
+            "{} can not be combined with --cells polar",
+            flag
+        ))),
+        None => Ok(()),
+    }
+}
+
+// a loaded maze is already carved, options that shape the carving or need its history are
+// refused, only a replayed maze comes with its history. options given in a config are not seen
+// by clap, so they are checked here as well
+fn check_load(args: &Args, flag: &str) -> Result<(), LabgenError> {
+    let carving = [
+        ("--cells polar", args.cells == CellShape::Polar),
+        ("--exclude", !args.exclude.is_empty()),
+        ("--border", args.border.is_some()),
+        ("--mask", args.mask.is_some()),
+        ("--weights", args.weights.is_some()),
+        ("--pattern", args.pattern.is_some()),
+        ("--template", args.template.is_some()),
+        ("--rooms-file", args.rooms_file.is_some()),
+        ("[[room]] tables", args.rooms.is_some()),
+        ("--overlay", args.overlay.is_some()),
+        ("--corridors", args.corridors.is_some()),
+        ("--reroll-until", args.reroll_until.is_some()),
+        ("--noise-frequency", args.noise_frequency.is_some()),
+        ("--noise-threshold", args.noise_threshold.is_some()),
+        ("--count", args.count > 1),
+        ("--daily", args.daily.is_some()),
+        ("--animate", args.animate && args.replay.is_none()),
+    ];
+    match carving.iter().find(|(_, used)| *used) {
+        Some((used, _)) => Err(LabgenError::Usage(format!(
+            "{} can not be combined with {}",
+            used, flag
+        ))),
+        None => Ok(()),
+    }
+}
+
+// a cell with its wall must fit in the u16 sizes of the images, whatever the size of the maze
+fn check_cell_width(passage_width: u16, wall_width: u16) -> Result<(), LabgenError> {
+    if passage_width.checked_add(wall_width).is_none() {
+        return Err(LabgenError::Usage(format!(
+            "--passagewidth and --wallwidth can add up to at most {}",
+            u16::MAX
+        )));
+    }
+    Ok(())
+}
+
+// outputs drawn as one image must fit in its u16 pixel dimensions, so a maze too large to be
+// drawn is not carved first. text, json, dot and html output and png tiles have no such limit
+fn check_image_size(args: &Args, width: u32, height: u32) -> Result<(), LabgenError> {
+    let unlimited = !args.animate
+        && args.animate_solve.is_none()
+        && (matches!(
+            args.format,
+            ImageFormat::Json | ImageFormat::Dot | ImageFormat::Text | ImageFormat::Html
+        ) || args.format == ImageFormat::Png
+            && args.tiles.is_some()
+            && args.color_by.is_none());
+    if unlimited {
+        return Ok(());
+    }
+    // only the viewport is drawn
+    let (width, height) = args
+        .viewport
+        .map_or((width, height), |v| (v.width, v.height));
+    let layout = PixelLayout::new(args.passage_width, args.wall_width);
+    if layout.image_size(width, height).is_none() {
+        return Err(LabgenError::Render(format!(
+            "a {}x{} maze with {} pixel cells is larger than {} pixels, png images can be split \
+            into tiles with --tiles",
+            width,
+            height,
+            // checked by check_cell_width
+            layout.cell_width().unwrap(),
+            u16::MAX
+        )));
+    }
+    Ok(())
+}
+
+// labels must lie inside the maze, two labels on one cell would be drawn over each other
+fn check_labels(labels: &[CellLabel], width: u32, height: u32) -> Result<(), LabgenError> {
+    if let Some(label) = labels
+        .iter()
+        .find(|l| l.pt.x as u32 >= width || l.pt.y as u32 >= height)
+    {
+        return Err(LabgenError::Geometry(format!(
+            "label {} lies outside of a {}x{} maze",
+            label, width, height
+        )));
+    }
+    for (i, label) in labels.iter().enumerate() {
+        if let Some(other) = labels[..i].iter().find(|l| l.pt == label.pt) {
+            return Err(LabgenError::Geometry(format!(
+                "label {} overlaps label {}",
+                label, other
+            )));
+        }
+    }
+    Ok(())
+}
+
+// excluded rects must cover at least one cell and lie inside the maze, rects reaching past its
+// edges used to be cut off without a word
+fn check_exclusions(rects: &[Rect], width: u32, height: u32) -> Result<(), LabgenError> {
+    for rect in rects {
+        if rect.width == 0 || rect.height == 0 {
+            return Err(LabgenError::Geometry(format!(
+                "--exclude {} covers no cells",
+                rect
+            )));
+        }
+        for (end, side, size) in [
+            (rect.x as u64 + rect.width as u64, "width", width),
+            (rect.y as u64 + rect.height as u64, "height", height),
+        ] {
+            if end > size as u64 {
+                return Err(LabgenError::Geometry(format!(
+                    "--exclude {} exceeds the maze {} of {}",
+                    rect, side, size
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn pdf_options(args: &Args) -> PdfOptions {
+    PdfOptions {
+        page: args.page,
+        margin: args.margin,
+        title: args.title.clone(),
+    }
+}
+
+fn generate_one(
+    args: &Args,
+    seed: u64,
+    file_path: String,
+    progress: bool,
+) -> Result<(u64, Option<u32>, Duration, Duration), LabgenError> {
+    // checked by run before generating
+    let (width, height) = (args.width.unwrap(), args.height.unwrap());
+    let extension = output_extension(args);
+    let options = Options {
+        bias: args.bias.clone(),
+        progress,
+        cancel: Some(cancel_token()),
+        ..Options::new(width, height, args.method, seed)
+    };
 
     let mut now = Instant::now();
-    let (nodes, hist) = generate_maze(args.width, args.height, args.method, &mut rng);
+    if args.cells == CellShape::Polar {
+        let maze = generate_polar(&options)?;
+        let maze_time = now.elapsed();
+
+        now = Instant::now();
+        let opts = ImageOptions {
+            file_path,
+            passage_width: args.passage_width,
+            wall_width: args.wall_width,
+            exclude_style: args.exclude_style,
+            exclude_color: args.exclude_color,
+            ..ImageOptions::default()
+        };
+        generate_png_polar(&maze, &opts)?;
+        let image_time = now.elapsed();
+
+        if let Some(command) = &args.exec {
+            run_exec(command, &format!("{}.{}", opts.file_path, extension))?;
+        }
+        return Ok((seed, None, maze_time, image_time));
+    }
+    check_image_size(args, width, height)?;
+    // rooms are laid out as a template of the size of the maze. rooms of the config are only
+    // set when there is one
+    let rooms = match (&args.rooms, &args.rooms_file) {
+        (Some(rooms), _) => Some((args.config.clone().unwrap(), rooms.clone())),
+        (None, Some(path)) => Some((path.clone(), read_rooms(path)?)),
+        (None, None) => None,
+    };
+    let (template, template_scale) = match rooms {
+        Some((path, rooms)) => {
+            rooms
+                .check(width, height)
+                .map_err(|e| LabgenError::Geometry(format!("{}: {}", path, e)))?;
+            for room in &rooms.rooms {
+                let cells = room.shape.cells();
+                for rect in &args.exclude {
+                    if let Some(pt) = cells.iter().find(|pt| rect.contains(**pt)) {
+                        return Err(LabgenError::Geometry(format!(
+                            "{}: {} overlaps --exclude {} at {}",
+                            path, room, rect, pt
+                        )));
+                    }
+                }
+            }
+            (Some(rooms.template(width, height)), 1)
+        }
+        None => (
+            args.template.as_deref().map(read_template).transpose()?,
+            args.template_scale,
+        ),
+    };
+    let options = Options {
+        wrap: args.wrap,
+        scan_order: args.scan_order,
+        orientation: args.orientation,
+        noise: NoiseOptions {
+            frequency: args.noise_frequency.unwrap_or(options.noise.frequency),
+            threshold: args.noise_threshold.unwrap_or(options.noise.threshold),
+        },
+        weights: args.weights.clone(),
+        cost_image: match &args.weights {
+            Some(WeightSource::Image(path)) => Some(read_png(path, CostImage::decode)?),
+            _ => None,
+        },
+        border: args.border,
+        exclude: args.exclude.clone(),
+        mask: args
+            .mask
+            .as_deref()
+            .map(|path| read_png(path, Mask::decode))
+            .transpose()?,
+        template,
+        template_scale,
+        room_doors: args.room_doors.clone(),
+        overlay: args.overlay.map(|room| OverlayScale {
+            room,
+            wall: args.overlay_wall,
+        }),
+        overlay_method: args.overlay_method,
+        pattern: args.pattern.clone(),
+        corridors: args.corridors,
+        reroll_until: args.reroll_until.clone(),
+        reroll_limit: args.reroll_limit,
+        loaded: args.loaded.clone(),
+        loaded_history: args.loaded_history.clone(),
+        random_rotate: args.random_rotate,
+        uncarve: args.uncarve,
+        uncarve_strategy: args.uncarve_strategy,
+        braid: args.braid,
+        invert: args.invert,
+        viewport: args.viewport,
+        labels: args.labels.clone(),
+        entrance: args.entrance,
+        exit: args.exit,
+        entrances: args.entrances,
+        // --solve and --animate-solve can not be given together
+        solve: args.solve.or(args.animate_solve),
+        seed_uncarve: args.seed_uncarve,
+        seed_braid: args.seed_braid,
+        seed_symmetry: args.seed_symmetry,
+        seed_openings: args.seed_openings,
+        seed_doors: args.seed_doors,
+        seed_corridors: args.seed_corridors,
+        seed_overlay: args.seed_overlay,
+        ..options
+    };
+    let Output {
+        maze: nodes,
+        history: hist,
+        seed,
+        start,
+        end,
+        labels,
+        solution,
+        uncarved,
+        warnings,
+    } = generate(&options)?;
     let maze_time = now.elapsed();
 
+    for warning in &warnings {
+        writeln!(info_output(&args.file_path), "warning: {}", warning)?;
+    }
+    if let Some(pt) = args.distance_from {
+        if !nodes.contains(pt) || nodes.is_removed(pt) {
+            return Err(LabgenError::Geometry(format!(
+                "distances can not be measured from {}, it is not a cell of the {}x{} maze",
+                pt, nodes.width, nodes.height
+            )));
+        }
+    }
+    let distance_from = args.distance_from.unwrap_or(start);
+    if let Some(format) = args.stats {
+        let stats = MazeStats::new(&nodes, &hist.steps);
+        let mut info = info_output(&args.file_path);
+        match format {
+            StatsFormat::Table => write!(info, "{}", stats.to_table())?,
+            StatsFormat::Json => writeln!(info, "{}", stats.to_json())?,
+        }
+    }
+
+    if let Some(path) = &args.save_history {
+        generate_history(&nodes, &hist, path)?;
+    }
+    // --animate-solve plays the search back instead of drawing the path
+    let (solution, animated) = match args.animate_solve {
+        Some(_) => (None, solution),
+        None => (solution, None),
+    };
     now = Instant::now();
     let opts = ImageOptions {
-        file_path: args.file_path,
+        file_path,
         passage_width: args.passage_width,
         wall_width: args.wall_width,
         color_map: [0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF],
+        exclude_style: args.exclude_style,
+        exclude_color: args.exclude_color,
+        solution,
+        labels,
+        rulers: args.rulers,
+        gradient: [args.gradient_start, args.gradient_end],
     };
     let ani_opts = AnimationOptions {
         frame_time: args.frame_time,
         pause_time: args.pause_time,
         batch_size: args.batch_size,
+        progress,
+        on_progress: None,
+        cancel: Some(cancel_token()),
+        skip_unchanged: args.skip_unchanged,
     };
 
-    if args.animate {
-        if args.compress {
-            generate_gif(&nodes, &hist, &opts, &ani_opts);
-        } else {
-            generate_gif_uncompressed(&nodes, &hist, &opts, &ani_opts);
+    if let Some(solution) = &animated {
+        generate_gif_solve(&nodes, solution, &opts, &ani_opts)?;
+    } else if args.animate && args.format == ImageFormat::Ansi {
+        // a progress bar would be drawn over the frames
+        let ani_opts = AnimationOptions {
+            progress: false,
+            ..ani_opts
+        };
+        generate_ansi_animation(&nodes, &hist.steps, &opts, &ani_opts)?;
+    } else if args.animate {
+        match args.animation_format {
+            AnimationFormat::Apng => generate_apng(&nodes, &hist.steps, &opts, &ani_opts)?,
+            AnimationFormat::Svg => generate_svg(&nodes, &hist.steps, &opts, &ani_opts)?,
+            AnimationFormat::Mp4 => {
+                generate_video(&nodes, &hist.steps, &opts, &ani_opts, VideoFormat::Mp4)?
+            }
+            AnimationFormat::Webm => {
+                generate_video(&nodes, &hist.steps, &opts, &ani_opts, VideoFormat::Webm)?
+            }
+            AnimationFormat::Gif if args.compress => {
+                generate_gif(&nodes, &hist.steps, &opts, &ani_opts)?
+            }
+            AnimationFormat::Gif => {
+                generate_gif_uncompressed(&nodes, &hist.steps, &opts, &ani_opts)?
+            }
+        }
+        if args.audio {
+            generate_wav(&nodes, &hist.steps, &opts, &ani_opts)?;
+        }
+        if let Some(count) = args.keyframes {
+            generate_keyframes(&nodes, &hist.steps, count, &opts)?;
+        }
+    } else if args.format == ImageFormat::Json {
+        generate_json(&nodes, &opts.file_path)?;
+    } else if args.format == ImageFormat::Dot {
+        generate_dot(&nodes, &opts.file_path)?;
+    } else if args.format == ImageFormat::Stl {
+        let mesh_opts = MeshOptions {
+            wall_height: args.wall_height,
+            base_thickness: args.base_thickness,
+        };
+        generate_stl(&nodes, &opts, &mesh_opts)?;
+    } else if args.format == ImageFormat::Ansi {
+        generate_ansi(&nodes, &opts)?;
+    } else if args.format == ImageFormat::Text {
+        generate_text(&nodes, &opts.file_path, &args.text_style)?;
+    } else if args.format == ImageFormat::Braille {
+        generate_braille(&nodes, &opts)?;
+    } else if args.format == ImageFormat::Html {
+        generate_html(&nodes, &opts, start, end)?;
+    } else if args.format == ImageFormat::DistanceMap {
+        generate_distance_map(&nodes, &distances(&nodes, distance_from), &opts)?;
+    } else if args.format == ImageFormat::Pdf {
+        generate_pdf(&nodes, &opts, &pdf_options(args))?;
+        if args.print_check {
+            let report = print_report(&nodes, &opts, &pdf_options(args), args.dpi)?;
+            let mut info = info_output(&opts.file_path);
+            writeln!(
+                info,
+                "print: walls {:.2} mm, passages {:.2} mm, ink on {:.1}% of the page",
+                report.wall,
+                report.passage,
+                report.ink * 100.0
+            )?;
+            for warning in &report.warnings {
+                writeln!(info, "warning: {}", warning)?;
+            }
         }
     } else {
-        generate_png(&nodes, &opts);
+        match args.color_by {
+            Some(ColorBy::Order) => {
+                generate_png_gradient(&nodes, &generation_order(&nodes, &hist.steps), &opts)
+            }
+            Some(ColorBy::Distance) => {
+                generate_png_gradient(&nodes, &distances(&nodes, distance_from), &opts)
+            }
+            Some(ColorBy::Noise) => generate_png_gradient(&nodes, &noise_levels(&nodes), &opts),
+            None => match args.tiles {
+                Some(size) => generate_tiles(
+                    &nodes,
+                    &opts,
+                    &TileOptions {
+                        size,
+                        overlap: args.tile_overlap,
+                        progress: !args.no_progress,
+                    },
+                ),
+                None => match args.taper {
+                    Some(min_width) => {
+                        generate_png_tapered(&nodes, &distances(&nodes, start), min_width, &opts)
+                    }
+                    None => generate_png(&nodes, &opts),
+                },
+            },
+        }?;
+    }
+    // an animation cut short is still written, but nothing else is done with it
+    if cancel_token().is_cancelled() {
+        return Err(LabgenError::Cancelled);
+    }
+    if let (Some(format), Some(solution)) = (args.solution_format, &opts.solution) {
+        generate_moves(&nodes, solution, format, &opts.file_path)?;
+    }
+    if args.noise_map {
+        let noise_opts = ImageOptions {
+            file_path: format!("{}-noise", opts.file_path),
+            ..opts.clone()
+        };
+        generate_png_noise(&nodes, &noise_opts)?;
     }
     let image_time = now.elapsed();
 
-    println!("seed: {}", seed);
-    //println!("dbg: {:?}", nodes.tiles);
-    println!(
-        "Elapsed time: maze {}.{:09.9}s, gif {}.{:09.9}s",
-        maze_time.as_secs(),
-        maze_time.as_nanos(),
-        image_time.as_secs(),
-        image_time.as_nanos()
-    );
+    if let Some(command) = &args.exec {
+        // tiles are written into a directory named like the image
+        let written = match args.tiles {
+            Some(_) => opts.file_path.clone(),
+            None => format!("{}.{}", opts.file_path, extension),
+        };
+        run_exec(command, &written)?;
+    }
+
+    Ok((seed, uncarved, maze_time, image_time))
+}
+
+// extension of the image or animation written for the arguments
+fn output_extension(args: &Args) -> &'static str {
+    if args.animate {
+        args.animation_format.extension()
+    } else if args.animate_solve.is_some() {
+        "gif"
+    } else {
+        args.format.extension()
+    }
+}
+
+// output path without extension. a directory, or a path ending in a separator, gets a file named
+// after name inside it, and an extension matching the written format is dropped so it is not
+// doubled. extensions of the other formats are refused rather than written as maze.png.gif
+fn output_base(file_path: &str, name: &str, extension: &str) -> Result<String, LabgenError> {
+    if file_path == "-" {
+        return Ok(file_path.to_string());
+    }
+    if file_path.ends_with('/')
+        || file_path.ends_with(std::path::MAIN_SEPARATOR)
+        || Path::new(file_path).is_dir()
+    {
+        return Ok(Path::new(file_path)
+            .join(name)
+            .to_string_lossy()
+            .into_owned());
+    }
+
+    let path = Path::new(file_path);
+    let given = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .filter(|e| {
+            [
+                "png", "json", "dot", "stl", "pdf", "html", "ans", "txt", "gif", "svg", "mp4",
+                "webm", "wav",
+            ]
+            .contains(&e.as_str())
+        });
+    match given {
+        Some(given) if given == extension => {
+            Ok(path.with_extension("").to_string_lossy().into_owned())
+        }
+        Some(given) => Err(LabgenError::Usage(format!(
+            "{} ends in .{} but a {} file is written",
+            file_path, given, extension
+        ))),
+        None => Ok(file_path.to_string()),
+    }
+}
+
+// generates the mazes of the arguments and writes every one to its file
+fn write_mazes(mut args: Args) -> Result<(), LabgenError> {
+    // resolved by run before generating
+    let seed = args.seed.unwrap();
+    args.file_path = output_base(
+        &args.file_path,
+        &format!("{}-{}", args.method, seed),
+        output_extension(&args),
+    )?;
+
+    if args.count == 1 {
+        let (seed, uncarved, maze_time, image_time) =
+            generate_one(&args, seed, args.file_path.clone(), !args.no_progress)?;
+
+        let mut info = info_output(&args.file_path);
+        writeln!(info, "seed: {}", seed)?;
+        if let Some(cells) = uncarved {
+            writeln!(info, "uncarved: {} cells", cells)?;
+        }
+        //println!("dbg: {:?}", nodes.tiles);
+        writeln!(
+            info,
+            "Elapsed time: maze {}.{:09.9}s, gif {}.{:09.9}s",
+            maze_time.as_secs(),
+            maze_time.as_nanos(),
+            image_time.as_secs(),
+            image_time.as_nanos()
+        )?;
+        return Ok(());
+    }
+
+    if args.file_path == "-" {
+        return Err(LabgenError::Usage(
+            "--count can not be combined with writing to stdout".to_string(),
+        ));
+    }
+
+    // every maze gets its own file and a seed drawn from an rng seeded with the given seed, so
+    // the whole batch is reproduced by the same seed
+    let mut seeds = StdRng::seed_from_u64(seed);
+    let digits = (args.count - 1).to_string().len();
+    let jobs: Vec<(u64, String)> = (0..args.count)
+        .map(|i| (seeds.gen(), format!("{}-{:0digits$}", args.file_path, i)))
+        .collect();
+
+    let now = Instant::now();
+    let progress = if args.no_progress {
+        Progress::hidden()
+    } else {
+        Progress::new("mazes", args.count as u64)
+    };
+    let results = jobs
+        .into_par_iter()
+        .map(|(seed, file_path)| {
+            let (seed, uncarved, _, _) = generate_one(&args, seed, file_path.clone(), false)?;
+            progress.inc();
+            Ok((file_path, seed, uncarved))
+        })
+        .collect::<Result<Vec<_>, LabgenError>>();
+    progress.finish();
+
+    let mut info = io::stdout();
+    for (file_path, seed, uncarved) in results? {
+        match uncarved {
+            Some(cells) => writeln!(info, "{}: seed {}, uncarved {}", file_path, seed, cells)?,
+            None => writeln!(info, "{}: seed {}", file_path, seed)?,
+        }
+    }
+    let elapsed = now.elapsed();
+    writeln!(
+        info,
+        "Elapsed time: {} mazes in {}.{:03}s",
+        args.count,
+        elapsed.as_secs(),
+        elapsed.subsec_millis()
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // options of the command line without a subcommand, with the matches they were parsed from
+    fn parse(argv: &[&str]) -> (Args, ArgMatches) {
+        let matches = Cli::command().try_get_matches_from(argv).unwrap();
+        let cli = Cli::from_arg_matches(&matches).unwrap();
+        (cli.args, matches)
+    }
+
+    fn args(argv: &str) -> Args {
+        Cli::try_parse_from(argv.split(' ')).unwrap().args
+    }
+
+    #[test]
+    fn oversized_images_are_refused_before_carving() {
+        let error = check_image_size(&args("labgen --passagewidth 400"), 20000, 20000).unwrap_err();
+        assert_eq!(error.exit_code(), 5);
+        assert!(error
+            .to_string()
+            .contains("a 20000x20000 maze with 401 pixel cells"));
+        for argv in [
+            "labgen --passagewidth 400 --format text",
+            "labgen --passagewidth 400 --tiles 1000",
+            "labgen --passagewidth 400 --viewport 0,0,10,10",
+        ] {
+            assert!(
+                check_image_size(&args(argv), 20000, 20000).is_ok(),
+                "{}",
+                argv
+            );
+        }
+    }
+
+    #[test]
+    fn unsupported_wraps_are_usage_errors() {
+        for (method, wrap) in [
+            ("eller", "full"),
+            ("eller", "vertical"),
+            ("binary-tree", "horizontal"),
+            ("sidewinder", "vertical"),
+            ("recursive-division", "full"),
+            ("noise", "horizontal"),
+        ] {
+            let (args, matches) = parse(&["labgen", "5", "5", "-m", method, "-w", wrap]);
+            let error = plain(args, &matches).unwrap_err();
+            assert_eq!(error.exit_code(), 2);
+            assert_eq!(
+                error.to_string(),
+                format!("{} can not generate mazes with --wrap {}", method, wrap)
+            );
+        }
+    }
+
+    // ids of the config options the command line replaces, or the error check_config reports
+    fn config_conflicts(config: &str, argv: &[&str]) -> Result<Vec<String>, LabgenError> {
+        let config: Config = toml::from_str(config).unwrap();
+        let (_, matches) = parse(argv);
+        check_config(&config, "labgen.toml", &matches)
+    }
+
+    #[test]
+    fn valid_configs_pass() {
+        let config = "width = 12\nheight = 8\nmethod = \"eller\"\nseed = 4\nanimate = true\n";
+        assert_eq!(
+            config_conflicts(config, &["labgen"]).unwrap(),
+            Vec::<String>::new()
+        );
+        // a height is checked with a stand-in width before it
+        assert!(config_conflicts("height = 8", &["labgen"]).is_ok());
+        // the command line replacing an option of the config is no conflict
+        assert!(config_conflicts("seed = 4", &["labgen", "-s", "5"])
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn config_values_out_of_range_are_usage_errors() {
+        let error = config_conflicts("overlay = 0", &["labgen"]).unwrap_err();
+        assert_eq!(error.exit_code(), 2);
+        assert!(error
+            .to_string()
+            .starts_with("invalid config labgen.toml: invalid value '0' for '--overlay <cells>'"));
+
+        let error = config_conflicts("width = 0", &["labgen"]).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("invalid config labgen.toml: invalid value '0' for '[width]'"));
+    }
+
+    #[test]
+    fn conflicting_config_options_are_usage_errors() {
+        let error = config_conflicts("invert = true\nanimate = true", &["labgen"]).unwrap_err();
+        assert_eq!(error.exit_code(), 2);
+        assert!(error.to_string().starts_with(
+            "invalid config labgen.toml: the argument '--animate' cannot be used with '--invert'"
+        ));
+    }
+
+    #[test]
+    fn config_options_conflicting_with_the_command_line_are_ignored() {
+        // the command line conflicts with the config option
+        let ignored = config_conflicts(
+            "seed = 4\nmethod = \"prim\"",
+            &["labgen", "--load", "m.json"],
+        );
+        assert_eq!(ignored.unwrap(), ["method", "seed"]);
+        // the config option conflicts with the command line
+        let ignored = config_conflicts("load = \"m.json\"\ninvert = true", &["labgen", "-s", "4"]);
+        assert_eq!(ignored.unwrap(), ["load"]);
+        let ignored = config_conflicts("animate = true", &["labgen", "--invert"]);
+        assert_eq!(ignored.unwrap(), ["animate"]);
+    }
 }
@@ -0,0 +1,171 @@
+use crate::maze::{ConnectionStatus, Grid, Point};
+use png::{ColorType, Decoder, DecodingError, Transformations};
+use std::io::Read;
+
+// black and white image giving a maze its outline. pixels darker than middle gray are black,
+// transparent pixels count as white
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mask {
+    pub width: u32,
+    pub height: u32,
+    pub black: Vec<bool>,
+}
+
+impl Mask {
+    // reads the first frame of a png of any color type or bit depth
+    pub fn decode(input: impl Read) -> Result<Self, DecodingError> {
+        let (width, height, pixels) = decode_pixels(input)?;
+        Ok(Mask {
+            width,
+            height,
+            black: pixels
+                .into_iter()
+                .map(|(luma, alpha)| luma < 128 && alpha >= 128)
+                .collect(),
+        })
+    }
+}
+
+// grayscale image giving the cost of carving into each cell for --weights image. black cells are
+// cheapest, transparent pixels count as white
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostImage {
+    pub width: u32,
+    pub height: u32,
+    pub costs: Vec<f32>,
+}
+
+impl CostImage {
+    // reads the first frame of a png of any color type or bit depth
+    pub fn decode(input: impl Read) -> Result<Self, DecodingError> {
+        let (width, height, pixels) = decode_pixels(input)?;
+        Ok(CostImage {
+            width,
+            height,
+            costs: pixels
+                .into_iter()
+                .map(|(luma, alpha)| {
+                    if alpha < 128 {
+                        1.0
+                    } else {
+                        luma as f32 / 255.0
+                    }
+                })
+                .collect(),
+        })
+    }
+
+    // stretches the image over the whole grid, every cell taking the cost of the pixel at its
+    // center
+    pub fn costs(&self, maze: &Grid) -> Vec<f32> {
+        let mut costs = Vec::with_capacity(maze.tiles.len());
+        for y in 0..maze.height {
+            for x in 0..maze.width {
+                costs.push(self.costs[pixel_at(maze, x, y, self.width, self.height)]);
+            }
+        }
+        costs
+    }
+}
+
+// width and height of an image with the luma and alpha of every pixel, row by row
+type Pixels = (u32, u32, Vec<(u32, u8)>);
+
+// reads the first frame of a png
+fn decode_pixels(input: impl Read) -> Result<Pixels, DecodingError> {
+    let mut decoder = Decoder::new(input);
+    decoder.set_transformations(Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+
+    let channels = info.color_type.samples();
+    let pixels = (0..info.height as usize)
+        .flat_map(|y| {
+            let line = &buffer[y * info.line_size..];
+            (0..info.width as usize).map(move |x| &line[x * channels..(x + 1) * channels])
+        })
+        .map(|pixel| match info.color_type {
+            ColorType::Grayscale => (pixel[0] as u32, 255),
+            ColorType::GrayscaleAlpha => (pixel[0] as u32, pixel[1]),
+            ColorType::Rgba => (luma(pixel), pixel[3]),
+            _ => (luma(pixel), 255),
+        })
+        .collect();
+    Ok((info.width, info.height, pixels))
+}
+
+// index of the pixel at the center of a cell, with the image stretched over the whole grid
+fn pixel_at(maze: &Grid, x: u32, y: u32, width: u32, height: u32) -> usize {
+    let px = ((2 * x as u64 + 1) * width as u64 / (2 * maze.width as u64)) as usize;
+    let py = ((2 * y as u64 + 1) * height as u64 / (2 * maze.height as u64)) as usize;
+    px + py * width as usize
+}
+
+// rec. 601 weights in integer thousandths
+fn luma(pixel: &[u8]) -> u32 {
+    (299 * pixel[0] as u32 + 587 * pixel[1] as u32 + 114 * pixel[2] as u32) / 1000
+}
+
+// stretches the mask over the whole grid and removes every cell whose center falls on a black
+// pixel
+pub fn apply_mask(maze: &mut Grid, mask: &Mask) {
+    for y in 0..maze.height {
+        for x in 0..maze.width {
+            if mask.black[pixel_at(maze, x, y, mask.width, mask.height)] {
+                maze.get_tile_mut(Point::new(x as i32, y as i32))
+                    .set_status(ConnectionStatus::Removed);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{MazeType, MazeWrap};
+    use png::Encoder;
+
+    fn png(width: u32, height: u32, color: ColorType, pixels: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut encoder = Encoder::new(&mut bytes, width, height);
+        encoder.set_color(color);
+        encoder
+            .write_header()
+            .unwrap()
+            .write_image_data(pixels)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn dark_opaque_pixels_are_black() {
+        let image = png(3, 1, ColorType::GrayscaleAlpha, &[0, 255, 0, 0, 200, 255]);
+        let mask = Mask::decode(image.as_slice()).unwrap();
+        assert_eq!((mask.width, mask.height), (3, 1));
+        assert_eq!(mask.black, [true, false, false]);
+
+        let image = png(2, 1, ColorType::Rgb, &[0, 0, 0, 255, 255, 0]);
+        let mask = Mask::decode(image.as_slice()).unwrap();
+        assert_eq!(mask.black, [true, false]);
+    }
+
+    #[test]
+    fn masks_stretch_over_the_grid() {
+        let image = png(2, 2, ColorType::Grayscale, &[0, 255, 255, 255]);
+        let mask = Mask::decode(image.as_slice()).unwrap();
+        let mut maze = Grid::new(4, 4, MazeWrap::None, 0, MazeType::default());
+        apply_mask(&mut maze, &mask);
+        for pt in maze.scan_cells() {
+            assert_eq!(maze.is_removed(pt), pt.x < 2 && pt.y < 2, "{:?}", pt);
+        }
+    }
+
+    #[test]
+    fn cells_cost_the_pixel_at_their_center() {
+        let image = png(2, 1, ColorType::GrayscaleAlpha, &[0, 255, 0, 0]);
+        let costs = CostImage::decode(image.as_slice()).unwrap();
+        let maze = Grid::new(4, 1, MazeWrap::None, 0, MazeType::default());
+        assert_eq!(costs.costs(&maze), [0.0, 0.0, 1.0, 1.0]);
+    }
+}
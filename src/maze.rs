@@ -1,12 +1,61 @@
-use rand;
+use crate::error::MazeGenError;
+use crate::progress::Progress;
+use clap::ValueEnum;
 use rand::rngs::StdRng;
-use rand::Rng;
+use rand::SeedableRng;
+use rand::{Rng, RngCore};
+use rayon::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::fmt;
 use std::ops::{Add, AddAssign};
+use std::str::FromStr;
 
+// written as x,y on the command line and in config files
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
 pub struct Point {
-    pub x: i16,
-    pub y: i16,
+    pub x: i32,
+    pub y: i32,
+}
+
+impl FromStr for Point {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (x, y) = s
+            .split_once(',')
+            .ok_or_else(|| format!("invalid cell \"{}\": expected x,y", s))?;
+        let coord = |v: &str| match v.trim().parse::<i32>() {
+            Ok(v) if v >= 0 => Ok(v),
+            Ok(_) => Err(format!("invalid cell \"{}\": negative coordinate", s)),
+            Err(e) => Err(format!("invalid cell \"{}\": {}", s, e)),
+        };
+        Ok(Point::new(coord(x)?, coord(y)?))
+    }
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.x, self.y)
+    }
+}
+
+impl TryFrom<String> for Point {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Point> for String {
+    fn from(pt: Point) -> Self {
+        pt.to_string()
+    }
 }
 
 impl Add for Point {
@@ -47,7 +96,22 @@ impl Point {
         }
     }
 
-    pub fn new(x: i16, y: i16) -> Self {
+    // travel in a direction, wrapping around the edges of a width x height area if the wrap
+    // mode allows it. points leaving a non-wrapping edge are returned out of bounds
+    pub fn travel_wrapped(self, dir: Direction, width: u32, height: u32, wrap: MazeWrap) -> Self {
+        let mut next = self.travel(dir);
+
+        if wrap.horizontal() {
+            next.x = next.x.rem_euclid(width as i32);
+        }
+        if wrap.vertical() {
+            next.y = next.y.rem_euclid(height as i32);
+        }
+
+        next
+    }
+
+    pub fn new(x: i32, y: i32) -> Self {
         Self { x, y }
     }
 }
@@ -65,14 +129,21 @@ impl Vector2<f32> {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
 pub enum ConnectionStatus {
     #[default]
+    #[cfg_attr(feature = "serde", serde(rename = "unvisited"))]
     UnVisited,
     Visited,
     InMaze,
+    // excluded from the maze, generation never enters these cells
+    Removed,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
 #[repr(u8)]
 pub enum MazeType {
     #[default]
@@ -84,9 +155,434 @@ pub enum MazeType {
     GrowingTree,
     Wilson,
     Kruskal,
+    Eller,
+    HuntAndKill,
+    RecursiveDivision,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum MazeWrap {
+    #[default]
+    None,
+    Horizontal,
+    Vertical,
+    Full,
+}
+
+// order in which methods that scan the grid visit its cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum ScanOrder {
+    /// left to right, one row after another
+    #[default]
+    Row,
+    /// along a hilbert curve stretched to cover the grid
+    Hilbert,
+}
+
+// way the bias of binary tree and sidewinder mazes points, binary tree takes a corner and
+// sidewinder a side
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum Orientation {
+    /// binary tree passages lead north or west, with open corridors along the top and left edges
+    Nw,
+    /// binary tree passages lead north or east
+    Ne,
+    /// binary tree passages lead south or west
+    Sw,
+    /// binary tree passages lead south or east
+    Se,
+    /// sidewinder runs lie along the open top edge and join the row above
+    North,
+    /// sidewinder runs lie along the open right edge and join the column to the right
+    East,
+    /// sidewinder runs lie along the open bottom edge and join the row below
+    South,
+    /// sidewinder runs lie along the open left edge and join the column to the left
+    West,
+}
+
+impl Orientation {
+    // binary tree carves toward the north west and sidewinder along the north edge, the symmetry
+    // turns such a maze to face this way
+    pub fn symmetry(self) -> Symmetry {
+        let (transpose, mirror_x, mirror_y) = match self {
+            Orientation::Nw | Orientation::North => (false, false, false),
+            Orientation::Ne => (false, true, false),
+            Orientation::Sw | Orientation::South => (false, false, true),
+            Orientation::Se => (false, true, true),
+            Orientation::West => (true, false, false),
+            Orientation::East => (true, true, false),
+        };
+        Symmetry {
+            transpose,
+            mirror_x,
+            mirror_y,
+        }
+    }
+
+    // whether the orientation names a corner rather than a side
+    pub fn is_corner(self) -> bool {
+        matches!(
+            self,
+            Orientation::Nw | Orientation::Ne | Orientation::Sw | Orientation::Se
+        )
+    }
+}
+
+// noise a noise maze is carved along, cells on either side of the threshold are flooded
+// differently
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NoiseOptions {
+    // number of noise features across the width and height of the maze
+    pub frequency: u16,
+    // noise level in hundredths dividing the cells
+    pub threshold: i8,
+}
+
+impl Default for NoiseOptions {
+    fn default() -> Self {
+        NoiseOptions {
+            frequency: 6,
+            threshold: 0,
+        }
+    }
+}
+
+// where weighted prim and kruskal read the cost of carving into each cell from, written as noise
+// or image:<path>
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
+pub enum WeightSource {
+    // the noise a noise maze with the same seed is split by
+    Noise,
+    // brightness of a png stretched over the grid, black is cheapest
+    Image(String),
+}
+
+impl FromStr for WeightSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "noise" => Ok(WeightSource::Noise),
+            Some(("image", path)) if !path.is_empty() => Ok(WeightSource::Image(path.to_string())),
+            _ => Err(format!(
+                "invalid weights \"{}\": expected noise or image:<path>",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for WeightSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WeightSource::Noise => write!(f, "noise"),
+            WeightSource::Image(path) => write!(f, "image:{}", path),
+        }
+    }
+}
+
+impl TryFrom<String> for WeightSource {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<WeightSource> for String {
+    fn from(weights: WeightSource) -> Self {
+        weights.to_string()
+    }
+}
+
+// seeds of the random streams used by each stage of making a maze. every stage after carving
+// derives a stream of its own from the seed, so changing what one stage does leaves the others
+// as they were. the command line replaces a stage seed with its --seed-<stage> flag, library
+// users can pick them separately or derive them per region of a maze too large to generate in
+// one place
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeedSchedule {
+    pub carve: u64,
+    pub uncarve: u64,
+    pub braid: u64,
+    pub symmetry: u64,
+    pub openings: u64,
+    pub doors: u64,
+    pub corridors: u64,
+    pub overlay: u64,
+}
+
+impl SeedSchedule {
+    // carving uses the seed itself, so seeds keep giving the mazes they always gave
+    pub fn new(seed: u64) -> Self {
+        let stage = |name: &[u8; 8]| mix(seed ^ u64::from_be_bytes(*name));
+        SeedSchedule {
+            carve: seed,
+            uncarve: stage(b"uncarve\0"),
+            braid: stage(b"braid\0\0\0"),
+            symmetry: stage(b"symmetry"),
+            openings: stage(b"openings"),
+            doors: stage(b"doors\0\0\0"),
+            corridors: stage(b"corridor"),
+            overlay: stage(b"overlay\0"),
+        }
+    }
+
+    // schedule of the region at column, row of a maze split into separately generated regions.
+    // it only depends on this schedule and the coordinates, so every machine generating a share
+    // of the regions derives the same seeds while neighboring regions get unrelated streams
+    pub fn region(&self, column: u32, row: u32) -> Self {
+        let offset = mix(((column as u64) << 32) | row as u64);
+        let derive = |seed: u64| mix(seed ^ offset);
+        SeedSchedule {
+            carve: derive(self.carve),
+            uncarve: derive(self.uncarve),
+            braid: derive(self.braid),
+            symmetry: derive(self.symmetry),
+            openings: derive(self.openings),
+            doors: derive(self.doors),
+            corridors: derive(self.corridors),
+            overlay: derive(self.overlay),
+        }
+    }
+}
+
+// splitmix64 finalizer, spreads every input bit over the whole output
+fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+// one of the eight ways to rotate or mirror a grid, applied as swapping the axes followed by
+// mirroring each axis of the result
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Symmetry {
+    pub transpose: bool,
+    pub mirror_x: bool,
+    pub mirror_y: bool,
+}
+
+impl Symmetry {
+    // random symmetry keeping the size of a width x height grid, any of the eight for square
+    // grids and otherwise only those that do not swap the axes
+    pub fn random(width: u32, height: u32, rng: &mut dyn RngCore) -> Self {
+        Symmetry {
+            transpose: width == height && rng.gen(),
+            mirror_x: rng.gen(),
+            mirror_y: rng.gen(),
+        }
+    }
+
+    // where a point of a width x height grid ends up
+    pub fn point(self, pt: Point, width: u32, height: u32) -> Point {
+        let (mut pt, mut width, mut height) = (pt, width as i32, height as i32);
+        if self.transpose {
+            pt = Point::new(pt.y, pt.x);
+            (width, height) = (height, width);
+        }
+        if self.mirror_x {
+            pt.x = width - 1 - pt.x;
+        }
+        if self.mirror_y {
+            pt.y = height - 1 - pt.y;
+        }
+        pt
+    }
+
+    pub fn direction(self, dir: Direction) -> Direction {
+        let mut dir = dir;
+        if self.transpose {
+            dir = match dir {
+                Direction::North => Direction::West,
+                Direction::West => Direction::North,
+                Direction::East => Direction::South,
+                Direction::South => Direction::East,
+                Direction::NoDir => Direction::NoDir,
+            };
+        }
+        if self.mirror_x && matches!(dir, Direction::East | Direction::West) {
+            dir = dir.opposite();
+        }
+        if self.mirror_y && matches!(dir, Direction::North | Direction::South) {
+            dir = dir.opposite();
+        }
+        dir
+    }
+
+    pub fn wrap(self, wrap: MazeWrap) -> MazeWrap {
+        match (self.transpose, wrap) {
+            (true, MazeWrap::Horizontal) => MazeWrap::Vertical,
+            (true, MazeWrap::Vertical) => MazeWrap::Horizontal,
+            _ => wrap,
+        }
+    }
+
+    fn size(self, width: u32, height: u32) -> (u32, u32) {
+        match self.transpose {
+            true => (height, width),
+            false => (width, height),
+        }
+    }
+}
+
+// longest dead end corridor thin_corridors trims from what is left of the maze, longer ones are
+// kept as winding passages that lead nowhere
+const STUB_LENGTH: u32 = 2;
+
+// which dead end uncarving removes next
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum UncarveStrategy {
+    /// any current dead end, favoring cells left behind by earlier removals
+    #[default]
+    Uniform,
+    /// only the dead ends of the finished maze
+    Original,
+    /// the end of the longest corridor leading to a dead end
+    Longest,
+    /// the end of the shortest corridor leading to a dead end
+    Shortest,
+}
+
+// outline of the maze, cells outside of the shape stretched over the whole grid are removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum BorderShape {
+    /// ellipse touching all four edges of the grid
+    Circle,
+    /// hexagon with flat top and bottom edges
+    Hex,
+    /// square rotated by 45 degrees with its corners on the edges of the grid
+    Diamond,
+}
+
+impl BorderShape {
+    // whether a point in the grid scaled to -1..1 on both axes lies inside the shape
+    fn contains(self, u: f32, v: f32) -> bool {
+        match self {
+            BorderShape::Circle => u * u + v * v <= 1.0,
+            BorderShape::Hex => v.abs() <= 1.0 && 2.0 * u.abs() + v.abs() <= 2.0,
+            BorderShape::Diamond => u.abs() + v.abs() <= 1.0,
+        }
+    }
+}
+
+// print value enums with the same names used on the command line
+impl fmt::Display for MazeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.to_possible_value().unwrap().get_name().fmt(f)
+    }
+}
+
+impl fmt::Display for MazeWrap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.to_possible_value().unwrap().get_name().fmt(f)
+    }
+}
+
+impl fmt::Display for ScanOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.to_possible_value().unwrap().get_name().fmt(f)
+    }
+}
+
+impl fmt::Display for Orientation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.to_possible_value().unwrap().get_name().fmt(f)
+    }
+}
+
+impl MazeType {
+    // methods that can carve around removed cells, the others visit every cell in a fixed
+    // pattern
+    pub fn supports_exclusions(self) -> bool {
+        matches!(
+            self,
+            MazeType::Backtrack
+                | MazeType::Prim
+                | MazeType::GrowingTree
+                | MazeType::Wilson
+                | MazeType::Kruskal
+                | MazeType::HuntAndKill
+        )
+    }
+
+    // methods that can complete a maze around passages carved before generation
+    pub fn supports_patterns(self) -> bool {
+        matches!(self, MazeType::Wilson | MazeType::Kruskal)
+    }
+
+    // methods that keep template rooms open and build the maze around them. recursive division
+    // never puts a wall through a room, so every room ends up in a chamber of its own, and hunt
+    // and kill joins the rooms it walked up to once its hunt finds no more cells
+    pub fn supports_rooms(self) -> bool {
+        self.supports_patterns()
+            || matches!(self, MazeType::RecursiveDivision | MazeType::HuntAndKill)
+    }
+
+    // widest wrap the method carves passages across, edges it does not support stay walled
+    pub fn supported_wrap(self) -> MazeWrap {
+        match self {
+            MazeType::Backtrack
+            | MazeType::Prim
+            | MazeType::GrowingTree
+            | MazeType::Wilson
+            | MazeType::Kruskal
+            | MazeType::HuntAndKill => MazeWrap::Full,
+            MazeType::Eller => MazeWrap::Horizontal,
+            MazeType::BinaryTree
+            | MazeType::Sidewinder
+            | MazeType::Noise
+            | MazeType::RecursiveDivision => MazeWrap::None,
+        }
+    }
+
+    // methods visiting cells in the order picked by --scan-order
+    pub fn uses_scan_order(self) -> bool {
+        matches!(
+            self,
+            MazeType::BinaryTree | MazeType::HuntAndKill | MazeType::Kruskal
+        )
+    }
+
+    // methods whose bias can be turned by --orientation
+    pub fn uses_orientation(self) -> bool {
+        matches!(self, MazeType::BinaryTree | MazeType::Sidewinder)
+    }
+
+    // methods carving the cheapest passages first when the grid has costs
+    pub fn uses_weights(self) -> bool {
+        matches!(self, MazeType::Prim | MazeType::Kruskal)
+    }
+}
+
+impl MazeWrap {
+    pub fn horizontal(self) -> bool {
+        matches!(self, MazeWrap::Horizontal | MazeWrap::Full)
+    }
+
+    pub fn vertical(self) -> bool {
+        matches!(self, MazeWrap::Vertical | MazeWrap::Full)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
 #[repr(u8)]
 pub enum Direction {
     NoDir = 0b0000,
@@ -109,6 +605,14 @@ impl From<u8> for Direction {
 }
 
 impl Direction {
+    // the four directions a cell can be open to, clockwise from north
+    pub const ALL: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+
     pub fn opposite(self) -> Self {
         match self {
             Direction::North => Direction::South,
@@ -120,162 +624,1869 @@ impl Direction {
     }
 }
 
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
-pub struct Tile {
-    pub status: ConnectionStatus,
-    pub connections: u8,
+// rectangular area of cells, stored in config files in the same x,y,width,height form used on
+// the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
 }
 
-impl Tile {
-    pub fn connect(&mut self, dir: Direction) {
-        self.connections |= dir as u8;
+impl Rect {
+    pub fn contains(&self, pt: Point) -> bool {
+        pt.x as i64 >= self.x as i64
+            && pt.y as i64 >= self.y as i64
+            && (pt.x as i64) < self.x as i64 + self.width as i64
+            && (pt.y as i64) < self.y as i64 + self.height as i64
     }
 
-    pub fn connected(&self, dir: Direction) -> bool {
-        self.connections & dir as u8 != 0
+    // cells covered by both rects, None when they do not overlap
+    pub fn intersect(&self, other: Rect) -> Option<Rect> {
+        let (x, y) = (self.x.max(other.x), self.y.max(other.y));
+        let right = (self.x as u64 + self.width as u64).min(other.x as u64 + other.width as u64);
+        let bottom = (self.y as u64 + self.height as u64).min(other.y as u64 + other.height as u64);
+        (right > x as u64 && bottom > y as u64).then(|| Rect {
+            x,
+            y,
+            width: (right - x as u64) as u32,
+            height: (bottom - y as u64) as u32,
+        })
     }
 
-    pub fn set_connected(&mut self, dir: Direction) {
-        self.connections = dir as u8;
+    // every cell of the rect in row order
+    pub fn cells(self) -> impl Iterator<Item = Point> {
+        (self.y..self.y + self.height)
+            .flat_map(move |y| (self.x..self.x + self.width).map(move |x| (x, y)))
+            .map(|(x, y)| Point::new(x as i32, y as i32))
     }
 }
 
-#[derive(Debug)]
-pub struct Grid {
-    pub tiles: Vec<Tile>,
-    pub width: u16,
-    pub height: u16,
+// parses rects written as x,y,width,height
+impl FromStr for Rect {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s
+            .split(',')
+            .map(|v| v.trim().parse::<u32>())
+            .collect::<Result<Vec<u32>, _>>()
+            .map_err(|e| format!("invalid rect \"{}\": {}", s, e))?;
+
+        match values[..] {
+            [x, y, width, height] => Ok(Rect {
+                x,
+                y,
+                width,
+                height,
+            }),
+            _ => Err(format!("invalid rect \"{}\": expected x,y,width,height", s)),
+        }
+    }
 }
 
-impl Grid {
-    pub fn get_index(&self, pos: Point) -> usize {
-        pos.x as usize + pos.y as usize * self.width as usize
+impl fmt::Display for Rect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{},{},{}", self.x, self.y, self.width, self.height)
     }
+}
 
-    pub fn contains(&self, pt: Point) -> bool {
-        pt.x >= 0 && (pt.x as u16) < self.width && pt.y >= 0 && (pt.y as u16) < self.height
+impl TryFrom<String> for Rect {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
     }
+}
 
-    pub fn get_tile(&self, pos: Point) -> Tile {
-        assert!(self.contains(pos));
-        self.tiles[pos.x as usize + pos.y as usize * self.width as usize]
+impl From<Rect> for String {
+    fn from(rect: Rect) -> Self {
+        rect.to_string()
     }
+}
 
-    pub fn get_tile_mut(&mut self, pos: Point) -> &mut Tile {
-        assert!(self.contains(pos));
-        &mut self.tiles[pos.x as usize + pos.y as usize * self.width as usize]
+// text attached to a cell, written as x,y,text on the command line and in config files. the
+// text may contain commas and the whole label may be wrapped in parentheses
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
+pub struct CellLabel {
+    pub pt: Point,
+    pub text: String,
+}
+
+impl FromStr for CellLabel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let inner = s
+            .strip_prefix('(')
+            .and_then(|s| s.strip_suffix(')'))
+            .unwrap_or(s);
+        let parts: Vec<&str> = inner.splitn(3, ',').collect();
+
+        match parts[..] {
+            [x, y, text] if !text.is_empty() => {
+                let coord = |v: &str| match v.trim().parse::<i32>() {
+                    Ok(v) if v >= 0 => Ok(v),
+                    Ok(_) => Err(format!("invalid label \"{}\": negative coordinate", s)),
+                    Err(e) => Err(format!("invalid label \"{}\": {}", s, e)),
+                };
+                Ok(CellLabel {
+                    pt: Point::new(coord(x)?, coord(y)?),
+                    text: text.to_string(),
+                })
+            }
+            _ => Err(format!("invalid label \"{}\": expected x,y,text", s)),
+        }
     }
+}
 
-    pub fn set_tile(&mut self, pos: Point, new: Tile) {
-        assert!(self.contains(pos));
-        self.tiles[pos.x as usize + pos.y as usize * self.width as usize] = new;
+impl fmt::Display for CellLabel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{},{}", self.pt.x, self.pt.y, self.text)
     }
 }
 
-fn opposite(src: u8) -> u8 {
-    ((src << 2) | (src >> 2)) & 0b1111
+impl TryFrom<String> for CellLabel {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
 }
 
-fn pick_random(points: &[(usize, Point)], rng: &mut StdRng) -> Option<(usize, Point)> {
-    if points.len() > 0 {
-        Some(points[rng.gen_range(0..points.len())])
-    } else {
-        None
+impl From<CellLabel> for String {
+    fn from(label: CellLabel) -> Self {
+        label.to_string()
     }
 }
 
-pub fn generate_maze(
-    width: u16,
-    height: u16,
-    mtype: MazeType,
-    rng: &mut StdRng,
-) -> (Grid, Vec<(Point, Direction)>) {
-    let maze: Grid = Grid {
-        tiles: vec![Tile::default(); width as usize * height as usize],
-        width: width,
-        height: height,
-    };
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum BorderSide {
+    Top,
+    Right,
+    Bottom,
+    Left,
+}
 
-    match mtype {
-        MazeType::Backtrack => create_maze_backtrack(maze, rng),
-        MazeType::Prim => create_maze_prim(maze, rng),
-        MazeType::BinaryTree => create_maze_binary(maze, rng),
-        MazeType::Sidewinder => create_maze_sidewinder(maze, rng),
-        MazeType::Noise => create_maze_noise(maze, rng),
-        MazeType::GrowingTree => create_maze_growingtree(maze, rng, GrowingTreeBias::Percent(10)),
-        MazeType::Wilson => create_maze_wilson(maze, rng),
-        MazeType::Kruskal => create_maze_kruskal(maze, rng),
+impl BorderSide {
+    // direction leading out of the maze through this side
+    pub fn outward(self) -> Direction {
+        match self {
+            BorderSide::Top => Direction::North,
+            BorderSide::Right => Direction::East,
+            BorderSide::Bottom => Direction::South,
+            BorderSide::Left => Direction::West,
+        }
+    }
+
+    // sides that are walls rather than edges the maze wraps around
+    pub fn walled(wrap: MazeWrap) -> Vec<BorderSide> {
+        BorderSide::value_variants()
+            .iter()
+            .copied()
+            .filter(|side| match side {
+                BorderSide::Top | BorderSide::Bottom => !wrap.vertical(),
+                BorderSide::Left | BorderSide::Right => !wrap.horizontal(),
+            })
+            .collect()
     }
 }
 
-fn create_maze_backtrack(mut maze: Grid, rng: &mut StdRng) -> (Grid, Vec<(Point, Direction)>) {
-    let mut stack: Vec<Point> = Vec::new();
-    let mut pos: Point = Point::new(
-        rng.gen_range(0..maze.width) as i16,
-        rng.gen_range(0..maze.height) as i16,
-    );
-    let mut history: Vec<(Point, Direction)> = Vec::with_capacity(maze.tiles.len());
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpeningPosition {
+    // cell counted from the top or left end of the side
+    Cell(u32),
+    Center,
+    Random,
+}
 
-    maze.get_tile_mut(pos).status = ConnectionStatus::InMaze;
-    stack.push(pos);
-    history.push((pos, Direction::NoDir.into()));
+// gap in the outer wall, written as side:cell, side:center or side:random, or just random for a
+// random side. cells are counted from 0 at the top or left end of the side
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
+pub struct Opening {
+    // None picks a random side
+    pub side: Option<BorderSide>,
+    pub position: OpeningPosition,
+}
 
-    while !stack.is_empty() {
-        let next = pick_random(
-            pos.adjacent()
-                .into_iter()
-                .enumerate()
-                .filter(|(_, x)| {
-                    maze.contains(*x) && maze.get_tile(*x).status == ConnectionStatus::UnVisited
-                })
-                .collect::<Vec<(usize, Point)>>()
-                .as_ref(),
-            rng,
-        );
+impl FromStr for Opening {
+    type Err = String;
 
-        match next {
-            None => {
-                pos = stack.pop().unwrap();
-            }
-            Some(next) => {
-                let dir = 0b0001 << next.0;
-                maze.get_tile_mut(pos).connect(dir.into());
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "random" {
+            return Ok(Opening {
+                side: None,
+                position: OpeningPosition::Random,
+            });
+        }
 
-                pos = next.1;
-                maze.get_tile_mut(pos).connect(opposite(dir).into());
-                maze.get_tile_mut(pos).status = ConnectionStatus::InMaze;
+        let invalid = |reason: &str| format!("invalid opening \"{}\": {}", s, reason);
+        let (side, position) = s
+            .split_once(':')
+            .ok_or_else(|| invalid("expected side:position or random"))?;
+        let side = BorderSide::from_str(side, false)
+            .map_err(|_| invalid("side must be top, right, bottom or left"))?;
+        let position = match position {
+            "center" => OpeningPosition::Center,
+            "random" => OpeningPosition::Random,
+            cell => OpeningPosition::Cell(cell.parse().map_err(|e| invalid(&format!("{}", e)))?),
+        };
 
-                stack.push(pos);
-                history.push((pos, opposite(dir).into()));
-            }
+        Ok(Opening {
+            side: Some(side),
+            position,
+        })
+    }
+}
+
+impl fmt::Display for Opening {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(side) = self.side else {
+            return write!(f, "random");
+        };
+        let side = side.to_possible_value().unwrap();
+        match self.position {
+            OpeningPosition::Cell(cell) => write!(f, "{}:{}", side.get_name(), cell),
+            OpeningPosition::Center => write!(f, "{}:center", side.get_name()),
+            OpeningPosition::Random => write!(f, "{}:random", side.get_name()),
         }
     }
+}
 
-    (maze, history)
+impl TryFrom<String> for Opening {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
 }
 
-fn create_maze_prim(mut maze: Grid, rng: &mut StdRng) -> (Grid, Vec<(Point, Direction)>) {
-    let mut open_tiles: Vec<Point> = Vec::new();
-    let mut history: Vec<(Point, Direction)> = Vec::with_capacity(maze.tiles.len());
-    let mut pos: Point = Point::new(
-        rng.gen_range(0..maze.width) as i16,
-        rng.gen_range(0..maze.height) as i16,
-    );
+impl From<Opening> for String {
+    fn from(opening: Opening) -> Self {
+        opening.to_string()
+    }
+}
 
-    maze.get_tile_mut(pos).status = ConnectionStatus::InMaze;
-    open_tiles.push(pos);
-    history.push((pos, Direction::NoDir.into()));
+// status of a cell in the bits above its four connections, so every cell takes a single byte and
+// mazes of tens of millions of cells fit in memory
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "TileFields", into = "TileFields"))]
+pub struct Tile(u8);
 
-    while !open_tiles.is_empty() {
-        let current_tile_index: usize = rng.gen_range(0..open_tiles.len());
-        pos = open_tiles[current_tile_index];
+// how a tile is serialized, with its status and connections spelled out
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct TileFields {
+    status: ConnectionStatus,
+    connections: u8,
+}
 
-        let next = pick_random(
-            pos.adjacent()
-                .into_iter()
-                .enumerate()
+#[cfg(feature = "serde")]
+impl TryFrom<TileFields> for Tile {
+    type Error = String;
+
+    fn try_from(fields: TileFields) -> Result<Self, Self::Error> {
+        if fields.connections > 0b1111 {
+            return Err(format!("invalid connections {}", fields.connections));
+        }
+        Ok(Tile::new(fields.status, fields.connections))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Tile> for TileFields {
+    fn from(tile: Tile) -> Self {
+        TileFields {
+            status: tile.status(),
+            connections: tile.connections(),
+        }
+    }
+}
+
+impl Tile {
+    pub fn new(status: ConnectionStatus, connections: u8) -> Self {
+        Tile((status as u8) << 4 | connections & 0b1111)
+    }
+
+    pub fn status(&self) -> ConnectionStatus {
+        match self.0 >> 4 {
+            0 => ConnectionStatus::UnVisited,
+            1 => ConnectionStatus::Visited,
+            2 => ConnectionStatus::InMaze,
+            _ => ConnectionStatus::Removed,
+        }
+    }
+
+    pub fn set_status(&mut self, status: ConnectionStatus) {
+        *self = Tile::new(status, self.connections());
+    }
+
+    // bits of the directions the cell is open to
+    pub fn connections(&self) -> u8 {
+        self.0 & 0b1111
+    }
+
+    pub fn set_connections(&mut self, connections: u8) {
+        *self = Tile::new(self.status(), connections);
+    }
+
+    pub fn add_connections(&mut self, connections: u8) {
+        self.set_connections(self.connections() | connections);
+    }
+
+    pub fn disconnect(&mut self, dir: Direction) {
+        self.set_connections(self.connections() & !(dir as u8));
+    }
+
+    pub fn connect(&mut self, dir: Direction) {
+        self.add_connections(dir as u8);
+    }
+
+    pub fn connected(&self, dir: Direction) -> bool {
+        self.connections() & dir as u8 != 0
+    }
+
+    pub fn set_connected(&mut self, dir: Direction) {
+        self.set_connections(dir as u8);
+    }
+}
+
+impl fmt::Debug for Tile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tile")
+            .field("status", &self.status())
+            .field("connections", &self.connections())
+            .finish()
+    }
+}
+
+// serialized as is. reading one back checks that the tiles cover the grid but not that they agree
+// with each other, read_json checks both for maze files
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "GridFields"))]
+pub struct Grid {
+    pub tiles: Vec<Tile>,
+    pub width: u32,
+    pub height: u32,
+    pub wrap: MazeWrap,
+    pub params: GenParams,
+    // cost from 0 to 1 of carving into each cell, filled from weights before carving and only
+    // read while carving. empty when passages are carved in random order
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub costs: Vec<f32>,
+}
+
+// how a grid is deserialized, before its tile count is checked
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct GridFields {
+    tiles: Vec<Tile>,
+    width: u32,
+    height: u32,
+    wrap: MazeWrap,
+    params: GenParams,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<GridFields> for Grid {
+    type Error = String;
+
+    fn try_from(fields: GridFields) -> Result<Self, Self::Error> {
+        let cells = fields.width as u64 * fields.height as u64;
+        if fields.tiles.len() as u64 != cells {
+            return Err(format!(
+                "{} tiles for a {}x{} grid",
+                fields.tiles.len(),
+                fields.width,
+                fields.height
+            ));
+        }
+        Ok(Grid {
+            tiles: fields.tiles,
+            width: fields.width,
+            height: fields.height,
+            wrap: fields.wrap,
+            params: fields.params,
+            costs: Vec::new(),
+        })
+    }
+}
+
+// options a maze is carved with besides its size and wrap, copied along from the grid into its
+// history and into every grid derived from it
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GenParams {
+    pub seed: u64,
+    pub method: MazeType,
+    pub scan_order: ScanOrder,
+    pub noise: NoiseOptions,
+    pub bias: GrowingTreeBias,
+    // way binary tree and sidewinder mazes point, None carves them toward the north west
+    pub orientation: Option<Orientation>,
+    pub weights: Option<WeightSource>,
+}
+
+impl GenParams {
+    // the given seed and method with every other option at its default
+    pub fn new(seed: u64, method: MazeType) -> Self {
+        Self {
+            seed,
+            method,
+            scan_order: ScanOrder::default(),
+            noise: NoiseOptions::default(),
+            bias: GrowingTreeBias::default(),
+            orientation: None,
+            weights: None,
+        }
+    }
+}
+
+// parameters a maze was generated with, embedded into every export so a maze can be
+// reproduced or reinterpreted without the caller supplying them again
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MazeMetadata {
+    pub seed: u64,
+    pub method: MazeType,
+    pub wrap: MazeWrap,
+    pub scan_order: ScanOrder,
+    // only set for noise mazes
+    pub noise: Option<NoiseOptions>,
+    // only set for growing tree mazes
+    pub bias: Option<GrowingTreeBias>,
+    // only set for binary tree and sidewinder mazes that were turned
+    pub orientation: Option<Orientation>,
+    // only set for methods that use weights
+    pub weights: Option<WeightSource>,
+    pub width: u32,
+    pub height: u32,
+    pub version: String,
+}
+
+impl MazeMetadata {
+    pub fn entries(&self) -> Vec<(&'static str, String)> {
+        let mut entries = vec![
+            ("seed", self.seed.to_string()),
+            ("method", self.method.to_string()),
+            ("wrap", self.wrap.to_string()),
+            ("scan-order", self.scan_order.to_string()),
+            ("size", format!("{}x{}", self.width, self.height)),
+            ("version", self.version.clone()),
+        ];
+        if let Some(noise) = self.noise {
+            entries.push((
+                "noise",
+                format!(
+                    "frequency={},threshold={}",
+                    noise.frequency, noise.threshold
+                ),
+            ));
+        }
+        if let Some(bias) = &self.bias {
+            entries.push(("bias", bias.to_string()));
+        }
+        if let Some(orientation) = self.orientation {
+            entries.push(("orientation", orientation.to_string()));
+        }
+        if let Some(weights) = &self.weights {
+            entries.push(("weights", weights.to_string()));
+        }
+        entries
+    }
+
+    // entries as key=value lines, for formats that only carry a single comment
+    pub fn comment(&self) -> String {
+        let lines: Vec<String> = self
+            .entries()
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect();
+        lines.join("\n")
+    }
+}
+
+impl Grid {
+    // grid without any connections, ready to be carved with the given method
+    pub fn new(width: u32, height: u32, wrap: MazeWrap, seed: u64, method: MazeType) -> Self {
+        Self {
+            tiles: vec![Tile::default(); width as usize * height as usize],
+            width,
+            height,
+            wrap,
+            params: GenParams::new(seed, method),
+            costs: Vec::new(),
+        }
+    }
+
+    // marks the cells inside the rect as removed, parts of the rect outside the grid are ignored
+    pub fn exclude(&mut self, rect: Rect) {
+        for y in rect.y..rect.y.saturating_add(rect.height).min(self.height) {
+            for x in rect.x..rect.x.saturating_add(rect.width).min(self.width) {
+                self.get_tile_mut(Point::new(x as i32, y as i32))
+                    .set_status(ConnectionStatus::Removed);
+            }
+        }
+    }
+
+    // removes every cell whose center lies outside of the shape
+    pub fn exclude_outside(&mut self, shape: BorderShape) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let u = (2 * x + 1) as f32 / self.width as f32 - 1.0;
+                let v = (2 * y + 1) as f32 / self.height as f32 - 1.0;
+                if !shape.contains(u, v) {
+                    self.get_tile_mut(Point::new(x as i32, y as i32))
+                        .set_status(ConnectionStatus::Removed);
+                }
+            }
+        }
+    }
+
+    pub fn is_removed(&self, pt: Point) -> bool {
+        self.get_tile(pt).status() == ConnectionStatus::Removed
+    }
+
+    // cells that can be reached from start without crossing removed cells
+    fn region(&self, start: Point) -> Vec<bool> {
+        let mut reached = vec![false; self.tiles.len()];
+        let mut open = vec![start];
+        reached[self.get_index(start)] = true;
+
+        while let Some(pos) = open.pop() {
+            for next in self.adjacent(pos) {
+                if self.contains(next) && !self.is_removed(next) && !reached[self.get_index(next)] {
+                    reached[self.get_index(next)] = true;
+                    open.push(next);
+                }
+            }
+        }
+
+        reached
+    }
+
+    pub fn metadata(&self) -> MazeMetadata {
+        MazeMetadata {
+            seed: self.params.seed,
+            method: self.params.method,
+            wrap: self.wrap,
+            scan_order: self.params.scan_order,
+            noise: (self.params.method == MazeType::Noise).then_some(self.params.noise),
+            bias: (self.params.method == MazeType::GrowingTree).then(|| self.params.bias.clone()),
+            orientation: self
+                .params
+                .orientation
+                .filter(|_| self.params.method.uses_orientation()),
+            weights: self
+                .params
+                .weights
+                .clone()
+                .filter(|_| self.params.method.uses_weights()),
+            width: self.width,
+            height: self.height,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+
+    // every cell of the grid in its scan order
+    pub fn scan_cells(&self) -> Vec<Point> {
+        let mut cells = Vec::with_capacity(self.tiles.len());
+        match self.params.scan_order {
+            ScanOrder::Row => {
+                for y in 0..self.height as i32 {
+                    for x in 0..self.width as i32 {
+                        cells.push(Point::new(x, y));
+                    }
+                }
+            }
+            ScanOrder::Hilbert if self.width >= self.height => gilbert(
+                &mut cells,
+                Point::new(0, 0),
+                Point::new(self.width as i32, 0),
+                Point::new(0, self.height as i32),
+            ),
+            ScanOrder::Hilbert => gilbert(
+                &mut cells,
+                Point::new(0, 0),
+                Point::new(0, self.height as i32),
+                Point::new(self.width as i32, 0),
+            ),
+        }
+        cells
+    }
+
+    pub fn travel(&self, pt: Point, dir: Direction) -> Point {
+        pt.travel_wrapped(dir, self.width, self.height, self.wrap)
+    }
+
+    // same order as Point::adjacent, but wrapping around edges according to the wrap mode
+    pub fn adjacent(&self, pt: Point) -> [Point; 4] {
+        [
+            self.travel(pt, Direction::North),
+            self.travel(pt, Direction::East),
+            self.travel(pt, Direction::South),
+            self.travel(pt, Direction::West),
+        ]
+    }
+
+    // copy of the cells inside a rect, keeping connections that leave the rect so passages
+    // crossing its border stay visible. None if the rect does not fit inside the maze
+    pub fn crop(&self, rect: Rect) -> Option<Grid> {
+        if rect.width == 0
+            || rect.height == 0
+            || rect.x as u64 + rect.width as u64 > self.width as u64
+            || rect.y as u64 + rect.height as u64 > self.height as u64
+        {
+            return None;
+        }
+
+        let mut tiles: Vec<Tile> = Vec::with_capacity(rect.width as usize * rect.height as usize);
+        for y in rect.y..(rect.y + rect.height) {
+            for x in rect.x..(rect.x + rect.width) {
+                tiles.push(self.get_tile(Point::new(x as i32, y as i32)));
+            }
+        }
+
+        Some(Grid {
+            tiles,
+            width: rect.width,
+            height: rect.height,
+            wrap: MazeWrap::None,
+            params: self.params.clone(),
+            costs: Vec::new(),
+        })
+    }
+
+    // cell next to the opening and the direction leading out of the maze from it. random sides
+    // and positions are drawn from rng, random sides only among walled sides and random positions
+    // only among cells that are not removed. None if the cell lies outside of the side, is
+    // removed, or there is no walled side or open cell to pick from
+    pub fn opening_cell(
+        &self,
+        opening: Opening,
+        rng: &mut dyn RngCore,
+    ) -> Option<(Point, Direction)> {
+        let side = match opening.side {
+            Some(side) => side,
+            None => {
+                let sides = BorderSide::walled(self.wrap);
+                *sides.get(rng.gen_range(0..sides.len().max(1)))?
+            }
+        };
+        let length = match side {
+            BorderSide::Top | BorderSide::Bottom => self.width,
+            BorderSide::Left | BorderSide::Right => self.height,
+        };
+        let cell = |i: u32| match side {
+            BorderSide::Top => Point::new(i as i32, 0),
+            BorderSide::Right => Point::new(self.width as i32 - 1, i as i32),
+            BorderSide::Bottom => Point::new(i as i32, self.height as i32 - 1),
+            BorderSide::Left => Point::new(0, i as i32),
+        };
+
+        let pt = match opening.position {
+            OpeningPosition::Cell(i) if i < length => cell(i),
+            OpeningPosition::Cell(_) => return None,
+            OpeningPosition::Center => cell(length / 2),
+            OpeningPosition::Random => {
+                let open: Vec<Point> = (0..length)
+                    .map(cell)
+                    .filter(|pt| !self.is_removed(*pt))
+                    .collect();
+                *open.get(rng.gen_range(0..open.len().max(1)))?
+            }
+        };
+        (!self.is_removed(pt)).then_some((pt, side.outward()))
+    }
+
+    // removes up to cells dead ends one after another, choosing among ties at random with seed.
+    // removing a dead end can leave its neighbor as a new one. returns how many cells were removed
+    pub fn uncarve(&mut self, cells: u32, strategy: UncarveStrategy, seed: u64) -> u32 {
+        self.uncarve_keeping(cells, strategy, seed, |_| false)
+    }
+
+    // uncarve, leaving the cells for which keep is true in place even when they are dead ends
+    fn uncarve_keeping(
+        &mut self,
+        cells: u32,
+        strategy: UncarveStrategy,
+        seed: u64,
+        keep: impl Fn(Point) -> bool,
+    ) -> u32 {
+        let mut rng: StdRng = StdRng::seed_from_u64(seed);
+        let degree = |maze: &Grid, pt: Point| maze.get_tile(pt).connections().count_ones();
+
+        let mut dead_ends: Vec<Point> = self
+            .scan_cells()
+            .into_iter()
+            .filter(|pt| degree(self, *pt) == 1 && !keep(*pt))
+            .collect();
+        // cells from each dead end up to the next junction, only kept up to date for the
+        // strategies that need it
+        let by_length = matches!(
+            strategy,
+            UncarveStrategy::Longest | UncarveStrategy::Shortest
+        );
+        let mut lengths: Vec<u32> = match by_length {
+            true => dead_ends
+                .iter()
+                .map(|pt| self.corridor_length(*pt))
+                .collect(),
+            false => Vec::new(),
+        };
+
+        let mut removed = 0;
+        while removed < cells && !dead_ends.is_empty() {
+            let index = match strategy {
+                UncarveStrategy::Uniform | UncarveStrategy::Original => {
+                    rng.gen_range(0..dead_ends.len())
+                }
+                UncarveStrategy::Longest | UncarveStrategy::Shortest => {
+                    let best = match strategy {
+                        UncarveStrategy::Longest => lengths.iter().max(),
+                        _ => lengths.iter().min(),
+                    };
+                    let ties: Vec<usize> = (0..lengths.len())
+                        .filter(|i| Some(&lengths[*i]) == best)
+                        .collect();
+                    ties[rng.gen_range(0..ties.len())]
+                }
+            };
+            let pt = dead_ends.swap_remove(index);
+            let length = match by_length {
+                true => lengths.swap_remove(index),
+                false => 0,
+            };
+
+            let next = self.remove_dead_end(pt);
+            removed += 1;
+
+            match degree(self, next) {
+                1 if strategy != UncarveStrategy::Original && !keep(next) => {
+                    dead_ends.push(next);
+                    if by_length {
+                        lengths.push(length - 1);
+                    }
+                }
+                // the junction became part of a corridor, joining the corridors around it
+                2 if by_length => {
+                    lengths = dead_ends
+                        .iter()
+                        .map(|pt| self.corridor_length(*pt))
+                        .collect()
+                }
+                // the neighbor of an original dead end may itself have been one
+                0 => {
+                    if let Some(i) = dead_ends.iter().position(|pt| *pt == next) {
+                        dead_ends.swap_remove(i);
+                        if by_length {
+                            lengths.swap_remove(i);
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        removed
+    }
+
+    // removes a dead end from the maze, returning the cell it led to
+    fn remove_dead_end(&mut self, pt: Point) -> Point {
+        let dir: Direction = self.get_tile(pt).connections().into();
+        let next = self.travel(pt, dir);
+        self.set_tile(pt, Tile::new(ConnectionStatus::Removed, 0));
+        self.get_tile_mut(next).disconnect(dir.opposite());
+        next
+    }
+
+    // keeps passages on percent of the open cells outside of the rooms, removing dead ends at
+    // random with seed like uncarve and then the stubs of up to STUB_LENGTH cells left branching
+    // off the corridors. room cells are never removed, so the corridors between rooms stay. returns
+    // how many cells were removed. percentages above 100 keep every cell like 100
+    pub fn thin_corridors(&mut self, percent: u8, rooms: &[Point], seed: u64) -> u32 {
+        let percent = percent.min(100);
+        let width = self.width as usize;
+        let mut in_room = vec![false; self.tiles.len()];
+        for pt in rooms {
+            in_room[self.get_index(*pt)] = true;
+        }
+        let keep = |pt: Point| in_room[pt.x as usize + pt.y as usize * width];
+
+        let area = self
+            .scan_cells()
+            .into_iter()
+            .filter(|pt| !self.is_removed(*pt) && !keep(*pt))
+            .count() as u32;
+        let mut removed = self.uncarve_keeping(
+            area - area * percent as u32 / 100,
+            UncarveStrategy::Uniform,
+            seed,
+            keep,
+        );
+
+        let dead_ends: Vec<Point> = self
+            .scan_cells()
+            .into_iter()
+            .filter(|pt| self.get_tile(*pt).connections().count_ones() == 1 && !keep(*pt))
+            .collect();
+        for pt in dead_ends {
+            // stubs trimmed before may have joined this one into a longer corridor
+            let Some(length) = self.stub_length(pt, keep) else {
+                continue;
+            };
+            let mut pos = pt;
+            for _ in 0..length {
+                pos = self.remove_dead_end(pos);
+            }
+            removed += length;
+        }
+
+        removed
+    }
+
+    // cells of the corridor from a dead end outside of the rooms to the junction it branches off,
+    // if it is a stub of at most STUB_LENGTH cells
+    fn stub_length(&self, start: Point, keep: impl Fn(Point) -> bool) -> Option<u32> {
+        let degree = |pt: Point| self.get_tile(pt).connections().count_ones();
+        if degree(start) != 1 {
+            return None;
+        }
+        let mut pos = start;
+        let mut from = Direction::NoDir;
+        for length in 1..=STUB_LENGTH {
+            let dir: Direction = (self.get_tile(pos).connections() & !(from as u8)).into();
+            let next = self.travel(pos, dir);
+            match degree(next) {
+                _ if keep(next) => return None,
+                2 => (),
+                // the other end of a corridor standing on its own
+                1 => return None,
+                _ => return Some(length),
+            }
+            pos = next;
+            from = dir.opposite();
+        }
+        None
+    }
+
+    // opens a wall at percent of the dead ends, picked at random with seed, so the maze has
+    // loops and more than one solution. walls to neighbors that are dead ends themselves are
+    // opened first, removing two dead ends at once. returns the opened walls in order
+    pub fn braid(&mut self, percent: u8, seed: u64) -> Vec<(Point, Direction)> {
+        let mut rng: StdRng = StdRng::seed_from_u64(seed);
+        let degree = |maze: &Grid, pt: Point| maze.get_tile(pt).connections().count_ones();
+
+        let mut dead_ends: Vec<Point> = self
+            .scan_cells()
+            .into_iter()
+            .filter(|pt| degree(self, *pt) == 1)
+            .collect();
+        for i in 0..dead_ends.len() {
+            let index = rng.gen_range(i..dead_ends.len());
+            dead_ends.swap(i, index);
+        }
+        dead_ends.truncate(dead_ends.len() * percent as usize / 100);
+
+        let mut opened: Vec<(Point, Direction)> = Vec::new();
+        for pt in dead_ends {
+            // already joined by a neighboring dead end
+            if degree(self, pt) != 1 {
+                continue;
+            }
+            let closed: Vec<(Direction, Point)> = Direction::ALL
+                .into_iter()
+                .filter(|dir| !self.get_tile(pt).connected(*dir))
+                .map(|dir| (dir, self.travel(pt, dir)))
+                .filter(|(_, next)| self.contains(*next) && !self.is_removed(*next))
+                .collect();
+            let dead: Vec<(Direction, Point)> = closed
+                .iter()
+                .copied()
+                .filter(|(_, next)| degree(self, *next) == 1)
+                .collect();
+            let choices = if dead.is_empty() { closed } else { dead };
+            if choices.is_empty() {
+                continue;
+            }
+
+            let (dir, next) = choices[rng.gen_range(0..choices.len())];
+            self.get_tile_mut(pt).connect(dir);
+            self.get_tile_mut(next).connect(dir.opposite());
+            opened.push((pt, dir));
+        }
+
+        opened
+    }
+
+    // cells of the corridor starting at a dead end, up to but not including the junction it
+    // leads to
+    fn corridor_length(&self, start: Point) -> u32 {
+        let mut pos = start;
+        let mut from = Direction::NoDir;
+        let mut length = 0;
+        loop {
+            let tile = self.get_tile(pos);
+            if tile.connections().count_ones() > 2 {
+                return length;
+            }
+            length += 1;
+            // the far end of a maze that is a single corridor
+            let ahead = tile.connections() & !(from as u8);
+            if ahead == 0 {
+                return length;
+            }
+            let dir: Direction = ahead.into();
+            pos = self.travel(pos, dir);
+            from = dir.opposite();
+        }
+    }
+
+    // copy of the grid rotated or mirrored by the symmetry, cells keep their status
+    pub fn transform(&self, symmetry: Symmetry) -> Grid {
+        let (width, height) = symmetry.size(self.width, self.height);
+        let mut transformed = Grid {
+            tiles: vec![Tile::default(); self.tiles.len()],
+            width,
+            height,
+            wrap: symmetry.wrap(self.wrap),
+            params: self.params.clone(),
+            costs: Vec::new(),
+        };
+
+        for (i, tile) in self.tiles.iter().enumerate() {
+            let pt = Point::new(
+                (i % self.width as usize) as i32,
+                (i / self.width as usize) as i32,
+            );
+            let mut moved = Tile::new(tile.status(), 0);
+            for dir in Direction::ALL {
+                if tile.connected(dir) {
+                    moved.connect(symmetry.direction(dir));
+                }
+            }
+            transformed.set_tile(symmetry.point(pt, self.width, self.height), moved);
+        }
+
+        transformed
+    }
+
+    // swaps walls and passages between neighboring cells. the outer border is kept, as are the
+    // walls around removed cells, so the result is no longer a perfect maze but always renders
+    pub fn invert(&self) -> Grid {
+        let mut inverted = self.clone();
+        for (i, tile) in inverted.tiles.iter_mut().enumerate() {
+            let pt = Point::new(
+                (i % self.width as usize) as i32,
+                (i / self.width as usize) as i32,
+            );
+            tile.set_connections(0);
+            if self.is_removed(pt) {
+                continue;
+            }
+
+            for dir in Direction::ALL {
+                let next = self.travel(pt, dir);
+                if self.contains(next)
+                    && next != pt
+                    && !self.is_removed(next)
+                    && !self.get_tile(pt).connected(dir)
+                {
+                    tile.connect(dir);
+                }
+            }
+        }
+
+        inverted
+    }
+
+    pub fn get_index(&self, pos: Point) -> usize {
+        pos.x as usize + pos.y as usize * self.width as usize
+    }
+
+    pub fn contains(&self, pt: Point) -> bool {
+        pt.x >= 0 && (pt.x as u32) < self.width && pt.y >= 0 && (pt.y as u32) < self.height
+    }
+
+    pub fn get_tile(&self, pos: Point) -> Tile {
+        assert!(self.contains(pos));
+        self.tiles[pos.x as usize + pos.y as usize * self.width as usize]
+    }
+
+    pub fn get_tile_mut(&mut self, pos: Point) -> &mut Tile {
+        assert!(self.contains(pos));
+        &mut self.tiles[pos.x as usize + pos.y as usize * self.width as usize]
+    }
+
+    pub fn set_tile(&mut self, pos: Point, new: Tile) {
+        assert!(self.contains(pos));
+        self.tiles[pos.x as usize + pos.y as usize * self.width as usize] = new;
+    }
+
+    // the cell at pt and its neighbor in dir, which is None where the wall leads out of the grid.
+    // None if the wall can not be edited because either cell is removed
+    fn wall_cells(&self, pt: Point, dir: Direction) -> Option<(Point, Option<Point>)> {
+        if dir == Direction::NoDir || !self.contains(pt) || self.is_removed(pt) {
+            return None;
+        }
+        let next = self.travel(pt, dir);
+        match self.contains(next) {
+            true if self.is_removed(next) => None,
+            true => Some((pt, Some(next))),
+            false => Some((pt, None)),
+        }
+    }
+
+    // opens the wall of the cell at pt toward dir and connects its neighbor back to it, adding
+    // both to the maze. a wall on the border of a grid that does not wrap opens the way out of it.
+    // None if the wall is already open or either cell is removed
+    pub fn carve(&mut self, pt: Point, dir: Direction) -> Option<WallEdit> {
+        let (pt, next) = self.wall_cells(pt, dir)?;
+        if self.get_tile(pt).connected(dir) {
+            return None;
+        }
+        let edit = WallEdit::new(self, pt, dir, next, true);
+        self.get_tile_mut(pt).connect(dir);
+        self.get_tile_mut(pt).set_status(ConnectionStatus::InMaze);
+        if let Some(next) = next {
+            self.get_tile_mut(next).connect(dir.opposite());
+            self.get_tile_mut(next).set_status(ConnectionStatus::InMaze);
+        }
+        Some(edit)
+    }
+
+    // closes the wall of the cell at pt toward dir on both sides, the cells stay in the maze even
+    // when no passage is left to reach them. None if the wall is already closed or either cell
+    // is removed
+    pub fn fill(&mut self, pt: Point, dir: Direction) -> Option<WallEdit> {
+        let (pt, next) = self.wall_cells(pt, dir)?;
+        if !self.get_tile(pt).connected(dir) {
+            return None;
+        }
+        let edit = WallEdit::new(self, pt, dir, next, false);
+        self.get_tile_mut(pt).disconnect(dir);
+        if let Some(next) = next {
+            self.get_tile_mut(next).disconnect(dir.opposite());
+        }
+        Some(edit)
+    }
+
+    // restores both cells of an edit as they were before it, edits made after it must have been
+    // undone already
+    pub fn undo(&mut self, edit: &WallEdit) {
+        for (pt, tile) in edit.before.into_iter().flatten() {
+            self.set_tile(pt, tile);
+        }
+    }
+}
+
+// one change made to the maze while generating it, as recorded by its history and played back
+// by the animations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MazeAction {
+    // adds the cell to the maze and, unless the direction is NoDir, connects it to its neighbor
+    // in that direction
+    Carve(Point, Direction),
+    // closes the wall between the cell and its neighbor in the direction
+    Wall(Point, Direction),
+    // adds every cell of the rect to the maze with all walls between them open, the field
+    // recursive division puts its walls into
+    Clear(Rect),
+    // the cells of the rect were looked at by hunt and kill searching for a cell to continue
+    // from. changes nothing, the animations highlight the row until the next action
+    Scan(Rect),
+}
+
+impl From<(Point, Direction)> for MazeAction {
+    fn from((pt, dir): (Point, Direction)) -> Self {
+        MazeAction::Carve(pt, dir)
+    }
+}
+
+impl MazeAction {
+    // the cell the action starts from, the top left cell of a rect
+    pub fn cell(self) -> Point {
+        match self {
+            MazeAction::Carve(pt, _) | MazeAction::Wall(pt, _) => pt,
+            MazeAction::Clear(rect) | MazeAction::Scan(rect) => {
+                Point::new(rect.x as i32, rect.y as i32)
+            }
+        }
+    }
+
+    // the cell and direction of a carving step, None for other actions
+    pub fn carve(self) -> Option<(Point, Direction)> {
+        match self {
+            MazeAction::Carve(pt, dir) => Some((pt, dir)),
+            _ => None,
+        }
+    }
+
+    fn moved(self, by: Point) -> Self {
+        match self {
+            MazeAction::Carve(pt, dir) => MazeAction::Carve(pt + by, dir),
+            MazeAction::Wall(pt, dir) => MazeAction::Wall(pt + by, dir),
+            MazeAction::Clear(rect) | MazeAction::Scan(rect) => self.with_rect(Rect {
+                x: (rect.x as i32 + by.x) as u32,
+                y: (rect.y as i32 + by.y) as u32,
+                ..rect
+            }),
+        }
+    }
+
+    // the same kind of action over another rect, for actions covering a rect
+    fn with_rect(self, rect: Rect) -> Self {
+        match self {
+            MazeAction::Scan(_) => MazeAction::Scan(rect),
+            _ => MazeAction::Clear(rect),
+        }
+    }
+}
+
+// actions taken while generating a maze, in order
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MazeHistory {
+    pub width: u32,
+    pub height: u32,
+    pub wrap: MazeWrap,
+    pub params: GenParams,
+    pub steps: Vec<MazeAction>,
+}
+
+impl MazeHistory {
+    pub fn new(maze: &Grid, steps: Vec<MazeAction>) -> Self {
+        Self {
+            width: maze.width,
+            height: maze.height,
+            wrap: maze.wrap,
+            params: maze.params.clone(),
+            steps,
+        }
+    }
+
+    // history of steps that only carve
+    pub fn carved(maze: &Grid, steps: Vec<(Point, Direction)>) -> Self {
+        Self::new(maze, steps.into_iter().map(MazeAction::from).collect())
+    }
+
+    // the maze built by applying every step to an empty grid. connections leading out of the
+    // grid, as left behind by crop, are kept on the cell inside
+    pub fn replay(&self) -> Grid {
+        let mut maze = Grid {
+            tiles: vec![Tile::default(); self.width as usize * self.height as usize],
+            width: self.width,
+            height: self.height,
+            wrap: self.wrap,
+            params: self.params.clone(),
+            costs: Vec::new(),
+        };
+
+        for step in &self.steps {
+            match *step {
+                MazeAction::Carve(pt, dir) => {
+                    maze.get_tile_mut(pt).set_status(ConnectionStatus::InMaze);
+                    if dir != Direction::NoDir {
+                        maze.get_tile_mut(pt).connect(dir);
+                        let next = maze.travel(pt, dir);
+                        if maze.contains(next) {
+                            maze.get_tile_mut(next).connect(dir.opposite());
+                        }
+                    }
+                }
+                MazeAction::Wall(pt, dir) => {
+                    maze.get_tile_mut(pt).disconnect(dir);
+                    let next = maze.travel(pt, dir);
+                    if maze.contains(next) {
+                        maze.get_tile_mut(next).disconnect(dir.opposite());
+                    }
+                }
+                MazeAction::Scan(_) => (),
+                MazeAction::Clear(rect) => {
+                    for pt in rect.cells() {
+                        maze.get_tile_mut(pt).set_status(ConnectionStatus::InMaze);
+                        for dir in Direction::ALL {
+                            if rect.contains(pt.travel(dir)) {
+                                maze.get_tile_mut(pt).connect(dir);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        maze
+    }
+
+    // true if replaying the steps gives exactly the connections of the maze
+    pub fn agrees_with(&self, maze: &Grid) -> bool {
+        let replayed = self.replay();
+        replayed.width == maze.width
+            && replayed.height == maze.height
+            && replayed
+                .tiles
+                .iter()
+                .zip(&maze.tiles)
+                .all(|(a, b)| a.connections() == b.connections())
+    }
+
+    // the same steps on a grid rotated or mirrored by the symmetry
+    pub fn transform(&self, symmetry: Symmetry) -> MazeHistory {
+        let (width, height) = symmetry.size(self.width, self.height);
+        MazeHistory {
+            width,
+            height,
+            wrap: symmetry.wrap(self.wrap),
+            steps: self
+                .steps
+                .iter()
+                .map(|step| {
+                    let point = |pt| symmetry.point(pt, self.width, self.height);
+                    match *step {
+                        MazeAction::Carve(pt, dir) => {
+                            MazeAction::Carve(point(pt), symmetry.direction(dir))
+                        }
+                        MazeAction::Wall(pt, dir) => {
+                            MazeAction::Wall(point(pt), symmetry.direction(dir))
+                        }
+                        MazeAction::Clear(rect) | MazeAction::Scan(rect) => {
+                            let a = point(Point::new(rect.x as i32, rect.y as i32));
+                            let b = point(Point::new(
+                                (rect.x + rect.width - 1) as i32,
+                                (rect.y + rect.height - 1) as i32,
+                            ));
+                            step.with_rect(Rect {
+                                x: a.x.min(b.x) as u32,
+                                y: a.y.min(b.y) as u32,
+                                width: a.x.abs_diff(b.x) + 1,
+                                height: a.y.abs_diff(b.y) + 1,
+                            })
+                        }
+                    }
+                })
+                .collect(),
+            params: self.params.clone(),
+        }
+    }
+
+    // steps inside a rect, moved so the rect's corner becomes the origin
+    pub fn crop(&self, rect: Rect) -> MazeHistory {
+        let origin = Point::new(-(rect.x as i32), -(rect.y as i32));
+        MazeHistory {
+            width: rect.width,
+            height: rect.height,
+            wrap: MazeWrap::None,
+            params: self.params.clone(),
+            steps: self
+                .steps
+                .iter()
+                .filter_map(|step| match *step {
+                    MazeAction::Clear(covered) | MazeAction::Scan(covered) => covered
+                        .intersect(rect)
+                        .map(|covered| step.with_rect(covered)),
+                    _ => rect.contains(step.cell()).then_some(*step),
+                })
+                .map(|step| step.moved(origin))
+                .collect(),
+        }
+    }
+}
+
+// a wall opened or closed by Grid::carve or Grid::fill, holding both cells as they were before
+// so the edit can be undone
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WallEdit {
+    pub pt: Point,
+    pub dir: Direction,
+    // opened rather than closed
+    pub carved: bool,
+    before: [Option<(Point, Tile)>; 2],
+}
+
+impl WallEdit {
+    fn new(maze: &Grid, pt: Point, dir: Direction, next: Option<Point>, carved: bool) -> Self {
+        WallEdit {
+            pt,
+            dir,
+            carved,
+            before: [
+                Some((pt, maze.get_tile(pt))),
+                next.map(|next| (next, maze.get_tile(next))),
+            ],
+        }
+    }
+
+    // whether the edit opened or closed the wall between the cell at pt and its neighbor in dir,
+    // seen from either side
+    fn edits_wall(&self, history: &MazeHistory, pt: Point, dir: Direction) -> bool {
+        let travel =
+            |pt: Point, dir| pt.travel_wrapped(dir, history.width, history.height, history.wrap);
+        (self.pt, self.dir) == (pt, dir)
+            || (travel(self.pt, self.dir), self.dir.opposite()) == (pt, dir)
+    }
+}
+
+// edits made to a maze in order, for an editor to undo them starting from the latest one
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EditJournal {
+    pub edits: Vec<WallEdit>,
+}
+
+impl EditJournal {
+    // carves the wall with Grid::carve and records the edit, false if nothing changed
+    pub fn carve(&mut self, maze: &mut Grid, pt: Point, dir: Direction) -> bool {
+        maze.carve(pt, dir).map(|e| self.edits.push(e)).is_some()
+    }
+
+    // fills the wall with Grid::fill and records the edit, false if nothing changed
+    pub fn fill(&mut self, maze: &mut Grid, pt: Point, dir: Direction) -> bool {
+        maze.fill(pt, dir).map(|e| self.edits.push(e)).is_some()
+    }
+
+    // undoes the latest edit still in the journal and returns it
+    pub fn undo(&mut self, maze: &mut Grid) -> Option<WallEdit> {
+        let edit = self.edits.pop()?;
+        maze.undo(&edit);
+        Some(edit)
+    }
+
+    // history of the edited maze, made of the steps of the history it was generated with followed
+    // by a step for every carved wall. a filled wall turns the steps that carved it into steps only
+    // adding their cell, or gets a step of its own when no step carved it, like the walls of a
+    // cleared field
+    pub fn history(&self, generated: &MazeHistory) -> MazeHistory {
+        let mut history = generated.clone();
+        for edit in &self.edits {
+            if edit.carved {
+                history.steps.push(MazeAction::Carve(edit.pt, edit.dir));
+                continue;
+            }
+            let mut carved = false;
+            for step in history.steps.iter_mut() {
+                if let MazeAction::Carve(pt, dir) = *step {
+                    if edit.edits_wall(generated, pt, dir) {
+                        *step = MazeAction::Carve(pt, Direction::NoDir);
+                        carved = true;
+                    }
+                }
+            }
+            if !carved {
+                history.steps.push(MazeAction::Wall(edit.pt, edit.dir));
+            }
+        }
+        history
+    }
+}
+
+// generalized hilbert curve over the rectangle at pos spanned by the major axis a and the minor
+// axis b, pushing every cell with each one next to the previous where the sides allow it
+fn gilbert(cells: &mut Vec<Point>, pos: Point, a: Point, b: Point) {
+    let w = (a.x + a.y).abs();
+    let h = (b.x + b.y).abs();
+    let da = Point::new(a.x.signum(), a.y.signum());
+    let db = Point::new(b.x.signum(), b.y.signum());
+
+    if h == 1 || w == 1 {
+        let (step, len) = if h == 1 { (da, w) } else { (db, h) };
+        let mut pt = pos;
+        for _ in 0..len {
+            cells.push(pt);
+            pt += step;
+        }
+        return;
+    }
+
+    let half = |p: Point| Point::new(p.x.div_euclid(2), p.y.div_euclid(2));
+    let mut a2 = half(a);
+    let mut b2 = half(b);
+    let neg = |p: Point| Point::new(-p.x, -p.y);
+    let sub = |l: Point, r: Point| l + neg(r);
+
+    if 2 * w > 3 * h {
+        // long rectangle, split it in two along the major axis
+        if (a2.x + a2.y).abs() % 2 == 1 && w > 2 {
+            a2 += da;
+        }
+        gilbert(cells, pos, a2, b);
+        gilbert(cells, pos + a2, sub(a, a2), b);
+    } else {
+        // up, across and back down
+        if (b2.x + b2.y).abs() % 2 == 1 && h > 2 {
+            b2 += db;
+        }
+        gilbert(cells, pos, b2, a2);
+        gilbert(cells, pos + b2, a, sub(b, b2));
+        gilbert(
+            cells,
+            pos + sub(a, da) + sub(b2, db),
+            neg(b2),
+            neg(sub(a, a2)),
+        );
+    }
+}
+
+fn opposite(src: u8) -> u8 {
+    ((src << 2) | (src >> 2)) & 0b1111
+}
+
+// random cell that is not removed, drawn the same way as a plain random cell so mazes without
+// removed cells are unaffected. the grid must contain at least one cell that is not removed
+fn random_open_cell(maze: &Grid, rng: &mut dyn RngCore) -> Point {
+    loop {
+        let pos = Point::new(
+            random_in(rng, 0, maze.width) as i32,
+            random_in(rng, 0, maze.height) as i32,
+        );
+        if !maze.is_removed(pos) {
+            return pos;
+        }
+    }
+}
+
+// random value in low..high. ranges within a u16 are drawn as one, the way they were drawn while
+// mazes were at most u16::MAX cells across, so seeds keep giving the same mazes
+fn random_in(rng: &mut dyn RngCore, low: u32, high: u32) -> u32 {
+    match (u16::try_from(low), u16::try_from(high)) {
+        (Ok(low), Ok(high)) => rng.gen_range(low..high) as u32,
+        _ => rng.gen_range(low..high),
+    }
+}
+
+fn pick_random(points: &[(usize, Point)], rng: &mut dyn RngCore) -> Option<(usize, Point)> {
+    if !points.is_empty() {
+        Some(points[rng.gen_range(0..points.len())])
+    } else {
+        None
+    }
+}
+
+// wrapping is supported by backtrack, prim, growing tree, wilson, kruskal and hunt and kill, the
+// other methods ignore it
+pub fn generate_maze(
+    width: u32,
+    height: u32,
+    mtype: MazeType,
+    wrap: MazeWrap,
+    seed: u64,
+) -> Result<(Grid, MazeHistory), MazeGenError> {
+    generate_maze_with_progress(width, height, mtype, wrap, seed, &Progress::hidden())
+}
+
+// same as generate_maze, advancing the progress once for every cell joining the maze. carving
+// stops once the progress is cancelled
+pub fn generate_maze_with_progress(
+    width: u32,
+    height: u32,
+    mtype: MazeType,
+    wrap: MazeWrap,
+    seed: u64,
+    progress: &Progress,
+) -> Result<(Grid, MazeHistory), MazeGenError> {
+    if width == 0 || height == 0 {
+        return Err(MazeGenError::Empty { width, height });
+    }
+    if width > i32::MAX as u32
+        || height > i32::MAX as u32
+        || (width as usize).checked_mul(height as usize).is_none()
+    {
+        return Err(MazeGenError::TooLarge { width, height });
+    }
+    let (maze, history) = carve_maze(Grid::new(width, height, wrap, seed, mtype), progress);
+    if progress.cancelled() {
+        return Err(MazeGenError::Cancelled);
+    }
+    Ok((maze, history))
+}
+
+// carves a maze into a grid with the grid's method, seeding the rng with the grid's seed. removed
+// cells are left untouched, as are cells cut off from the rest of the maze by them. only methods
+// that support exclusions may be used on grids with removed cells, and only methods that support
+// patterns on grids with passages already carved. a cancelled progress stops carving, leaving
+// cells of the maze unjoined
+pub fn carve_maze(maze: Grid, progress: &Progress) -> (Grid, MazeHistory) {
+    let method = maze.params.method;
+    carve_maze_with(maze, &method, progress)
+}
+
+// same as carve_maze, carving with generator rather than the grid's method. the grid's method is
+// kept as it is and only ends up in the metadata of the maze
+pub fn carve_maze_with(
+    maze: Grid,
+    generator: &dyn MazeGenerator,
+    progress: &Progress,
+) -> (Grid, MazeHistory) {
+    if maze
+        .tiles
+        .iter()
+        .all(|t| t.status() == ConnectionStatus::Removed)
+    {
+        // removed cells can still hold passages, like rooms sealed during generation
+        let history = MazeHistory::carved(&maze, carved_steps(&maze));
+        return (maze, history);
+    }
+
+    let carved = carved_steps(&maze);
+    for _ in &carved {
+        progress.inc();
+    }
+
+    let regions = open_regions(&maze);
+    let (maze, steps) = if regions.len() > 1 && generator.supports_exclusions() {
+        carve_regions(maze, &regions, generator, progress)
+    } else {
+        let mut rng: StdRng = StdRng::seed_from_u64(maze.params.seed);
+        generator.carve_actions(maze, &mut rng, progress)
+    };
+
+    let carved = carved.into_iter().map(MazeAction::from);
+    let history = MazeHistory::new(&maze, carved.chain(steps).collect());
+    debug_assert!(history.agrees_with(&maze));
+    (maze, history)
+}
+
+// a way of carving a maze, for methods other than those of MazeType. carve gets a grid with its
+// wrap set and joins every cell that is not removed, returning the grid with the steps that
+// joined the cells in order: a cell with NoDir to start from, then each cell with the direction
+// of the passage leading back to the maze. removed cells and passages already carved must be left
+// as they are, and the progress advanced once for every cell joining the maze
+pub trait MazeGenerator: Sync {
+    fn carve(
+        &self,
+        maze: Grid,
+        rng: &mut dyn RngCore,
+        progress: &Progress,
+    ) -> (Grid, Vec<(Point, Direction)>);
+
+    // same as carve, returning every action taken rather than only the steps joining cells, for
+    // generators doing more than carving like adding walls
+    fn carve_actions(
+        &self,
+        maze: Grid,
+        rng: &mut dyn RngCore,
+        progress: &Progress,
+    ) -> (Grid, Vec<MazeAction>) {
+        let (maze, steps) = self.carve(maze, rng, progress);
+        (maze, steps.into_iter().map(MazeAction::from).collect())
+    }
+
+    // whether the generator can carve around removed cells. when it can, every group of cells cut
+    // off from the others by removed cells is carved on its own with the others removed, so a
+    // maze is still carved into each of them
+    fn supports_exclusions(&self) -> bool {
+        true
+    }
+}
+
+impl MazeGenerator for MazeType {
+    fn carve(
+        &self,
+        maze: Grid,
+        rng: &mut dyn RngCore,
+        progress: &Progress,
+    ) -> (Grid, Vec<(Point, Direction)>) {
+        let (maze, actions) = self.carve_actions(maze, rng, progress);
+        // a maze made by adding walls is walked like passages carved before generation
+        let steps = if actions
+            .iter()
+            .any(|action| matches!(action, MazeAction::Wall(..) | MazeAction::Clear(_)))
+        {
+            carved_steps(&maze)
+        } else {
+            actions.into_iter().filter_map(MazeAction::carve).collect()
+        };
+        (maze, steps)
+    }
+
+    fn carve_actions(
+        &self,
+        maze: Grid,
+        rng: &mut dyn RngCore,
+        progress: &Progress,
+    ) -> (Grid, Vec<MazeAction>) {
+        let (maze, steps) = match self {
+            MazeType::Backtrack => create_maze_backtrack(maze, rng, progress),
+            MazeType::Prim => create_maze_prim(maze, rng, progress),
+            MazeType::BinaryTree => create_maze_oriented(maze, rng, progress, create_maze_binary),
+            MazeType::Sidewinder => {
+                create_maze_oriented(maze, rng, progress, create_maze_sidewinder)
+            }
+            MazeType::Noise => create_maze_noise(maze, rng, progress),
+            MazeType::GrowingTree => {
+                let bias = maze.params.bias.clone();
+                create_maze_growingtree(maze, rng, &bias, progress)
+            }
+            MazeType::Wilson => create_maze_wilson(maze, rng, progress),
+            MazeType::Kruskal => create_maze_kruskal(maze, rng, progress),
+            MazeType::Eller => create_maze_eller(maze, rng, progress),
+            MazeType::HuntAndKill => return create_maze_huntandkill(maze, rng, progress),
+            MazeType::RecursiveDivision => return create_maze_division(maze, rng, progress),
+        };
+        (maze, steps.into_iter().map(MazeAction::from).collect())
+    }
+
+    fn supports_exclusions(&self) -> bool {
+        MazeType::supports_exclusions(*self)
+    }
+}
+
+// groups of open cells cut off from each other by removed cells, ordered by their first cell in
+// row order
+fn open_regions(maze: &Grid) -> Vec<Vec<Point>> {
+    let mut seen = vec![false; maze.tiles.len()];
+    let mut regions = Vec::new();
+    for (i, tile) in maze.tiles.iter().enumerate() {
+        if tile.status() == ConnectionStatus::Removed || seen[i] {
+            continue;
+        }
+        let start = Point::new(
+            (i % maze.width as usize) as i32,
+            (i / maze.width as usize) as i32,
+        );
+        seen[i] = true;
+        let mut region = vec![start];
+        let mut open = vec![start];
+        while let Some(pos) = open.pop() {
+            for next in maze.adjacent(pos) {
+                if maze.contains(next) && !maze.is_removed(next) && !seen[maze.get_index(next)] {
+                    seen[maze.get_index(next)] = true;
+                    region.push(next);
+                    open.push(next);
+                }
+            }
+        }
+        regions.push(region);
+    }
+    regions
+}
+
+// carves every region on its own and in parallel, each cut out to the rect around it, or taking
+// the whole grid when it wraps, with the other regions removed. regions draw from the streams of
+// SeedSchedule::region so the maze does not depend on which thread carves what, and their steps
+// follow each other in the order of the regions
+fn carve_regions(
+    mut maze: Grid,
+    regions: &[Vec<Point>],
+    generator: &dyn MazeGenerator,
+    progress: &Progress,
+) -> (Grid, Vec<MazeAction>) {
+    let schedule = SeedSchedule::new(maze.params.seed);
+    let carved: Vec<_> = regions
+        .par_iter()
+        .enumerate()
+        .map(|(i, region)| {
+            let rect = match maze.wrap {
+                MazeWrap::None => {
+                    let xs = region.iter().map(|pt| pt.x as u32);
+                    let ys = region.iter().map(|pt| pt.y as u32);
+                    let (left, top) = (xs.clone().min().unwrap(), ys.clone().min().unwrap());
+                    Rect {
+                        x: left,
+                        y: top,
+                        width: xs.max().unwrap() + 1 - left,
+                        height: ys.max().unwrap() + 1 - top,
+                    }
+                }
+                _ => Rect {
+                    x: 0,
+                    y: 0,
+                    width: maze.width,
+                    height: maze.height,
+                },
+            };
+            let origin = Point::new(rect.x as i32, rect.y as i32);
+            let back = Point::new(-origin.x, -origin.y);
+            let mut part = Grid {
+                wrap: maze.wrap,
+                ..maze.crop(rect).unwrap()
+            };
+            if !maze.costs.is_empty() {
+                part.costs = (rect.y..rect.y + rect.height)
+                    .flat_map(|y| (rect.x..rect.x + rect.width).map(move |x| (x, y)))
+                    .map(|(x, y)| maze.costs[x as usize + y as usize * maze.width as usize])
+                    .collect();
+            }
+            let mut inside = vec![false; part.tiles.len()];
+            for pt in region {
+                inside[part.get_index(*pt + back)] = true;
+            }
+            for (tile, inside) in part.tiles.iter_mut().zip(inside) {
+                if !inside {
+                    *tile = Tile::new(ConnectionStatus::Removed, 0);
+                }
+            }
+            // passages leaving the region are put back once it is carved
+            for pt in region {
+                let pt = *pt + back;
+                for dir in Direction::ALL {
+                    let next = part.travel(pt, dir);
+                    if !part.contains(next) || part.is_removed(next) {
+                        part.get_tile_mut(pt).disconnect(dir);
+                    }
+                }
+            }
+
+            let mut rng = StdRng::seed_from_u64(schedule.region(i as u32, 0).carve);
+            let (part, steps) = generator.carve_actions(part, &mut rng, progress);
+            (origin, part, steps)
+        })
+        .collect();
+
+    let mut steps = Vec::new();
+    for (region, (origin, part, part_steps)) in regions.iter().zip(carved) {
+        let back = Point::new(-origin.x, -origin.y);
+        for pt in region {
+            let mut tile = part.get_tile(*pt + back);
+            tile.add_connections(maze.get_tile(*pt).connections());
+            maze.set_tile(*pt, tile);
+        }
+        steps.extend(part_steps.into_iter().map(|step| step.moved(origin)));
+    }
+    (maze, steps)
+}
+
+// steps rebuilding the passages already carved into a grid, walking each connected group of cells
+// from its first cell in row order. passages closing a loop, like those inside rooms, follow once
+// every group is walked
+fn carved_steps(maze: &Grid) -> Vec<(Point, Direction)> {
+    let mut steps: Vec<(Point, Direction)> = Vec::new();
+    let mut seen = vec![false; maze.tiles.len()];
+    // connections of each cell already rebuilt by a step
+    let mut walked = vec![0u8; maze.tiles.len()];
+
+    for (i, tile) in maze.tiles.iter().enumerate() {
+        if tile.connections() == 0 || seen[i] {
+            continue;
+        }
+        let start = Point::new(
+            (i % maze.width as usize) as i32,
+            (i / maze.width as usize) as i32,
+        );
+        seen[i] = true;
+        steps.push((start, Direction::NoDir));
+
+        let mut open = vec![start];
+        while let Some(pos) = open.pop() {
+            for dir in Direction::ALL {
+                let next = maze.travel(pos, dir);
+                if maze.get_tile(pos).connected(dir) && !seen[maze.get_index(next)] {
+                    seen[maze.get_index(next)] = true;
+                    walked[maze.get_index(pos)] |= dir as u8;
+                    walked[maze.get_index(next)] |= dir.opposite() as u8;
+                    steps.push((next, dir.opposite()));
+                    open.push(next);
+                }
+            }
+        }
+    }
+    for (i, tile) in maze.tiles.iter().enumerate() {
+        let pt = Point::new(
+            (i % maze.width as usize) as i32,
+            (i / maze.width as usize) as i32,
+        );
+        for dir in [Direction::East, Direction::South] {
+            if tile.connected(dir) && walked[i] & dir as u8 == 0 {
+                steps.push((pt, dir));
+            }
+        }
+    }
+
+    steps
+}
+
+fn create_maze_backtrack(
+    mut maze: Grid,
+    rng: &mut dyn RngCore,
+    progress: &Progress,
+) -> (Grid, Vec<(Point, Direction)>) {
+    let mut stack: Vec<Point> = Vec::new();
+    let mut pos: Point = random_open_cell(&maze, rng);
+    let mut history: Vec<(Point, Direction)> = Vec::with_capacity(maze.tiles.len());
+
+    maze.get_tile_mut(pos).set_status(ConnectionStatus::InMaze);
+    stack.push(pos);
+    history.push((pos, Direction::NoDir));
+    progress.inc();
+
+    loop {
+        if progress.cancelled() {
+            break;
+        }
+        let next = pick_random(
+            maze.adjacent(pos)
+                .into_iter()
+                .enumerate()
+                .filter(|(_, x)| {
+                    maze.contains(*x) && maze.get_tile(*x).status() == ConnectionStatus::UnVisited
+                })
+                .collect::<Vec<(usize, Point)>>()
+                .as_ref(),
+            rng,
+        );
+
+        match next {
+            // the first cell is only left once it is stuck as well, it can have unvisited
+            // neighbors left on another side like in the middle of a corridor
+            None => match stack.pop() {
+                Some(prev) => pos = prev,
+                None => break,
+            },
+            Some(next) => {
+                let dir = 0b0001 << next.0;
+                maze.get_tile_mut(pos).connect(dir.into());
+
+                pos = next.1;
+                maze.get_tile_mut(pos).connect(opposite(dir).into());
+                maze.get_tile_mut(pos).set_status(ConnectionStatus::InMaze);
+
+                stack.push(pos);
+                history.push((pos, opposite(dir).into()));
+                progress.inc();
+            }
+        }
+    }
+
+    (maze, history)
+}
+
+fn create_maze_prim(
+    mut maze: Grid,
+    rng: &mut dyn RngCore,
+    progress: &Progress,
+) -> (Grid, Vec<(Point, Direction)>) {
+    if !maze.costs.is_empty() {
+        return create_maze_prim_weighted(maze, rng, progress);
+    }
+    let mut open_tiles: Vec<Point> = Vec::new();
+    let mut history: Vec<(Point, Direction)> = Vec::with_capacity(maze.tiles.len());
+    let mut pos: Point = random_open_cell(&maze, rng);
+
+    maze.get_tile_mut(pos).set_status(ConnectionStatus::InMaze);
+    open_tiles.push(pos);
+    history.push((pos, Direction::NoDir));
+    progress.inc();
+
+    while !open_tiles.is_empty() {
+        if progress.cancelled() {
+            break;
+        }
+        let current_tile_index: usize = rng.gen_range(0..open_tiles.len());
+        pos = open_tiles[current_tile_index];
+
+        let next = pick_random(
+            maze.adjacent(pos)
+                .into_iter()
+                .enumerate()
                 .filter(|(_, x)| {
-                    maze.contains(*x) && maze.get_tile(*x).status == ConnectionStatus::UnVisited
+                    maze.contains(*x) && maze.get_tile(*x).status() == ConnectionStatus::UnVisited
                 })
                 .collect::<Vec<(usize, Point)>>()
                 .as_ref(),
@@ -292,103 +2503,579 @@ fn create_maze_prim(mut maze: Grid, rng: &mut StdRng) -> (Grid, Vec<(Point, Dire
 
                 pos = next.1;
                 maze.get_tile_mut(pos).connect(opposite(dir).into());
-                maze.get_tile_mut(pos).status = ConnectionStatus::InMaze;
+                maze.get_tile_mut(pos).set_status(ConnectionStatus::InMaze);
 
                 open_tiles.push(pos);
                 history.push((pos, opposite(dir).into()));
+                progress.inc();
+            }
+        }
+    }
+
+    (maze, history)
+}
+
+// random share of its own cost added to the cost of every passage. cheap passages get little
+// jitter and follow the landscape closely, expensive ones are carved in close to random order
+const WEIGHT_JITTER: f32 = 1.0;
+
+// average cost of the two cells a passage joins with some jitter, and a little more so passages
+// of no cost are not carved in the order they were found
+fn passage_cost(maze: &Grid, pt: Point, dir: Direction, rng: &mut dyn RngCore) -> f32 {
+    let next = maze.travel(pt, dir);
+    let cost = (maze.costs[maze.get_index(pt)] + maze.costs[maze.get_index(next)]) / 2.0;
+    cost * (1.0 + rng.gen_range(0.0..WEIGHT_JITTER)) + rng.gen_range(0.0..0.001)
+}
+
+// true prim, always carving the cheapest passage leading out of the maze. in cheap areas long
+// passages run along lines of equal cost, expensive areas branch like plain prim
+fn create_maze_prim_weighted(
+    mut maze: Grid,
+    rng: &mut dyn RngCore,
+    progress: &Progress,
+) -> (Grid, Vec<(Point, Direction)>) {
+    let mut history: Vec<(Point, Direction)> = Vec::with_capacity(maze.tiles.len());
+    let mut passages: Vec<(Point, Direction)> = Vec::new();
+    let mut frontier: BinaryHeap<(Reverse<u32>, usize)> = BinaryHeap::new();
+    let mut pos: Point = random_open_cell(&maze, rng);
+
+    maze.get_tile_mut(pos).set_status(ConnectionStatus::InMaze);
+    history.push((pos, Direction::NoDir));
+    progress.inc();
+
+    loop {
+        if progress.cancelled() {
+            break;
+        }
+        for dir in Direction::ALL {
+            let next = maze.travel(pos, dir);
+            if maze.contains(next) && maze.get_tile(next).status() == ConnectionStatus::UnVisited {
+                // costs are never negative, so their bits sort the same way they do
+                let cost = passage_cost(&maze, pos, dir, rng).to_bits();
+                frontier.push((Reverse(cost), passages.len()));
+                passages.push((pos, dir));
             }
         }
+
+        // passages leading to a cell that joined the maze since they were found are dropped
+        let cheapest = std::iter::from_fn(|| frontier.pop())
+            .map(|(_, i)| passages[i])
+            .find(|(pt, dir)| {
+                maze.get_tile(maze.travel(*pt, *dir)).status() == ConnectionStatus::UnVisited
+            });
+        let Some((from, dir)) = cheapest else {
+            break;
+        };
+
+        pos = maze.travel(from, dir);
+        maze.get_tile_mut(from).connect(dir);
+        maze.get_tile_mut(pos).connect(dir.opposite());
+        maze.get_tile_mut(pos).set_status(ConnectionStatus::InMaze);
+        history.push((pos, dir.opposite()));
+        progress.inc();
     }
 
     (maze, history)
 }
 
-fn create_maze_binary(mut maze: Grid, rng: &mut StdRng) -> (Grid, Vec<(Point, Direction)>) {
+// one of the create_maze functions below
+type CarveFn = fn(Grid, &mut dyn RngCore, &Progress) -> (Grid, Vec<(Point, Direction)>);
+
+// carves a binary tree or sidewinder maze pointing north west the way they are written, on a grid
+// that the orientation of the maze then turns into place
+fn create_maze_oriented(
+    maze: Grid,
+    rng: &mut dyn RngCore,
+    progress: &Progress,
+    create: CarveFn,
+) -> (Grid, Vec<(Point, Direction)>) {
+    let Some(symmetry) = maze.params.orientation.map(Orientation::symmetry) else {
+        return create(maze, rng, progress);
+    };
+    let (width, height) = symmetry.size(maze.width, maze.height);
+    let upright = Grid {
+        width,
+        height,
+        wrap: symmetry.wrap(maze.wrap),
+        ..maze
+    };
+
+    let (upright, steps) = create(upright, rng, progress);
+    let steps = steps
+        .into_iter()
+        .map(|(pt, dir)| (symmetry.point(pt, width, height), symmetry.direction(dir)))
+        .collect();
+    (upright.transform(symmetry), steps)
+}
+
+fn create_maze_binary(
+    mut maze: Grid,
+    rng: &mut dyn RngCore,
+    progress: &Progress,
+) -> (Grid, Vec<(Point, Direction)>) {
     use crate::maze::Direction::*;
 
     let mut history: Vec<(Point, Direction)> = Vec::with_capacity(maze.tiles.len());
 
-    for y in 0..maze.height as i16 {
-        for x in 0..maze.width as i16 {
-            let dir: u8 = if x > 0 && y > 0 {
-                rng.gen_range(0..=1)
-            } else if x > 0 {
-                0
-            } else if y > 0 {
-                1
+    for pt in maze.scan_cells() {
+        if progress.cancelled() {
+            break;
+        }
+        let (x, y) = (pt.x, pt.y);
+        let dir: u8 = if x > 0 && y > 0 {
+            rng.gen_range(0..=1)
+        } else if x > 0 {
+            0
+        } else if y > 0 {
+            1
+        } else {
+            2
+        };
+
+        if dir == 0 {
+            maze.get_tile_mut(Point::new(x, y)).connect(West);
+            history.push((Point::new(x, y), West));
+            maze.get_tile_mut(Point::new(x - 1, y)).connect(East);
+        } else if dir == 1 {
+            maze.get_tile_mut(Point::new(x, y)).connect(North);
+            history.push((Point::new(x, y), North));
+            maze.get_tile_mut(Point::new(x, y - 1)).connect(South);
+        } else {
+            history.push((Point::new(x, y), NoDir));
+        }
+
+        maze.get_tile_mut(Point::new(x, y))
+            .set_status(ConnectionStatus::InMaze);
+        progress.inc();
+    }
+
+    (maze, history)
+}
+
+fn create_maze_sidewinder(
+    mut maze: Grid,
+    rng: &mut dyn RngCore,
+    progress: &Progress,
+) -> (Grid, Vec<(Point, Direction)>) {
+    use crate::maze::Direction::*;
+
+    let mut history: Vec<(Point, Direction)> = Vec::with_capacity(maze.tiles.len() * 3 / 2);
+
+    history.push((Point { x: 0, y: 0 }, NoDir));
+    progress.inc();
+
+    // the first row is a single corridor
+    for x in 1..maze.width as i32 {
+        maze.get_tile_mut(Point { x: x - 1, y: 0 }).connect(East);
+        maze.get_tile_mut(Point { x, y: 0 }).connect(West);
+        history.push((Point { x, y: 0 }, West));
+        progress.inc();
+    }
+
+    for y in 1..maze.height as i32 {
+        if progress.cancelled() {
+            break;
+        }
+        let mut range_start = 0;
+        for x in 0..maze.width as i32 {
+            if rng.gen::<bool>() && (x as u32) < maze.width - 1 {
+                maze.get_tile_mut(Point::new(x, y)).connect(East);
+                maze.get_tile_mut(Point::new(x + 1, y)).connect(West);
+                maze.get_tile_mut(Point::new(x, y))
+                    .set_status(ConnectionStatus::InMaze);
+                history.push((Point::new(x, y), East));
             } else {
-                2
-            };
+                if maze.get_tile(Point::new(x, y)).connected(West) {
+                    maze.get_tile_mut(Point::new(x, y))
+                        .set_status(ConnectionStatus::InMaze);
+                    history.push((Point::new(x, y), West));
+                }
+
+                let chosen = random_in(rng, range_start as u32, x as u32 + 1) as i32;
+                maze.get_tile_mut(Point::new(chosen, y)).connect(North);
+                maze.get_tile_mut(Point::new(chosen, y - 1)).connect(South);
+                history.push((Point::new(chosen, y), North));
+
+                range_start = x + 1;
+            }
+            progress.inc();
+        }
+    }
+
+    (maze, history)
+}
+
+// eller's algorithm: carves one row at a time, only remembering which cells of the current row
+// share a set. neighbors in different sets are joined at random, then every set continues into
+// the next row through at least one passage south. the last row joins all remaining sets. with
+// horizontal wrapping the seam between the first and last column is joined like any other pair,
+// vertical wrapping is not used
+fn create_maze_eller(
+    mut maze: Grid,
+    rng: &mut dyn RngCore,
+    progress: &Progress,
+) -> (Grid, Vec<(Point, Direction)>) {
+    use crate::maze::Direction::*;
+
+    let width = maze.width as usize;
+    let mut history: Vec<(Point, Direction)> = Vec::with_capacity(maze.tiles.len() * 2);
+    // sets of the current row as a union-find over its columns
+    let mut region_map: Vec<u32> = vec![0; width];
+    // column of the row above each cell continues from, None for cells starting a new set
+    let mut carried: Vec<Option<u32>> = vec![None; width];
+    let mut first_of_set: Vec<Option<usize>> = vec![None; width];
+    let mut drops: Vec<bool> = vec![false; width];
+    let mut set_size: Vec<u32> = vec![0; width];
+    let mut set_pick: Vec<usize> = vec![0; width];
+    let mut set_dropped: Vec<bool> = vec![false; width];
+
+    for y in 0..maze.height as i32 {
+        if progress.cancelled() {
+            break;
+        }
+        let last_row = y as u32 == maze.height - 1;
+
+        // cells continuing a set from the row above start out joined
+        for (x, parent) in region_map.iter_mut().enumerate() {
+            *parent = x as u32;
+        }
+        first_of_set.fill(None);
+        for (x, set) in carried.iter().enumerate() {
+            if let Some(set) = *set {
+                match first_of_set[set as usize] {
+                    Some(first) => {
+                        merge_sets(&mut region_map, x, first);
+                    }
+                    None => first_of_set[set as usize] = Some(x),
+                }
+            }
+        }
+
+        // join neighbors in different sets, always on the last row
+        for (x, from_above) in carried.iter().enumerate() {
+            let pt = Point::new(x as i32, y);
+            maze.get_tile_mut(pt).set_status(ConnectionStatus::InMaze);
+            progress.inc();
+
+            if x > 0 && (last_row || rng.gen::<bool>()) && merge_sets(&mut region_map, x - 1, x) {
+                maze.get_tile_mut(Point::new(x as i32 - 1, y)).connect(East);
+                maze.get_tile_mut(pt).connect(West);
+                history.push((pt, West));
+            } else if from_above.is_none() {
+                history.push((pt, NoDir));
+            }
+        }
+        if maze.wrap.horizontal()
+            && width > 1
+            && (last_row || rng.gen::<bool>())
+            && merge_sets(&mut region_map, width - 1, 0)
+        {
+            maze.get_tile_mut(Point::new(width as i32 - 1, y))
+                .connect(East);
+            maze.get_tile_mut(Point::new(0, y)).connect(West);
+            history.push((Point::new(0, y), West));
+        }
+
+        if last_row {
+            break;
+        }
+
+        // every set continues south through a random subset of its cells, at least one. a
+        // random cell of each set is kept with reservoir sampling in case none was picked
+        set_size.fill(0);
+        set_dropped.fill(false);
+        for (x, drop) in drops.iter_mut().enumerate() {
+            let set = set_lookup_flatten(&mut region_map, x) as usize;
+            *drop = rng.gen::<bool>();
+            set_dropped[set] |= *drop;
+            set_size[set] += 1;
+            if rng.gen_range(0..set_size[set]) == 0 {
+                set_pick[set] = x;
+            }
+        }
+        for set in 0..width {
+            if set_size[set] > 0 && !set_dropped[set] {
+                drops[set_pick[set]] = true;
+            }
+        }
+
+        for (x, (drop, carry)) in drops.iter().zip(carried.iter_mut()).enumerate() {
+            *carry = None;
+            if *drop {
+                let below = Point::new(x as i32, y + 1);
+                maze.get_tile_mut(Point::new(x as i32, y)).connect(South);
+                maze.get_tile_mut(below).connect(North);
+                history.push((below, North));
+                *carry = Some(region_map[x]);
+            }
+        }
+    }
+
+    (maze, history)
+}
+
+// random walk through unvisited cells until it gets stuck, then a scan of the grid in its scan
+// order for an unvisited cell next to the maze to continue the walk from. the rows looked at by
+// every scan are recorded in the history. the walk starts from passages carved before generation
+// if there are any, which are joined into a single tree at the end
+fn create_maze_huntandkill(
+    mut maze: Grid,
+    rng: &mut dyn RngCore,
+    progress: &Progress,
+) -> (Grid, Vec<MazeAction>) {
+    let mut history: Vec<MazeAction> = Vec::with_capacity(maze.tiles.len());
+    let carved = maze
+        .scan_cells()
+        .into_iter()
+        .find(|pt| maze.get_tile(*pt).status() == ConnectionStatus::InMaze);
+    let mut pos: Point = carved.unwrap_or_else(|| random_open_cell(&maze, rng));
+
+    // cells the walk can not reach are left out of the hunt, it would never find them
+    let region = maze.region(pos);
+    let scan: Vec<Point> = maze
+        .scan_cells()
+        .into_iter()
+        .filter(|pt| region[maze.get_index(*pt)])
+        .collect();
+    // every cell before this one in the scan is already in the maze
+    let mut scan_start = 0;
+
+    if carved.is_none() {
+        maze.get_tile_mut(pos).set_status(ConnectionStatus::InMaze);
+        history.push(MazeAction::Carve(pos, Direction::NoDir));
+        progress.inc();
+    }
+
+    loop {
+        if progress.cancelled() {
+            break;
+        }
+        // kill, walk to a random unvisited neighbor
+        let next = pick_random(
+            maze.adjacent(pos)
+                .into_iter()
+                .enumerate()
+                .filter(|(_, x)| {
+                    maze.contains(*x) && maze.get_tile(*x).status() == ConnectionStatus::UnVisited
+                })
+                .collect::<Vec<(usize, Point)>>()
+                .as_ref(),
+            rng,
+        );
+        if let Some((i, next)) = next {
+            let dir: Direction = (0b0001 << i).into();
+            maze.get_tile_mut(pos).connect(dir);
+            maze.get_tile_mut(next).connect(dir.opposite());
+            maze.get_tile_mut(next).set_status(ConnectionStatus::InMaze);
+            history.push(MazeAction::Carve(next, dir.opposite()));
+            progress.inc();
+            pos = next;
+            continue;
+        }
+
+        // hunt, join the first unvisited cell bordering the maze to a random neighbor in it
+        while scan_start < scan.len()
+            && maze.get_tile(scan[scan_start]).status() == ConnectionStatus::InMaze
+        {
+            scan_start += 1;
+        }
+        let found = scan[scan_start..].iter().enumerate().find_map(|(i, pt)| {
+            if maze.get_tile(*pt).status() != ConnectionStatus::UnVisited {
+                return None;
+            }
+            let joins: Vec<(usize, Point)> = maze
+                .adjacent(*pt)
+                .into_iter()
+                .enumerate()
+                .filter(|(_, x)| {
+                    maze.contains(*x) && maze.get_tile(*x).status() == ConnectionStatus::InMaze
+                })
+                .collect();
+            (!joins.is_empty()).then_some((scan_start + i, *pt, joins))
+        });
+        let Some((index, hunted, joins)) = found else {
+            break;
+        };
+        history.extend(
+            scanned_rows(&scan[scan_start..=index])
+                .into_iter()
+                .map(MazeAction::Scan),
+        );
 
-            if dir == 0 {
-                maze.get_tile_mut(Point::new(x, y)).connect(West);
-                history.push((Point::new(x, y), West));
-                maze.get_tile_mut(Point::new(x - 1, y)).connect(East);
-            } else if dir == 1 {
-                maze.get_tile_mut(Point::new(x, y)).connect(North);
-                history.push((Point::new(x, y), North));
-                maze.get_tile_mut(Point::new(x, y - 1)).connect(South);
-            } else {
-                history.push((Point::new(x, y), NoDir));
-            }
+        let (i, _) = pick_random(&joins, rng).unwrap();
+        let dir: Direction = (0b0001 << i).into();
+        maze.get_tile_mut(hunted).connect(dir);
+        maze.get_tile_mut(maze.travel(hunted, dir))
+            .connect(dir.opposite());
+        maze.get_tile_mut(hunted)
+            .set_status(ConnectionStatus::InMaze);
+        history.push(MazeAction::Carve(hunted, dir));
+        progress.inc();
+        pos = hunted;
+    }
 
-            maze.get_tile_mut(Point::new(x, y)).status = ConnectionStatus::InMaze;
-        }
+    // hunted cells join whichever carved area they border, so separate areas carved before
+    // generation each grew their own tree
+    if carved.is_some() {
+        let mut joined = Vec::new();
+        join_parts(&mut maze, rng, &mut joined);
+        history.extend(joined.into_iter().map(MazeAction::from));
     }
 
     (maze, history)
 }
 
-fn create_maze_sidewinder(mut maze: Grid, rng: &mut StdRng) -> (Grid, Vec<(Point, Direction)>) {
-    use crate::maze::Direction::*;
-
-    let mut history: Vec<(Point, Direction)> = Vec::with_capacity(maze.tiles.len() * 3 / 2);
-
-    maze.get_tile_mut(Point { x: 0, y: 0 }).connect(East);
-    history.push((Point { x: 0, y: 0 }, NoDir));
-
-    for x in 1..(maze.width - 1) as i16 {
-        maze.get_tile_mut(Point { x: x, y: 0 }).connections |= East as u8 | West as u8;
-        history.push((Point { x: x, y: 0 }, West));
+// the rows a hunt looked at, each covering the scanned cells of its row
+fn scanned_rows(cells: &[Point]) -> Vec<Rect> {
+    let mut rows: BTreeMap<i32, (i32, i32)> = BTreeMap::new();
+    for pt in cells {
+        let row = rows.entry(pt.y).or_insert((pt.x, pt.x));
+        *row = (row.0.min(pt.x), row.1.max(pt.x));
     }
+    rows.into_iter()
+        .map(|(y, (left, right))| Rect {
+            x: left as u32,
+            y: y as u32,
+            width: (right - left + 1) as u32,
+            height: 1,
+        })
+        .collect()
+}
 
-    maze.get_tile_mut(Point::new((maze.width - 1) as i16, 0))
-        .connect(West);
-    history.push((Point::new((maze.width - 1) as i16, 0), West));
-
-    for y in 1..maze.height as i16 {
-        let mut range_start = 0;
-        for x in 0..maze.width as i16 {
-            if rng.gen::<bool>() && (x as u16) < maze.width - 1 {
-                maze.get_tile_mut(Point::new(x, y)).connect(East);
-                maze.get_tile_mut(Point::new(x + 1, y)).connect(West);
-                maze.get_tile_mut(Point::new(x, y)).status = ConnectionStatus::InMaze;
-                history.push((Point::new(x, y), East));
-            } else {
-                if maze.get_tile(Point::new(x, y)).connected(West) {
-                    maze.get_tile_mut(Point::new(x, y)).status = ConnectionStatus::InMaze;
-                    history.push((Point::new(x, y), West));
-                }
+// starts from a field without inner walls and splits it with a wall that has a single gap, then
+// splits both halves the same way until every chamber is a single row or column. passages that
+// were carved before (template rooms) are never walled off, so a room stays one open chamber that
+// the walls are built around. the history opens the whole field and then adds the walls one
+// segment at a time
+fn create_maze_division(
+    mut maze: Grid,
+    rng: &mut dyn RngCore,
+    progress: &Progress,
+) -> (Grid, Vec<MazeAction>) {
+    let (width, height) = (maze.width as i32, maze.height as i32);
+    let field = Rect {
+        x: 0,
+        y: 0,
+        width: maze.width,
+        height: maze.height,
+    };
+    // a wall segment may not cut a passage carved before the field was opened
+    let carved: Vec<u8> = maze.tiles.iter().map(|tile| tile.connections()).collect();
+    let open =
+        |x: u32, y: u32, dir: Direction| carved[(y * field.width + x) as usize] & dir as u8 != 0;
+    for y in 0..height {
+        for x in 0..width {
+            let tile = maze.get_tile_mut(Point::new(x, y));
+            tile.set_status(ConnectionStatus::InMaze);
+            if x > 0 {
+                tile.connect(Direction::West);
+            }
+            if x + 1 < width {
+                tile.connect(Direction::East);
+            }
+            if y > 0 {
+                tile.connect(Direction::North);
+            }
+            if y + 1 < height {
+                tile.connect(Direction::South);
+            }
+            progress.inc();
+        }
+    }
+    let mut history = vec![MazeAction::Clear(field)];
 
-                let chosen = rng.gen_range(range_start..=x);
-                maze.get_tile_mut(Point::new(chosen, y)).connect(North);
-                maze.get_tile_mut(Point::new(chosen, y - 1)).connect(South);
-                history.push((Point::new(chosen, y), North));
+    let mut chambers = vec![field];
+    while let Some(chamber) = chambers.pop() {
+        if progress.cancelled() {
+            break;
+        }
+        if chamber.width < 2 || chamber.height < 2 {
+            continue;
+        }
+        let xs = chamber.x..chamber.x + chamber.width;
+        let ys = chamber.y..chamber.y + chamber.height;
+        // rows a wall can run below and columns a wall can run right of without crossing a room
+        let rows: Vec<u32> = (chamber.y..chamber.y + chamber.height - 1)
+            .filter(|y| xs.clone().all(|x| !open(x, *y, Direction::South)))
+            .collect();
+        let columns: Vec<u32> = (chamber.x..chamber.x + chamber.width - 1)
+            .filter(|x| ys.clone().all(|y| !open(*x, y, Direction::East)))
+            .collect();
+        // walls run across the longer side so chambers stay roughly square
+        let horizontal = match chamber.width.cmp(&chamber.height) {
+            std::cmp::Ordering::Less => true,
+            std::cmp::Ordering::Greater => false,
+            std::cmp::Ordering::Equal => rng.gen(),
+        };
+        let horizontal = match (rows.is_empty(), columns.is_empty()) {
+            (true, true) => continue,
+            (true, false) => false,
+            (false, true) => true,
+            (false, false) => horizontal,
+        };
 
-                range_start = x + 1;
+        if horizontal {
+            // wall below row wall
+            let wall = rows[random_in(rng, 0, rows.len() as u32) as usize];
+            let gap = random_in(rng, chamber.x, chamber.x + chamber.width);
+            for x in xs.filter(|x| *x != gap) {
+                let above = Point::new(x as i32, wall as i32);
+                maze.get_tile_mut(above).disconnect(Direction::South);
+                maze.get_tile_mut(above.travel(Direction::South))
+                    .disconnect(Direction::North);
+                history.push(MazeAction::Wall(above, Direction::South));
+            }
+            chambers.push(Rect {
+                height: wall - chamber.y + 1,
+                ..chamber
+            });
+            chambers.push(Rect {
+                y: wall + 1,
+                height: chamber.y + chamber.height - wall - 1,
+                ..chamber
+            });
+        } else {
+            // wall right of column wall
+            let wall = columns[random_in(rng, 0, columns.len() as u32) as usize];
+            let gap = random_in(rng, chamber.y, chamber.y + chamber.height);
+            for y in ys.filter(|y| *y != gap) {
+                let left = Point::new(wall as i32, y as i32);
+                maze.get_tile_mut(left).disconnect(Direction::East);
+                maze.get_tile_mut(left.travel(Direction::East))
+                    .disconnect(Direction::West);
+                history.push(MazeAction::Wall(left, Direction::East));
             }
+            chambers.push(Rect {
+                width: wall - chamber.x + 1,
+                ..chamber
+            });
+            chambers.push(Rect {
+                x: wall + 1,
+                width: chamber.x + chamber.width - wall - 1,
+                ..chamber
+            });
         }
     }
 
     (maze, history)
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// which open cell the growing tree method grows from next, written as oldest, newest, random, a
+// percentage or a mix of these with weights like newest:50,random:50
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
 pub enum GrowingTreeBias {
+    // lowest river factor
     Oldest,
+    // like backtrack
     Newest,
+    // similar to prim
     Random,
+    // a random one of the newest percent of the open cells
     Percent(u8),
+    // one of the other biases picked at random for every cell, weighted by the numbers
+    Mix(Vec<(GrowingTreeBias, u8)>),
 }
 
 impl Default for GrowingTreeBias {
@@ -397,39 +3084,128 @@ impl Default for GrowingTreeBias {
     }
 }
 
+impl GrowingTreeBias {
+    // index into open of the cell to grow from, open must not be empty
+    pub(crate) fn select(&self, open: usize, rng: &mut dyn RngCore) -> usize {
+        match self {
+            GrowingTreeBias::Oldest => 0,
+            GrowingTreeBias::Newest => open - 1,
+            GrowingTreeBias::Random => rng.gen_range(0..open),
+            GrowingTreeBias::Percent(p) => {
+                let first = (open * (100 - (*p).min(100) as usize) / 100).min(open - 1);
+                rng.gen_range(first..open)
+            }
+            GrowingTreeBias::Mix(biases) => {
+                let total: u32 = biases.iter().map(|(_, w)| *w as u32).sum();
+                let mut pick = rng.gen_range(0..total.max(1));
+                for (bias, weight) in biases {
+                    if pick < *weight as u32 {
+                        return bias.select(open, rng);
+                    }
+                    pick -= *weight as u32;
+                }
+                open - 1
+            }
+        }
+    }
+}
+
+impl FromStr for GrowingTreeBias {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| format!("invalid bias \"{}\": {}", s, reason);
+        let single = |s: &str| match s {
+            "oldest" => Ok(GrowingTreeBias::Oldest),
+            "newest" => Ok(GrowingTreeBias::Newest),
+            "random" => Ok(GrowingTreeBias::Random),
+            percent => match percent.parse::<u8>() {
+                Ok(p) if (1..=100).contains(&p) => Ok(GrowingTreeBias::Percent(p)),
+                _ => Err(invalid(
+                    "expected oldest, newest, random or a percentage from 1 to 100",
+                )),
+            },
+        };
+        if !s.contains(':') {
+            return single(s);
+        }
+
+        let biases = s
+            .split(',')
+            .map(|part| {
+                let (bias, weight) = part
+                    .split_once(':')
+                    .ok_or_else(|| invalid("every part of a mix needs a :weight"))?;
+                let weight = match weight.parse::<u8>() {
+                    Ok(w) if w > 0 => w,
+                    _ => return Err(invalid("weights must be from 1 to 255")),
+                };
+                Ok((single(bias)?, weight))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(GrowingTreeBias::Mix(biases))
+    }
+}
+
+impl fmt::Display for GrowingTreeBias {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GrowingTreeBias::Oldest => write!(f, "oldest"),
+            GrowingTreeBias::Newest => write!(f, "newest"),
+            GrowingTreeBias::Random => write!(f, "random"),
+            GrowingTreeBias::Percent(p) => write!(f, "{}", p),
+            GrowingTreeBias::Mix(biases) => {
+                let parts: Vec<String> = biases
+                    .iter()
+                    .map(|(bias, weight)| format!("{}:{}", bias, weight))
+                    .collect();
+                write!(f, "{}", parts.join(","))
+            }
+        }
+    }
+}
+
+impl TryFrom<String> for GrowingTreeBias {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<GrowingTreeBias> for String {
+    fn from(bias: GrowingTreeBias) -> Self {
+        bias.to_string()
+    }
+}
+
 fn create_maze_growingtree(
     mut maze: Grid,
-    rng: &mut StdRng,
-    bias: GrowingTreeBias,
+    rng: &mut dyn RngCore,
+    bias: &GrowingTreeBias,
+    progress: &Progress,
 ) -> (Grid, Vec<(Point, Direction)>) {
     let mut history: Vec<(Point, Direction)> = Vec::with_capacity(maze.tiles.len());
     let mut open: Vec<Point> = Vec::new();
 
-    let pos = Point::new(
-        rng.gen_range(0..maze.width) as i16,
-        rng.gen_range(0..maze.height) as i16,
-    );
-    maze.get_tile_mut(pos).status = ConnectionStatus::InMaze;
+    let pos = random_open_cell(&maze, rng);
+    maze.get_tile_mut(pos).set_status(ConnectionStatus::InMaze);
     history.push((pos, Direction::NoDir));
+    progress.inc();
     open.push(pos);
 
     while !open.is_empty() {
-        let selected_index = match bias {
-            GrowingTreeBias::Oldest => 0,              // lowest river factor
-            GrowingTreeBias::Newest => open.len() - 1, // backtrack
-            GrowingTreeBias::Random => rng.gen_range(0..open.len()), // similar to prim
-            GrowingTreeBias::Percent(p) => {
-                rng.gen_range((open.len() / 100 * (100 - p as usize))..open.len())
-            }
-        };
+        if progress.cancelled() {
+            break;
+        }
+        let selected_index = bias.select(open.len(), rng);
         let selected = open[selected_index];
         let next = pick_random(
-            selected
-                .adjacent()
+            maze.adjacent(selected)
                 .into_iter()
                 .enumerate()
                 .filter(|(_, x)| {
-                    maze.contains(*x) && maze.get_tile(*x).status == ConnectionStatus::UnVisited
+                    maze.contains(*x) && maze.get_tile(*x).status() == ConnectionStatus::UnVisited
                 })
                 .collect::<Vec<(usize, Point)>>()
                 .as_ref(),
@@ -446,10 +3222,12 @@ fn create_maze_growingtree(
 
                 let selected = next.1;
                 maze.get_tile_mut(selected).connect(opposite(dir).into());
-                maze.get_tile_mut(selected).status = ConnectionStatus::InMaze;
+                maze.get_tile_mut(selected)
+                    .set_status(ConnectionStatus::InMaze);
 
                 open.push(selected);
                 history.push((selected, opposite(dir).into()));
+                progress.inc();
             }
         }
     }
@@ -457,30 +3235,54 @@ fn create_maze_growingtree(
     (maze, history)
 }
 
-fn create_maze_wilson(mut maze: Grid, rng: &mut StdRng) -> (Grid, Vec<(Point, Direction)>) {
+fn create_maze_wilson(
+    mut maze: Grid,
+    rng: &mut dyn RngCore,
+    progress: &Progress,
+) -> (Grid, Vec<(Point, Direction)>) {
     let mut history: Vec<(Point, Direction)> = Vec::with_capacity(maze.tiles.len());
     let mut reservoir: Vec<Point> = Vec::with_capacity(maze.tiles.len());
 
     // generate reservoir
-    for y in 0..maze.height as i16 {
-        for x in 0..maze.width as i16 {
-            reservoir.push(Point::new(x, y));
+    for y in 0..maze.height as i32 {
+        for x in 0..maze.width as i32 {
+            if !maze.is_removed(Point::new(x, y)) {
+                reservoir.push(Point::new(x, y));
+            }
         }
     }
+    // fisher-yates in linear time over the cells in row order, so a seed always starts its walks
+    // from the same cells. the order is only ever popped from, never sorted again
     for i in 0..reservoir.len() {
+        if progress.cancelled() {
+            break;
+        }
         let index = rng.gen_range(i..reservoir.len());
-        let temp = reservoir[i];
-        reservoir[i] = reservoir[index];
-        reservoir[index] = temp;
+        reservoir.swap(i, index);
     }
 
-    let mut anchor = reservoir.pop().unwrap();
-    maze.get_tile_mut(anchor).status = ConnectionStatus::InMaze;
-    history.push((anchor, Direction::NoDir));
+    // walks end on passages carved before generation, otherwise on a random first cell
+    let carved = reservoir
+        .iter()
+        .copied()
+        .find(|pt| maze.get_tile(*pt).status() == ConnectionStatus::InMaze);
+    let mut anchor = match carved {
+        Some(pt) => pt,
+        None => reservoir.pop().unwrap(),
+    };
+    // walks starting in a region the anchor can not reach would never end
+    let region = maze.region(anchor);
+    reservoir.retain(|pt| region[maze.get_index(*pt)]);
+    if carved.is_none() {
+        maze.get_tile_mut(anchor)
+            .set_status(ConnectionStatus::InMaze);
+        history.push((anchor, Direction::NoDir));
+        progress.inc();
+    }
 
     'outer: while !reservoir.is_empty() {
         // pick a cell not already in the maze
-        while maze.get_tile(anchor).status == ConnectionStatus::InMaze {
+        while maze.get_tile(anchor).status() == ConnectionStatus::InMaze {
             anchor = match reservoir.pop() {
                 Some(v) => v,
                 None => break 'outer,
@@ -489,87 +3291,166 @@ fn create_maze_wilson(mut maze: Grid, rng: &mut StdRng) -> (Grid, Vec<(Point, Di
         let mut pos = anchor;
 
         // start a random loop erased walk from the chosen cell
-        maze.get_tile_mut(pos).status = ConnectionStatus::Visited;
-        while maze.get_tile(pos).status != ConnectionStatus::InMaze {
+        maze.get_tile_mut(pos).set_status(ConnectionStatus::Visited);
+        while maze.get_tile(pos).status() != ConnectionStatus::InMaze {
             let next = pick_random(
-                pos.adjacent()
+                maze.adjacent(pos)
                     .into_iter()
                     .enumerate()
-                    .filter(|(_, x)| maze.contains(*x))
+                    .filter(|(_, x)| maze.contains(*x) && !maze.is_removed(*x))
                     .collect::<Vec<(usize, Point)>>()
                     .as_ref(),
                 rng,
             )
-            .unwrap(); // safe to unwrap because a cell will always have at least 2 adjacent cells in the maze
+            .unwrap(); // safe to unwrap because every cell in the anchor's region has an open neighbor
 
             let dir = 0b0001 << next.0;
             maze.get_tile_mut(pos).set_connected(dir.into());
-            maze.get_tile_mut(pos).status = ConnectionStatus::Visited;
+            maze.get_tile_mut(pos).set_status(ConnectionStatus::Visited);
             pos = next.1;
         }
 
         // carve the final path into the maze
         pos = anchor;
         let mut dir = Direction::NoDir as u8;
-        while maze.get_tile(pos).status != ConnectionStatus::InMaze {
-            let temp_dir = maze.get_tile(pos).connections;
-            maze.get_tile_mut(pos).status = ConnectionStatus::InMaze;
+        while maze.get_tile(pos).status() != ConnectionStatus::InMaze {
+            let temp_dir = maze.get_tile(pos).connections();
+            maze.get_tile_mut(pos).set_status(ConnectionStatus::InMaze);
             maze.get_tile_mut(pos).connect(opposite(dir).into());
             dir = temp_dir;
 
             history.push((pos, dir.into()));
-            pos = pos.travel(dir.into());
+            progress.inc();
+            pos = maze.travel(pos, dir.into());
         }
         maze.get_tile_mut(pos).connect(opposite(dir).into());
     }
 
+    // walks end on whichever carved area they reach first, so separate areas carved before
+    // generation each grew their own tree
+    if carved.is_some() {
+        join_parts(&mut maze, rng, &mut history);
+    }
+
     (maze, history)
 }
 
+// opens one random wall between every pair of trees of the maze until a single tree is left
+fn join_parts(maze: &mut Grid, rng: &mut dyn RngCore, history: &mut Vec<(Point, Direction)>) {
+    let mut region_map: Vec<u32> = (0..maze.tiles.len() as u32).collect();
+    let mut edges: Vec<(Point, Direction)> = Vec::new();
+    for i in 0..maze.tiles.len() {
+        let pt = Point::new(
+            (i % maze.width as usize) as i32,
+            (i / maze.width as usize) as i32,
+        );
+        if maze.tiles[i].status() != ConnectionStatus::InMaze {
+            continue;
+        }
+        for dir in [Direction::East, Direction::South] {
+            let next = maze.travel(pt, dir);
+            if !maze.contains(next) || maze.get_tile(next).status() != ConnectionStatus::InMaze {
+                continue;
+            }
+            if maze.tiles[i].connected(dir) {
+                merge_sets(&mut region_map, i, maze.get_index(next));
+            } else {
+                edges.push((pt, dir));
+            }
+        }
+    }
+    for i in 0..edges.len() {
+        let index = rng.gen_range(i..edges.len());
+        edges.swap(i, index);
+    }
+
+    for (pt, dir) in edges {
+        let next = maze.travel(pt, dir);
+        if merge_sets(&mut region_map, maze.get_index(pt), maze.get_index(next)) {
+            maze.get_tile_mut(pt).connect(dir);
+            maze.get_tile_mut(next).connect(dir.opposite());
+            history.push((pt, dir));
+        }
+    }
+}
+
 // merge_sets 60x faster than simple array and 600x faster with set_lookup_flatten
-fn create_maze_kruskal(mut maze: Grid, rng: &mut StdRng) -> (Grid, Vec<(Point, Direction)>) {
+fn create_maze_kruskal(
+    mut maze: Grid,
+    rng: &mut dyn RngCore,
+    progress: &Progress,
+) -> (Grid, Vec<(Point, Direction)>) {
     let mut history: Vec<(Point, Direction)> = Vec::with_capacity(maze.tiles.len());
     let mut edges: Vec<(Point, Direction)> = Vec::with_capacity(maze.tiles.len() * 2);
     let mut region_map: Vec<u32> = (0..maze.tiles.len() as u32).collect();
 
-    // generate edges
-    for y in 0..maze.height as i16 {
-        for x in 0..maze.width as i16 {
-            if x > 0 {
-                edges.push((Point::new(x, y), Direction::West));
-            }
-            if y > 0 {
-                edges.push((Point::new(x, y), Direction::North));
+    // generate edges, skipping any that touch a removed cell
+    for pt in maze.scan_cells() {
+        let (x, y) = (pt.x, pt.y);
+        if maze.is_removed(pt) {
+            continue;
+        }
+        if (x > 0 || maze.wrap.horizontal()) && !maze.is_removed(maze.travel(pt, Direction::West)) {
+            edges.push((pt, Direction::West));
+        }
+        if (y > 0 || maze.wrap.vertical()) && !maze.is_removed(maze.travel(pt, Direction::North)) {
+            edges.push((pt, Direction::North));
+        }
+    }
+    // passages carved before generation already join their cells
+    for (i, tile) in maze.tiles.iter().enumerate() {
+        let pt = Point::new(
+            (i % maze.width as usize) as i32,
+            (i / maze.width as usize) as i32,
+        );
+        for dir in [Direction::East, Direction::South] {
+            if tile.connected(dir) {
+                merge_sets(&mut region_map, i, maze.get_index(maze.travel(pt, dir)));
             }
         }
     }
-    // shuffle edges
-    for i in 0..edges.len() {
-        let index = rng.gen_range(i..edges.len());
-        let temp = edges[i];
-        edges[i] = edges[index];
-        edges[index] = temp;
+    if maze.costs.is_empty() {
+        // shuffle edges
+        for i in 0..edges.len() {
+            let index = rng.gen_range(i..edges.len());
+            edges.swap(i, index);
+        }
+    } else {
+        // cheapest edges first
+        let mut costs: Vec<(f32, (Point, Direction))> = edges
+            .into_iter()
+            .map(|(pt, dir)| (passage_cost(&maze, pt, dir, rng), (pt, dir)))
+            .collect();
+        costs.sort_by(|a, b| a.0.total_cmp(&b.0));
+        edges = costs.into_iter().map(|(_, edge)| edge).collect();
     }
 
     // generate maze
     for edge in edges {
+        if progress.cancelled() {
+            break;
+        }
         // if edge connects 2 different regions
         if merge_sets(
             &mut region_map,
             maze.get_index(edge.0),
-            maze.get_index(edge.0.travel(edge.1)),
+            maze.get_index(maze.travel(edge.0, edge.1)),
         ) {
-            if maze.get_tile(edge.0).status != ConnectionStatus::InMaze {
-                maze.get_tile_mut(edge.0).status = ConnectionStatus::InMaze;
+            if maze.get_tile(edge.0).status() != ConnectionStatus::InMaze {
+                maze.get_tile_mut(edge.0)
+                    .set_status(ConnectionStatus::InMaze);
+                progress.inc();
             }
             history.push(edge);
             maze.get_tile_mut(edge.0).connect(edge.1);
 
-            if maze.get_tile(edge.0.travel(edge.1)).status != ConnectionStatus::InMaze {
-                maze.get_tile_mut(edge.0.travel(edge.1)).status = ConnectionStatus::InMaze;
-                history.push((edge.0.travel(edge.1), Direction::NoDir));
+            if maze.get_tile(maze.travel(edge.0, edge.1)).status() != ConnectionStatus::InMaze {
+                maze.get_tile_mut(maze.travel(edge.0, edge.1))
+                    .set_status(ConnectionStatus::InMaze);
+                history.push((maze.travel(edge.0, edge.1), Direction::NoDir));
+                progress.inc();
             }
-            maze.get_tile_mut(edge.0.travel(edge.1))
+            maze.get_tile_mut(maze.travel(edge.0, edge.1))
                 .connect(edge.1.opposite());
         }
     }
@@ -628,29 +3509,30 @@ fn normalize(v: Vector2<f32>) -> Vector2<f32> {
 }
 
 fn generate_noise(
-    world_width: u16,
-    world_height: u16,
-    grid_width: u16,
-    grid_height: u16,
-    rng: &mut StdRng,
+    world_width: u32,
+    world_height: u32,
+    grid_width: u32,
+    grid_height: u32,
+    rng: &mut dyn RngCore,
 ) -> Vec<f32> {
     // can over-estimate length and be fine
-    let cell_width = if world_width % (grid_width - 1) == 0 {
+    let cell_width = if world_width.is_multiple_of(grid_width - 1) {
         world_width / (grid_width - 1)
     } else {
         world_width / (grid_width - 1) + 1
     };
-    let cell_height = if world_height % (grid_height - 1) == 0 {
+    let cell_height = if world_height.is_multiple_of(grid_height - 1) {
         world_height / (grid_height - 1)
     } else {
         world_height / (grid_height - 1) + 1
     };
 
-    let mut points: Vec<f32> = vec![0.0f32; (world_width * world_height) as usize];
-    let mut grid: Vec<Vector2<f32>> = Vec::with_capacity((grid_width * grid_height) as usize);
+    let mut points: Vec<f32> = vec![0.0f32; world_width as usize * world_height as usize];
+    let mut grid: Vec<Vector2<f32>> =
+        Vec::with_capacity(grid_width as usize * grid_height as usize);
 
     // fill grid with random direction vectors
-    for _ in 0..(grid_width * grid_height) {
+    for _ in 0..grid_width as usize * grid_height as usize {
         grid.push(normalize(Vector2 {
             x: rng.gen_range(-1.0..=1.0),
             y: rng.gen_range(-1.0..=1.0),
@@ -692,19 +3574,19 @@ fn generate_noise(
             // dot product of each offset vector and its respective direction vector
             let dots: [f32; 4] = [
                 Vector2::dot(
-                    grid[((grid_pos.x + 0) + (grid_pos.y + 0) * grid_width) as usize],
+                    grid[grid_pos.x as usize + grid_pos.y as usize * grid_width as usize],
                     offset_vectors[0],
                 ),
                 Vector2::dot(
-                    grid[((grid_pos.x + 1) + (grid_pos.y + 0) * grid_width) as usize],
+                    grid[grid_pos.x as usize + 1 + grid_pos.y as usize * grid_width as usize],
                     offset_vectors[1],
                 ),
                 Vector2::dot(
-                    grid[((grid_pos.x + 0) + (grid_pos.y + 1) * grid_width) as usize],
+                    grid[grid_pos.x as usize + (grid_pos.y as usize + 1) * grid_width as usize],
                     offset_vectors[2],
                 ),
                 Vector2::dot(
-                    grid[((grid_pos.x + 1) + (grid_pos.y + 1) * grid_width) as usize],
+                    grid[grid_pos.x as usize + 1 + (grid_pos.y as usize + 1) * grid_width as usize],
                     offset_vectors[3],
                 ),
             ];
@@ -721,53 +3603,44 @@ fn generate_noise(
             let int_y = interpolate(int_x1, int_x2, step.y);
 
             // dot product will range from -cell_width to cell_width
-            points[(x + y * world_width) as usize] = int_y / (cell_width as f32) * 1.5;
+            points[x as usize + y as usize * world_width as usize] =
+                int_y / (cell_width as f32) * 1.5;
         }
     }
 
-    for p in &mut points {
-        *p = if *p <= 0.0 { -1.0 } else { 1.0 };
-    }
-    /*
-
-    let path = Path::new(r"./noise.png");
-    let file = File::create(path).unwrap();
-    let ref mut w = BufWriter::new(file);
-
-    let mut encoder = png::Encoder::new(w, world_width as u32, world_height as u32);
-    encoder.set_color(png::ColorType::Rgb);
-
-    let mut writer = encoder.write_header().unwrap();
-
-    let mut pixels: Vec<ColorRGB> = vec![
-        ColorRGB {
-            red: 0,
-            green: 0,
-            blue: 0
-        };
-        (world_width * world_height) as usize
-    ];
-
-    for i in 0..(world_width * world_height) as usize {
-        pixels[i] = get_color(points[i]);
-    }
+    points
+}
 
-    writer
-        .write_image_data(&ColorRGB::as_bytes(&pixels))
-        .unwrap();
-        */
+// the noise a noise maze is split by, about -1 to 1 for every cell. carve_maze draws it from the
+// start of the rng it seeds with the seed of the maze, so it is drawn the same way here
+pub fn noise_map(maze: &Grid) -> Vec<f32> {
+    let lattice = maze.params.noise.frequency as u32 + 1;
+    let mut rng = StdRng::seed_from_u64(maze.params.seed);
+    generate_noise(maze.width, maze.height, lattice, lattice, &mut rng)
+}
 
-    points
+// the noise map moved into 0 to 1, as costs for --weights noise
+pub fn noise_costs(maze: &Grid) -> Vec<f32> {
+    noise_map(maze)
+        .into_iter()
+        .map(|v| ((v + 1.0) / 2.0).clamp(0.0, 1.0))
+        .collect()
 }
 
-fn flood_tile_prim(maze: &mut Grid, noise_map: &Vec<u8>, mut pos: Point, rng: &mut StdRng) {
-    if pos.x >= maze.width as i16 || pos.y >= maze.height as i16 {
+fn flood_tile_prim(
+    maze: &mut Grid,
+    history: &mut Vec<(Point, Direction)>,
+    noise_map: &[u8],
+    mut pos: Point,
+    rng: &mut dyn RngCore,
+) {
+    if pos.x >= maze.width as i32 || pos.y >= maze.height as i32 {
         return;
     }
-    if noise_map[(pos.x + pos.y * maze.width as i16) as usize] != 0 {
+    if noise_map[(pos.x + pos.y * maze.width as i32) as usize] != 0 {
         return;
     }
-    if maze.tiles[(pos.x + pos.y * maze.width as i16) as usize].status
+    if maze.tiles[(pos.x + pos.y * maze.width as i32) as usize].status()
         != ConnectionStatus::UnVisited
     {
         return;
@@ -776,7 +3649,8 @@ fn flood_tile_prim(maze: &mut Grid, noise_map: &Vec<u8>, mut pos: Point, rng: &m
     let mut open_tiles: Vec<Point> = Vec::new();
 
     open_tiles.push(pos);
-    maze.tiles[(pos.x + pos.y * maze.width as i16) as usize].status = ConnectionStatus::InMaze;
+    maze.tiles[(pos.x + pos.y * maze.width as i32) as usize].set_status(ConnectionStatus::InMaze);
+    history.push((pos, Direction::NoDir));
     while !open_tiles.is_empty() {
         let current_tile_index: usize = rng.gen_range(0..open_tiles.len());
         pos = open_tiles[current_tile_index];
@@ -787,8 +3661,8 @@ fn flood_tile_prim(maze: &mut Grid, noise_map: &Vec<u8>, mut pos: Point, rng: &m
                 .enumerate()
                 .filter(|(_, x)| {
                     maze.contains(*x)
-                        && maze.get_tile(*x).status == ConnectionStatus::UnVisited
-                        && noise_map[(x.x + x.y * maze.width as i16) as usize] == 1
+                        && maze.get_tile(*x).status() == ConnectionStatus::UnVisited
+                        && noise_map[(x.x + x.y * maze.width as i32) as usize] == 1
                 })
                 .collect::<Vec<(usize, Point)>>()
                 .as_ref(),
@@ -805,22 +3679,29 @@ fn flood_tile_prim(maze: &mut Grid, noise_map: &Vec<u8>, mut pos: Point, rng: &m
                 pos = next.1;
                 maze.get_tile_mut(pos)
                     .connect(opposite(0b0001 << next.0).into());
-                maze.get_tile_mut(pos).status = ConnectionStatus::InMaze;
+                maze.get_tile_mut(pos).set_status(ConnectionStatus::InMaze);
 
                 open_tiles.push(pos);
+                history.push((pos, opposite(0b0001 << next.0).into()));
             }
         }
     }
 }
 
-fn flood_tile_backtrack(maze: &mut Grid, noise_map: &Vec<u8>, mut pos: Point, rng: &mut StdRng) {
-    if pos.x >= maze.width as i16 || pos.y >= maze.height as i16 {
+fn flood_tile_backtrack(
+    maze: &mut Grid,
+    history: &mut Vec<(Point, Direction)>,
+    noise_map: &[u8],
+    mut pos: Point,
+    rng: &mut dyn RngCore,
+) {
+    if pos.x >= maze.width as i32 || pos.y >= maze.height as i32 {
         return;
     }
-    if noise_map[(pos.x + pos.y * maze.width as i16) as usize] != 1 {
+    if noise_map[(pos.x + pos.y * maze.width as i32) as usize] != 1 {
         return;
     }
-    if maze.tiles[(pos.x + pos.y * maze.width as i16) as usize].status
+    if maze.tiles[(pos.x + pos.y * maze.width as i32) as usize].status()
         != ConnectionStatus::UnVisited
     {
         return;
@@ -829,7 +3710,8 @@ fn flood_tile_backtrack(maze: &mut Grid, noise_map: &Vec<u8>, mut pos: Point, rn
     let mut tile_stack: Vec<Point> = Vec::new();
 
     tile_stack.push(pos);
-    maze.get_tile_mut(pos).status = ConnectionStatus::InMaze;
+    maze.get_tile_mut(pos).set_status(ConnectionStatus::InMaze);
+    history.push((pos, Direction::NoDir));
 
     while !tile_stack.is_empty() {
         let next = pick_random(
@@ -838,8 +3720,8 @@ fn flood_tile_backtrack(maze: &mut Grid, noise_map: &Vec<u8>, mut pos: Point, rn
                 .enumerate()
                 .filter(|(_, x)| {
                     maze.contains(*x)
-                        && maze.get_tile(*x).status == ConnectionStatus::UnVisited
-                        && noise_map[(x.x + x.y * maze.width as i16) as usize] == 1
+                        && maze.get_tile(*x).status() == ConnectionStatus::UnVisited
+                        && noise_map[(x.x + x.y * maze.width as i32) as usize] == 1
                 })
                 .collect::<Vec<(usize, Point)>>()
                 .as_ref(),
@@ -857,24 +3739,36 @@ fn flood_tile_backtrack(maze: &mut Grid, noise_map: &Vec<u8>, mut pos: Point, rn
                 pos = next.1;
                 maze.get_tile_mut(pos)
                     .connect(opposite(0b0001 << next.0).into());
-                maze.get_tile_mut(pos).status = ConnectionStatus::InMaze;
+                maze.get_tile_mut(pos).set_status(ConnectionStatus::InMaze);
 
                 tile_stack.push(pos);
+                history.push((pos, opposite(0b0001 << next.0).into()));
             }
         }
     }
 }
 
-fn create_maze_noise(mut maze: Grid, rng: &mut StdRng) -> (Grid, Vec<(Point, Direction)>) {
-    let noise_map: Vec<u8> = generate_noise(maze.width, maze.height, 7, 7, rng)
+fn create_maze_noise(
+    mut maze: Grid,
+    rng: &mut dyn RngCore,
+    progress: &Progress,
+) -> (Grid, Vec<(Point, Direction)>) {
+    let mut history: Vec<(Point, Direction)> = Vec::with_capacity(maze.tiles.len());
+    let lattice = maze.params.noise.frequency as u32 + 1;
+    let threshold = maze.params.noise.threshold as f32 / 100.0;
+    let noise_map: Vec<u8> = generate_noise(maze.width, maze.height, lattice, lattice, rng)
         .iter()
-        .map(|x| if *x < 0.0 { 0 } else { 1 })
+        .map(|x| if *x <= threshold { 0 } else { 1 })
         .collect();
 
-    for y in 0..maze.height as i16 {
-        for x in 0..maze.width as i16 {
-            flood_tile_prim(&mut maze, &noise_map, Point { x, y }, rng);
-            flood_tile_backtrack(&mut maze, &noise_map, Point { x, y }, rng);
+    for y in 0..maze.height as i32 {
+        if progress.cancelled() {
+            break;
+        }
+        for x in 0..maze.width as i32 {
+            flood_tile_prim(&mut maze, &mut history, &noise_map, Point { x, y }, rng);
+            flood_tile_backtrack(&mut maze, &mut history, &noise_map, Point { x, y }, rng);
+            progress.inc();
         }
     }
 
@@ -882,5 +3776,592 @@ fn create_maze_noise(mut maze: Grid, rng: &mut StdRng) -> (Grid, Vec<(Point, Dir
         need to add random stopping and then also implement connecting of maze regions
     */
 
-    (maze, Vec::new())
+    (maze, history)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        arbitrary::{maze_with_exclusion, maze_with_history, rect_within, MAX_SIZE},
+        error::MazeGenError,
+        progress::{CancelToken, ProgressFn},
+        template::{apply_template, room_cells, Template},
+    };
+    use proptest::{prelude::*, sample::select};
+    use rand::{Rng, RngCore};
+    use std::{
+        collections::VecDeque,
+        sync::{Arc, Mutex},
+    };
+
+    // joins cells breadth first from a random open cell, standing in for a generator written
+    // outside of the crate
+    struct BreadthFirst;
+
+    impl MazeGenerator for BreadthFirst {
+        fn carve(
+            &self,
+            mut maze: Grid,
+            rng: &mut dyn RngCore,
+            progress: &Progress,
+        ) -> (Grid, Vec<(Point, Direction)>) {
+            let open: Vec<Point> = maze
+                .scan_cells()
+                .into_iter()
+                .filter(|pt| !maze.is_removed(*pt))
+                .collect();
+            let start = open[rng.gen_range(0..open.len())];
+            maze.get_tile_mut(start)
+                .set_status(ConnectionStatus::InMaze);
+            progress.inc();
+            let mut steps = vec![(start, Direction::NoDir)];
+            let mut queue = VecDeque::from([start]);
+            while let Some(pt) = queue.pop_front() {
+                for dir in Direction::ALL {
+                    let next = maze.travel(pt, dir);
+                    if maze.contains(next)
+                        && maze.get_tile(next).status() == ConnectionStatus::UnVisited
+                    {
+                        maze.get_tile_mut(pt).connect(dir);
+                        maze.get_tile_mut(next).connect(dir.opposite());
+                        maze.get_tile_mut(next).set_status(ConnectionStatus::InMaze);
+                        progress.inc();
+                        steps.push((next, dir.opposite()));
+                        queue.push_back(next);
+                    }
+                }
+            }
+            (maze, steps)
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn crop_keeps_interior_connections((maze, rect) in rect_within()) {
+            let cropped = maze.crop(rect).unwrap();
+            prop_assert_eq!((cropped.width, cropped.height), (rect.width, rect.height));
+            for y in 0..rect.height as i32 {
+                for x in 0..rect.width as i32 {
+                    let inner = Point::new(x, y);
+                    let outer = Point::new(x + rect.x as i32, y + rect.y as i32);
+                    for (dir, inside) in [
+                        (Direction::East, x + 1 < rect.width as i32),
+                        (Direction::South, y + 1 < rect.height as i32),
+                    ] {
+                        if inside {
+                            prop_assert_eq!(
+                                cropped.get_tile(inner).connected(dir),
+                                maze.get_tile(outer).connected(dir)
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        #[test]
+        #[cfg(feature = "serde")]
+        fn serde_round_trips_mazes_and_histories((maze, history) in maze_with_history()) {
+            let read: Grid = serde_json::from_str(&serde_json::to_string(&maze).unwrap()).unwrap();
+            prop_assert_eq!(&read.tiles, &maze.tiles);
+            prop_assert_eq!(read.metadata(), maze.metadata());
+            let mut short = maze.clone();
+            short.tiles.pop();
+            let read = serde_json::from_str::<Grid>(&serde_json::to_string(&short).unwrap());
+            prop_assert!(read.is_err());
+            let read: MazeHistory =
+                serde_json::from_str(&serde_json::to_string(&history).unwrap()).unwrap();
+            prop_assert_eq!(read, history);
+        }
+
+        #[test]
+        fn excluded_cells_stay_disconnected((maze, history, rect) in maze_with_exclusion()) {
+            prop_assert!(history.agrees_with(&maze));
+            for (i, tile) in maze.tiles.iter().enumerate() {
+                let pt = Point::new(
+                    (i % maze.width as usize) as i32,
+                    (i / maze.width as usize) as i32,
+                );
+                prop_assert_eq!(maze.is_removed(pt), rect.contains(pt));
+                if maze.is_removed(pt) {
+                    prop_assert_eq!(tile.connections(), 0, "removed {:?} was carved", pt);
+                }
+            }
+        }
+
+        #[test]
+        fn exclusions_leave_every_region_a_perfect_maze(
+            (maze, _, _) in maze_with_exclusion(),
+        ) {
+            // groups of cells joined by passages, or by neighboring without removed cells between
+            let groups = |joined: &dyn Fn(Point, Direction) -> bool| {
+                let mut reached = vec![false; maze.tiles.len()];
+                let mut count = 0;
+                for start in maze.scan_cells() {
+                    if maze.is_removed(start) || reached[maze.get_index(start)] {
+                        continue;
+                    }
+                    count += 1;
+                    reached[maze.get_index(start)] = true;
+                    let mut open = vec![start];
+                    while let Some(pt) = open.pop() {
+                        for dir in Direction::ALL {
+                            let next = maze.travel(pt, dir);
+                            if joined(pt, dir) && !reached[maze.get_index(next)] {
+                                reached[maze.get_index(next)] = true;
+                                open.push(next);
+                            }
+                        }
+                    }
+                }
+                count
+            };
+            let regions = groups(&|pt, dir| {
+                let next = maze.travel(pt, dir);
+                maze.contains(next) && !maze.is_removed(next)
+            });
+            let parts = groups(&|pt, dir| maze.get_tile(pt).connected(dir));
+            prop_assert_eq!(parts, regions);
+
+            let open = maze.tiles.iter().filter(|t| t.status() != ConnectionStatus::Removed).count();
+            let passages: u32 = maze.tiles.iter().map(|t| t.connections().count_ones()).sum();
+            prop_assert_eq!(passages as usize, 2 * (open - regions));
+        }
+
+        #[test]
+        fn progress_callbacks_count_every_cell(
+            (width, height) in (1..=MAX_SIZE, 1..=MAX_SIZE),
+            method in any::<MazeType>(),
+            wrap in any::<MazeWrap>(),
+            seed in any::<u64>(),
+        ) {
+            let reports = Arc::new(Mutex::new(Vec::new()));
+            let seen = reports.clone();
+            let progress = Progress::with_callback(
+                width as u64 * height as u64,
+                ProgressFn::new(move |done, total| seen.lock().unwrap().push((done, total))),
+            );
+            // a lone cell has no walls for kruskal to remove, it never joins a maze
+            prop_assume!(method != MazeType::Kruskal || width * height > 1);
+            generate_maze_with_progress(width, height, method, wrap, seed, &progress).unwrap();
+            progress.finish();
+
+            let reports = reports.lock().unwrap();
+            let cells = width as u64 * height as u64;
+            prop_assert_eq!(reports.last(), Some(&(cells, cells)));
+            prop_assert!(reports.windows(2).all(|w| w[0].0 <= w[1].0));
+        }
+
+        #[test]
+        fn cancelled_generation_stops_early(
+            (width, height) in (1..=MAX_SIZE, 1..=MAX_SIZE),
+            method in any::<MazeType>(),
+            wrap in any::<MazeWrap>(),
+            seed in any::<u64>(),
+        ) {
+            let cancel = CancelToken::new();
+            cancel.cancel();
+            let progress = Progress::hidden().cancel_on(cancel);
+            let generated = generate_maze_with_progress(width, height, method, wrap, seed, &progress);
+            prop_assert_eq!(generated.unwrap_err(), MazeGenError::Cancelled);
+        }
+
+        #[test]
+        fn generators_carve_around_exclusions(
+            (width, height) in (1..=MAX_SIZE, 1..=MAX_SIZE),
+            wrap in any::<MazeWrap>(),
+            seed in any::<u64>(),
+            rect in any::<Rect>(),
+        ) {
+            let mut maze = Grid::new(width, height, wrap, seed, MazeType::Backtrack);
+            maze.exclude(rect);
+            let (maze, history) = carve_maze_with(maze, &BreadthFirst, &Progress::hidden());
+            prop_assert!(history.agrees_with(&maze));
+
+            // every region is started once and joined into a tree from there
+            let open = maze.tiles.iter().filter(|t| t.status() != ConnectionStatus::Removed).count();
+            let regions = history
+                .steps
+                .iter()
+                .filter(|step| step.carve().is_some_and(|(_, dir)| dir == Direction::NoDir))
+                .count();
+            let passages: u32 = maze.tiles.iter().map(|t| t.connections().count_ones()).sum();
+            prop_assert_eq!(passages as usize, 2 * (open - regions));
+            prop_assert!(maze
+                .tiles
+                .iter()
+                .all(|t| t.status() != ConnectionStatus::UnVisited));
+        }
+
+        #[test]
+        fn invert_twice_restores_maze(maze in any::<Grid>()) {
+            let inverted = maze.invert();
+            for (i, tile) in inverted.tiles.iter().enumerate() {
+                let pt = Point::new(
+                    (i % maze.width as usize) as i32,
+                    (i / maze.width as usize) as i32,
+                );
+                for dir in Direction::ALL.into_iter().filter(|d| tile.connected(*d)) {
+                    let next = inverted.travel(pt, dir);
+                    prop_assert!(inverted.get_tile(next).connected(dir.opposite()));
+                }
+            }
+            prop_assert_eq!(inverted.invert().tiles, maze.tiles);
+        }
+
+        #[test]
+        fn growing_tree_biases_grow_perfect_mazes(
+            width in 1..=MAX_SIZE,
+            height in 1..=MAX_SIZE,
+            seed in any::<u64>(),
+            bias in any::<GrowingTreeBias>(),
+        ) {
+            let mut maze = Grid::new(width, height, MazeWrap::None, seed, MazeType::GrowingTree);
+            maze.params.bias = bias;
+            let (maze, history) = carve_maze(maze, &Progress::hidden());
+            prop_assert!(history.agrees_with(&maze));
+            let passages: u32 = maze.tiles.iter().map(|t| t.connections().count_ones()).sum();
+            prop_assert_eq!(passages as usize, 2 * (maze.tiles.len() - 1));
+        }
+
+        #[test]
+        fn growing_tree_biases_parse_back(bias in any::<GrowingTreeBias>()) {
+            prop_assert_eq!(bias.to_string().parse::<GrowingTreeBias>(), Ok(bias));
+        }
+
+        #[test]
+        fn weighted_mazes_are_perfect(
+            width in 1..=MAX_SIZE,
+            height in 1..=MAX_SIZE,
+            wrap in any::<MazeWrap>(),
+            seed in any::<u64>(),
+            method in select(vec![MazeType::Prim, MazeType::Kruskal]),
+            costs in prop::collection::vec(0.0..=1.0f32, (MAX_SIZE * MAX_SIZE) as usize),
+        ) {
+            let mut maze = Grid::new(width, height, wrap, seed, method);
+            maze.params.weights = Some(WeightSource::Noise);
+            maze.costs = costs[..maze.tiles.len()].to_vec();
+            let (maze, history) = carve_maze(maze, &Progress::hidden());
+            prop_assert!(history.agrees_with(&maze));
+            let passages: u32 = maze.tiles.iter().map(|t| t.connections().count_ones()).sum();
+            prop_assert_eq!(passages as usize, 2 * (maze.tiles.len() - 1));
+        }
+
+        #[test]
+        fn oriented_mazes_are_perfect(
+            width in 1..=MAX_SIZE,
+            height in 1..=MAX_SIZE,
+            seed in any::<u64>(),
+            orientation in select(Orientation::value_variants()),
+        ) {
+            let method = if orientation.is_corner() {
+                MazeType::BinaryTree
+            } else {
+                MazeType::Sidewinder
+            };
+            let mut maze = Grid::new(width, height, MazeWrap::None, seed, method);
+            maze.params.orientation = Some(orientation);
+            let (maze, history) = carve_maze(maze, &Progress::hidden());
+            prop_assert_eq!((maze.width, maze.height), (width, height));
+            prop_assert!(history.agrees_with(&maze));
+            let passages: u32 = maze.tiles.iter().map(|t| t.connections().count_ones()).sum();
+            prop_assert_eq!(passages as usize, 2 * (maze.tiles.len() - 1));
+        }
+
+        #[test]
+        fn weight_sources_parse_back(path in "\\PC{1,16}") {
+            for weights in [WeightSource::Noise, WeightSource::Image(path.clone())] {
+                prop_assert_eq!(weights.to_string().parse::<WeightSource>(), Ok(weights));
+            }
+        }
+
+        #[test]
+        fn scan_orders_visit_every_cell_once(
+            width in 1..=MAX_SIZE,
+            height in 1..=MAX_SIZE,
+            scan_order in select(ScanOrder::value_variants()),
+        ) {
+            let mut maze = Grid::new(width, height, MazeWrap::None, 0, MazeType::default());
+            maze.params.scan_order = scan_order;
+            let mut visited = vec![false; maze.tiles.len()];
+            for pt in maze.scan_cells() {
+                prop_assert!(maze.contains(pt), "{:?} is off the grid", pt);
+                prop_assert!(!visited[maze.get_index(pt)], "{:?} visited twice", pt);
+                visited[maze.get_index(pt)] = true;
+            }
+            prop_assert!(visited.iter().all(|v| *v));
+        }
+
+        #[test]
+        fn uncarving_removes_dead_ends(
+            mut maze in any::<Grid>(),
+            cells in 0..600u32,
+            strategy in select(UncarveStrategy::value_variants()),
+        ) {
+            let passages = |maze: &Grid| -> u32 {
+                maze.tiles.iter().map(|t| t.connections().count_ones()).sum()
+            };
+            let before = passages(&maze);
+            let removed = maze.uncarve(cells, strategy, maze.params.seed);
+            prop_assert!(removed <= cells);
+            // every removed cell was a dead end, taking one passage with it
+            prop_assert_eq!(passages(&maze), before - 2 * removed);
+            let open = maze.tiles.iter().filter(|t| t.status() != ConnectionStatus::Removed).count();
+            prop_assert_eq!(open, maze.tiles.len() - removed as usize);
+        }
+
+        #[test]
+        fn full_braiding_leaves_only_walled_in_dead_ends(
+            (mut maze, mut history) in maze_with_history(),
+        ) {
+            let dead_ends: Vec<Point> = maze
+                .scan_cells()
+                .into_iter()
+                .filter(|pt| maze.get_tile(*pt).connections().count_ones() == 1)
+                .collect();
+            let opened = maze.braid(100, maze.params.seed);
+            history.steps.extend(opened.into_iter().map(MazeAction::from));
+            prop_assert!(history.agrees_with(&maze));
+            // noise mazes can have unconnected cells that become new dead ends once joined
+            for pt in dead_ends {
+                let tile = maze.get_tile(pt);
+                if tile.connections().count_ones() != 1 {
+                    continue;
+                }
+                for dir in Direction::ALL {
+                    let next = maze.travel(pt, dir);
+                    prop_assert!(
+                        tile.connected(dir) || !maze.contains(next) || maze.is_removed(next)
+                    );
+                }
+            }
+        }
+
+        #[test]
+        fn edits_replay_and_undo(
+            (mut maze, history) in maze_with_history(),
+            edits in prop::collection::vec((any::<bool>(), 0..MAX_SIZE, 0..MAX_SIZE, 0..4usize), 0..40),
+        ) {
+            let generated = maze.clone();
+            let mut journal = EditJournal::default();
+            for (carve, x, y, dir) in edits {
+                let (pt, dir) = (Point::new(x as i32, y as i32), Direction::ALL[dir]);
+                let changed = match carve {
+                    true => journal.carve(&mut maze, pt, dir),
+                    false => journal.fill(&mut maze, pt, dir),
+                };
+                if changed {
+                    prop_assert_eq!(maze.get_tile(pt).connected(dir), carve);
+                }
+            }
+            // edits keep passages reciprocal, so the journal replays on top of the history
+            for pt in maze.scan_cells() {
+                for dir in Direction::ALL.into_iter().filter(|d| maze.get_tile(pt).connected(*d)) {
+                    let next = maze.travel(pt, dir);
+                    if maze.contains(next) {
+                        prop_assert!(maze.get_tile(next).connected(dir.opposite()));
+                    }
+                }
+            }
+            prop_assert!(journal.history(&history).agrees_with(&maze));
+            while journal.undo(&mut maze).is_some() {}
+            prop_assert_eq!(maze.tiles, generated.tiles);
+        }
+
+        #[test]
+        fn points_parse_back(x in 0..i32::MAX, y in 0..i32::MAX) {
+            let pt = Point::new(x, y);
+            prop_assert_eq!(pt.to_string().parse::<Point>(), Ok(pt));
+        }
+
+        #[test]
+        fn symmetries_move_maze_and_history(
+            (maze, history) in maze_with_history(),
+            transpose in any::<bool>(),
+            mirror_x in any::<bool>(),
+            mirror_y in any::<bool>(),
+        ) {
+            let symmetry = Symmetry { transpose, mirror_x, mirror_y };
+            let transformed = maze.transform(symmetry);
+            prop_assert!(history.transform(symmetry).agrees_with(&transformed));
+            let passages = |maze: &Grid| -> u32 {
+                maze.tiles.iter().map(|t| t.connections().count_ones()).sum()
+            };
+            prop_assert_eq!(passages(&transformed), passages(&maze));
+        }
+
+        #[test]
+        fn thinned_corridors_keep_rooms_joined(
+            rows in prop::collection::vec("[.R]{1,8}", 1..8),
+            scale in 1..=3u32,
+            method in select(vec![MazeType::Wilson, MazeType::Kruskal]),
+            percent in any::<u8>(),
+            seed in any::<u64>(),
+        ) {
+            let template: Template = rows.join("\n").parse().unwrap();
+            let mut maze = Grid::new(
+                template.width * scale,
+                template.height * scale,
+                MazeWrap::None,
+                seed,
+                method,
+            );
+            apply_template(&mut maze, &template, scale);
+            let (mut maze, _) = carve_maze(maze, &Progress::hidden());
+            let rooms = room_cells(&maze, &template, scale);
+            let removed = maze.thin_corridors(percent, &rooms, seed);
+            let open: Vec<Point> = maze.scan_cells().into_iter().filter(|pt| !maze.is_removed(*pt)).collect();
+            prop_assert_eq!(open.len(), maze.tiles.len() - removed as usize);
+            prop_assert!(rooms.iter().all(|pt| !maze.is_removed(*pt)));
+
+            let mut reached = vec![false; maze.tiles.len()];
+            let mut queue = vec![open[0]];
+            reached[maze.get_index(open[0])] = true;
+            while let Some(pt) = queue.pop() {
+                for dir in Direction::ALL {
+                    let next = maze.travel(pt, dir);
+                    if maze.get_tile(pt).connected(dir) && !reached[maze.get_index(next)] {
+                        prop_assert!(!maze.is_removed(next), "{:?} leads into a removed cell", pt);
+                        reached[maze.get_index(next)] = true;
+                        queue.push(next);
+                    }
+                }
+            }
+            prop_assert!(open.iter().all(|pt| reached[maze.get_index(*pt)]));
+        }
+
+        #[test]
+        fn division_walls_off_rooms_as_chambers(
+            rows in prop::collection::vec("[.R]{1,8}", 1..8),
+            scale in 1..=3u32,
+            seed in any::<u64>(),
+        ) {
+            let template: Template = rows.join("\n").parse().unwrap();
+            let mut maze = Grid::new(
+                template.width * scale,
+                template.height * scale,
+                MazeWrap::None,
+                seed,
+                MazeType::RecursiveDivision,
+            );
+            apply_template(&mut maze, &template, scale);
+            let before = maze.clone();
+            let (maze, history) = carve_maze(maze, &Progress::hidden());
+            prop_assert!(history.agrees_with(&maze));
+            // after the rooms the field is opened once and only walls are added to it
+            let field = Rect { x: 0, y: 0, width: maze.width, height: maze.height };
+            let rooms = history.steps.iter().take_while(|step| step.carve().is_some()).count();
+            prop_assert_eq!(history.steps.get(rooms), Some(&MazeAction::Clear(field)));
+            prop_assert!(history.steps[rooms + 1..]
+                .iter()
+                .all(|step| matches!(step, MazeAction::Wall(..))));
+
+            for (tile, room) in maze.tiles.iter().zip(&before.tiles) {
+                prop_assert_eq!(tile.connections() & room.connections(), room.connections());
+            }
+            let mut reached = vec![false; maze.tiles.len()];
+            let mut queue = vec![Point::new(0, 0)];
+            reached[0] = true;
+            while let Some(pt) = queue.pop() {
+                for dir in Direction::ALL {
+                    let next = maze.travel(pt, dir);
+                    if maze.get_tile(pt).connected(dir) && !reached[maze.get_index(next)] {
+                        reached[maze.get_index(next)] = true;
+                        queue.push(next);
+                    }
+                }
+            }
+            prop_assert!(reached.iter().all(|r| *r));
+        }
+
+        #[test]
+        fn hunt_and_kill_joins_rooms_and_records_its_scans(
+            rows in prop::collection::vec("[.R]{1,8}", 1..8),
+            scale in 1..=3u32,
+            order in select(vec![ScanOrder::Row, ScanOrder::Hilbert]),
+            seed in any::<u64>(),
+        ) {
+            let template: Template = rows.join("\n").parse().unwrap();
+            let mut maze = Grid::new(
+                template.width * scale,
+                template.height * scale,
+                MazeWrap::None,
+                seed,
+                MazeType::HuntAndKill,
+            );
+            maze.params.scan_order = order;
+            apply_template(&mut maze, &template, scale);
+            let before = maze.clone();
+            let (maze, history) = carve_maze(maze, &Progress::hidden());
+            prop_assert!(history.agrees_with(&maze));
+            for (tile, room) in maze.tiles.iter().zip(&before.tiles) {
+                prop_assert_eq!(tile.connections() & room.connections(), room.connections());
+            }
+            let mut reached = vec![false; maze.tiles.len()];
+            let mut queue = vec![Point::new(0, 0)];
+            reached[0] = true;
+            while let Some(pt) = queue.pop() {
+                for dir in Direction::ALL {
+                    let next = maze.travel(pt, dir);
+                    if maze.get_tile(pt).connected(dir) && !reached[maze.get_index(next)] {
+                        reached[maze.get_index(next)] = true;
+                        queue.push(next);
+                    }
+                }
+            }
+            prop_assert!(reached.iter().all(|r| *r));
+
+            // every hunt looks at the row of the cell it found, and walks on from that cell
+            let mut scanned: Vec<Rect> = Vec::new();
+            for step in &history.steps {
+                match step {
+                    MazeAction::Scan(row) => {
+                        prop_assert_eq!(row.height, 1);
+                        scanned.push(*row);
+                    }
+                    MazeAction::Carve(pt, _) if !scanned.is_empty() => {
+                        prop_assert!(scanned.iter().any(|row| row.contains(*pt)));
+                        scanned.clear();
+                    }
+                    _ => prop_assert!(scanned.is_empty()),
+                }
+            }
+        }
+
+        #[test]
+        fn stages_draw_from_separate_streams(seed in any::<u64>()) {
+            let schedule = SeedSchedule::new(seed);
+            prop_assert_eq!(schedule.carve, seed);
+            let stages = [
+                schedule.carve,
+                schedule.uncarve,
+                schedule.braid,
+                schedule.symmetry,
+                schedule.openings,
+                schedule.doors,
+                schedule.corridors,
+                schedule.overlay,
+            ];
+            for (i, stage) in stages.iter().enumerate() {
+                prop_assert!(!stages[i + 1..].contains(stage));
+            }
+        }
+
+        #[test]
+        fn openings_parse_back(opening in any::<Opening>()) {
+            prop_assert_eq!(opening.to_string().parse::<Opening>(), Ok(opening));
+        }
+
+        #[test]
+        fn rects_parse_back(rect in any::<Rect>()) {
+            prop_assert_eq!(rect.to_string().parse::<Rect>(), Ok(rect));
+        }
+
+        #[test]
+        fn labels_parse_back(x in 0..=i32::MAX, y in 0..=i32::MAX, text in "\\PC{1,8}") {
+            let label = CellLabel { pt: Point::new(x, y), text };
+            prop_assert_eq!(label.to_string().parse::<CellLabel>(), Ok(label));
+        }
+    }
 }
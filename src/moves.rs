@@ -0,0 +1,89 @@
+use crate::{
+    error::LabgenError,
+    image::write_output,
+    maze::{Direction, Grid},
+    solver::Solution,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+
+// file a solved path is written to, for programs that walk the maze rather than look at it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum SolutionFormat {
+    /// text with the moves as N, E, S and W on the first line and the x,y of every cell of the
+    /// path on the second
+    Moves,
+    /// json object with the moves and the cells of the path
+    Json,
+}
+
+impl SolutionFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            SolutionFormat::Moves => "txt",
+            SolutionFormat::Json => "json",
+        }
+    }
+}
+
+fn letter(dir: Direction) -> char {
+    match dir {
+        Direction::North => 'N',
+        Direction::East => 'E',
+        Direction::South => 'S',
+        Direction::West => 'W',
+        Direction::NoDir => '-',
+    }
+}
+
+// writes the moves from the start to the end of a solution together with the cells they pass.
+// an unsolved maze has no moves and an empty path
+pub fn render_moves(
+    maze: &Grid,
+    solution: &Solution,
+    format: SolutionFormat,
+    out: &mut dyn Write,
+) -> Result<(), LabgenError> {
+    let moves: String = solution.moves(maze).into_iter().map(letter).collect();
+    match format {
+        SolutionFormat::Moves => {
+            let cells: Vec<String> = solution
+                .path
+                .iter()
+                .map(|pt| format!("{},{}", pt.x, pt.y))
+                .collect();
+            writeln!(out, "{}", moves)?;
+            writeln!(out, "{}", cells.join(" "))?;
+        }
+        SolutionFormat::Json => {
+            let cells: Vec<String> = solution
+                .path
+                .iter()
+                .map(|pt| format!("[{},{}]", pt.x, pt.y))
+                .collect();
+            writeln!(
+                out,
+                "{{\"moves\":\"{}\",\"path\":[{}]}}",
+                moves,
+                cells.join(",")
+            )?;
+        }
+    }
+    Ok(())
+}
+
+// written next to the image, with -solution added to its name
+pub fn generate_moves(
+    maze: &Grid,
+    solution: &Solution,
+    format: SolutionFormat,
+    file_path: &str,
+) -> Result<(), LabgenError> {
+    let file_path = format!("{}-solution", file_path);
+    write_output(&file_path, format.extension(), |out| {
+        render_moves(maze, solution, format, out)
+    })
+}
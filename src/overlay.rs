@@ -0,0 +1,140 @@
+use crate::maze::{ConnectionStatus, Direction, Grid, Point};
+
+// how the cells of a coarse maze are scaled up onto a fine grid, like PixelLayout does for pixels.
+// every coarse cell covers room by room fine cells, followed by wall fine cells of thick wall
+// toward its east and south neighbors. there is no thick wall around the outside, the fine maze
+// has its own
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OverlayScale {
+    pub room: u16,
+    pub wall: u16,
+}
+
+impl OverlayScale {
+    pub fn cell_width(&self) -> u16 {
+        self.room + self.wall
+    }
+
+    // size of the coarse maze covering a fine grid, the last coarse cells along each axis may be
+    // cut off by its edge but keep at least one fine cell of room
+    pub fn coarse_size(&self, width: u32, height: u32) -> (u32, u32) {
+        let cells = |fine: u32| fine.div_ceil(self.cell_width() as u32).max(1);
+        (cells(width), cells(height))
+    }
+
+    // coarse cell covering the fine cell at pt, together with the position of pt inside the
+    // fine cells it covers
+    pub fn coarse_point(&self, pt: Point) -> (Point, (u16, u16)) {
+        let cell_width = self.cell_width() as i32;
+        (
+            Point::new(pt.x / cell_width, pt.y / cell_width),
+            ((pt.x % cell_width) as u16, (pt.y % cell_width) as u16),
+        )
+    }
+}
+
+// removes the fine cells under the walls of the coarse maze, so the fine maze carved around them
+// follows the coarse maze at a larger scale. fine cells are kept where they lie inside the room of
+// a coarse cell, on the wall between two joined coarse cells, or on a wall corner between four
+// coarse cells joined all around it. removed coarse cells remove all of their fine cells, and
+// cells that were already removed stay removed
+pub fn overlay_maze(fine: &mut Grid, coarse: &Grid, scale: OverlayScale) {
+    let joined = |pt: Point, dir: Direction| {
+        let next = pt.travel(dir);
+        coarse.contains(next) && coarse.get_tile(pt).connected(dir) && !coarse.is_removed(next)
+    };
+
+    for y in 0..fine.height as i32 {
+        for x in 0..fine.width as i32 {
+            let pt = Point::new(x, y);
+            let (cell, (dx, dy)) = scale.coarse_point(pt);
+            if !coarse.contains(cell) {
+                continue;
+            }
+            let (wall_x, wall_y) = (dx >= scale.room, dy >= scale.room);
+            let kept = !coarse.is_removed(cell)
+                && match (wall_x, wall_y) {
+                    (false, false) => true,
+                    (true, false) => joined(cell, Direction::East),
+                    (false, true) => joined(cell, Direction::South),
+                    (true, true) => {
+                        joined(cell, Direction::East)
+                            && joined(cell, Direction::South)
+                            && joined(cell.travel(Direction::East), Direction::South)
+                            && joined(cell.travel(Direction::South), Direction::East)
+                    }
+                };
+            if !kept {
+                fine.get_tile_mut(pt).set_status(ConnectionStatus::Removed);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        arbitrary::MAX_SIZE,
+        maze::{carve_maze, generate_maze, MazeType, MazeWrap},
+        progress::Progress,
+    };
+    use proptest::{prelude::*, sample::select};
+
+    proptest! {
+        #[test]
+        fn overlays_leave_a_single_maze(
+            width in 1..=MAX_SIZE,
+            height in 1..=MAX_SIZE,
+            room in 1..=4u16,
+            wall in 1..=3u16,
+            coarse_method in select(vec![MazeType::Wilson, MazeType::Kruskal]),
+            method in select(vec![MazeType::Wilson, MazeType::Kruskal]),
+            seed in any::<u64>(),
+        ) {
+            let scale = OverlayScale { room, wall };
+            let (coarse_width, coarse_height) = scale.coarse_size(width, height);
+            let (coarse, _) = generate_maze(
+                coarse_width,
+                coarse_height,
+                coarse_method,
+                MazeWrap::None,
+                seed,
+            ).unwrap();
+            let mut maze = Grid::new(width, height, MazeWrap::None, seed, method);
+            overlay_maze(&mut maze, &coarse, scale);
+            let (maze, history) = carve_maze(maze, &Progress::hidden());
+            prop_assert!(history.agrees_with(&maze));
+
+            // the cells inside the room of every coarse cell are kept
+            for y in 0..height as i32 {
+                for x in 0..width as i32 {
+                    let (_, (dx, dy)) = scale.coarse_point(Point::new(x, y));
+                    if dx < room && dy < room {
+                        prop_assert!(!maze.is_removed(Point::new(x, y)));
+                    }
+                }
+            }
+
+            // and joined into a single perfect maze
+            let kept = (0..maze.tiles.len())
+                .filter(|i| maze.tiles[*i].status() != ConnectionStatus::Removed)
+                .count();
+            let passages: u32 = maze.tiles.iter().map(|t| t.connections().count_ones()).sum();
+            prop_assert_eq!(passages as usize, 2 * (kept - 1));
+            let mut reached = vec![false; maze.tiles.len()];
+            let mut open = vec![Point::new(0, 0)];
+            reached[0] = true;
+            while let Some(pt) = open.pop() {
+                for dir in Direction::ALL {
+                    let next = maze.travel(pt, dir);
+                    if maze.get_tile(pt).connected(dir) && !reached[maze.get_index(next)] {
+                        reached[maze.get_index(next)] = true;
+                        open.push(next);
+                    }
+                }
+            }
+            prop_assert_eq!(reached.iter().filter(|r| **r).count(), kept);
+        }
+    }
+}
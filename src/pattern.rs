@@ -0,0 +1,384 @@
+use crate::maze::{ConnectionStatus, Direction, Grid, Point};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+// limits of an l-system, far beyond the moves any grid can hold but small enough to expand in
+// memory. rules that grow without moving, like X=XX, would otherwise never stop growing
+const MAX_ITERATIONS: u32 = 64;
+const MAX_SYMBOLS: usize = 1 << 24;
+
+// path carved into a grid before generation, methods that support patterns then complete the
+// maze around it. written as spiral, hilbert or lsystem:axiom:rules:iterations on the command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
+pub enum Pattern {
+    // corridor spiraling inward from the top left corner, one cell apart from itself
+    Spiral,
+    // hilbert curve with one cell between its turns, filling the largest square that fits
+    Hilbert,
+    // turtle path of an l-system starting in the center heading east. F moves forward one cell,
+    // + turns right and - turns left, other symbols are only used by the rules
+    LSystem {
+        axiom: String,
+        rules: Vec<(char, String)>,
+        iterations: u32,
+    },
+}
+
+impl FromStr for Pattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "spiral" => return Ok(Pattern::Spiral),
+            "hilbert" => return Ok(Pattern::Hilbert),
+            _ => (),
+        }
+
+        let invalid = |reason: &str| format!("invalid pattern \"{}\": {}", s, reason);
+        let Some(lsystem) = s.strip_prefix("lsystem:") else {
+            return Err(invalid("expected spiral, hilbert or lsystem:..."));
+        };
+        let parts: Vec<&str> = lsystem.split(':').collect();
+        let [axiom, rules, iterations] = parts[..] else {
+            return Err(invalid("expected lsystem:axiom:rules:iterations"));
+        };
+
+        let rules = rules
+            .split(',')
+            .filter(|r| !r.is_empty())
+            .map(|rule| {
+                let (symbol, replacement) = rule
+                    .split_once('=')
+                    .ok_or_else(|| invalid("rules are written as X=replacement"))?;
+                let mut chars = symbol.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Ok((c, replacement.to_string())),
+                    _ => Err(invalid("rules replace a single symbol")),
+                }
+            })
+            .collect::<Result<Vec<(char, String)>, String>>()?;
+
+        let iterations: u32 = iterations.parse().map_err(|e| invalid(&format!("{}", e)))?;
+        if iterations > MAX_ITERATIONS {
+            return Err(invalid(&format!(
+                "an l-system can take at most {} iterations",
+                MAX_ITERATIONS
+            )));
+        }
+        if expanded_len(axiom, &rules, iterations) > MAX_SYMBOLS {
+            return Err(invalid(&format!(
+                "the expanded l-system is longer than {} symbols",
+                MAX_SYMBOLS
+            )));
+        }
+
+        Ok(Pattern::LSystem {
+            axiom: axiom.to_string(),
+            rules,
+            iterations,
+        })
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pattern::Spiral => write!(f, "spiral"),
+            Pattern::Hilbert => write!(f, "hilbert"),
+            Pattern::LSystem {
+                axiom,
+                rules,
+                iterations,
+            } => {
+                let rules: Vec<String> =
+                    rules.iter().map(|(c, r)| format!("{}={}", c, r)).collect();
+                write!(f, "lsystem:{}:{}:{}", axiom, rules.join(","), iterations)
+            }
+        }
+    }
+}
+
+impl TryFrom<String> for Pattern {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Pattern> for String {
+    fn from(pattern: Pattern) -> Self {
+        pattern.to_string()
+    }
+}
+
+fn turn_right(dir: Direction) -> Direction {
+    match dir {
+        Direction::North => Direction::East,
+        Direction::East => Direction::South,
+        Direction::South => Direction::West,
+        Direction::West => Direction::North,
+        Direction::NoDir => Direction::NoDir,
+    }
+}
+
+// walks forward and turns right whenever the next cell is outside the grid or would touch the
+// corridor already walked, stopping once turning does not help either
+fn spiral(width: u32, height: u32) -> Vec<Point> {
+    let inside = |pt: Point| pt.x >= 0 && pt.y >= 0 && pt.x < width as i32 && pt.y < height as i32;
+    let mut visited = vec![false; width as usize * height as usize];
+    let index = |pt: Point| pt.x as usize + pt.y as usize * width as usize;
+
+    let mut pos = Point::new(0, 0);
+    let mut dir = Direction::East;
+    let mut path = vec![pos];
+    visited[index(pos)] = true;
+
+    'walk: loop {
+        for _ in 0..2 {
+            let ahead = pos.travel(dir);
+            let beyond = ahead.travel(dir);
+            if inside(ahead)
+                && !visited[index(ahead)]
+                && !(inside(beyond) && visited[index(beyond)])
+            {
+                pos = ahead;
+                visited[index(pos)] = true;
+                path.push(pos);
+                continue 'walk;
+            }
+            dir = turn_right(dir);
+        }
+        return path;
+    }
+}
+
+// cell of the hilbert curve of the given order at distance d along it
+fn hilbert_point(order: u32, d: u32) -> Point {
+    let (mut x, mut y, mut t) = (0, 0, d);
+    let mut s = 1;
+    while s < 1 << order {
+        let rx = 1 & (t / 2);
+        let ry = 1 & (t ^ rx);
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - x;
+                y = s - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        x += s * rx;
+        y += s * ry;
+        t /= 4;
+        s *= 2;
+    }
+    Point::new(x as i32, y as i32)
+}
+
+// hilbert curve spread out to every other cell, centered in the grid
+fn hilbert(width: u32, height: u32) -> Vec<Point> {
+    // a curve of order n spans 2^(n+1) - 1 cells once spread out
+    let size = width.min(height);
+    let order = (1..31).take_while(|n| (1 << (n + 1)) - 1 <= size).last();
+    let Some(order) = order else {
+        return vec![Point::new(0, 0)];
+    };
+
+    let span = (1 << (order + 1)) - 1;
+    let offset = Point::new(((width - span) / 2) as i32, ((height - span) / 2) as i32);
+
+    let mut path: Vec<Point> = Vec::new();
+    for d in 0..1u32 << (2 * order) {
+        let pt = hilbert_point(order, d);
+        let pt = Point::new(pt.x * 2, pt.y * 2) + offset;
+        if let Some(prev) = path.last().copied() {
+            path.push(Point::new((prev.x + pt.x) / 2, (prev.y + pt.y) / 2));
+        }
+        path.push(pt);
+    }
+    path
+}
+
+// replacement of a symbol by the rules, symbols without a rule stay as they are
+fn replacement(rules: &[(char, String)], c: char) -> Option<&str> {
+    rules
+        .iter()
+        .find(|(s, _)| *s == c)
+        .map(|(_, replacement)| replacement.as_str())
+}
+
+// length of the axiom after expanding it iterations times, without building the string. stops
+// counting past MAX_SYMBOLS
+fn expanded_len(axiom: &str, rules: &[(char, String)], iterations: u32) -> usize {
+    // length every symbol grows to, each iteration replacing it by the lengths of its replacement
+    let len_of = |lengths: &[(char, usize)], c: char| {
+        lengths
+            .iter()
+            .find(|(s, _)| *s == c)
+            .map_or(1, |(_, len)| *len)
+    };
+    let mut lengths: Vec<(char, usize)> = rules.iter().map(|(c, _)| (*c, 1)).collect();
+    for _ in 0..iterations.min(MAX_ITERATIONS) {
+        lengths = rules
+            .iter()
+            .map(|(c, replacement)| {
+                let len = replacement
+                    .chars()
+                    .map(|r| len_of(&lengths, r))
+                    .fold(0, |sum: usize, len| sum.saturating_add(len));
+                (*c, len.min(MAX_SYMBOLS + 1))
+            })
+            .collect();
+    }
+    axiom
+        .chars()
+        .map(|c| len_of(&lengths, c))
+        .fold(0, |sum: usize, len| sum.saturating_add(len))
+}
+
+// turns of the turtle walking an l-system, unbounded so the caller stops it at the edge of the
+// grid. the expansion stops growing once it has max_moves moves, or before it grows longer than
+// MAX_SYMBOLS for l-systems that were not parsed
+fn lsystem(
+    axiom: &str,
+    rules: &[(char, String)],
+    iterations: u32,
+    max_moves: usize,
+) -> Vec<Direction> {
+    let mut symbols = axiom.to_string();
+    for _ in 0..iterations.min(MAX_ITERATIONS) {
+        if symbols.chars().filter(|c| *c == 'F').count() >= max_moves {
+            break;
+        }
+        let len = symbols
+            .chars()
+            .map(|c| replacement(rules, c).map_or(1, |r| r.chars().count()))
+            .fold(0, |sum: usize, len| sum.saturating_add(len));
+        if len > MAX_SYMBOLS {
+            break;
+        }
+        symbols = symbols
+            .chars()
+            .map(|c| match replacement(rules, c) {
+                Some(replacement) => replacement.to_string(),
+                None => c.to_string(),
+            })
+            .collect();
+    }
+
+    let mut dir = Direction::East;
+    let mut moves = Vec::new();
+    for c in symbols.chars() {
+        match c {
+            'F' if moves.len() < max_moves => moves.push(dir),
+            '+' => dir = turn_right(dir),
+            '-' => dir = turn_right(turn_right(turn_right(dir))),
+            _ => (),
+        }
+    }
+    moves
+}
+
+// carves the pattern as a tree of passages. the path stops at the first cell outside the grid or
+// removed from it, and stepping onto a cell it already carved moves there without closing a loop
+pub fn carve_pattern(maze: &mut Grid, pattern: &Pattern) {
+    let (mut pos, moves) = match pattern {
+        Pattern::Spiral => path_moves(spiral(maze.width, maze.height)),
+        Pattern::Hilbert => path_moves(hilbert(maze.width, maze.height)),
+        Pattern::LSystem {
+            axiom,
+            rules,
+            iterations,
+        } => (
+            Point::new(maze.width as i32 / 2, maze.height as i32 / 2),
+            lsystem(axiom, rules, *iterations, maze.tiles.len() * 4),
+        ),
+    };
+
+    if maze.is_removed(pos) {
+        return;
+    }
+    maze.get_tile_mut(pos).set_status(ConnectionStatus::InMaze);
+
+    for dir in moves {
+        let next = maze.travel(pos, dir);
+        if !maze.contains(next) || maze.is_removed(next) {
+            return;
+        }
+        if maze.get_tile(next).status() != ConnectionStatus::InMaze {
+            maze.get_tile_mut(pos).connect(dir);
+            maze.get_tile_mut(next).connect(dir.opposite());
+            maze.get_tile_mut(next).set_status(ConnectionStatus::InMaze);
+        }
+        pos = next;
+    }
+}
+
+// first cell of a path and the directions leading from each cell to the next
+fn path_moves(path: Vec<Point>) -> (Point, Vec<Direction>) {
+    let moves = path
+        .windows(2)
+        .map(|pair| {
+            Direction::ALL
+                .into_iter()
+                .find(|d| pair[0].travel(*d) == pair[1])
+                .unwrap()
+        })
+        .collect();
+    (path[0], moves)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arbitrary::maze_with_pattern;
+    use proptest::prelude::*;
+
+    #[test]
+    fn expanded_len_counts_every_symbol() {
+        let rules = vec![('F', "F+F".to_string()), ('X', "XFX".to_string())];
+        let mut symbols = "FX".to_string();
+        for iterations in 0..6 {
+            assert_eq!(expanded_len("FX", &rules, iterations), symbols.len());
+            symbols = symbols
+                .chars()
+                .map(|c| match replacement(&rules, c) {
+                    Some(r) => r.to_string(),
+                    None => c.to_string(),
+                })
+                .collect();
+        }
+    }
+
+    #[test]
+    fn growth_without_moves_is_refused() {
+        let err = "lsystem:X:X=XX:40".parse::<Pattern>().unwrap_err();
+        assert!(err.contains("longer than"), "{}", err);
+        let err = "lsystem:F:F=F:65".parse::<Pattern>().unwrap_err();
+        assert!(err.contains("iterations"), "{}", err);
+        assert!("lsystem:F:F=F:64".parse::<Pattern>().is_ok());
+    }
+
+    #[test]
+    fn unparsed_lsystems_stop_growing() {
+        let rules = vec![('X', "XX".to_string())];
+        assert_eq!(lsystem("FX", &rules, u32::MAX, 100).len(), 1);
+    }
+
+    proptest! {
+        #[test]
+        fn patterns_grow_perfect_mazes((maze, history) in maze_with_pattern()) {
+            prop_assert!(history.agrees_with(&maze));
+            let passages: u32 = maze.tiles.iter().map(|t| t.connections().count_ones()).sum();
+            prop_assert_eq!(passages as usize, 2 * (maze.tiles.len() - 1));
+        }
+
+        #[test]
+        fn patterns_parse_back(pattern in any::<Pattern>()) {
+            prop_assert_eq!(pattern.to_string().parse::<Pattern>(), Ok(pattern));
+        }
+    }
+}
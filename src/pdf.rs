@@ -0,0 +1,440 @@
+use crate::{
+    error::LabgenError,
+    image::{add_rulers, draw_maze, image_size, write_output, ImageOptions},
+    maze::Grid,
+    svg::pixel_rects,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+
+// paper the pdf pages are sized for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum PageSize {
+    /// 210 by 297 millimeters
+    A4,
+    /// 8.5 by 11 inches
+    Letter,
+}
+
+impl PageSize {
+    // width and height in points
+    fn size(self) -> (f32, f32) {
+        match self {
+            PageSize::A4 => (595.28, 841.89),
+            PageSize::Letter => (612.0, 792.0),
+        }
+    }
+}
+
+// printable pages of a maze, the maze is scaled to fill the page inside the margin on every side
+#[derive(Debug, Clone, PartialEq)]
+pub struct PdfOptions {
+    pub page: PageSize,
+    // in millimeters
+    pub margin: f32,
+    // printed above the maze
+    pub title: Option<String>,
+}
+
+impl Default for PdfOptions {
+    fn default() -> Self {
+        PdfOptions {
+            page: PageSize::A4,
+            margin: 15.0,
+            title: None,
+        }
+    }
+}
+
+impl PdfOptions {
+    // whether the margins leave any room for the maze
+    pub fn fits(&self) -> bool {
+        let (width, height) = self.page.size();
+        let margin = mm(self.margin);
+        self.margin.is_finite() && self.margin >= 0.0 && 2.0 * margin < width.min(height)
+    }
+}
+
+const POINTS_PER_MM: f32 = 72.0 / 25.4;
+const TITLE_SIZE: f32 = 16.0;
+// between the baseline of the title and the top of the maze
+const TITLE_GAP: f32 = 10.0;
+
+fn mm(length: f32) -> f32 {
+    length * POINTS_PER_MM
+}
+
+// shortest decimal of at most 3 places
+fn number(v: f32) -> String {
+    let text = format!("{:.3}", v);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    match text {
+        "-0" => "0".to_string(),
+        _ => text.to_string(),
+    }
+}
+
+// literal string in the latin 1 range of the standard fonts' encoding, other characters are
+// replaced by a question mark
+fn string(text: &str) -> Vec<u8> {
+    let mut bytes = vec![b'('];
+    for c in text.chars() {
+        match c {
+            '\\' | '(' | ')' => bytes.extend_from_slice(&[b'\\', c as u8]),
+            '\n' => bytes.extend_from_slice(b"\\n"),
+            ' '..='~' | '\u{a0}'..='\u{ff}' => bytes.push(c as u32 as u8),
+            _ => bytes.push(b'?'),
+        }
+    }
+    bytes.push(b')');
+    bytes
+}
+
+// top edge of the maze in points from the bottom of the page, below the title if there is one,
+// and the points per pixel it is drawn with to fill the page inside the margins
+fn placement(pdf_opts: &PdfOptions, titled: bool, width: u16, height: u16) -> (f32, f32) {
+    let (page_width, page_height) = pdf_opts.page.size();
+    let margin = mm(pdf_opts.margin);
+    let mut top = page_height - margin;
+    if titled {
+        top -= TITLE_SIZE + TITLE_GAP;
+    }
+    let scale = ((page_width - 2.0 * margin) / width as f32)
+        .min((top - margin) / height as f32)
+        .max(0.0);
+    (top, scale)
+}
+
+// drawing commands of a page showing the image of the maze, which is filled with one path of
+// rects per color so no seams show between pixels of the same color
+fn page_content(
+    maze: &Grid,
+    opts: &ImageOptions,
+    pdf_opts: &PdfOptions,
+    title: Option<&str>,
+) -> Result<Vec<u8>, LabgenError> {
+    let (width, height) = image_size(maze, &opts.layout())?;
+    let pixels = draw_maze(maze, opts, |_| 1)?;
+    let (pixels, width, height) = add_rulers(pixels, width, height, maze, opts)?;
+
+    let (page_width, page_height) = pdf_opts.page.size();
+    let margin = mm(pdf_opts.margin);
+    let mut content: Vec<u8> = Vec::new();
+    if let Some(title) = title {
+        write!(
+            content,
+            "BT /F1 {} Tf {} {} Td ",
+            number(TITLE_SIZE),
+            number(margin),
+            number(page_height - margin - TITLE_SIZE)
+        )?;
+        content.extend_from_slice(&string(title));
+        writeln!(content, " Tj ET")?;
+    }
+
+    // centered horizontally, with pixel coordinates growing down from the top left corner
+    let (top, scale) = placement(pdf_opts, title.is_some(), width, height);
+    let left = (page_width - width as f32 * scale) / 2.0;
+    writeln!(
+        content,
+        "q {} 0 0 {} {} {} cm",
+        number(scale),
+        number(-scale),
+        number(left),
+        number(top)
+    )?;
+
+    let palette = opts.palette();
+    let transparent = opts.transparent();
+    let mut rects = pixel_rects(&pixels, width, |i| Some(pixels[i]) != transparent);
+    rects.sort_by_key(|(_, color)| *color);
+    for group in rects.chunk_by(|a, b| a.1 == b.1) {
+        let rgb = &palette[group[0].1 as usize * 3..group[0].1 as usize * 3 + 3];
+        writeln!(
+            content,
+            "{} {} {} rg",
+            number(rgb[0] as f32 / 255.0),
+            number(rgb[1] as f32 / 255.0),
+            number(rgb[2] as f32 / 255.0)
+        )?;
+        for (rect, _) in group {
+            writeln!(
+                content,
+                "{} {} {} {} re",
+                rect.left, rect.top, rect.width, rect.height
+            )?;
+        }
+        writeln!(content, "f")?;
+    }
+    writeln!(content, "Q")?;
+    Ok(content)
+}
+
+// writes the maze as a pdf ready to print, drawn as in the png image without the solution. a
+// solution in opts is drawn on a second page. the maze is drawn as vector rects, so it stays sharp
+// at any size
+pub fn render_pdf(
+    maze: &Grid,
+    opts: &ImageOptions,
+    pdf_opts: &PdfOptions,
+    out: &mut dyn Write,
+) -> Result<(), LabgenError> {
+    if !pdf_opts.fits() {
+        return Err(LabgenError::Render(format!(
+            "margins of {} millimeters leave no room on the page",
+            pdf_opts.margin
+        )));
+    }
+    let puzzle = ImageOptions {
+        solution: None,
+        ..opts.clone()
+    };
+    let solution_title = pdf_opts.title.as_ref().map(|t| format!("{} (solution)", t));
+    let mut pages = vec![page_content(
+        maze,
+        &puzzle,
+        pdf_opts,
+        pdf_opts.title.as_deref(),
+    )?];
+    if opts.solution.is_some() {
+        pages.push(page_content(
+            maze,
+            opts,
+            pdf_opts,
+            solution_title.as_deref(),
+        )?);
+    }
+
+    // objects are numbered from 1: the catalog, page tree, font and document info, followed by
+    // every page and its content
+    let (page_width, page_height) = pdf_opts.page.size();
+    let kids: Vec<String> = (0..pages.len())
+        .map(|i| format!("{} 0 R", 5 + 2 * i))
+        .collect();
+    let metadata = maze.metadata();
+    let mut info = b"<< /Title ".to_vec();
+    info.extend(string(pdf_opts.title.as_deref().unwrap_or(&format!(
+        "{}x{} {} maze",
+        maze.width, maze.height, maze.params.method
+    ))));
+    info.extend_from_slice(b" /Subject ");
+    info.extend(string(&metadata.comment()));
+    info.extend_from_slice(b" /Producer ");
+    info.extend(string(&format!("labgen {}", metadata.version)));
+    info.extend_from_slice(b" >>");
+
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            pages.len()
+        )
+        .into_bytes(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_vec(),
+        info,
+    ];
+    for (i, content) in pages.into_iter().enumerate() {
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+                number(page_width),
+                number(page_height),
+                6 + 2 * i
+            )
+            .into_bytes(),
+        );
+        let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        stream.extend(content);
+        stream.extend_from_slice(b"endstream");
+        objects.push(stream);
+    }
+
+    // the cross reference table holds the byte offset of every object
+    let mut pdf: Vec<u8> = b"%PDF-1.4\n".to_vec();
+    let mut offsets: Vec<usize> = Vec::new();
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend(format!("{} 0 obj\n", i + 1).into_bytes());
+        pdf.extend_from_slice(object);
+        pdf.extend_from_slice(b"\nendobj\n");
+    }
+    let xref = pdf.len();
+    writeln!(pdf, "xref\n0 {}\n0000000000 65535 f ", objects.len() + 1)?;
+    for offset in offsets {
+        writeln!(pdf, "{:010} 00000 n ", offset)?;
+    }
+    write!(
+        pdf,
+        "trailer\n<< /Size {} /Root 1 0 R /Info 4 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    )?;
+    out.write_all(&pdf)?;
+    Ok(())
+}
+
+pub fn generate_pdf(
+    maze: &Grid,
+    opts: &ImageOptions,
+    pdf_opts: &PdfOptions,
+) -> Result<(), LabgenError> {
+    write_output(&opts.file_path, "pdf", |out| {
+        render_pdf(maze, opts, pdf_opts, out)
+    })
+}
+
+// walls thinner than this fade or break up on most printers
+const MIN_WALL_MM: f32 = 0.2;
+// passages narrower than this are hard to trace with a pen
+const MIN_PASSAGE_MM: f32 = 3.0;
+// pages inked over more of their area than this take long to dry and may curl
+const MAX_INK: f32 = 0.3;
+
+// how the maze comes out on paper when the pdf is printed, with widths in millimeters. ink is the
+// fraction of the page covered, counting every pixel by how dark its color is
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrintReport {
+    pub wall: f32,
+    pub passage: f32,
+    pub ink: f32,
+    pub warnings: Vec<String>,
+}
+
+// checks the puzzle page of the pdf for walls too thin to print at dpi, passages too narrow to
+// draw in and walls covering too much of the page. every warning suggests widths or a maze size
+// that print better, estimated as if the maze kept filling the same space on the page
+pub fn print_report(
+    maze: &Grid,
+    opts: &ImageOptions,
+    pdf_opts: &PdfOptions,
+    dpi: u16,
+) -> Result<PrintReport, LabgenError> {
+    let (width, height) = image_size(maze, &opts.layout())?;
+    let pixels = draw_maze(maze, opts, |_| 1)?;
+    let (pixels, width, height) = add_rulers(pixels, width, height, maze, opts)?;
+    let (_, scale) = placement(pdf_opts, pdf_opts.title.is_some(), width, height);
+    let px_mm = scale / POINTS_PER_MM;
+
+    let palette = opts.palette();
+    let transparent = opts.transparent();
+    let dark: f32 = pixels
+        .iter()
+        .filter(|i| Some(**i) != transparent)
+        .map(|i| {
+            let rgb = &palette[*i as usize * 3..*i as usize * 3 + 3];
+            1.0 - rgb.iter().map(|c| *c as f32).sum::<f32>() / (3.0 * 255.0)
+        })
+        .sum();
+    let (page_width, page_height) = pdf_opts.page.size();
+
+    let wall = opts.wall_width as f32 * px_mm;
+    let passage = opts.passage_width as f32 * px_mm;
+    let cell = wall + passage;
+    let mut warnings = Vec::new();
+
+    // a wall needs at least a couple of the printer's dots to come out solid
+    let min_wall = MIN_WALL_MM.max(2.0 * 25.4 / dpi as f32);
+    if wall < min_wall {
+        let passage_width = ((cell / min_wall).floor() as u16).saturating_sub(1).max(1);
+        warnings.push(format!(
+            "walls are {:.2} mm wide and may break up when printed at {} dpi, try \
+            --passagewidth {} --wallwidth 1",
+            wall, dpi, passage_width
+        ));
+    }
+    if passage < MIN_PASSAGE_MM {
+        // the cells needed for passages of the minimum width, keeping the ratio of the widths
+        let cells = |cells: u32| (cells as f32 * passage / MIN_PASSAGE_MM).floor().max(1.0);
+        warnings.push(format!(
+            "passages are {:.2} mm wide and hard to trace with a pen, try a maze of at most \
+            {}x{} cells or a larger page",
+            passage,
+            cells(maze.width),
+            cells(maze.height)
+        ));
+    }
+    let ink = dark * scale * scale / (page_width * page_height);
+    if ink > MAX_INK {
+        // thinner walls give their pixels to the passages, single pixel walls get wider passages
+        let (passage_width, wall_width) = match opts.wall_width {
+            0 | 1 => (opts.passage_width * 2, opts.wall_width),
+            w => (opts.passage_width + w - w / 2, w / 2),
+        };
+        warnings.push(format!(
+            "walls cover {:.0}% of the page, try --passagewidth {} --wallwidth {} to save ink",
+            ink * 100.0,
+            passage_width,
+            wall_width
+        ));
+    }
+
+    Ok(PrintReport {
+        wall,
+        passage,
+        ink,
+        warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{generate_maze, MazeType, MazeWrap};
+
+    #[test]
+    fn numbers_take_at_most_three_places() {
+        assert_eq!(number(1.0), "1");
+        assert_eq!(number(2.5), "2.5");
+        assert_eq!(number(0.12345), "0.123");
+        assert_eq!(number(-0.0001), "0");
+    }
+
+    #[test]
+    fn strings_escape_parentheses_and_replace_other_characters() {
+        assert_eq!(string("a(b)\\c\n"), b"(a\\(b\\)\\\\c\\n)");
+        assert_eq!(string("é€"), b"(\xE9?)");
+    }
+
+    #[test]
+    fn margins_must_leave_room_for_the_maze() {
+        assert!(PdfOptions::default().fits());
+        for margin in [-1.0, 106.0, f32::NAN] {
+            let pdf_opts = PdfOptions {
+                margin,
+                ..PdfOptions::default()
+            };
+            assert!(!pdf_opts.fits());
+            let (maze, _) = generate_maze(3, 3, MazeType::Wilson, MazeWrap::None, 1).unwrap();
+            let rendered = render_pdf(&maze, &ImageOptions::default(), &pdf_opts, &mut Vec::new());
+            assert!(matches!(rendered, Err(LabgenError::Render(_))));
+        }
+    }
+
+    #[test]
+    fn documents_are_complete() {
+        let (maze, _) = generate_maze(8, 6, MazeType::Wilson, MazeWrap::None, 1).unwrap();
+        let pdf_opts = PdfOptions {
+            title: Some("maze".to_string()),
+            ..PdfOptions::default()
+        };
+        let mut out = Vec::new();
+        render_pdf(&maze, &ImageOptions::default(), &pdf_opts, &mut out).unwrap();
+        assert!(out.starts_with(b"%PDF-"));
+        assert!(out.ends_with(b"%%EOF\n"));
+    }
+
+    #[test]
+    fn thin_walls_are_reported() {
+        let (maze, _) = generate_maze(200, 200, MazeType::Wilson, MazeWrap::None, 1).unwrap();
+        let report =
+            print_report(&maze, &ImageOptions::default(), &PdfOptions::default(), 300).unwrap();
+        assert!(report.wall < MIN_WALL_MM);
+        assert!(report.warnings[0].starts_with("walls are"));
+    }
+}
@@ -0,0 +1,641 @@
+use crate::{
+    error::LabgenError,
+    mask::{apply_mask, CostImage, Mask},
+    maze::{
+        carve_maze, generate_maze, noise_costs, BorderShape, CellLabel, Grid, GrowingTreeBias,
+        MazeAction, MazeHistory, MazeType, MazeWrap, NoiseOptions, Opening, Orientation, Point,
+        Rect, ScanOrder, SeedSchedule, Symmetry, UncarveStrategy, WeightSource,
+    },
+    overlay::{overlay_maze, OverlayScale},
+    pattern::{carve_pattern, Pattern},
+    polar::{carve_polar, PolarGrid},
+    progress::{CancelToken, Progress},
+    solver::{distance_field, solve, Solution, SolverType},
+    stats::{farthest_cells, Entrances, MazeStats, StatsPredicate},
+    template::{
+        apply_template, even_doors, place_doors, room_cells, seal_rooms, RoomDoors, Template,
+        TemplateCell,
+    },
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+// everything deciding the maze a run of the command line produces, from carving it to the ends of
+// its solution. files are read by the caller, so masks, cost images and templates are given
+// decoded. the stage seeds left at None are derived from seed like SeedSchedule::new does
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub width: u32,
+    pub height: u32,
+    pub method: MazeType,
+    pub wrap: MazeWrap,
+    pub seed: u64,
+    pub scan_order: ScanOrder,
+    pub bias: GrowingTreeBias,
+    pub orientation: Option<Orientation>,
+    pub noise: NoiseOptions,
+    pub weights: Option<WeightSource>,
+    // costs of WeightSource::Image
+    pub cost_image: Option<CostImage>,
+    pub border: Option<BorderShape>,
+    pub exclude: Vec<Rect>,
+    pub mask: Option<Mask>,
+    pub template: Option<Template>,
+    pub template_scale: u32,
+    pub room_doors: RoomDoors,
+    pub overlay: Option<OverlayScale>,
+    pub overlay_method: MazeType,
+    pub pattern: Option<Pattern>,
+    pub corridors: Option<u8>,
+    // mazes are carved again with other seeds until one satisfies the predicate
+    pub reroll_until: Option<StatsPredicate>,
+    pub reroll_limit: u32,
+    // a maze read from a file takes the place of carving one, with the history it was saved with
+    pub loaded: Option<Grid>,
+    pub loaded_history: Option<MazeHistory>,
+    pub random_rotate: bool,
+    pub uncarve: Option<u32>,
+    pub uncarve_strategy: UncarveStrategy,
+    pub braid: Option<u8>,
+    pub invert: bool,
+    pub viewport: Option<Rect>,
+    pub labels: Vec<CellLabel>,
+    pub entrance: Option<Opening>,
+    pub exit: Option<Opening>,
+    pub entrances: Option<Entrances>,
+    pub solve: Option<SolverType>,
+    pub seed_uncarve: Option<u64>,
+    pub seed_braid: Option<u64>,
+    pub seed_symmetry: Option<u64>,
+    pub seed_openings: Option<u64>,
+    pub seed_doors: Option<u64>,
+    pub seed_corridors: Option<u64>,
+    pub seed_overlay: Option<u64>,
+    // show a bar while carving
+    pub progress: bool,
+    pub cancel: Option<CancelToken>,
+}
+
+// the defaults of the command line
+impl Options {
+    pub fn new(width: u32, height: u32, method: MazeType, seed: u64) -> Self {
+        Self {
+            width,
+            height,
+            method,
+            wrap: MazeWrap::None,
+            seed,
+            scan_order: ScanOrder::default(),
+            bias: GrowingTreeBias::default(),
+            orientation: None,
+            noise: NoiseOptions::default(),
+            weights: None,
+            cost_image: None,
+            border: None,
+            exclude: Vec::new(),
+            mask: None,
+            template: None,
+            template_scale: 1,
+            room_doors: RoomDoors::Auto,
+            overlay: None,
+            overlay_method: MazeType::Backtrack,
+            pattern: None,
+            corridors: None,
+            reroll_until: None,
+            reroll_limit: 1000,
+            loaded: None,
+            loaded_history: None,
+            random_rotate: false,
+            uncarve: None,
+            uncarve_strategy: UncarveStrategy::default(),
+            braid: None,
+            invert: false,
+            viewport: None,
+            labels: Vec::new(),
+            entrance: None,
+            exit: None,
+            entrances: None,
+            solve: None,
+            seed_uncarve: None,
+            seed_braid: None,
+            seed_symmetry: None,
+            seed_openings: None,
+            seed_doors: None,
+            seed_corridors: None,
+            seed_overlay: None,
+            progress: false,
+            cancel: None,
+        }
+    }
+
+    // seeds of every stage of the maze with the given seed, stages with a seed of their own use
+    // it instead
+    pub fn seed_schedule(&self, seed: u64) -> SeedSchedule {
+        let schedule = SeedSchedule::new(seed);
+        SeedSchedule {
+            uncarve: self.seed_uncarve.unwrap_or(schedule.uncarve),
+            braid: self.seed_braid.unwrap_or(schedule.braid),
+            symmetry: self.seed_symmetry.unwrap_or(schedule.symmetry),
+            openings: self.seed_openings.unwrap_or(schedule.openings),
+            doors: self.seed_doors.unwrap_or(schedule.doors),
+            corridors: self.seed_corridors.unwrap_or(schedule.corridors),
+            overlay: self.seed_overlay.unwrap_or(schedule.overlay),
+            ..schedule
+        }
+    }
+
+    fn progress(&self, message: &'static str, len: u64) -> Progress {
+        let progress = if self.progress {
+            Progress::new(message, len)
+        } else {
+            Progress::hidden()
+        };
+        match &self.cancel {
+            Some(cancel) => progress.cancel_on(cancel.clone()),
+            None => progress,
+        }
+    }
+}
+
+// the finished maze with everything the renderers need to draw it
+#[derive(Debug, Clone)]
+pub struct Output {
+    pub maze: Grid,
+    pub history: MazeHistory,
+    // seed of the kept maze, another one than the options give when it was rerolled
+    pub seed: u64,
+    pub start: Point,
+    pub end: Point,
+    // the labels of the options moved along with the viewport, and the ends of Entrances::Longest
+    pub labels: Vec<CellLabel>,
+    pub solution: Option<Solution>,
+    // cells walled up again by uncarve
+    pub uncarved: Option<u32>,
+    // ends of the solution moved off excluded or unreachable cells
+    pub warnings: Vec<String>,
+}
+
+// carves the maze of the options and takes it through every later stage: rotation, uncarving,
+// braiding, inversion, the viewport, openings and the solution
+pub fn generate(options: &Options) -> Result<Output, LabgenError> {
+    let (width, height) = (options.width, options.height);
+    let template = options.template.as_ref();
+    let scale = options.template_scale;
+    if options.room_doors != RoomDoors::Auto && !template.is_some_and(|t| t.has(TemplateCell::Room))
+    {
+        return Err(LabgenError::Usage(
+            "--room-doors can only be combined with a --template or --rooms-file with rooms"
+                .to_string(),
+        ));
+    }
+    if let Some(template) = template {
+        check_excluded_rooms(&options.exclude, template, scale)?;
+        if template.has(TemplateCell::Room) && !options.method.supports_rooms() {
+            return Err(LabgenError::Usage(format!(
+                "{} can not grow a maze around template rooms",
+                options.method
+            )));
+        }
+        // sealed rooms are removed from the grid, which division would open up again
+        if template.has(TemplateCell::Room)
+            && options.room_doors != RoomDoors::Auto
+            && !options.method.supports_exclusions()
+        {
+            return Err(LabgenError::Usage(format!(
+                "{} can only open rooms with --room-doors auto",
+                options.method
+            )));
+        }
+        if template.has(TemplateCell::Excluded) && !options.method.supports_exclusions() {
+            return Err(LabgenError::Usage(format!(
+                "{} can not generate mazes with excluded cells",
+                options.method
+            )));
+        }
+    }
+    let carve = |seed: u64| {
+        let schedule = options.seed_schedule(seed);
+        let progress = options.progress("carving", width as u64 * height as u64);
+        let mut maze = Grid::new(width, height, options.wrap, seed, options.method);
+        maze.params.scan_order = options.scan_order;
+        maze.params.bias = options.bias.clone();
+        maze.params.orientation = options.orientation;
+        maze.params.noise = options.noise;
+        if let Some(shape) = options.border {
+            maze.exclude_outside(shape);
+        }
+        for rect in &options.exclude {
+            maze.exclude(*rect);
+        }
+        if let Some(mask) = &options.mask {
+            apply_mask(&mut maze, mask);
+        }
+        if let Some(template) = template {
+            apply_template(&mut maze, template, scale);
+            if options.room_doors != RoomDoors::Auto {
+                seal_rooms(&mut maze, template, scale);
+            }
+        }
+        if let Some(overlay) = options.overlay {
+            let (coarse_width, coarse_height) = overlay.coarse_size(width, height);
+            let (coarse, _) = generate_maze(
+                coarse_width,
+                coarse_height,
+                options.overlay_method,
+                MazeWrap::None,
+                schedule.overlay,
+            )?;
+            overlay_maze(&mut maze, &coarse, overlay);
+        }
+        if let Some(pattern) = &options.pattern {
+            carve_pattern(&mut maze, pattern);
+        }
+        maze.params.weights = options.weights.clone();
+        maze.costs = match (&options.weights, &options.cost_image) {
+            (Some(WeightSource::Noise), _) => noise_costs(&maze),
+            (_, Some(image)) => image.costs(&maze),
+            _ => Vec::new(),
+        };
+        let (mut maze, mut history) = carve_maze(maze, &progress);
+        progress.finish();
+        if progress.cancelled() {
+            return Err(LabgenError::Cancelled);
+        }
+        if let Some(template) = template.filter(|_| options.room_doors != RoomDoors::Auto) {
+            let doors = match &options.room_doors {
+                RoomDoors::List(doors) => doors.clone(),
+                RoomDoors::Even { count, sides } => {
+                    even_doors(&maze, template, scale, *count, sides, schedule.doors)
+                }
+                _ => Vec::new(),
+            };
+            let opened =
+                place_doors(&mut maze, template, scale, &doors).map_err(LabgenError::Usage)?;
+            history
+                .steps
+                .extend(opened.into_iter().map(MazeAction::from));
+        }
+        if let Some(percent) = options.corridors {
+            let rooms = match template {
+                Some(template) => room_cells(&maze, template, scale),
+                None => Vec::new(),
+            };
+            maze.thin_corridors(percent, &rooms, schedule.corridors);
+        }
+        Ok((maze, history))
+    };
+    let mut seed = options.seed;
+    let (mut maze, mut history) = match &options.loaded {
+        Some(maze) => (
+            maze.clone(),
+            options
+                .loaded_history
+                .clone()
+                .unwrap_or_else(|| MazeHistory::new(maze, Vec::new())),
+        ),
+        None => generate_matching(
+            carve,
+            &mut seed,
+            options.reroll_until.as_ref(),
+            options.reroll_limit,
+        )?,
+    };
+    // stages draw from their own streams, so a stage seed only changes its own stage
+    let schedule = options.seed_schedule(seed);
+
+    if options.random_rotate {
+        let mut rng = StdRng::seed_from_u64(schedule.symmetry);
+        let symmetry = Symmetry::random(maze.width, maze.height, &mut rng);
+        maze = maze.transform(symmetry);
+        history = history.transform(symmetry);
+    }
+    let uncarved = options
+        .uncarve
+        .map(|cells| maze.uncarve(cells, options.uncarve_strategy, schedule.uncarve));
+    if let Some(percent) = options.braid {
+        // played after the generation so animations show the walls being opened
+        let braided = maze.braid(percent, schedule.braid);
+        history
+            .steps
+            .extend(braided.into_iter().map(MazeAction::from));
+    }
+    if options.invert {
+        maze = maze.invert();
+    }
+    let mut labels = options.labels.clone();
+    if let Some(viewport) = options.viewport {
+        maze = maze.crop(viewport).ok_or_else(|| {
+            LabgenError::Geometry(format!(
+                "viewport {} does not fit inside a {}x{} maze",
+                viewport, width, height
+            ))
+        })?;
+        history = history.crop(viewport);
+        let origin = Point::new(-(viewport.x as i32), -(viewport.y as i32));
+        labels = labels
+            .into_iter()
+            .filter(|l| viewport.contains(l.pt))
+            .map(|l| CellLabel {
+                pt: l.pt + origin,
+                ..l
+            })
+            .collect();
+    }
+
+    // random openings get their own rng so they leave the maze unchanged
+    let mut rng = StdRng::seed_from_u64(schedule.openings);
+    let mut open = |opening: Option<Opening>| -> Result<Option<Point>, LabgenError> {
+        let Some(opening) = opening else {
+            return Ok(None);
+        };
+        let (pt, dir) = maze.opening_cell(opening, &mut rng).ok_or_else(|| {
+            LabgenError::Geometry(format!(
+                "opening {} does not lie on a walled border cell of the {}x{} maze",
+                opening, maze.width, maze.height
+            ))
+        })?;
+        maze.get_tile_mut(pt).connect(dir);
+        history.steps.push(MazeAction::Carve(pt, dir));
+        Ok(Some(pt))
+    };
+    let mut start = open(options.entrance)?.unwrap_or(Point::new(0, 0));
+    let mut end =
+        open(options.exit)?.unwrap_or(Point::new(maze.width as i32 - 1, maze.height as i32 - 1));
+    if options.entrances == Some(Entrances::Longest) {
+        if let Some((a, b, _)) = farthest_cells(&maze) {
+            (start, end) = (a, b);
+            labels.push(CellLabel {
+                pt: start,
+                text: "S".to_string(),
+            });
+            labels.push(CellLabel {
+                pt: end,
+                text: "E".to_string(),
+            });
+        }
+    }
+    let mut warnings = Vec::new();
+    let mut solution = None;
+    if let Some(solver) = options.solve {
+        (start, end) = solution_ends(&maze, start, end, &mut warnings)?;
+        solution = Some(solve(&maze, start, end, solver).ok_or_else(|| {
+            LabgenError::Geometry(format!("there is no path from {} to {}", start, end))
+        })?);
+    }
+
+    Ok(Output {
+        maze,
+        history,
+        seed,
+        start,
+        end,
+        labels,
+        solution,
+        uncarved,
+        warnings,
+    })
+}
+
+// carves the polar maze of the options, only their height, method, seed and bias apply to it
+pub fn generate_polar(options: &Options) -> Result<PolarGrid, LabgenError> {
+    let mut maze = PolarGrid::new(options.height as u16, options.seed, options.method);
+    maze.bias = options.bias.clone();
+    let progress = options.progress("carving", maze.tiles.len() as u64);
+    let maze = carve_polar(maze, &progress);
+    progress.finish();
+    if progress.cancelled() {
+        return Err(LabgenError::Cancelled);
+    }
+    Ok(maze)
+}
+
+// the solution runs between opposite corners unless --entrance, --exit or --entrances say
+// otherwise, and those corners can be excluded or, for the end, cut off from the start by rooms
+// or exclusions. the nearest cell the solution can use is taken in their place with a warning, a
+// maze without any has nothing to solve
+fn solution_ends(
+    maze: &Grid,
+    start: Point,
+    end: Point,
+    warnings: &mut Vec<String>,
+) -> Result<(Point, Point), LabgenError> {
+    let mut warn = |pt: Point, name: &str, reason: &str, used: Point| {
+        warnings.push(format!(
+            "the {} {} {}, the solution uses {} instead",
+            name, pt, reason, used
+        ))
+    };
+    let start = match nearest_cell(maze, start, |pt| !maze.is_removed(pt)) {
+        Some(used) if used == start => start,
+        Some(used) => {
+            warn(start, "start", "is excluded", used);
+            used
+        }
+        None => {
+            return Err(LabgenError::Geometry(format!(
+                "every cell of the {}x{} maze is excluded, there is no path to solve",
+                maze.width, maze.height
+            )))
+        }
+    };
+    let reached = distance_field(maze, start, |_| 1);
+    let reachable = |pt: Point| reached.cost[maze.get_index(pt)].is_some();
+    if reachable(end) {
+        return Ok((start, end));
+    }
+    let used = nearest_cell(maze, end, |pt| pt != start && reachable(pt)).ok_or_else(|| {
+        LabgenError::Geometry(format!(
+            "no other cell can be reached from the start {}, there is no path to solve",
+            start
+        ))
+    })?;
+    let reason = match maze.is_removed(end) {
+        true => "is excluded",
+        false => "can not be reached from the start",
+    };
+    warn(end, "end", reason, used);
+    Ok((start, used))
+}
+
+// the cell closest to pt for which keep holds, pt itself if it does
+fn nearest_cell(maze: &Grid, pt: Point, keep: impl Fn(Point) -> bool) -> Option<Point> {
+    let distance = |other: &Point| {
+        let (dx, dy) = ((other.x - pt.x) as i64, (other.y - pt.y) as i64);
+        dx * dx + dy * dy
+    };
+    (0..maze.height as i32)
+        .flat_map(|y| (0..maze.width as i32).map(move |x| Point::new(x, y)))
+        .filter(|other| keep(*other))
+        .min_by_key(distance)
+}
+
+// generates mazes until one satisfies the predicate, the first uses the given seed and every
+// further attempt a seed drawn from an rng seeded with it. seed is set to the one of the kept maze
+pub fn generate_matching(
+    generate: impl Fn(u64) -> Result<(Grid, MazeHistory), LabgenError>,
+    seed: &mut u64,
+    predicate: Option<&StatsPredicate>,
+    limit: u32,
+) -> Result<(Grid, MazeHistory), LabgenError> {
+    let (mut maze, mut history) = generate(*seed)?;
+    let Some(predicate) = predicate else {
+        return Ok((maze, history));
+    };
+
+    let mut seeds = StdRng::seed_from_u64(*seed);
+    for attempt in 1.. {
+        if predicate.matches(&MazeStats::new(&maze, &history.steps)) {
+            break;
+        }
+        if attempt >= limit {
+            return Err(LabgenError::NoMatch(format!(
+                "no maze satisfied \"{}\" in {} attempts",
+                predicate, limit
+            )));
+        }
+        *seed = seeds.gen();
+        (maze, history) = generate(*seed)?;
+    }
+
+    Ok((maze, history))
+}
+
+// rooms are opened into the maze, excluding any of their cells would cut them apart
+fn check_excluded_rooms(
+    rects: &[Rect],
+    template: &Template,
+    scale: u32,
+) -> Result<(), LabgenError> {
+    for rect in rects {
+        for y in rect.y..rect.y + rect.height {
+            for x in rect.x..rect.x + rect.width {
+                let pt = Point::new(x as i32, y as i32);
+                if template.cell_at(pt, scale) == TemplateCell::Room {
+                    return Err(LabgenError::Geometry(format!(
+                        "--exclude {} covers the room cell at {}",
+                        rect, pt
+                    )));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn default_options_carve_like_generate_maze() {
+        let (maze, history) = generate_maze(9, 7, MazeType::Wilson, MazeWrap::None, 3).unwrap();
+        let output = generate(&Options::new(9, 7, MazeType::Wilson, 3)).unwrap();
+        assert_eq!(output.maze.tiles, maze.tiles);
+        assert_eq!(output.history.steps, history.steps);
+        assert_eq!(
+            (output.start, output.end),
+            (Point::new(0, 0), Point::new(8, 6))
+        );
+        assert_eq!(output.solution, None);
+
+        let options = Options {
+            solve: Some(SolverType::Bfs),
+            ..Options::new(9, 7, MazeType::Wilson, 3)
+        };
+        let solution = generate(&options).unwrap().solution.unwrap();
+        assert_eq!(solution.path.first(), Some(&Point::new(0, 0)));
+        assert_eq!(solution.path.last(), Some(&Point::new(8, 6)));
+    }
+
+    // counts the mazes generated while looking for one that satisfies predicate
+    fn attempts(predicate: &str, seed: &mut u64) -> (Result<(), LabgenError>, u32) {
+        let generated = Cell::new(0);
+        let generate = |seed| {
+            generated.set(generated.get() + 1);
+            Ok(generate_maze(4, 4, MazeType::Wilson, MazeWrap::None, seed)?)
+        };
+        let predicate: StatsPredicate = predicate.parse().unwrap();
+        let found = generate_matching(generate, seed, Some(&predicate), 5);
+        (found.map(|_| ()), generated.get())
+    }
+
+    #[test]
+    fn unmatched_predicates_give_up_after_the_limit() {
+        let mut seed = 7;
+        let (found, generated) = attempts("deadends<0", &mut seed);
+        let error = found.unwrap_err();
+        assert_eq!(error.exit_code(), 6);
+        assert_eq!(
+            error.to_string(),
+            "no maze satisfied \"deadends<0\" in 5 attempts"
+        );
+        assert_eq!(generated, 5);
+    }
+
+    #[test]
+    fn matching_mazes_keep_their_seed() {
+        let mut seed = 7;
+        let (found, generated) = attempts("deadends>=0", &mut seed);
+        assert!(found.is_ok());
+        assert_eq!((seed, generated), (7, 1));
+    }
+
+    #[test]
+    fn excluded_corners_are_solved_from_the_nearest_cell() {
+        let (mut maze, _) = generate_maze(4, 3, MazeType::Kruskal, MazeWrap::None, 1).unwrap();
+        maze.exclude(Rect {
+            x: 0,
+            y: 0,
+            width: 2,
+            height: 1,
+        });
+        let end = Point::new(3, 2);
+        let mut warnings = Vec::new();
+        let ends = solution_ends(&maze, Point::new(0, 0), end, &mut warnings).unwrap();
+        assert_eq!(ends, (Point::new(0, 1), end));
+        assert_eq!(
+            warnings,
+            ["the start 0,0 is excluded, the solution uses 0,1 instead"]
+        );
+
+        maze.exclude(Rect {
+            x: 0,
+            y: 0,
+            width: 4,
+            height: 3,
+        });
+        let error = solution_ends(&maze, Point::new(0, 0), end, &mut Vec::new()).unwrap_err();
+        assert_eq!(error.exit_code(), 3);
+    }
+
+    #[test]
+    fn unreachable_ends_are_moved_next_to_the_start() {
+        // a column of excluded cells cuts the maze in two
+        let mut maze = Grid::new(5, 3, MazeWrap::None, 2, MazeType::Kruskal);
+        maze.exclude(Rect {
+            x: 2,
+            y: 0,
+            width: 1,
+            height: 3,
+        });
+        let (maze, _) = carve_maze(maze, &Progress::hidden());
+        let (start, end) =
+            solution_ends(&maze, Point::new(0, 0), Point::new(4, 2), &mut Vec::new()).unwrap();
+        assert_eq!((start, end), (Point::new(0, 0), Point::new(1, 2)));
+        assert!(solve(&maze, start, Point::new(4, 2), SolverType::Bfs).is_none());
+        assert!(solve(&maze, start, end, SolverType::Bfs).is_some());
+
+        // a start walled in on its own leaves nothing to solve
+        let mut maze = Grid::new(3, 1, MazeWrap::None, 2, MazeType::Kruskal);
+        maze.exclude(Rect {
+            x: 1,
+            y: 0,
+            width: 1,
+            height: 1,
+        });
+        let (maze, _) = carve_maze(maze, &Progress::hidden());
+        let error =
+            solution_ends(&maze, Point::new(0, 0), Point::new(2, 0), &mut Vec::new()).unwrap_err();
+        assert_eq!(error.exit_code(), 3);
+    }
+}
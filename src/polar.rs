@@ -0,0 +1,221 @@
+use crate::maze::{GrowingTreeBias, MazeType};
+use crate::progress::Progress;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::f32::consts::TAU;
+
+// shape of the cells a maze is made of
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum CellShape {
+    /// rows and columns of square cells
+    #[default]
+    Square,
+    /// rings of cells around a single center cell
+    Polar,
+}
+
+// cells of the first ring around the center
+const FIRST_RING: u32 = 6;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PolarTile {
+    // passage to the following cell of the same ring, clockwise in images
+    pub next: bool,
+    // passage to the cell of the ring inside this one
+    pub inward: bool,
+}
+
+// maze of cells in concentric rings around a center cell. a ring is split into twice as many
+// cells as the one inside it once its cells would otherwise be more than twice as wide as tall
+#[derive(Debug, Clone)]
+pub struct PolarGrid {
+    pub ring_sizes: Vec<u32>,
+    // index of the first cell of each ring in tiles
+    pub ring_starts: Vec<usize>,
+    pub tiles: Vec<PolarTile>,
+    pub seed: u64,
+    pub method: MazeType,
+    pub bias: GrowingTreeBias,
+}
+
+impl PolarGrid {
+    pub fn new(rings: u16, seed: u64, method: MazeType) -> Self {
+        let mut ring_sizes: Vec<u32> = Vec::with_capacity(rings as usize);
+        for ring in 0..rings as u32 {
+            let size = match ring {
+                0 => 1,
+                1 => FIRST_RING,
+                _ => {
+                    let inner = ring_sizes[ring as usize - 1];
+                    // width of the cells in cell heights if the ring kept the inner count
+                    if TAU * ring as f32 / inner as f32 >= 2.0 {
+                        inner * 2
+                    } else {
+                        inner
+                    }
+                }
+            };
+            ring_sizes.push(size);
+        }
+        let ring_starts: Vec<usize> = ring_sizes
+            .iter()
+            .scan(0, |start, size| {
+                let first = *start;
+                *start += *size as usize;
+                Some(first)
+            })
+            .collect();
+
+        Self {
+            tiles: vec![PolarTile::default(); ring_sizes.iter().sum::<u32>() as usize],
+            ring_sizes,
+            ring_starts,
+            seed,
+            method,
+            bias: GrowingTreeBias::default(),
+        }
+    }
+
+    pub fn rings(&self) -> usize {
+        self.ring_sizes.len()
+    }
+
+    pub fn index(&self, ring: usize, cell: u32) -> usize {
+        self.ring_starts[ring] + cell as usize
+    }
+
+    // ring and position within the ring of a cell index
+    pub fn cell(&self, index: usize) -> (usize, u32) {
+        let ring = self.ring_starts.partition_point(|start| *start <= index) - 1;
+        (ring, (index - self.ring_starts[ring]) as u32)
+    }
+
+    // cell of the ring inside this one that a cell opens into
+    pub fn parent(&self, ring: usize, cell: u32) -> u32 {
+        (cell as u64 * self.ring_sizes[ring - 1] as u64 / self.ring_sizes[ring] as u64) as u32
+    }
+
+    // every cell sharing a wall with the cell
+    pub fn neighbors(&self, index: usize) -> Vec<usize> {
+        let (ring, cell) = self.cell(index);
+        let size = self.ring_sizes[ring];
+        let mut neighbors = Vec::new();
+
+        if size > 1 {
+            neighbors.push(self.index(ring, (cell + 1) % size));
+            neighbors.push(self.index(ring, (cell + size - 1) % size));
+        }
+        if ring > 0 {
+            neighbors.push(self.index(ring - 1, self.parent(ring, cell)));
+        }
+        if ring + 1 < self.rings() {
+            let outer = self.ring_sizes[ring + 1];
+            let per_cell = outer / size;
+            for i in 0..per_cell {
+                neighbors.push(self.index(ring + 1, cell * per_cell + i));
+            }
+        }
+
+        neighbors.sort_unstable();
+        neighbors.dedup();
+        neighbors
+    }
+
+    // opens the wall between two neighboring cells
+    pub fn connect(&mut self, a: usize, b: usize) {
+        let ((ring_a, cell_a), (ring_b, cell_b)) = (self.cell(a), self.cell(b));
+        if ring_a == ring_b {
+            let size = self.ring_sizes[ring_a];
+            if (cell_a + 1) % size == cell_b {
+                self.tiles[a].next = true;
+            } else {
+                self.tiles[b].next = true;
+            }
+        } else if ring_a > ring_b {
+            self.tiles[a].inward = true;
+        } else {
+            self.tiles[b].inward = true;
+        }
+    }
+}
+
+// only the methods that grow a tree from a single cell work on rings
+pub fn supports_polar(method: MazeType) -> bool {
+    matches!(method, MazeType::Backtrack | MazeType::GrowingTree)
+}
+
+// carves a maze into the rings with the grid's method, seeding the rng with the grid's seed.
+// backtrack always continues from the newest cell, growing tree uses the same bias as on square
+// grids
+pub fn carve_polar(mut maze: PolarGrid, progress: &Progress) -> PolarGrid {
+    let mut rng: StdRng = StdRng::seed_from_u64(maze.seed);
+    let bias = match maze.method {
+        MazeType::GrowingTree => maze.bias.clone(),
+        _ => GrowingTreeBias::Newest,
+    };
+
+    let mut visited = vec![false; maze.tiles.len()];
+    let start = rng.gen_range(0..maze.tiles.len());
+    visited[start] = true;
+    progress.inc();
+    let mut open: Vec<usize> = vec![start];
+
+    while !open.is_empty() {
+        if progress.cancelled() {
+            break;
+        }
+        let selected_index = bias.select(open.len(), &mut rng);
+        let selected = open[selected_index];
+        let unvisited: Vec<usize> = maze
+            .neighbors(selected)
+            .into_iter()
+            .filter(|n| !visited[*n])
+            .collect();
+
+        if unvisited.is_empty() {
+            open.remove(selected_index);
+            continue;
+        }
+        let next = unvisited[rng.gen_range(0..unvisited.len())];
+        maze.connect(selected, next);
+        visited[next] = true;
+        open.push(next);
+        progress.inc();
+    }
+
+    maze
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arbitrary::MAX_SIZE;
+    use proptest::{prelude::*, sample::select};
+
+    proptest! {
+        #[test]
+        fn polar_mazes_are_trees(
+            rings in 1..=MAX_SIZE,
+            method in select(vec![MazeType::Backtrack, MazeType::GrowingTree]),
+            seed in any::<u64>(),
+        ) {
+            let maze = carve_polar(PolarGrid::new(rings as u16, seed, method), &Progress::hidden());
+            let passages: usize = maze
+                .tiles
+                .iter()
+                .map(|t| t.next as usize + t.inward as usize)
+                .sum();
+            prop_assert_eq!(passages, maze.tiles.len() - 1);
+            // the center cell has no ring inside it and no other cell in its own ring
+            for (i, tile) in maze.tiles.iter().enumerate() {
+                let (ring, _) = maze.cell(i);
+                prop_assert!(!(ring == 0 && (tile.inward || tile.next)));
+            }
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::{
+    fmt,
+    io::{self, IsTerminal},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+// a bar only appears once its task has been running for this long
+const SHOW_AFTER: Duration = Duration::from_secs(2);
+// steps are counted locally and handed to the bar in chunks of at most this size, updating the
+// bar on every step would slow down generation of large mazes
+const MAX_CHUNK: u64 = 1024;
+
+// called with the steps done and the steps in total, for programs showing progress their own way
+#[derive(Clone)]
+pub struct ProgressFn(pub Arc<dyn Fn(u64, u64) + Send + Sync>);
+
+impl ProgressFn {
+    pub fn new(f: impl Fn(u64, u64) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(f))
+    }
+}
+
+impl fmt::Debug for ProgressFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressFn")
+    }
+}
+
+impl PartialEq for ProgressFn {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+// shared flag asking long running work to stop early, set from another thread or a signal
+// handler. carving stops with the maze unfinished and animations end on the frame they reached,
+// still written as a complete file
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(pub Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl PartialEq for CancelToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+// progress bar for long running work drawn on stderr. it stays hidden for tasks that finish
+// quickly and when stderr is not a terminal, so piped output and logs are left alone
+pub struct Progress {
+    bar: ProgressBar,
+    count: AtomicU64,
+    len: u64,
+    chunk: u64,
+    // only taken for bars that may be drawn, there is no clock to read on wasm
+    start: Option<Instant>,
+    enabled: bool,
+    // told about every chunk of steps, whether the bar is drawn or not
+    callback: Option<ProgressFn>,
+    cancel: Option<CancelToken>,
+}
+
+impl Progress {
+    pub fn new(message: &'static str, len: u64) -> Self {
+        let bar = ProgressBar::with_draw_target(Some(len), ProgressDrawTarget::hidden());
+        bar.set_style(
+            ProgressStyle::with_template("{msg:>10} [{bar:40}] {human_pos}/{human_len} ({eta})")
+                .unwrap()
+                .progress_chars("=> "),
+        );
+        bar.set_message(message);
+
+        Self {
+            bar,
+            count: AtomicU64::new(0),
+            len,
+            chunk: (len / 1000).clamp(1, MAX_CHUNK),
+            start: Some(Instant::now()),
+            enabled: io::stderr().is_terminal(),
+            callback: None,
+            cancel: None,
+        }
+    }
+
+    // progress that is never drawn, only handed to callback
+    pub fn with_callback(len: u64, callback: ProgressFn) -> Self {
+        Self {
+            len,
+            chunk: (len / 1000).clamp(1, MAX_CHUNK),
+            callback: Some(callback),
+            ..Self::hidden()
+        }
+    }
+
+    // also hands the progress to callback, next to the bar
+    pub fn report_to(self, callback: ProgressFn) -> Self {
+        Self {
+            callback: Some(callback),
+            ..self
+        }
+    }
+
+    // lets the work stop early once cancel is set
+    pub fn cancel_on(self, cancel: CancelToken) -> Self {
+        Self {
+            cancel: Some(cancel),
+            ..self
+        }
+    }
+
+    // checked by the work between its steps
+    pub fn cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.is_cancelled())
+    }
+
+    // progress that is never drawn
+    pub fn hidden() -> Self {
+        Self {
+            bar: ProgressBar::hidden(),
+            count: AtomicU64::new(0),
+            len: 0,
+            chunk: MAX_CHUNK,
+            start: None,
+            enabled: false,
+            callback: None,
+            cancel: None,
+        }
+    }
+
+    pub fn inc(&self) {
+        if !self.enabled && self.callback.is_none() {
+            return;
+        }
+
+        let count = self.count.fetch_add(1, Ordering::Relaxed) + 1;
+        if !count.is_multiple_of(self.chunk) {
+            return;
+        }
+        if let Some(callback) = &self.callback {
+            (callback.0)(count, self.len);
+        }
+        if self.enabled {
+            self.bar.set_position(count);
+            if self.bar.is_hidden()
+                && self
+                    .start
+                    .is_some_and(|start| start.elapsed() >= SHOW_AFTER)
+            {
+                self.bar.set_draw_target(ProgressDrawTarget::stderr());
+            }
+        }
+    }
+
+    // removes the bar so it does not mix with the summary printed afterwards. the callback is told
+    // once more with every step done, as the last chunk is usually not full
+    pub fn finish(&self) {
+        if let Some(callback) = &self.callback {
+            (callback.0)(self.count.load(Ordering::Relaxed), self.len);
+        }
+        self.bar.finish_and_clear();
+    }
+}
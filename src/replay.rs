@@ -0,0 +1,140 @@
+use crate::{
+    error::LabgenError,
+    maze::{Grid, MazeAction, MazeHistory, Rect},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+};
+
+// start of every history file, followed by the version of its layout
+const MAGIC: &[u8; 8] = b"labgenhi";
+const VERSION: u8 = 2;
+
+// a finished maze with the steps that carved it, so it can be drawn and animated again with other
+// image options without generating it again
+#[derive(Debug, Serialize, Deserialize)]
+struct HistoryFile {
+    maze: Grid,
+    history: MazeHistory,
+}
+
+// writes the maze and its history as bincode
+pub fn render_history(
+    maze: &Grid,
+    history: &MazeHistory,
+    out: &mut dyn Write,
+) -> Result<(), LabgenError> {
+    out.write_all(MAGIC)?;
+    out.write_all(&[VERSION])?;
+    let file = HistoryFile {
+        maze: maze.clone(),
+        history: history.clone(),
+    };
+    bincode::serialize_into(&mut *out, &file).map_err(|e| match *e {
+        bincode::ErrorKind::Io(e) => LabgenError::Io(e),
+        e => LabgenError::Render(e.to_string()),
+    })
+}
+
+// written to file_path as given, it is named by the user rather than derived from the image
+pub fn generate_history(
+    maze: &Grid,
+    history: &MazeHistory,
+    file_path: &str,
+) -> Result<(), LabgenError> {
+    let file = File::create(file_path)
+        .map_err(|e| io::Error::new(e.kind(), format!("could not create {}: {}", file_path, e)))?;
+    let mut out = BufWriter::new(file);
+    render_history(maze, history, &mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+// reads a maze and history written by render_history. the tiles must fit the size of the maze,
+// the history may disagree with the maze when it was changed after carving, like by --invert
+pub fn read_history(mut input: impl Read) -> Result<(Grid, MazeHistory), String> {
+    let mut header = [0; 9];
+    input
+        .read_exact(&mut header)
+        .map_err(|_| "not a labgen history file".to_string())?;
+    if &header[..8] != MAGIC {
+        return Err("not a labgen history file".to_string());
+    }
+    if header[8] != VERSION {
+        return Err(format!(
+            "history file version {} is not supported, expected {}",
+            header[8], VERSION
+        ));
+    }
+    let file: HistoryFile = bincode::deserialize_from(input).map_err(|e| e.to_string())?;
+    let maze = file.maze;
+    if maze.width == 0 || maze.height == 0 {
+        return Err(format!(
+            "a {}x{} maze has no cells",
+            maze.width, maze.height
+        ));
+    }
+    if maze.tiles.len() != maze.width as usize * maze.height as usize {
+        return Err(format!(
+            "a {}x{} maze needs {} tiles but {} are given",
+            maze.width,
+            maze.height,
+            maze.width as usize * maze.height as usize,
+            maze.tiles.len()
+        ));
+    }
+    let history = file.history;
+    let field = Rect {
+        x: 0,
+        y: 0,
+        width: maze.width,
+        height: maze.height,
+    };
+    let fits = |step: &MazeAction| match step {
+        MazeAction::Clear(rect) | MazeAction::Scan(rect) => rect.intersect(field) == Some(*rect),
+        _ => maze.contains(step.cell()),
+    };
+    if (history.width, history.height) != (maze.width, maze.height)
+        || !history.steps.iter().all(fits)
+    {
+        return Err("the history does not fit its maze".to_string());
+    }
+    Ok((maze, history))
+}
+
+// whether the file starts like a history file, other files are read as json mazes
+pub fn is_history_file(file_path: &str) -> Result<bool, LabgenError> {
+    let file = File::open(file_path)
+        .map_err(|e| io::Error::new(e.kind(), format!("could not read {}: {}", file_path, e)))?;
+    let mut header = Vec::new();
+    file.take(MAGIC.len() as u64).read_to_end(&mut header)?;
+    Ok(header == MAGIC)
+}
+
+pub fn load_history(file_path: &str) -> Result<(Grid, MazeHistory), LabgenError> {
+    let file = File::open(file_path)
+        .map_err(|e| io::Error::new(e.kind(), format!("could not read {}: {}", file_path, e)))?;
+    read_history(BufReader::new(file))
+        .map_err(|e| LabgenError::Usage(format!("{}: {}", file_path, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arbitrary::maze_with_history;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn history_files_read_back((maze, history) in maze_with_history()) {
+            let mut file = Vec::new();
+            render_history(&maze, &history, &mut file).unwrap();
+            let (read_maze, read) = read_history(&file[..]).unwrap();
+            prop_assert_eq!(&read_maze.tiles, &maze.tiles);
+            prop_assert_eq!(read_maze.metadata(), maze.metadata());
+            prop_assert_eq!(read, history);
+        }
+    }
+}
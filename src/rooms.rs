@@ -0,0 +1,336 @@
+use crate::{
+    maze::{Direction, Point, Rect},
+    template::{Template, TemplateCell},
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+// outline of a room, written as rect:x,y,width,height, circle:x,y,radius around the center of a
+// cell, or polygon:x,y;x,y;... with at least three corners. polygon corners lie on the grid lines,
+// x,y being the top left corner of cell x,y. a room covers every cell whose center it contains
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
+pub enum RoomShape {
+    Rect(Rect),
+    Circle { center: Point, radius: u32 },
+    Polygon(Vec<Point>),
+}
+
+impl RoomShape {
+    // left, top, right and bottom edge of the smallest rect covering every cell of the shape,
+    // right and bottom lying just past its last cells
+    pub fn extent(&self) -> [i64; 4] {
+        match self {
+            RoomShape::Rect(rect) => [
+                rect.x as i64,
+                rect.y as i64,
+                rect.x as i64 + rect.width as i64,
+                rect.y as i64 + rect.height as i64,
+            ],
+            RoomShape::Circle { center, radius } => {
+                let (x, y, r) = (center.x as i64, center.y as i64, *radius as i64);
+                [x - r, y - r, x + r + 1, y + r + 1]
+            }
+            RoomShape::Polygon(corners) => {
+                let xs = corners.iter().map(|pt| pt.x as i64);
+                let ys = corners.iter().map(|pt| pt.y as i64);
+                [
+                    xs.clone().min().unwrap(),
+                    ys.clone().min().unwrap(),
+                    xs.max().unwrap(),
+                    ys.max().unwrap(),
+                ]
+            }
+        }
+    }
+
+    pub fn contains(&self, pt: Point) -> bool {
+        match self {
+            RoomShape::Rect(rect) => rect.contains(pt),
+            RoomShape::Circle { center, radius } => {
+                let (dx, dy) = (pt.x as i64 - center.x as i64, pt.y as i64 - center.y as i64);
+                let r = *radius as i64;
+                // rounder than r * r, which leaves single cells sticking out at the four ends
+                dx * dx + dy * dy <= r * r + r
+            }
+            RoomShape::Polygon(corners) => {
+                // even-odd rule, casting a ray from the center of the cell toward the right
+                let (px, py) = (pt.x as f64 + 0.5, pt.y as f64 + 0.5);
+                let mut inside = false;
+                for (i, a) in corners.iter().enumerate() {
+                    let b = corners[(i + 1) % corners.len()];
+                    let (ax, ay, bx, by) = (a.x as f64, a.y as f64, b.x as f64, b.y as f64);
+                    if (ay > py) != (by > py) && px < ax + (py - ay) * (bx - ax) / (by - ay) {
+                        inside = !inside;
+                    }
+                }
+                inside
+            }
+        }
+    }
+
+    // cells covered by the shape, row by row
+    pub fn cells(&self) -> Vec<Point> {
+        let [left, top, right, bottom] = self.extent();
+        let clamp = |v: i64| v.clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+        (clamp(top)..clamp(bottom))
+            .flat_map(|y| (clamp(left)..clamp(right)).map(move |x| Point::new(x, y)))
+            .filter(|pt| self.contains(*pt))
+            .collect()
+    }
+}
+
+impl FromStr for RoomShape {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| format!("invalid room shape \"{}\": {}", s, reason);
+        let numbers = |v: &str| {
+            v.split(',')
+                .map(|n| n.trim().parse::<u32>())
+                .collect::<Result<Vec<u32>, _>>()
+                .map_err(|e| invalid(&e.to_string()))
+        };
+        // coordinates are kept within the points of the grid
+        let point = |x: u32, y: u32| match (i32::try_from(x), i32::try_from(y)) {
+            (Ok(x), Ok(y)) => Ok(Point::new(x, y)),
+            _ => Err(invalid("coordinates can be at most 2147483647")),
+        };
+
+        match s.split_once(':') {
+            Some(("rect", rect)) => rect.parse().map(RoomShape::Rect),
+            Some(("circle", circle)) => match numbers(circle)?[..] {
+                [x, y, radius] => Ok(RoomShape::Circle {
+                    center: point(x, y)?,
+                    radius,
+                }),
+                _ => Err(invalid("expected circle:x,y,radius")),
+            },
+            Some(("polygon", polygon)) => {
+                let corners = polygon
+                    .split(';')
+                    .map(|corner| match numbers(corner)?[..] {
+                        [x, y] => point(x, y),
+                        _ => Err(invalid("expected polygon:x,y;x,y;x,y;...")),
+                    })
+                    .collect::<Result<Vec<Point>, String>>()?;
+                if corners.len() < 3 {
+                    return Err(invalid("a polygon needs at least three corners"));
+                }
+                Ok(RoomShape::Polygon(corners))
+            }
+            _ => Err(invalid(
+                "expected rect:x,y,width,height, circle:x,y,radius or polygon:x,y;x,y;x,y;...",
+            )),
+        }
+    }
+}
+
+impl fmt::Display for RoomShape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoomShape::Rect(rect) => write!(f, "rect:{}", rect),
+            RoomShape::Circle { center, radius } => write!(f, "circle:{},{}", center, radius),
+            RoomShape::Polygon(corners) => {
+                let corners: Vec<String> = corners.iter().map(|pt| pt.to_string()).collect();
+                write!(f, "polygon:{}", corners.join(";"))
+            }
+        }
+    }
+}
+
+impl TryFrom<String> for RoomShape {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<RoomShape> for String {
+    fn from(shape: RoomShape) -> Self {
+        shape.to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Room {
+    // only used to point out the room in error messages
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub name: Option<String>,
+    pub shape: RoomShape,
+}
+
+impl fmt::Display for Room {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "room \"{}\" ({})", name, self.shape),
+            None => write!(f, "room {}", self.shape),
+        }
+    }
+}
+
+// rooms of a rooms file, a toml file with a [[room]] table for every room:
+//
+//   [[room]]
+//   name = "hall"
+//   shape = "rect:2,2,6,4"
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Rooms {
+    #[cfg_attr(feature = "serde", serde(default, rename = "room"))]
+    pub rooms: Vec<Room>,
+}
+
+#[cfg(feature = "serde")]
+impl FromStr for Rooms {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        toml::from_str(s).map_err(|e| e.to_string())
+    }
+}
+
+impl Rooms {
+    // every room must cover at least one cell of a width x height maze and none outside of it,
+    // with all of its cells joined into a single area. rooms may not overlap or touch, touching
+    // rooms would open into each other and become one
+    pub fn check(&self, width: u32, height: u32) -> Result<(), String> {
+        let mut owner: Vec<Option<usize>> = vec![None; width as usize * height as usize];
+        let index = |pt: Point| pt.x as usize + pt.y as usize * width as usize;
+        let inside =
+            |pt: Point| pt.x >= 0 && pt.y >= 0 && pt.x < width as i32 && pt.y < height as i32;
+
+        for (i, room) in self.rooms.iter().enumerate() {
+            let [left, top, right, bottom] = room.shape.extent();
+            if left < 0 || top < 0 || right > width as i64 || bottom > height as i64 {
+                return Err(format!(
+                    "{} lies outside of a {}x{} maze",
+                    room, width, height
+                ));
+            }
+            let cells = room.shape.cells();
+            if cells.is_empty() {
+                return Err(format!("{} covers no cells", room));
+            }
+            for pt in &cells {
+                if let Some(other) = owner[index(*pt)] {
+                    return Err(format!("{} overlaps {} at {}", room, self.rooms[other], pt));
+                }
+                owner[index(*pt)] = Some(i);
+            }
+            if reachable(&owner, width, height, cells[0]) != cells.len() {
+                return Err(format!(
+                    "{} falls apart into separate parts, it is too thin in places",
+                    room
+                ));
+            }
+        }
+
+        for y in 0..height as i32 {
+            for x in 0..width as i32 {
+                let pt = Point::new(x, y);
+                for dir in [Direction::East, Direction::South] {
+                    let next = pt.travel(dir);
+                    if !inside(next) {
+                        continue;
+                    }
+                    if let (Some(a), Some(b)) = (owner[index(pt)], owner[index(next)]) {
+                        if a != b {
+                            return Err(format!(
+                                "{} touches {} at {}, rooms need maze cells between them",
+                                self.rooms[a], self.rooms[b], pt
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // template of a width x height maze with the rooms as room cells, to be applied with a scale
+    // of 1. cells of rooms outside of the maze are left out
+    pub fn template(&self, width: u32, height: u32) -> Template {
+        let mut cells = vec![TemplateCell::Maze; width as usize * height as usize];
+        for pt in self.rooms.iter().flat_map(|room| room.shape.cells()) {
+            if (pt.x as u32) < width && (pt.y as u32) < height {
+                cells[pt.x as usize + pt.y as usize * width as usize] = TemplateCell::Room;
+            }
+        }
+        Template {
+            width,
+            height,
+            cells,
+        }
+    }
+}
+
+// number of cells of the same room reached from start by steps between neighboring cells, with
+// owner holding the room of every cell of a width x height grid
+fn reachable(owner: &[Option<usize>], width: u32, height: u32, start: Point) -> usize {
+    let index = |pt: Point| pt.x as usize + pt.y as usize * width as usize;
+    let mut reached = vec![false; owner.len()];
+    reached[index(start)] = true;
+    let mut open = vec![start];
+    let mut count = 1;
+    while let Some(pt) = open.pop() {
+        for dir in Direction::ALL {
+            let next = pt.travel(dir);
+            if next.x < 0 || next.y < 0 || next.x >= width as i32 || next.y >= height as i32 {
+                continue;
+            }
+            if owner[index(next)] == owner[index(start)] && !reached[index(next)] {
+                reached[index(next)] = true;
+                open.push(next);
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        arbitrary::MAX_SIZE,
+        maze::{Grid, MazeType, MazeWrap},
+        template::apply_template,
+    };
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn room_shapes_parse_back(shape in any::<RoomShape>()) {
+            prop_assert_eq!(shape.to_string().parse::<RoomShape>(), Ok(shape));
+        }
+
+        #[test]
+        fn checked_rooms_open_into_one_area(shape in any::<RoomShape>()) {
+            let rooms = Rooms { rooms: vec![Room { name: None, shape: shape.clone() }] };
+            prop_assume!(rooms.check(MAX_SIZE, MAX_SIZE).is_ok());
+            let mut maze = Grid::new(MAX_SIZE, MAX_SIZE, MazeWrap::None, 0, MazeType::Kruskal);
+            apply_template(&mut maze, &rooms.template(MAX_SIZE, MAX_SIZE), 1);
+            let cells = shape.cells();
+            let mut reached = vec![cells[0]];
+            let mut open = vec![cells[0]];
+            while let Some(pt) = open.pop() {
+                for dir in Direction::ALL {
+                    let next = maze.travel(pt, dir);
+                    if maze.get_tile(pt).connected(dir) && !reached.contains(&next) {
+                        prop_assert!(shape.contains(next), "{} opens out of the room", pt);
+                        reached.push(next);
+                        open.push(next);
+                    }
+                }
+            }
+            prop_assert_eq!(reached.len(), cells.len());
+        }
+    }
+}
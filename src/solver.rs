@@ -0,0 +1,498 @@
+use crate::maze::{Direction, Grid, Point};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, VecDeque},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum SolverType {
+    #[default]
+    Bfs,
+    AStar,
+    Tremaux,
+}
+
+// a single event of the search, the direction points back toward the cell it was reached from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SolveStep {
+    Open(Point, Direction),
+    Close(Point, Direction),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Solution {
+    // cells from start to end, empty if the end could not be reached
+    pub path: Vec<Point>,
+    pub steps: Vec<SolveStep>,
+}
+
+impl Solution {
+    // direction of every move along the path from the start to the end, walking through the
+    // passages across wrapped edges where the path takes them
+    pub fn moves(&self, maze: &Grid) -> Vec<Direction> {
+        self.path
+            .windows(2)
+            .map(|pair| {
+                Direction::ALL
+                    .into_iter()
+                    .find(|dir| {
+                        maze.get_tile(pair[0]).connected(*dir)
+                            && maze.travel(pair[0], *dir) == pair[1]
+                    })
+                    .unwrap()
+            })
+            .collect()
+    }
+}
+
+fn neighbors(maze: &Grid, pos: Point) -> impl Iterator<Item = (Point, Direction)> + '_ {
+    Direction::ALL
+        .into_iter()
+        .filter(move |dir| maze.get_tile(pos).connected(*dir))
+        .map(move |dir| (maze.travel(pos, dir), dir))
+        .filter(|(pt, _)| maze.contains(*pt))
+}
+
+// manhattan distance, taking the shorter way around any wrapped axis
+fn manhattan(maze: &Grid, a: Point, b: Point) -> u32 {
+    let mut dx = (a.x - b.x).unsigned_abs();
+    let mut dy = (a.y - b.y).unsigned_abs();
+
+    if maze.wrap.horizontal() {
+        dx = dx.min(maze.width - dx);
+    }
+    if maze.wrap.vertical() {
+        dy = dy.min(maze.height - dy);
+    }
+
+    dx + dy
+}
+
+fn dir_index(dir: Direction) -> usize {
+    (dir as u8).trailing_zeros() as usize
+}
+
+// tremaux's algorithm: walk the maze marking each passage as it is traversed. new passages are
+// preferred, arriving at an already visited cell through a new passage turns back, and a passage
+// is never walked more than twice. the passages marked exactly once form the path. unlike wall
+// following this only relies on local connections, so it also works on wrapped mazes
+fn solve_tremaux(
+    maze: &Grid,
+    start: Point,
+    end: Point,
+    parent: &mut [Option<Direction>],
+    steps: &mut Vec<SolveStep>,
+) -> bool {
+    let mut marks: Vec<[u8; 4]> = vec![[0; 4]; maze.tiles.len()];
+    let mut pos = start;
+    let mut back = Direction::NoDir;
+
+    while pos != end {
+        let index = maze.get_index(pos);
+
+        let next = if back != Direction::NoDir
+            && marks[index][dir_index(back)] == 1
+            && marks[index].iter().filter(|m| **m > 0).count() > 1
+        {
+            // reached a visited cell through a new passage, go back the way we came
+            Some(back)
+        } else {
+            neighbors(maze, pos)
+                .map(|(_, dir)| dir)
+                .filter(|dir| marks[index][dir_index(*dir)] < 2)
+                .min_by_key(|dir| (marks[index][dir_index(*dir)], *dir == back))
+        };
+
+        let Some(dir) = next else {
+            // every passage has been walked twice, the end is unreachable
+            return false;
+        };
+
+        let next_pos = maze.travel(pos, dir);
+        let next_index = maze.get_index(next_pos);
+        marks[index][dir_index(dir)] += 1;
+        marks[next_index][dir_index(dir.opposite())] += 1;
+
+        if marks[index][dir_index(dir)] == 2 {
+            steps.push(SolveStep::Close(pos, dir));
+        }
+        if parent[next_index].is_none() {
+            parent[next_index] = Some(dir.opposite());
+            steps.push(SolveStep::Open(next_pos, dir.opposite()));
+        }
+
+        pos = next_pos;
+        back = dir.opposite();
+    }
+
+    // follow the passages marked once from the start, overwriting the parents of the cells on
+    // the path so the path can be reconstructed the same way as for the other solvers
+    let mut pos = start;
+    let mut back = Direction::NoDir;
+    while pos != end {
+        let index = maze.get_index(pos);
+        let dir = neighbors(maze, pos)
+            .map(|(_, dir)| dir)
+            .find(|dir| *dir != back && marks[index][dir_index(*dir)] == 1)
+            .unwrap();
+
+        pos = maze.travel(pos, dir);
+        back = dir.opposite();
+        parent[maze.get_index(pos)] = Some(back);
+    }
+
+    true
+}
+
+// the path the solver finds from start to end with every step of its search, None if end can not
+// be reached from start
+pub fn solve(maze: &Grid, start: Point, end: Point, solver: SolverType) -> Option<Solution> {
+    let mut parent: Vec<Option<Direction>> = vec![None; maze.tiles.len()];
+    let mut steps: Vec<SolveStep> = Vec::new();
+
+    parent[maze.get_index(start)] = Some(Direction::NoDir);
+    steps.push(SolveStep::Open(start, Direction::NoDir));
+
+    let found = match solver {
+        SolverType::Bfs => {
+            let mut open: VecDeque<Point> = VecDeque::from([start]);
+            let mut found = false;
+
+            while let Some(pos) = open.pop_front() {
+                steps.push(SolveStep::Close(pos, parent[maze.get_index(pos)].unwrap()));
+                if pos == end {
+                    found = true;
+                    break;
+                }
+
+                for (next, dir) in neighbors(maze, pos) {
+                    if parent[maze.get_index(next)].is_none() {
+                        parent[maze.get_index(next)] = Some(dir.opposite());
+                        steps.push(SolveStep::Open(next, dir.opposite()));
+                        open.push_back(next);
+                    }
+                }
+            }
+
+            found
+        }
+        SolverType::AStar => {
+            let mut cost: Vec<u32> = vec![u32::MAX; maze.tiles.len()];
+            let mut closed: Vec<bool> = vec![false; maze.tiles.len()];
+            // ordered by estimated total cost, ties broken by the most recently opened cell
+            let mut open: BinaryHeap<(Reverse<u32>, usize, i32, i32)> = BinaryHeap::new();
+            let mut counter = 0;
+
+            cost[maze.get_index(start)] = 0;
+            open.push((
+                Reverse(manhattan(maze, start, end)),
+                counter,
+                start.x,
+                start.y,
+            ));
+            let mut found = false;
+
+            while let Some((_, _, x, y)) = open.pop() {
+                let pos = Point::new(x, y);
+                let index = maze.get_index(pos);
+                if closed[index] {
+                    continue;
+                }
+                closed[index] = true;
+                steps.push(SolveStep::Close(pos, parent[index].unwrap()));
+                if pos == end {
+                    found = true;
+                    break;
+                }
+
+                for (next, dir) in neighbors(maze, pos) {
+                    let next_index = maze.get_index(next);
+                    if !closed[next_index] && cost[index] + 1 < cost[next_index] {
+                        cost[next_index] = cost[index] + 1;
+                        parent[next_index] = Some(dir.opposite());
+                        steps.push(SolveStep::Open(next, dir.opposite()));
+
+                        counter += 1;
+                        open.push((
+                            Reverse(cost[next_index] + manhattan(maze, next, end)),
+                            counter,
+                            next.x,
+                            next.y,
+                        ));
+                    }
+                }
+            }
+
+            found
+        }
+        SolverType::Tremaux => solve_tremaux(maze, start, end, &mut parent, &mut steps),
+    };
+
+    if !found {
+        return None;
+    }
+    let mut path: Vec<Point> = Vec::new();
+    let mut pos = end;
+    path.push(pos);
+    while pos != start {
+        pos = maze.travel(pos, parent[maze.get_index(pos)].unwrap());
+        path.push(pos);
+    }
+    path.reverse();
+
+    Some(Solution { path, steps })
+}
+
+// shortest path from start to end through the carved connections, None if end can not be reached
+pub fn solve_maze(maze: &Grid, start: Point, end: Point) -> Option<Vec<Point>> {
+    solve(maze, start, end, SolverType::Bfs).map(|solution| solution.path)
+}
+
+// cheapest walks from every cell to a goal, solved once so games can look up the best move from
+// any cell without searching again
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DistanceField {
+    pub goal: Point,
+    // cost of the cheapest walk to the goal, indexed like Grid::tiles. None where the goal can
+    // not be reached
+    pub cost: Vec<Option<u32>>,
+    // first step of that walk, NoDir at the goal
+    pub toward: Vec<Option<Direction>>,
+}
+
+// solves the cheapest walk from every cell to goal, where stepping into a cell costs its weight.
+// passages across wrapped edges are followed like any other, a weight of 1 everywhere gives the
+// shortest paths
+pub fn distance_field(maze: &Grid, goal: Point, weight: impl Fn(Point) -> u32) -> DistanceField {
+    let mut cost: Vec<Option<u32>> = vec![None; maze.tiles.len()];
+    let mut toward: Vec<Option<Direction>> = vec![None; maze.tiles.len()];
+    let mut closed: Vec<bool> = vec![false; maze.tiles.len()];
+
+    // dijkstra outward from the goal, every passage can be walked both ways
+    let mut open: BinaryHeap<(Reverse<u32>, i32, i32)> = BinaryHeap::new();
+    cost[maze.get_index(goal)] = Some(0);
+    toward[maze.get_index(goal)] = Some(Direction::NoDir);
+    open.push((Reverse(0), goal.x, goal.y));
+
+    while let Some((Reverse(d), x, y)) = open.pop() {
+        let pos = Point::new(x, y);
+        if closed[maze.get_index(pos)] {
+            continue;
+        }
+        closed[maze.get_index(pos)] = true;
+
+        let step = weight(pos);
+        for (next, dir) in neighbors(maze, pos) {
+            let index = maze.get_index(next);
+            let through = d.saturating_add(step);
+            if !closed[index] && cost[index].is_none_or(|c| through < c) {
+                cost[index] = Some(through);
+                toward[index] = Some(dir.opposite());
+                open.push((Reverse(through), next.x, next.y));
+            }
+        }
+    }
+
+    DistanceField { goal, cost, toward }
+}
+
+// the move from a cell along a cheapest walk to the goal of the field, NoDir once there and None
+// if the goal can not be reached from it
+pub fn next_step_toward(maze: &Grid, field: &DistanceField, from: Point) -> Option<Direction> {
+    field.toward[maze.get_index(from)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{generate_maze, GenParams, MazeType, MazeWrap, Tile};
+
+    const SOLVERS: [SolverType; 3] = [SolverType::Bfs, SolverType::AStar, SolverType::Tremaux];
+
+    fn assert_connected_path(maze: &Grid, path: &[Point]) {
+        for pair in path.windows(2) {
+            let dir = Direction::ALL
+                .into_iter()
+                .find(|d| maze.travel(pair[0], *d) == pair[1])
+                .expect("path steps between non adjacent cells");
+            assert!(maze.get_tile(pair[0]).connected(dir));
+            assert!(maze.get_tile(pair[1]).connected(dir.opposite()));
+        }
+    }
+
+    #[test]
+    fn solves_mazes_wider_than_a_u16() {
+        let width = u16::MAX as u32 + 10;
+        let (maze, _) = generate_maze(width, 2, MazeType::Kruskal, MazeWrap::None, 7).unwrap();
+        let end = Point::new(width as i32 - 1, 1);
+        for solver in SOLVERS {
+            let solution = solve(&maze, Point::new(0, 0), end, solver).unwrap();
+            assert_eq!(solution.path.last(), Some(&end));
+            assert_connected_path(&maze, &solution.path);
+        }
+    }
+
+    #[test]
+    fn unreachable_ends_have_no_solution() {
+        // two cells joined to each other and a third one walled off
+        let mut maze = Grid {
+            tiles: vec![Tile::default(); 3],
+            width: 3,
+            height: 1,
+            wrap: MazeWrap::None,
+            params: GenParams::new(0, MazeType::default()),
+            costs: Vec::new(),
+        };
+        maze.get_tile_mut(Point::new(0, 0))
+            .set_connections(Direction::East as u8);
+        maze.get_tile_mut(Point::new(1, 0))
+            .set_connections(Direction::West as u8);
+
+        for solver in SOLVERS {
+            assert!(solve(&maze, Point::new(0, 0), Point::new(2, 0), solver).is_none());
+            assert!(solve(&maze, Point::new(0, 0), Point::new(1, 0), solver).is_some());
+        }
+        assert_eq!(solve_maze(&maze, Point::new(2, 0), Point::new(0, 0)), None);
+    }
+
+    #[test]
+    fn moves_walk_the_path() {
+        for wrap in [MazeWrap::None, MazeWrap::Full] {
+            let (maze, _) = generate_maze(12, 9, MazeType::Wilson, wrap, 4).unwrap();
+            let solution =
+                solve(&maze, Point::new(0, 0), Point::new(11, 8), SolverType::Bfs).unwrap();
+            let moves = solution.moves(&maze);
+            assert_eq!(moves.len() + 1, solution.path.len());
+
+            let mut pt = solution.path[0];
+            for (dir, next) in moves.iter().zip(&solution.path[1..]) {
+                assert!(maze.get_tile(pt).connected(*dir));
+                pt = maze.travel(pt, *dir);
+                assert_eq!(pt, *next);
+            }
+        }
+    }
+
+    #[test]
+    fn solvers_follow_wrapped_connections() {
+        for wrap in [MazeWrap::Horizontal, MazeWrap::Vertical, MazeWrap::Full] {
+            for seed in 0..8 {
+                let (maze, _) = generate_maze(12, 9, MazeType::Backtrack, wrap, seed).unwrap();
+                let (start, end) = (Point::new(0, 0), Point::new(11, 8));
+
+                let lengths: Vec<usize> = SOLVERS
+                    .iter()
+                    .map(|solver| {
+                        let solution = solve(&maze, start, end, *solver).unwrap();
+                        assert_eq!(solution.path.first(), Some(&start));
+                        assert_eq!(solution.path.last(), Some(&end));
+                        assert_connected_path(&maze, &solution.path);
+                        solution.path.len()
+                    })
+                    .collect();
+
+                // a perfect maze has exactly one simple path
+                assert!(lengths.iter().all(|l| *l == lengths[0]));
+            }
+        }
+    }
+
+    #[test]
+    fn solvers_take_the_wrapped_shortcut() {
+        // a single row where the first and last cell only meet across the seam
+        let mut maze = Grid {
+            tiles: vec![Tile::default(); 4],
+            width: 4,
+            height: 1,
+            wrap: MazeWrap::Horizontal,
+            params: GenParams::new(0, MazeType::default()),
+            costs: Vec::new(),
+        };
+        for (x, dirs) in [
+            (0, Direction::East as u8 | Direction::West as u8),
+            (1, Direction::East as u8 | Direction::West as u8),
+            (2, Direction::West as u8),
+            (3, Direction::East as u8),
+        ] {
+            maze.get_tile_mut(Point::new(x, 0)).set_connections(dirs);
+        }
+
+        for solver in SOLVERS {
+            let solution = solve(&maze, Point::new(1, 0), Point::new(3, 0), solver).unwrap();
+            assert_eq!(
+                solution.path,
+                vec![Point::new(1, 0), Point::new(0, 0), Point::new(3, 0)]
+            );
+        }
+    }
+
+    #[test]
+    fn hints_walk_the_shortest_path() {
+        for wrap in [MazeWrap::None, MazeWrap::Full] {
+            let (maze, _) = generate_maze(12, 9, MazeType::Wilson, wrap, 3).unwrap();
+            let goal = Point::new(5, 4);
+            let field = distance_field(&maze, goal, |_| 1);
+
+            for y in 0..maze.height as i32 {
+                for x in 0..maze.width as i32 {
+                    let start = Point::new(x, y);
+                    let mut path = vec![start];
+                    while let Some(dir) = next_step_toward(&maze, &field, *path.last().unwrap()) {
+                        if dir == Direction::NoDir {
+                            break;
+                        }
+                        path.push(maze.travel(*path.last().unwrap(), dir));
+                    }
+                    assert_connected_path(&maze, &path);
+                    assert_eq!(
+                        solve_maze(&maze, start, goal).map(|p| p.len()),
+                        Some(path.len())
+                    );
+                    assert_eq!(
+                        field.cost[maze.get_index(start)],
+                        Some(path.len() as u32 - 1)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hints_avoid_heavy_cells() {
+        // a loop of six cells, the goal is two steps away over the top or four around the bottom
+        let mut maze = Grid::new(3, 2, MazeWrap::None, 0, MazeType::default());
+        for (x, y, dirs) in [
+            (0, 0, Direction::East as u8 | Direction::South as u8),
+            (1, 0, Direction::East as u8 | Direction::West as u8),
+            (2, 0, Direction::West as u8 | Direction::South as u8),
+            (0, 1, Direction::North as u8 | Direction::East as u8),
+            (1, 1, Direction::East as u8 | Direction::West as u8),
+            (2, 1, Direction::North as u8 | Direction::West as u8),
+        ] {
+            maze.get_tile_mut(Point::new(x, y)).set_connections(dirs);
+        }
+        let (start, goal) = (Point::new(0, 0), Point::new(2, 0));
+
+        let field = distance_field(&maze, goal, |_| 1);
+        assert_eq!(
+            next_step_toward(&maze, &field, start),
+            Some(Direction::East)
+        );
+
+        let swamp = Point::new(1, 0);
+        let field = distance_field(&maze, goal, |pt| if pt == swamp { 10 } else { 1 });
+        assert_eq!(
+            next_step_toward(&maze, &field, start),
+            Some(Direction::South)
+        );
+        assert_eq!(field.cost[maze.get_index(start)], Some(4));
+        assert_eq!(
+            next_step_toward(&maze, &field, goal),
+            Some(Direction::NoDir)
+        );
+    }
+}
@@ -0,0 +1,849 @@
+use crate::{
+    maze::{
+        noise_map, CellLabel, ConnectionStatus, Direction, Grid, MazeAction, MazeMetadata, Point,
+    },
+    solver::solve_maze,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, fmt, str::FromStr};
+
+// steps a path count may take before it gives up
+const SEARCH_LIMIT: u32 = 10_000_000;
+
+// step index at which each cell first appeared in the generation history, indexed like
+// Grid::tiles. a cleared rect adds all of its cells at once, walls and scans add none. cells
+// never added by the history are None
+pub fn generation_order(maze: &Grid, history: &[MazeAction]) -> Vec<Option<u32>> {
+    let mut order: Vec<Option<u32>> = vec![None; maze.tiles.len()];
+
+    for (step, action) in history.iter().enumerate() {
+        let cells: Vec<Point> = match action {
+            MazeAction::Carve(pt, _) => vec![*pt],
+            MazeAction::Wall(..) | MazeAction::Scan(_) => Vec::new(),
+            MazeAction::Clear(rect) => rect.cells().collect(),
+        };
+        for pt in cells {
+            let index = maze.get_index(pt);
+            if order[index].is_none() {
+                order[index] = Some(step as u32);
+            }
+        }
+    }
+
+    order
+}
+
+// noise of every cell of a noise maze in thousandths above its lowest value of -1, indexed like
+// Grid::tiles. removed cells are None
+pub fn noise_levels(maze: &Grid) -> Vec<Option<u32>> {
+    noise_map(maze)
+        .iter()
+        .zip(&maze.tiles)
+        .map(|(v, tile)| {
+            (tile.status() != ConnectionStatus::Removed)
+                .then(|| ((v.clamp(-1.0, 1.0) + 1.0) * 1000.0) as u32)
+        })
+        .collect()
+}
+
+// length of the shortest path from start to each cell through the passages, indexed like
+// Grid::tiles. cells that can not be reached are None
+pub fn distances(maze: &Grid, start: Point) -> Vec<Option<u32>> {
+    let mut distance: Vec<Option<u32>> = vec![None; maze.tiles.len()];
+    if maze.is_removed(start) {
+        return distance;
+    }
+
+    // every passage has the same length, so a breadth first search visits cells in order of
+    // distance
+    let mut open = VecDeque::from([start]);
+    distance[maze.get_index(start)] = Some(0);
+    while let Some(pt) = open.pop_front() {
+        let d = distance[maze.get_index(pt)].unwrap();
+        for dir in Direction::ALL {
+            let next = maze.travel(pt, dir);
+            if maze.get_tile(pt).connected(dir)
+                && maze.contains(next)
+                && distance[maze.get_index(next)].is_none()
+            {
+                distance[maze.get_index(next)] = Some(d + 1);
+                open.push_back(next);
+            }
+        }
+    }
+
+    distance
+}
+
+// number of (horizontal, vertical) passages between cells, openings out of the grid are left out
+pub fn passage_counts(maze: &Grid) -> (u32, u32) {
+    let joined = |pt: Point, dir: Direction| {
+        (maze.get_tile(pt).connected(dir) && maze.contains(maze.travel(pt, dir))) as u32
+    };
+    cells(maze).fold((0, 0), |(h, v), pt| {
+        (
+            h + joined(pt, Direction::East),
+            v + joined(pt, Direction::South),
+        )
+    })
+}
+
+// fraction of corridor cells (exactly two connections) that continue straight through
+pub fn straightness(maze: &Grid) -> f32 {
+    let vertical = Direction::North as u8 | Direction::South as u8;
+    let horizontal = Direction::East as u8 | Direction::West as u8;
+
+    let (straight, corridors) = maze
+        .tiles
+        .iter()
+        .filter(|tile| tile.connections().count_ones() == 2)
+        .fold((0, 0), |(s, c), tile| {
+            let is_straight = tile.connections() == vertical || tile.connections() == horizontal;
+            (s + is_straight as u32, c + 1)
+        });
+
+    if corridors == 0 {
+        0.0
+    } else {
+        straight as f32 / corridors as f32
+    }
+}
+
+// number of cells joined to 0 through 4 other cells
+pub fn degree_counts(maze: &Grid) -> [u32; 5] {
+    // removed cells have no passages and count among the cells without connections
+    let mut counts = [0; 5];
+    counts[0] = maze.tiles.len() as u32 - cells(maze).count() as u32;
+    for pt in cells(maze) {
+        counts[neighbors(maze, pt).len()] += 1;
+    }
+    counts
+}
+
+// cells joined to the cell at pt by a passage, passages leaving the grid through an opening are
+// left out
+fn neighbors(maze: &Grid, pt: Point) -> Vec<Point> {
+    Direction::ALL
+        .into_iter()
+        .filter(|dir| maze.get_tile(pt).connected(*dir))
+        .map(|dir| maze.travel(pt, dir))
+        .filter(|next| maze.contains(*next))
+        .collect()
+}
+
+fn cells(maze: &Grid) -> impl Iterator<Item = Point> + '_ {
+    (0..maze.height as i32)
+        .flat_map(move |y| (0..maze.width as i32).map(move |x| Point::new(x, y)))
+        .filter(|pt| !maze.is_removed(*pt))
+}
+
+// average number of passages in a corridor, a run of passages between two cells that are
+// junctions or dead ends. every corridor has two such ends, a maze of nothing but corridor cells
+// joined in loops counts as a single corridor
+pub fn passage_length(maze: &Grid) -> f32 {
+    let (ends, connections) = cells(maze)
+        .map(|pt| neighbors(maze, pt).len() as u32)
+        .fold((0, 0), |(e, c), d| (e + if d == 2 { 0 } else { d }, c + d));
+    let passages = connections as f32 / 2.0;
+    if ends == 0 {
+        passages
+    } else {
+        passages / (ends as f32 / 2.0)
+    }
+}
+
+// the two cells furthest apart along the passages with the number of passages between them,
+// found by searching again from the cell farthest away from the first one. exact for perfect
+// mazes where there is only one path between two cells, mazes with loops may have a pair further
+// apart. None for a maze without cells
+pub fn farthest_cells(maze: &Grid) -> Option<(Point, Point, u32)> {
+    let farthest = |start: Point| {
+        distances(maze, start)
+            .iter()
+            .enumerate()
+            .filter_map(|(i, d)| d.map(|d| (i, d)))
+            .max_by_key(|(_, d)| *d)
+            .map(|(i, d)| {
+                let width = maze.width as usize;
+                (Point::new((i % width) as i32, (i / width) as i32), d)
+            })
+    };
+    let (start, _) = farthest(cells(maze).next()?)?;
+    let (end, d) = farthest(start)?;
+    Some((start, end, d))
+}
+
+// length in cells of the path between the farthest_cells
+pub fn longest_path(maze: &Grid) -> u32 {
+    farthest_cells(maze).map_or(0, |(_, _, d)| d + 1)
+}
+
+// average number of cells walked from a dead end to the first junction. long winding dead ends
+// make a maze flow like a river and score high, many short dead ends score close to 1
+pub fn river(maze: &Grid) -> f32 {
+    let mut lengths = Vec::new();
+    for end in cells(maze).filter(|pt| neighbors(maze, *pt).len() == 1) {
+        let (mut previous, mut pt) = (end, neighbors(maze, end)[0]);
+        let mut length = 1;
+        // a corridor can only loop back on itself across a wrapped edge of a tiny maze
+        while length < maze.tiles.len() {
+            let next = neighbors(maze, pt);
+            if next.len() != 2 {
+                break;
+            }
+            length += 1;
+            (previous, pt) = (
+                pt,
+                if next[0] == previous {
+                    next[1]
+                } else {
+                    next[0]
+                },
+            );
+        }
+        lengths.push(length);
+    }
+
+    if lengths.is_empty() {
+        0.0
+    } else {
+        lengths.iter().sum::<usize>() as f32 / lengths.len() as f32
+    }
+}
+
+// shannon entropy in bits of the distribution of cell degrees, a maze made entirely of
+// corridors scores 0 while an even mix of all four degrees scores 2
+pub fn branching_entropy(maze: &Grid) -> f32 {
+    let total = maze.tiles.len() as f32;
+    degree_counts(maze)
+        .iter()
+        .filter(|n| **n > 0)
+        .map(|n| {
+            let p = *n as f32 / total;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+// fraction of the walls between neighboring cells that are the same in both mazes, 1 for
+// identical mazes. None if the mazes differ in size. edges across a wrapped border are compared
+// when either maze wraps that way
+pub fn similarity(a: &Grid, b: &Grid) -> Option<f32> {
+    if a.width != b.width || a.height != b.height {
+        return None;
+    }
+    let wrap_horizontal = a.wrap.horizontal() || b.wrap.horizontal();
+    let wrap_vertical = a.wrap.vertical() || b.wrap.vertical();
+
+    let (mut same, mut total) = (0, 0);
+    for y in 0..a.height {
+        for x in 0..a.width {
+            let pt = Point::new(x as i32, y as i32);
+            let (tile_a, tile_b) = (a.get_tile(pt), b.get_tile(pt));
+
+            for (dir, exists) in [
+                (Direction::East, x + 1 < a.width || wrap_horizontal),
+                (Direction::South, y + 1 < a.height || wrap_vertical),
+            ] {
+                if exists {
+                    same += (tile_a.connected(dir) == tile_b.connected(dir)) as u32;
+                    total += 1;
+                }
+            }
+        }
+    }
+
+    if total == 0 {
+        Some(1.0)
+    } else {
+        Some(same as f32 / total as f32)
+    }
+}
+
+// length in cells of the path from the top left to the bottom right cell, 0 if there is none
+pub fn solution_length(maze: &Grid) -> usize {
+    let end = Point::new(maze.width as i32 - 1, maze.height as i32 - 1);
+    solve_maze(maze, Point::new(0, 0), end).map_or(0, |path| path.len())
+}
+
+// whether exactly one simple path leads from start to end. that is the case when every passage
+// of the shortest path is a bridge, one that is not part of any loop, which a depth first search
+// finds by the lowest discovery order reachable below each cell
+pub fn unique_solution(maze: &Grid, start: Point, end: Point) -> bool {
+    let Some(path) = solve_maze(maze, start, end) else {
+        return false;
+    };
+
+    let mut order: Vec<u32> = vec![u32::MAX; maze.tiles.len()];
+    let mut low: Vec<u32> = vec![u32::MAX; maze.tiles.len()];
+    // direction each cell was entered from its parent in, so only that passage is skipped when
+    // two passages join the same cells across a wrapped edge
+    let mut entry: Vec<Option<Direction>> = vec![None; maze.tiles.len()];
+    order[maze.get_index(start)] = 0;
+    low[maze.get_index(start)] = 0;
+    let mut discovered = 1;
+    let mut stack: Vec<(Point, usize)> = vec![(start, 0)];
+    while let Some(&(pt, tried)) = stack.last() {
+        if tried == 4 {
+            stack.pop();
+            if let Some(&(parent, _)) = stack.last() {
+                let reach = low[maze.get_index(pt)];
+                let parent_low = &mut low[maze.get_index(parent)];
+                *parent_low = (*parent_low).min(reach);
+            }
+            continue;
+        }
+        stack.last_mut().unwrap().1 += 1;
+
+        let dir = Direction::ALL[tried];
+        let next = maze.travel(pt, dir);
+        if !maze.get_tile(pt).connected(dir)
+            || !maze.contains(next)
+            || entry[maze.get_index(pt)] == Some(dir.opposite())
+        {
+            continue;
+        }
+        let index = maze.get_index(next);
+        if order[index] == u32::MAX {
+            order[index] = discovered;
+            low[index] = discovered;
+            entry[index] = Some(dir);
+            discovered += 1;
+            stack.push((next, 0));
+        } else {
+            let reach = order[index];
+            let pt_low = &mut low[maze.get_index(pt)];
+            *pt_low = (*pt_low).min(reach);
+        }
+    }
+
+    path.windows(2).all(|pair| {
+        let (a, b) = (maze.get_index(pair[0]), maze.get_index(pair[1]));
+        let dir = Direction::ALL
+            .into_iter()
+            .find(|d| maze.travel(pair[0], *d) == pair[1] && maze.get_tile(pair[0]).connected(*d))
+            .unwrap();
+        if entry[b] == Some(dir) {
+            low[b] > order[a]
+        } else if entry[a] == Some(dir.opposite()) {
+            low[a] > order[b]
+        } else {
+            false
+        }
+    })
+}
+
+// number of simple paths from start to end at most max_length cells long. counting stops at
+// limit, or once the search has taken SEARCH_LIMIT steps since loops can make the number of paths
+// explode. a perfect maze has exactly one between any two connected cells
+pub fn count_paths(maze: &Grid, start: Point, end: Point, max_length: usize, limit: u32) -> u32 {
+    if !maze.contains(start) || !maze.contains(end) {
+        return 0;
+    }
+    // passages are reciprocal, so every cell reachable from the start has a distance to the end
+    // once the start has one. paths that can no longer reach the end in time are cut short
+    let to_end = distances(maze, end);
+    match to_end[maze.get_index(start)] {
+        Some(d) if (d as usize) < max_length => (),
+        _ => return 0,
+    }
+
+    let mut on_path = vec![false; maze.tiles.len()];
+    on_path[maze.get_index(start)] = true;
+    // cells of the current path with the number of directions already tried from each, kept on
+    // the heap since paths can be as long as the maze has cells
+    let mut path: Vec<(Point, usize)> = vec![(start, 0)];
+    let mut count = 0;
+    for _ in 0..SEARCH_LIMIT {
+        let Some(&(pt, tried)) = path.last() else {
+            break;
+        };
+        if pt == end || tried == 4 {
+            if pt == end {
+                count += 1;
+                if count >= limit {
+                    break;
+                }
+            }
+            on_path[maze.get_index(pt)] = false;
+            path.pop();
+            continue;
+        }
+        path.last_mut().unwrap().1 += 1;
+
+        let dir = Direction::ALL[tried];
+        let next = maze.travel(pt, dir);
+        if maze.get_tile(pt).connected(dir)
+            && maze.contains(next)
+            && !on_path[maze.get_index(next)]
+            && path.len() + (to_end[maze.get_index(next)].unwrap() as usize) < max_length
+        {
+            on_path[maze.get_index(next)] = true;
+            path.push((next, 0));
+        }
+    }
+
+    count
+}
+
+// solutions longer than this many times the shortest one are not counted
+const SOLUTION_SLACK: usize = 2;
+// solutions are counted up to this many
+const SOLUTION_LIMIT: u32 = 1000;
+
+// number of distinct solutions from the top left to the bottom right cell up to SOLUTION_SLACK
+// times as long as the shortest. only a unique solution gives 1, one that is not unique has at
+// least a second even when it is longer or the search gave up before finding it
+pub fn solution_count(maze: &Grid) -> u32 {
+    let (start, end) = (
+        Point::new(0, 0),
+        Point::new(maze.width as i32 - 1, maze.height as i32 - 1),
+    );
+    let length = solution_length(maze);
+    if length == 0 {
+        0
+    } else if unique_solution(maze, start, end) {
+        1
+    } else {
+        count_paths(maze, start, end, SOLUTION_SLACK * length, SOLUTION_LIMIT).max(2)
+    }
+}
+
+// how the statistics of a generated maze are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum StatsFormat {
+    /// one metric per line
+    Table,
+    /// json object like the one printed by the stats command
+    Json,
+}
+
+// where the entrance and exit are placed instead of the top left and bottom right cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum Entrances {
+    /// the two cells furthest apart, for the longest solution the maze can have
+    Longest,
+}
+
+pub struct MazeStats {
+    pub metadata: MazeMetadata,
+    pub steps: usize,
+    pub dead_ends: u32,
+    pub three_way_junctions: u32,
+    pub four_way_junctions: u32,
+    pub passage_length: f32,
+    pub longest_path: u32,
+    pub river: f32,
+    pub solution_length: usize,
+    pub solutions: u32,
+    pub horizontal_passages: u32,
+    pub vertical_passages: u32,
+    pub straightness: f32,
+    pub branching_entropy: f32,
+    pub generation_order: Vec<Option<u32>>,
+    pub labels: Vec<CellLabel>,
+}
+
+impl MazeStats {
+    pub fn new(maze: &Grid, history: &[MazeAction]) -> Self {
+        let (horizontal_passages, vertical_passages) = passage_counts(maze);
+        let degrees = degree_counts(maze);
+
+        Self {
+            metadata: maze.metadata(),
+            steps: history.len(),
+            dead_ends: degrees[1],
+            three_way_junctions: degrees[3],
+            four_way_junctions: degrees[4],
+            passage_length: passage_length(maze),
+            longest_path: longest_path(maze),
+            river: river(maze),
+            solution_length: solution_length(maze),
+            solutions: solution_count(maze),
+            horizontal_passages,
+            vertical_passages,
+            straightness: straightness(maze),
+            branching_entropy: branching_entropy(maze),
+            generation_order: generation_order(maze, history),
+            labels: Vec::new(),
+        }
+    }
+
+    // fraction of passages that run horizontally, 0.5 means no directional bias
+    pub fn horizontal_bias(&self) -> f32 {
+        let total = self.horizontal_passages + self.vertical_passages;
+        if total == 0 {
+            0.0
+        } else {
+            self.horizontal_passages as f32 / total as f32
+        }
+    }
+
+    // numeric value of one of the names in METRICS
+    pub fn metric(&self, name: &str) -> Option<f32> {
+        Some(match name {
+            "steps" => self.steps as f32,
+            "deadends" => self.dead_ends as f32,
+            "three_way_junctions" => self.three_way_junctions as f32,
+            "four_way_junctions" => self.four_way_junctions as f32,
+            "passage_length" => self.passage_length,
+            "longest_path" => self.longest_path as f32,
+            "river" => self.river,
+            "solution" => self.solution_length as f32,
+            "solutions" => self.solutions as f32,
+            "horizontal_passages" => self.horizontal_passages as f32,
+            "vertical_passages" => self.vertical_passages as f32,
+            "horizontal_bias" => self.horizontal_bias(),
+            "straightness" => self.straightness,
+            "branching_entropy" => self.branching_entropy,
+            _ => return None,
+        })
+    }
+
+    // named values shared by the json and table output
+    fn values(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("steps", self.steps.to_string()),
+            ("dead_ends", self.dead_ends.to_string()),
+            ("three_way_junctions", self.three_way_junctions.to_string()),
+            ("four_way_junctions", self.four_way_junctions.to_string()),
+            ("passage_length", format!("{:.3}", self.passage_length)),
+            ("longest_path", self.longest_path.to_string()),
+            ("river", format!("{:.3}", self.river)),
+            ("solution_length", self.solution_length.to_string()),
+            ("solutions", self.solutions.to_string()),
+            ("horizontal_passages", self.horizontal_passages.to_string()),
+            ("vertical_passages", self.vertical_passages.to_string()),
+            ("horizontal_bias", format!("{:.3}", self.horizontal_bias())),
+            ("straightness", format!("{:.3}", self.straightness)),
+            (
+                "branching_entropy",
+                format!("{:.3}", self.branching_entropy),
+            ),
+        ]
+    }
+
+    pub fn to_json(&self) -> String {
+        let metadata: Vec<String> = self
+            .metadata
+            .entries()
+            .iter()
+            .map(|(k, v)| format!("\"{}\":\"{}\"", k, v))
+            .collect();
+        let values: Vec<String> = self
+            .values()
+            .iter()
+            .map(|(k, v)| format!("\"{}\":{}", k, v))
+            .collect();
+        let order: Vec<String> = self
+            .generation_order
+            .iter()
+            .map(|v| match v {
+                Some(v) => v.to_string(),
+                None => "null".to_string(),
+            })
+            .collect();
+
+        let labels: Vec<String> = self
+            .labels
+            .iter()
+            .map(|l| {
+                format!(
+                    "{{\"x\":{},\"y\":{},\"text\":\"{}\"}}",
+                    l.pt.x,
+                    l.pt.y,
+                    l.text.replace('\\', "\\\\").replace('"', "\\\"")
+                )
+            })
+            .collect();
+
+        format!(
+            "{{\"metadata\":{{{}}},{},\"generation_order\":[{}],\"labels\":[{}]}}",
+            metadata.join(","),
+            values.join(","),
+            order.join(","),
+            labels.join(",")
+        )
+    }
+
+    pub fn to_table(&self) -> String {
+        self.metadata
+            .entries()
+            .into_iter()
+            .chain(self.values())
+            .map(|(k, v)| format!("{:<22}{}\n", format!("{}:", k.replace('_', " ")), v))
+            .collect()
+    }
+}
+
+// names accepted by MazeStats::metric and in predicates
+pub const METRICS: [&str; 14] = [
+    "steps",
+    "deadends",
+    "three_way_junctions",
+    "four_way_junctions",
+    "passage_length",
+    "longest_path",
+    "river",
+    "solution",
+    "solutions",
+    "horizontal_passages",
+    "vertical_passages",
+    "horizontal_bias",
+    "straightness",
+    "branching_entropy",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+}
+
+// two character operators come first so "<=" is not read as "<"
+const COMPARISONS: [(&str, Comparison); 6] = [
+    ("<=", Comparison::LessEqual),
+    (">=", Comparison::GreaterEqual),
+    ("==", Comparison::Equal),
+    ("!=", Comparison::NotEqual),
+    ("<", Comparison::Less),
+    (">", Comparison::Greater),
+];
+
+// condition on the statistics of a maze written as metric comparisons joined by &&, for example
+// "deadends<50 && solution>200"
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
+pub struct StatsPredicate {
+    clauses: Vec<(&'static str, Comparison, f32)>,
+}
+
+impl StatsPredicate {
+    pub fn matches(&self, stats: &MazeStats) -> bool {
+        self.clauses.iter().all(|(name, cmp, target)| {
+            let value = stats.metric(name).unwrap();
+            match cmp {
+                Comparison::Less => value < *target,
+                Comparison::LessEqual => value <= *target,
+                Comparison::Greater => value > *target,
+                Comparison::GreaterEqual => value >= *target,
+                Comparison::Equal => value == *target,
+                Comparison::NotEqual => value != *target,
+            }
+        })
+    }
+}
+
+impl FromStr for StatsPredicate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // the value would swallow the rest of the text and fail to parse as a number
+        let unsupported = |clause: &str, op: &str| {
+            format!(
+                "unsupported operator \"{}\" in \"{}\", clauses can only be joined by &&",
+                op,
+                clause.trim()
+            )
+        };
+        if s.contains("||") {
+            return Err(unsupported(s, "||"));
+        }
+        let clauses = s
+            .split("&&")
+            .map(|clause| {
+                let (op, cmp, at) = COMPARISONS
+                    .iter()
+                    .filter_map(|(op, cmp)| clause.find(op).map(|at| (*op, *cmp, at)))
+                    .min_by_key(|(_, _, at)| *at)
+                    .ok_or_else(|| format!("missing comparison in \"{}\"", clause.trim()))?;
+
+                let name = clause[..at].trim();
+                let name = *METRICS.iter().find(|m| **m == name).ok_or_else(|| {
+                    format!(
+                        "unknown metric \"{}\", expected one of {}",
+                        name,
+                        METRICS.join(", ")
+                    )
+                })?;
+                let value = clause[at + op.len()..].trim();
+                if let Some(c) = value.chars().find(|c| "<>=!&|".contains(*c)) {
+                    return Err(unsupported(clause, &c.to_string()));
+                }
+                let value = value
+                    .parse::<f32>()
+                    .map_err(|e| format!("invalid value in \"{}\": {}", clause.trim(), e))?;
+
+                Ok((name, cmp, value))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok(StatsPredicate { clauses })
+    }
+}
+
+impl fmt::Display for StatsPredicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (name, cmp, value)) in self.clauses.iter().enumerate() {
+            let op = COMPARISONS.iter().find(|(_, c)| c == cmp).unwrap().0;
+            if i > 0 {
+                write!(f, " && ")?;
+            }
+            write!(f, "{}{}{}", name, op, value)?;
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<String> for StatsPredicate {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<StatsPredicate> for String {
+    fn from(predicate: StatsPredicate) -> Self {
+        predicate.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        arbitrary::MAX_SIZE,
+        maze::{generate_maze, MazeType, MazeWrap},
+    };
+    use proptest::{prelude::*, sample::select};
+
+    #[test]
+    fn predicates_parse_back() {
+        for text in [
+            "deadends<50",
+            "solution>=200.5 && river!=0",
+            "steps==3 && steps<=4",
+        ] {
+            let predicate: StatsPredicate = text.parse().unwrap();
+            assert_eq!(predicate.to_string(), text);
+            assert_eq!(predicate.to_string().parse(), Ok(predicate));
+        }
+        let predicate: StatsPredicate = " deadends < 50 &&solution> 2 ".parse().unwrap();
+        assert_eq!(predicate.to_string(), "deadends<50 && solution>2");
+    }
+
+    #[test]
+    fn invalid_predicates_say_why() {
+        let error = |text: &str| text.parse::<StatsPredicate>().unwrap_err();
+        assert_eq!(
+            error("solution>5 || deadends<3"),
+            "unsupported operator \"||\" in \"solution>5 || deadends<3\", clauses can only be \
+            joined by &&"
+        );
+        assert!(error("solution>5 & deadends<3").starts_with("unsupported operator \"&\""));
+        assert!(error("solution=5").starts_with("missing comparison in \"solution=5\""));
+        assert!(error("walls<5").starts_with("unknown metric \"walls\""));
+        assert!(error("solution<many").starts_with("invalid value in \"solution<many\""));
+    }
+
+    #[test]
+    fn predicates_need_every_clause() {
+        let (maze, history) = generate_maze(6, 6, MazeType::Wilson, MazeWrap::None, 4).unwrap();
+        let stats = MazeStats::new(&maze, &history.steps);
+        let steps = history.steps.len();
+        let parse = |text: String| text.parse::<StatsPredicate>().unwrap();
+        assert!(parse(format!("steps=={}", steps)).matches(&stats));
+        assert!(parse(format!("steps>={} && steps<={}", steps, steps)).matches(&stats));
+        assert!(!parse(format!("steps=={} && steps!={}", steps, steps)).matches(&stats));
+    }
+
+    #[test]
+    fn openings_are_not_passages() {
+        let (mut maze, _) = generate_maze(5, 5, MazeType::Kruskal, MazeWrap::None, 3).unwrap();
+        let (passages, degrees) = (passage_counts(&maze), degree_counts(&maze));
+        // a dead end on the border opened through the edge of the grid
+        let (pt, dir) = cells(&maze)
+            .flat_map(|pt| Direction::ALL.into_iter().map(move |dir| (pt, dir)))
+            .find(|(pt, dir)| {
+                maze.get_tile(*pt).connections().count_ones() == 1
+                    && !maze.contains(maze.travel(*pt, *dir))
+            })
+            .unwrap();
+        maze.get_tile_mut(pt).connect(dir);
+        assert_eq!(passage_counts(&maze), passages);
+        assert_eq!(degree_counts(&maze), degrees);
+
+        // passages across the seams of a wrapped maze join two cells of the grid
+        let (wrapped, _) = generate_maze(5, 5, MazeType::Wilson, MazeWrap::Full, 3).unwrap();
+        let (h, v) = passage_counts(&wrapped);
+        assert_eq!(h + v, 24);
+    }
+
+    proptest! {
+        #[test]
+        fn unique_solutions_have_a_single_path(
+            width in 1..=6u32,
+            height in 1..=6u32,
+            method in any::<MazeType>(),
+            wrap in any::<MazeWrap>(),
+            seed in any::<u64>(),
+            percent in 0..=100u8,
+        ) {
+            // small enough for every path to be counted
+            let (mut maze, _) = generate_maze(width, height, method, wrap, seed).unwrap();
+            maze.braid(percent, maze.params.seed);
+            let (start, end) = (
+                Point::new(0, 0),
+                Point::new(maze.width as i32 - 1, maze.height as i32 - 1),
+            );
+            let paths = count_paths(&maze, start, end, maze.tiles.len(), 2);
+            prop_assert_eq!(unique_solution(&maze, start, end), paths == 1);
+        }
+
+        #[test]
+        fn perfect_maze_stats_add_up(
+            width in 1..=MAX_SIZE,
+            height in 1..=MAX_SIZE,
+            method in select(vec![MazeType::Wilson, MazeType::Kruskal]),
+            seed in any::<u64>(),
+        ) {
+            let (maze, _) = generate_maze(width, height, method, MazeWrap::None, seed).unwrap();
+            // the solution is one of the paths between two cells, the longest path the longest
+            prop_assert!(longest_path(&maze) as usize >= solution_length(&maze));
+            prop_assert!(longest_path(&maze) as usize <= maze.tiles.len());
+            // a tree of n cells has n - 1 passages, at least one per corridor
+            let passages = (maze.tiles.len() - 1) as f32;
+            prop_assert!(passage_length(&maze) <= passages);
+            prop_assert!(river(&maze) <= maze.tiles.len() as f32);
+        }
+
+        #[test]
+        fn farthest_cells_span_perfect_mazes(
+            width in 1..=8u32,
+            height in 1..=8u32,
+            method in select(vec![MazeType::Wilson, MazeType::Kruskal]),
+            seed in any::<u64>(),
+        ) {
+            let (maze, _) = generate_maze(width, height, method, MazeWrap::None, seed).unwrap();
+            let (start, end, length) = farthest_cells(&maze).unwrap();
+            prop_assert_eq!(distances(&maze, start)[maze.get_index(end)], Some(length));
+            for y in 0..height as i32 {
+                for x in 0..width as i32 {
+                    let furthest = distances(&maze, Point::new(x, y)).into_iter().flatten().max();
+                    prop_assert!(furthest <= Some(length));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use crate::{
+    error::LabgenError,
+    image::{draw_maze, image_size, write_output, ImageOptions},
+    layout::PixelRect,
+    maze::Grid,
+    svg::pixel_rects,
+};
+use std::io::Write;
+
+// heights in millimeters of the mesh a maze is extruded into, every pixel of the image is a
+// millimeter square
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MeshOptions {
+    pub wall_height: f32,
+    pub base_thickness: f32,
+}
+
+impl Default for MeshOptions {
+    fn default() -> Self {
+        MeshOptions {
+            wall_height: 5.0,
+            base_thickness: 2.0,
+        }
+    }
+}
+
+type Triangle = [[f32; 3]; 3];
+
+// the twelve triangles of a box from the pixels of rect, between the heights bottom and top. the
+// image is flipped so the maze is not mirrored when seen from above, with y pointing up
+fn push_box(
+    triangles: &mut Vec<Triangle>,
+    rect: PixelRect,
+    image_height: u16,
+    bottom: f32,
+    top: f32,
+) {
+    let x = [rect.left as f32, (rect.left + rect.width) as f32];
+    let y = [
+        (image_height - rect.top - rect.height) as f32,
+        (image_height - rect.top) as f32,
+    ];
+    let z = [bottom, top];
+    let corner = |i: usize, j: usize, k: usize| [x[i], y[j], z[k]];
+
+    // every face as its corners counterclockwise seen from outside
+    let faces = [
+        [
+            corner(0, 0, 0),
+            corner(0, 1, 0),
+            corner(1, 1, 0),
+            corner(1, 0, 0),
+        ],
+        [
+            corner(0, 0, 1),
+            corner(1, 0, 1),
+            corner(1, 1, 1),
+            corner(0, 1, 1),
+        ],
+        [
+            corner(0, 0, 0),
+            corner(1, 0, 0),
+            corner(1, 0, 1),
+            corner(0, 0, 1),
+        ],
+        [
+            corner(0, 1, 0),
+            corner(0, 1, 1),
+            corner(1, 1, 1),
+            corner(1, 1, 0),
+        ],
+        [
+            corner(0, 0, 0),
+            corner(0, 0, 1),
+            corner(0, 1, 1),
+            corner(0, 1, 0),
+        ],
+        [
+            corner(1, 0, 0),
+            corner(1, 1, 0),
+            corner(1, 1, 1),
+            corner(1, 0, 1),
+        ],
+    ];
+    for [a, b, c, d] in faces {
+        triangles.push([a, b, c]);
+        triangles.push([a, c, d]);
+    }
+}
+
+fn normal([a, b, c]: &Triangle) -> [f32; 3] {
+    let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+    let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+    let n = [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ];
+    let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+    n.map(|c| c / len)
+}
+
+// extrudes the walls of the png image into a binary stl mesh for 3d printing, standing on a base
+// plate under the whole image. excluded cells are extruded as drawn in the image: solid with the
+// wall style, as ridges with the hatch style, as floor with the color style and cut out of the
+// base when transparent. walls are boxes reaching through the base, slicers merge the
+// overlapping shells
+pub fn render_stl(
+    maze: &Grid,
+    opts: &ImageOptions,
+    mesh_opts: &MeshOptions,
+    out: &mut dyn Write,
+) -> Result<(), LabgenError> {
+    let (width, height) = image_size(maze, &opts.layout())?;
+    let plain = ImageOptions {
+        solution: None,
+        labels: Vec::new(),
+        rulers: false,
+        ..opts.clone()
+    };
+    let pixels = draw_maze(maze, &plain, |_| 1)?;
+    let transparent = opts.transparent();
+    let solid: Vec<u8> = pixels
+        .iter()
+        .map(|p| (Some(*p) != transparent) as u8)
+        .collect();
+
+    let top = mesh_opts.base_thickness + mesh_opts.wall_height;
+    let mut triangles: Vec<Triangle> = Vec::new();
+    for (rect, _) in pixel_rects(&solid, width, |i| solid[i] == 1) {
+        push_box(&mut triangles, rect, height, 0.0, mesh_opts.base_thickness);
+    }
+    for (rect, _) in pixel_rects(&pixels, width, |i| pixels[i] == 0) {
+        push_box(&mut triangles, rect, height, 0.0, top);
+    }
+
+    // 80 byte header, which must not start with solid as that marks the text format
+    let mut header = format!(
+        "labgen {}x{} {} maze, seed {}",
+        maze.width, maze.height, maze.params.method, maze.params.seed
+    )
+    .into_bytes();
+    header.resize(80, b' ');
+    out.write_all(&header)?;
+    out.write_all(&(triangles.len() as u32).to_le_bytes())?;
+    for triangle in &triangles {
+        for v in std::iter::once(normal(triangle)).chain(triangle.iter().copied()) {
+            for c in v {
+                out.write_all(&c.to_le_bytes())?;
+            }
+        }
+        out.write_all(&0u16.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+pub fn generate_stl(
+    maze: &Grid,
+    opts: &ImageOptions,
+    mesh_opts: &MeshOptions,
+) -> Result<(), LabgenError> {
+    write_output(&opts.file_path, "stl", |out| {
+        render_stl(maze, opts, mesh_opts, out)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{generate_maze, MazeType, MazeWrap};
+
+    #[test]
+    fn boxes_have_outward_faces() {
+        let mut triangles = Vec::new();
+        let rect = PixelRect {
+            left: 1,
+            top: 2,
+            width: 3,
+            height: 4,
+        };
+        push_box(&mut triangles, rect, 10, 0.0, 5.0);
+        assert_eq!(triangles.len(), 12);
+        // every normal points away from the center of the box
+        let center = [2.5, 6.0, 2.5];
+        for triangle in &triangles {
+            let n = normal(triangle);
+            let outward: f32 = (0..3).map(|i| n[i] * (triangle[0][i] - center[i])).sum();
+            assert!(outward > 0.0, "{:?} faces inward", triangle);
+        }
+    }
+
+    #[test]
+    fn meshes_count_their_triangles() {
+        let (maze, _) = generate_maze(4, 4, MazeType::Wilson, MazeWrap::None, 2).unwrap();
+        let mut out = Vec::new();
+        render_stl(
+            &maze,
+            &ImageOptions::default(),
+            &MeshOptions::default(),
+            &mut out,
+        )
+        .unwrap();
+        // the binary format would be read as text after a header starting with solid
+        assert!(!out.starts_with(b"solid"));
+        let count = u32::from_le_bytes(out[80..84].try_into().unwrap()) as usize;
+        assert!(count > 0);
+        assert_eq!(out.len(), 84 + 50 * count);
+    }
+}
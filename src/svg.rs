@@ -0,0 +1,198 @@
+use crate::{
+    error::LabgenError,
+    image::{
+        animation_steps, draw_exclusions, draw_labels, draw_openings, encoding_progress, fill_rect,
+        image_size, solution_text, write_output, AnimationOptions, ImageOptions,
+    },
+    layout::PixelRect,
+    maze::{Grid, MazeAction},
+};
+use std::{collections::HashMap, io::Write};
+
+// pixels for which keep holds as rects of a single palette index. each row is split into runs of
+// one color, and a run spanning the same columns as one on the row above extends that rect down
+pub(crate) fn pixel_rects(
+    pixels: &[u8],
+    width: u16,
+    keep: impl Fn(usize) -> bool,
+) -> Vec<(PixelRect, u8)> {
+    let mut rects: Vec<(PixelRect, u8)> = Vec::new();
+    // rects reaching down to the previous row, by their columns and color
+    let mut open: HashMap<(u16, u16, u8), usize> = HashMap::new();
+
+    for (y, row) in pixels.chunks_exact(width as usize).enumerate() {
+        let mut reaching: HashMap<(u16, u16, u8), usize> = HashMap::new();
+        let mut x = 0;
+        while x < row.len() {
+            let index = y * width as usize + x;
+            if !keep(index) {
+                x += 1;
+                continue;
+            }
+            let color = row[x];
+            let run = row[x..]
+                .iter()
+                .enumerate()
+                .take_while(|(i, c)| **c == color && keep(index + i))
+                .count();
+
+            let key = (x as u16, run as u16, color);
+            let rect = match open.get(&key) {
+                Some(&rect) => {
+                    rects[rect].0.height += 1;
+                    rect
+                }
+                None => {
+                    let rect = PixelRect {
+                        left: x as u16,
+                        top: y as u16,
+                        width: run as u16,
+                        height: 1,
+                    };
+                    rects.push((rect, color));
+                    rects.len() - 1
+                }
+            };
+            reaching.insert(key, rect);
+            x += run;
+        }
+        open = reaching;
+    }
+
+    rects
+}
+
+fn write_rects(
+    out: &mut dyn Write,
+    rects: &[(PixelRect, u8)],
+    palette: &[u8],
+) -> Result<(), LabgenError> {
+    for (rect, color) in rects {
+        let rgb = &palette[*color as usize * 3..*color as usize * 3 + 3];
+        writeln!(
+            out,
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#{:02x}{:02x}{:02x}\"/>",
+            rect.left, rect.top, rect.width, rect.height, rgb[0], rgb[1], rgb[2]
+        )?;
+    }
+    Ok(())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+// hides the element it is placed in until at, given in hundredths of a second into an animation
+// looping every total hundredths. elements shown from the start are left alone, so viewers
+// without animation support show the finished maze
+fn write_reveal(out: &mut dyn Write, at: u64, total: u64) -> Result<(), LabgenError> {
+    if at == 0 {
+        return Ok(());
+    }
+    writeln!(
+        out,
+        "<animate attributeName=\"opacity\" values=\"0;1\" keyTimes=\"0;{}\" calcMode=\"discrete\" \
+        dur=\"{}ms\" repeatCount=\"indefinite\"/>",
+        at as f64 / total as f64,
+        total * 10
+    )?;
+    Ok(())
+}
+
+// plays the generation as an svg animation with the timing of the gif written with the same
+// options. every batch of cells is a group of rects revealed when its frame would be shown, drawn
+// over the walls and excluded cells that make up the first frame
+pub fn render_svg(
+    maze: &Grid,
+    history: &[MazeAction],
+    opts: &ImageOptions,
+    ani_opts: &AnimationOptions,
+    out: &mut dyn Write,
+) -> Result<(), LabgenError> {
+    let layout = opts.layout();
+    let (width, height) = image_size(maze, &layout)?;
+    let palette = opts.palette();
+
+    let mut state: Vec<u8> = vec![0; width as usize * height as usize];
+    draw_exclusions(&mut state, width, 0, 0, maze, opts);
+
+    // the solver is played back once the maze is finished
+    let steps = animation_steps(maze, history, opts);
+
+    // openings and labels are drawn over the finished maze, only the pixels they change are kept
+    let mut finished = state.clone();
+    for (rect, color) in steps.iter().flatten() {
+        fill_rect(&mut finished, width, *rect, *color);
+    }
+    let mut labeled = finished.clone();
+    draw_openings(&mut labeled, width, maze, opts);
+    draw_labels(&mut labeled, width, maze, opts);
+
+    let batch_size = ani_opts.batch_size as usize;
+    let frame_time = ani_opts.frame_time as u64;
+    // an unfinished last batch appears together with the final frame
+    let end = (steps.len() / batch_size) as u64 * frame_time;
+    let total = end + ani_opts.pause_time as u64;
+
+    writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" \
+        viewBox=\"0 0 {0} {1}\" shape-rendering=\"crispEdges\">",
+        width, height
+    )?;
+    writeln!(
+        out,
+        "<title>{}x{} {} maze</title>",
+        maze.width, maze.height, maze.params.method
+    )?;
+    writeln!(out, "<desc>{}</desc>", escape(&maze.metadata().comment()))?;
+    // metadata is never drawn, the path is only there for programs checking the puzzle
+    if let Some(path) = solution_text(opts) {
+        writeln!(out, "<metadata id=\"solution\">{}</metadata>", path)?;
+    }
+
+    let transparent = opts.transparent();
+    write_rects(
+        out,
+        &pixel_rects(&state, width, |i| Some(state[i]) != transparent),
+        &palette,
+    )?;
+
+    let progress = encoding_progress(ani_opts, steps.len().div_ceil(batch_size) as u64);
+    for (frame, batch) in steps.chunks(batch_size).enumerate() {
+        if progress.cancelled() {
+            break;
+        }
+        writeln!(out, "<g>")?;
+        write_reveal(out, frame as u64 * frame_time, total)?;
+        let rects: Vec<(PixelRect, u8)> = batch.iter().flatten().copied().collect();
+        write_rects(out, &rects, &palette)?;
+        writeln!(out, "</g>")?;
+        progress.inc();
+    }
+
+    let final_rects = pixel_rects(&labeled, width, |i| labeled[i] != finished[i]);
+    if !final_rects.is_empty() {
+        writeln!(out, "<g>")?;
+        write_reveal(out, end, total)?;
+        write_rects(out, &final_rects, &palette)?;
+        writeln!(out, "</g>")?;
+    }
+
+    writeln!(out, "</svg>")?;
+    progress.finish();
+    Ok(())
+}
+
+pub fn generate_svg(
+    maze: &Grid,
+    history: &[MazeAction],
+    opts: &ImageOptions,
+    ani_opts: &AnimationOptions,
+) -> Result<(), LabgenError> {
+    write_output(&opts.file_path, "svg", |out| {
+        render_svg(maze, history, opts, ani_opts, out)
+    })
+}
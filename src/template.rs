@@ -0,0 +1,574 @@
+use crate::maze::{ConnectionStatus, Direction, Grid, Point};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplateCell {
+    // left for the generation method to carve
+    Maze,
+    // open area without walls between its cells, joined to the rest of the maze by the method
+    Room,
+    Excluded,
+}
+
+// layout of a maze drawn as text, one character per cell: . for maze, R for room and # for
+// excluded cells. shorter lines are padded with maze cells
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    pub width: u32,
+    pub height: u32,
+    pub cells: Vec<TemplateCell>,
+}
+
+impl Template {
+    pub fn has(&self, cell: TemplateCell) -> bool {
+        self.cells.contains(&cell)
+    }
+
+    fn get(&self, x: u32, y: u32) -> TemplateCell {
+        self.cells[x as usize + y as usize * self.width as usize]
+    }
+
+    // cell covering pt once laid out with scale, cells beyond the template are maze cells
+    pub fn cell_at(&self, pt: Point, scale: u32) -> TemplateCell {
+        let (x, y) = (pt.x as u32 / scale, pt.y as u32 / scale);
+        if x < self.width && y < self.height {
+            self.get(x, y)
+        } else {
+            TemplateCell::Maze
+        }
+    }
+}
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines: Vec<&str> = s.lines().map(|l| l.trim_end()).collect();
+        let height = lines.len();
+        let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+        if width == 0 {
+            return Err("template is empty".to_string());
+        }
+        if width > u32::MAX as usize || height > u32::MAX as usize {
+            return Err("template is larger than the largest maze".to_string());
+        }
+
+        let mut cells = vec![TemplateCell::Maze; width * height];
+        for (y, line) in lines.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                cells[x + y * width] = match c {
+                    '.' => TemplateCell::Maze,
+                    'R' => TemplateCell::Room,
+                    '#' => TemplateCell::Excluded,
+                    c => {
+                        return Err(format!(
+                            "unknown template cell '{}' on line {}, expected ., R or #",
+                            c,
+                            y + 1
+                        ))
+                    }
+                };
+            }
+        }
+
+        Ok(Template {
+            width: width as u32,
+            height: height as u32,
+            cells,
+        })
+    }
+}
+
+// how the rooms of a template are joined to the maze around them
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
+pub enum RoomDoors {
+    // the generation method grows the maze into rooms wherever it reaches them
+    #[default]
+    Auto,
+    // rooms stay sealed and the maze is grown around them
+    None,
+    // rooms are sealed during generation and opened at these walls afterwards, each given by a
+    // room cell and the direction of the wall
+    List(Vec<(Point, Direction)>),
+    // rooms are sealed during generation and each given count doors afterwards, taking the sides
+    // in turn so no side gets a second door before every side has one. only the listed sides get
+    // doors, all four when the list is empty
+    Even {
+        count: u32,
+        sides: Vec<Direction>,
+    },
+}
+
+const DOOR_DIRECTIONS: [(&str, Direction); 4] = [
+    ("north", Direction::North),
+    ("east", Direction::East),
+    ("south", Direction::South),
+    ("west", Direction::West),
+];
+
+fn direction(name: &str) -> Option<Direction> {
+    DOOR_DIRECTIONS
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, dir)| *dir)
+}
+
+fn direction_name(dir: Direction) -> &'static str {
+    DOOR_DIRECTIONS.iter().find(|(_, d)| *d == dir).unwrap().0
+}
+
+// auto, none, even:count with an optional :side,side,... or doors written as x,y,direction
+// separated by ;
+impl FromStr for RoomDoors {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => return Ok(RoomDoors::Auto),
+            "none" => return Ok(RoomDoors::None),
+            _ => (),
+        }
+
+        let invalid = |reason: &str| format!("invalid room doors \"{}\": {}", s, reason);
+        if let Some(even) = s.strip_prefix("even:") {
+            let (count, sides) = even.split_once(':').unwrap_or((even, ""));
+            let count = match count.trim().parse::<u32>() {
+                Ok(0) => return Err(invalid("rooms need at least one door")),
+                Ok(count) => count,
+                Err(e) => return Err(invalid(&e.to_string())),
+            };
+            let mut listed = Vec::new();
+            let sides = sides.trim();
+            for side in sides.split(',').map(|side| side.trim()) {
+                if sides.is_empty() {
+                    break;
+                }
+                let dir = direction(side)
+                    .ok_or_else(|| invalid("sides must be north, east, south or west"))?;
+                if listed.contains(&dir) {
+                    return Err(invalid(&format!("{} is listed twice", side)));
+                }
+                listed.push(dir);
+            }
+            return Ok(RoomDoors::Even {
+                count,
+                sides: listed,
+            });
+        }
+
+        let door = |door: &str| {
+            let parts: Vec<&str> = door.split(',').map(|p| p.trim()).collect();
+            let [x, y, dir] = parts[..] else {
+                return Err(invalid("expected auto, none or x,y,direction;..."));
+            };
+            let coord = |v: &str| match v.parse::<i32>() {
+                Ok(v) if v >= 0 => Ok(v),
+                Ok(_) => Err(invalid("negative coordinate")),
+                Err(e) => Err(invalid(&e.to_string())),
+            };
+            let dir = direction(dir)
+                .ok_or_else(|| invalid("direction must be north, east, south or west"))?;
+            Ok((Point::new(coord(x)?, coord(y)?), dir))
+        };
+        s.split(';')
+            .map(door)
+            .collect::<Result<_, _>>()
+            .map(RoomDoors::List)
+    }
+}
+
+impl fmt::Display for RoomDoors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoomDoors::Auto => write!(f, "auto"),
+            RoomDoors::None => write!(f, "none"),
+            RoomDoors::List(doors) => {
+                let doors: Vec<String> = doors
+                    .iter()
+                    .map(|(pt, dir)| format!("{},{},{}", pt.x, pt.y, direction_name(*dir)))
+                    .collect();
+                write!(f, "{}", doors.join(";"))
+            }
+            RoomDoors::Even { count, sides } if sides.is_empty() => write!(f, "even:{}", count),
+            RoomDoors::Even { count, sides } => {
+                let sides: Vec<&str> = sides.iter().map(|dir| direction_name(*dir)).collect();
+                write!(f, "even:{}:{}", count, sides.join(","))
+            }
+        }
+    }
+}
+
+impl TryFrom<String> for RoomDoors {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<RoomDoors> for String {
+    fn from(doors: RoomDoors) -> Self {
+        doors.to_string()
+    }
+}
+
+// lays the template over the top left of the grid with every character covering scale by scale
+// cells. excluded cells are removed and neighboring room cells are joined into open areas that
+// are already part of the maze, so methods that support patterns grow the maze around them
+pub fn apply_template(maze: &mut Grid, template: &Template, scale: u32) {
+    let cell = |pt: Point| template.get(pt.x as u32 / scale, pt.y as u32 / scale);
+    let width = maze.width.min(template.width.saturating_mul(scale));
+    let height = maze.height.min(template.height.saturating_mul(scale));
+
+    for y in 0..height as i32 {
+        for x in 0..width as i32 {
+            let pt = Point::new(x, y);
+            match cell(pt) {
+                TemplateCell::Maze => (),
+                TemplateCell::Excluded => {
+                    maze.get_tile_mut(pt).set_status(ConnectionStatus::Removed)
+                }
+                TemplateCell::Room => {
+                    maze.get_tile_mut(pt).set_status(ConnectionStatus::InMaze);
+                    for dir in [Direction::East, Direction::South] {
+                        let next = pt.travel(dir);
+                        if next.x < width as i32
+                            && next.y < height as i32
+                            && cell(next) == TemplateCell::Room
+                        {
+                            maze.get_tile_mut(pt).connect(dir);
+                            maze.get_tile_mut(next).connect(dir.opposite());
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// cells of the grid covered by room cells of the template, as laid out by apply_template
+pub fn room_cells(maze: &Grid, template: &Template, scale: u32) -> Vec<Point> {
+    let width = maze.width.min(template.width.saturating_mul(scale));
+    let height = maze.height.min(template.height.saturating_mul(scale));
+    (0..height as i32)
+        .flat_map(|y| (0..width as i32).map(move |x| Point::new(x, y)))
+        .filter(|pt| template.get(pt.x as u32 / scale, pt.y as u32 / scale) == TemplateCell::Room)
+        .collect()
+}
+
+// removes the rooms laid out by apply_template from the grid while the maze is generated, keeping
+// the passages inside them, so the maze grows around them without entering
+pub fn seal_rooms(maze: &mut Grid, template: &Template, scale: u32) {
+    for pt in room_cells(maze, template, scale) {
+        maze.get_tile_mut(pt).set_status(ConnectionStatus::Removed);
+    }
+}
+
+// puts rooms sealed by seal_rooms back into the generated maze and opens the listed doors, which
+// must lead from a room cell to a maze cell. returns the opened walls, to be added to the history
+pub fn place_doors(
+    maze: &mut Grid,
+    template: &Template,
+    scale: u32,
+    doors: &[(Point, Direction)],
+) -> Result<Vec<(Point, Direction)>, String> {
+    let rooms = room_cells(maze, template, scale);
+    for pt in &rooms {
+        maze.get_tile_mut(*pt).set_status(ConnectionStatus::InMaze);
+    }
+
+    let mut opened = Vec::new();
+    for (pt, dir) in doors {
+        let next = maze.travel(*pt, *dir);
+        if !rooms.contains(pt) || rooms.contains(&next) {
+            return Err(format!(
+                "the door at {},{} does not lead out of a room",
+                pt.x, pt.y
+            ));
+        }
+        // listed twice
+        if maze.get_tile(*pt).connected(*dir) {
+            continue;
+        }
+        if !maze.contains(next) || maze.carve(*pt, *dir).is_none() {
+            return Err(format!(
+                "the door at {},{} does not lead into the maze",
+                pt.x, pt.y
+            ));
+        }
+        opened.push((*pt, *dir));
+    }
+    Ok(opened)
+}
+
+// walls leading out of each room of the template into the maze, count per room spread evenly over
+// its sides: every round gives each side that still has walls left one door, picked at random
+// with seed. rooms are the areas of joined room cells, a room with fewer walls gets all of them
+pub fn even_doors(
+    maze: &Grid,
+    template: &Template,
+    scale: u32,
+    count: u32,
+    sides: &[Direction],
+    seed: u64,
+) -> Vec<(Point, Direction)> {
+    let sides = if sides.is_empty() {
+        &Direction::ALL[..]
+    } else {
+        sides
+    };
+    let in_room =
+        |pt: Point| maze.contains(pt) && template.cell_at(pt, scale) == TemplateCell::Room;
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut reached = vec![false; maze.tiles.len()];
+    let mut doors = Vec::new();
+    for start in room_cells(maze, template, scale) {
+        if reached[maze.get_index(start)] {
+            continue;
+        }
+        reached[maze.get_index(start)] = true;
+
+        // the walls of the room by side, each in random order
+        let mut walls: Vec<Vec<Point>> = vec![Vec::new(); sides.len()];
+        let mut open = vec![start];
+        while let Some(pt) = open.pop() {
+            for dir in Direction::ALL {
+                let next = maze.travel(pt, dir);
+                if in_room(next) {
+                    if !reached[maze.get_index(next)] {
+                        reached[maze.get_index(next)] = true;
+                        open.push(next);
+                    }
+                } else if let Some(side) = sides.iter().position(|d| *d == dir) {
+                    if maze.contains(next) && !maze.is_removed(next) {
+                        walls[side].push(pt);
+                    }
+                }
+            }
+        }
+        for side in walls.iter_mut() {
+            for i in 0..side.len() {
+                let index = rng.gen_range(i..side.len());
+                side.swap(i, index);
+            }
+        }
+
+        let mut placed = 0;
+        while placed < count && walls.iter().any(|side| !side.is_empty()) {
+            for (side, dir) in walls.iter_mut().zip(sides) {
+                if placed == count {
+                    break;
+                }
+                if let Some(pt) = side.pop() {
+                    doors.push((pt, *dir));
+                    placed += 1;
+                }
+            }
+        }
+    }
+    doors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        maze::{carve_maze, MazeType, MazeWrap},
+        progress::Progress,
+    };
+    use proptest::{prelude::*, sample::select};
+
+    #[test]
+    fn templates_pad_short_lines_with_maze_cells() {
+        let template: Template = ".R\n#".parse().unwrap();
+        assert_eq!((template.width, template.height), (2, 2));
+        assert_eq!(
+            template.cells,
+            [
+                TemplateCell::Maze,
+                TemplateCell::Room,
+                TemplateCell::Excluded,
+                TemplateCell::Maze
+            ]
+        );
+        // cells beyond the template are left to the maze
+        assert_eq!(template.cell_at(Point::new(3, 1), 2), TemplateCell::Room);
+        assert_eq!(template.cell_at(Point::new(4, 0), 2), TemplateCell::Maze);
+    }
+
+    #[test]
+    fn unknown_template_cells_are_refused() {
+        assert_eq!(
+            "..\n.x".parse::<Template>(),
+            Err("unknown template cell 'x' on line 2, expected ., R or #".to_string())
+        );
+        assert!("\n".parse::<Template>().is_err());
+    }
+
+    #[test]
+    fn room_doors_parse_back() {
+        for text in [
+            "auto",
+            "none",
+            "1,2,north",
+            "0,0,west;3,4,south",
+            "even:2",
+            "even:3:north,west",
+        ] {
+            let doors: RoomDoors = text.parse().unwrap();
+            assert_eq!(doors.to_string(), text);
+        }
+        assert_eq!(
+            "1, 2, east".parse(),
+            Ok(RoomDoors::List(vec![(Point::new(1, 2), Direction::East)]))
+        );
+        for text in [
+            "1,2",
+            "1,2,up",
+            "-1,2,east",
+            "1,x,east",
+            "even:0",
+            "even:x",
+            "even:2:up",
+            "even:2:north,north",
+        ] {
+            assert!(text.parse::<RoomDoors>().is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn doors_lead_from_a_room_into_the_maze() {
+        let template: Template = ".R.".parse().unwrap();
+        let mut maze = Grid::new(3, 1, MazeWrap::None, 0, MazeType::Kruskal);
+        apply_template(&mut maze, &template, 1);
+        seal_rooms(&mut maze, &template, 1);
+        let (mut maze, _) = carve_maze(maze, &Progress::hidden());
+        let west = (Point::new(1, 0), Direction::West);
+        assert_eq!(
+            place_doors(
+                &mut maze.clone(),
+                &template,
+                1,
+                &[(Point::new(0, 0), Direction::East)]
+            ),
+            Err("the door at 0,0 does not lead out of a room".to_string())
+        );
+        assert_eq!(
+            place_doors(&mut maze, &template, 1, &[west, west]),
+            Ok(vec![west])
+        );
+        assert!(maze.get_tile(Point::new(0, 0)).connected(Direction::East));
+    }
+
+    #[test]
+    fn even_doors_take_the_sides_in_turn() {
+        let template: Template = ".....\n.RR..\n.RR..\n.....\n....R".parse().unwrap();
+        let mut maze = Grid::new(5, 5, MazeWrap::None, 7, MazeType::Kruskal);
+        apply_template(&mut maze, &template, 1);
+        seal_rooms(&mut maze, &template, 1);
+        let sides = |doors: &[(Point, Direction)], room: Point| {
+            let mut sides: Vec<Direction> = doors
+                .iter()
+                .filter(|(pt, _)| (pt.x - room.x).abs() <= 1 && (pt.y - room.y).abs() <= 1)
+                .map(|(_, dir)| *dir)
+                .collect();
+            sides.sort_by_key(|dir| Direction::ALL.iter().position(|d| d == dir));
+            sides
+        };
+
+        let doors = even_doors(&maze, &template, 1, 4, &[], 1);
+        assert_eq!(sides(&doors, Point::new(1, 1)), Direction::ALL);
+        // the corner room only has two walls
+        assert_eq!(
+            sides(&doors, Point::new(4, 4)),
+            [Direction::North, Direction::West]
+        );
+
+        let doors = even_doors(&maze, &template, 1, 5, &[], 1);
+        assert_eq!(sides(&doors, Point::new(1, 1)).len(), 5);
+        let doors = even_doors(&maze, &template, 1, 3, &[Direction::South], 1);
+        assert_eq!(
+            sides(&doors, Point::new(1, 1)),
+            [Direction::South, Direction::South]
+        );
+
+        let (mut maze, _) = carve_maze(maze, &Progress::hidden());
+        let doors = even_doors(&maze, &template, 1, 2, &[], 1);
+        assert_eq!(place_doors(&mut maze, &template, 1, &doors), Ok(doors));
+    }
+
+    proptest! {
+        #[test]
+        fn template_rooms_join_a_single_maze(
+            rows in prop::collection::vec("[.R]{1,8}", 1..8),
+            scale in 1..=3u32,
+            method in select(vec![MazeType::Wilson, MazeType::Kruskal]),
+            wrap in any::<MazeWrap>(),
+            seed in any::<u64>(),
+        ) {
+            let template: Template = rows.join("\n").parse().unwrap();
+            let mut maze = Grid::new(
+                template.width * scale,
+                template.height * scale,
+                wrap,
+                seed,
+                method,
+            );
+            apply_template(&mut maze, &template, scale);
+            let (maze, history) = carve_maze(maze, &Progress::hidden());
+            prop_assert!(history.agrees_with(&maze));
+
+            let mut reached = vec![false; maze.tiles.len()];
+            let mut open = vec![Point::new(0, 0)];
+            reached[0] = true;
+            while let Some(pt) = open.pop() {
+                for dir in Direction::ALL {
+                    let next = maze.travel(pt, dir);
+                    if maze.get_tile(pt).connected(dir) && !reached[maze.get_index(next)] {
+                        reached[maze.get_index(next)] = true;
+                        open.push(next);
+                    }
+                }
+            }
+            prop_assert!(reached.iter().all(|r| *r));
+        }
+
+        #[test]
+        fn sealed_rooms_keep_their_walls(
+            rows in prop::collection::vec("[.R]{1,8}", 1..8),
+            scale in 1..=3u32,
+            method in select(vec![MazeType::Wilson, MazeType::Kruskal]),
+            seed in any::<u64>(),
+        ) {
+            let template: Template = rows.join("\n").parse().unwrap();
+            let mut maze = Grid::new(
+                template.width * scale,
+                template.height * scale,
+                MazeWrap::None,
+                seed,
+                method,
+            );
+            apply_template(&mut maze, &template, scale);
+            seal_rooms(&mut maze, &template, scale);
+            let (mut maze, history) = carve_maze(maze, &Progress::hidden());
+            prop_assert!(place_doors(&mut maze, &template, scale, &[]).unwrap().is_empty());
+            prop_assert!(history.agrees_with(&maze));
+
+            let room = |pt: Point| {
+                template.cells[(pt.x as u32 / scale + pt.y as u32 / scale * template.width) as usize]
+                    == TemplateCell::Room
+            };
+            for pt in maze.scan_cells() {
+                prop_assert!(!maze.is_removed(pt));
+                for dir in Direction::ALL.into_iter().filter(|d| maze.get_tile(pt).connected(*d)) {
+                    prop_assert_eq!(room(pt), room(maze.travel(pt, dir)));
+                }
+            }
+        }
+    }
+}
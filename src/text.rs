@@ -0,0 +1,193 @@
+use crate::{
+    error::LabgenError,
+    image::write_output,
+    maze::{Direction, Grid, Point},
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{fmt, io::Write, str::FromStr};
+
+// glyphs of the wall corners, indexed by the walls meeting at a corner with the bits of the
+// directions they leave it in. walls between corners use the glyphs of a straight corner
+pub const TILE_MAPS: [(&str, &str); 6] = [
+    ("ascii", " |-+||++-+-+++++"),
+    ("light", " ╵╶└╷│┌├╴┘─┴┐┤┬┼"),
+    ("heavy", " ╹╺┗╻┃┏┣╸┛━┻┓┫┳╋"),
+    ("double", " ║═╚║║╔╠═╝═╩╗╣╦╬"),
+    ("rounded", " ╵╶╰╷│╭├╴╯─┴╮┤┬┼"),
+    ("block", " ███████████████"),
+];
+
+// glyphs drawing the walls of text mazes, one of the TILE_MAPS by name or 16 custom characters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
+pub struct TileMap(pub [char; 16]);
+
+impl Default for TileMap {
+    fn default() -> Self {
+        "light".parse().unwrap()
+    }
+}
+
+impl FromStr for TileMap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let glyphs = TILE_MAPS
+            .iter()
+            .find(|(name, _)| *name == s)
+            .map_or(s, |(_, glyphs)| glyphs);
+        let chars: Vec<char> = glyphs.chars().collect();
+        chars.try_into().map(TileMap).map_err(|_| {
+            let names: Vec<&str> = TILE_MAPS.iter().map(|(name, _)| *name).collect();
+            format!(
+                "invalid text style \"{}\": expected one of {} or 16 characters",
+                s,
+                names.join(", ")
+            )
+        })
+    }
+}
+
+// the name of a built in map, otherwise its characters
+impl fmt::Display for TileMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let glyphs: String = self.0.iter().collect();
+        match TILE_MAPS.iter().find(|(_, g)| *g == glyphs) {
+            Some((name, _)) => write!(f, "{}", name),
+            None => write!(f, "{}", glyphs),
+        }
+    }
+}
+
+impl TryFrom<String> for TileMap {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<TileMap> for String {
+    fn from(map: TileMap) -> Self {
+        map.to_string()
+    }
+}
+
+// columns of text taken by the inside of a cell, about as wide as the two rows of a cell are high
+const CELL_COLUMNS: usize = 3;
+
+// whether a wall separates the cell at pt from its neighbor in dir. cells that are removed or
+// outside the grid have no walls between them, the outer wall is open where a passage leaves the
+// grid like an opening or a passage across a wrapped edge
+fn wall(maze: &Grid, pt: Point, dir: Direction) -> bool {
+    let next = pt.travel(dir);
+    let present = |pt: Point| maze.contains(pt) && !maze.is_removed(pt);
+    match (present(pt), present(next)) {
+        (false, false) => false,
+        (true, _) => !maze.get_tile(pt).connected(dir),
+        (false, true) => !maze.get_tile(next).connected(dir.opposite()),
+    }
+}
+
+// draws the walls of the maze with box drawing characters, every cell taking two rows of text
+// with the corners and walls above it on the first. excluded cells are left blank
+pub fn render_text(maze: &Grid, map: &TileMap, out: &mut dyn Write) -> Result<(), LabgenError> {
+    let horizontal = map.0[Direction::East as usize | Direction::West as usize];
+    let vertical = map.0[Direction::North as usize | Direction::South as usize];
+
+    for y in 0..=maze.height as i32 {
+        // corners are named after the cell to their bottom right
+        let mut corners = String::new();
+        let mut cells = String::new();
+        for x in 0..=maze.width as i32 {
+            let pt = Point::new(x, y);
+            let up_left = Point::new(x - 1, y - 1);
+            let mut walls = 0;
+            if wall(maze, up_left, Direction::East) {
+                walls |= Direction::North as usize;
+            }
+            let above = wall(maze, pt, Direction::North);
+            if above {
+                walls |= Direction::East as usize;
+            }
+            if wall(maze, pt.travel(Direction::West), Direction::East) {
+                walls |= Direction::South as usize;
+            }
+            if wall(maze, up_left, Direction::South) {
+                walls |= Direction::West as usize;
+            }
+            corners.push(map.0[walls]);
+
+            if x < maze.width as i32 {
+                let glyph = if above { horizontal } else { ' ' };
+                corners.extend(std::iter::repeat_n(glyph, CELL_COLUMNS));
+            }
+            let left = wall(maze, pt, Direction::West);
+            cells.push(if left { vertical } else { ' ' });
+            cells.extend(std::iter::repeat_n(' ', CELL_COLUMNS));
+        }
+        writeln!(out, "{}", corners.trim_end())?;
+        if y < maze.height as i32 {
+            writeln!(out, "{}", cells.trim_end())?;
+        }
+    }
+    Ok(())
+}
+
+pub fn generate_text(maze: &Grid, file_path: &str, map: &TileMap) -> Result<(), LabgenError> {
+    write_output(file_path, "txt", |out| render_text(maze, map, out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{generate_maze, ConnectionStatus, MazeType, MazeWrap};
+    use proptest::prelude::*;
+
+    fn text(maze: &Grid, map: &str) -> String {
+        let mut out = Vec::new();
+        render_text(maze, &map.parse().unwrap(), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn tile_maps_are_named_or_spelled_out() {
+        assert_eq!(TileMap::default().to_string(), "light");
+        let custom: TileMap = "0123456789abcdef".parse().unwrap();
+        assert_eq!(custom.0[10], 'a');
+        assert_eq!(custom.to_string(), "0123456789abcdef");
+        assert!("thin"
+            .parse::<TileMap>()
+            .unwrap_err()
+            .contains("ascii, light"));
+    }
+
+    #[test]
+    fn cells_take_two_rows() {
+        let (maze, _) = generate_maze(1, 1, MazeType::Wilson, MazeWrap::None, 0).unwrap();
+        assert_eq!(text(&maze, "ascii"), "+---+\n|   |\n+---+\n");
+        assert_eq!(text(&maze, "light"), "┌───┐\n│   │\n└───┘\n");
+    }
+
+    #[test]
+    fn passages_open_the_walls_between_cells() {
+        let mut maze = Grid::new(2, 1, MazeWrap::None, 0, MazeType::default());
+        maze.carve(Point::new(0, 0), Direction::East).unwrap();
+        assert_eq!(text(&maze, "light"), "┌───────┐\n│       │\n└───────┘\n");
+        let mut removed = Grid::new(2, 1, MazeWrap::None, 0, MazeType::default());
+        removed
+            .get_tile_mut(Point::new(1, 0))
+            .set_status(ConnectionStatus::Removed);
+        assert_eq!(text(&removed, "light"), "┌───┐\n│   │\n└───┘\n");
+    }
+
+    proptest! {
+        #[test]
+        fn tile_maps_parse_back(glyphs in prop::array::uniform16(any::<char>())) {
+            let map = TileMap(glyphs);
+            prop_assert_eq!(map.to_string().parse::<TileMap>(), Ok(map));
+        }
+    }
+}
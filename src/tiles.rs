@@ -0,0 +1,329 @@
+use crate::{
+    error::LabgenError,
+    image::{cell_width, draw_maze, write_png, ImageOptions},
+    maze::{Grid, Rect},
+    progress::Progress,
+};
+use rayon::prelude::*;
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+// square tiles of size pixels cutting up the image of a maze too large to render at once. each
+// tile also shows overlap pixels of its neighbors on every side
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TileOptions {
+    pub size: u16,
+    pub overlap: u16,
+    // show a bar while writing the tiles
+    pub progress: bool,
+}
+
+impl TileOptions {
+    // side of a tile image including the overlap, None if it does not fit in a u16
+    pub fn span(&self) -> Option<u16> {
+        self.size.checked_add(self.overlap.checked_mul(2)?)
+    }
+}
+
+// pixel size of the whole image, which can be larger than any single image
+fn full_size(maze: &Grid, opts: &ImageOptions) -> Result<(u64, u64), LabgenError> {
+    let layout = opts.layout();
+    let cell_width = cell_width(&layout)?;
+    let span = |cells: u32| cells as u64 * cell_width as u64 + layout.wall_width as u64;
+    Ok((span(maze.width), span(maze.height)))
+}
+
+// number of tile columns and rows covering the image
+pub fn tile_count(
+    maze: &Grid,
+    opts: &ImageOptions,
+    tile_opts: &TileOptions,
+) -> Result<(u32, u32), LabgenError> {
+    let (width, height) = full_size(maze, opts)?;
+    let size = tile_opts.size as u64;
+    Ok((width.div_ceil(size) as u32, height.div_ceil(size) as u32))
+}
+
+// renders the tile at column, row pixel for pixel as that part of the image of the whole maze,
+// with the wall color beyond its edges. tiles only show the maze and its excluded cells
+pub fn render_tile(
+    maze: &Grid,
+    opts: &ImageOptions,
+    tile_opts: &TileOptions,
+    column: u32,
+    row: u32,
+    out: &mut dyn Write,
+) -> Result<(), LabgenError> {
+    let span = tile_opts.span().ok_or_else(|| {
+        LabgenError::Render(format!(
+            "tiles of {} pixels with an overlap of {} are larger than {} pixels",
+            tile_opts.size,
+            tile_opts.overlap,
+            u16::MAX
+        ))
+    })?;
+    let (width, height) = full_size(maze, opts)?;
+    let cell_width = opts.layout().cell_width().unwrap() as u64;
+    let left = column as i64 * tile_opts.size as i64 - tile_opts.overlap as i64;
+    let top = row as i64 * tile_opts.size as i64 - tile_opts.overlap as i64;
+
+    // the cells drawn into the tile plus one more on every side, so passages and excluded areas
+    // crossing its edges are drawn as in the whole image
+    let cells = |start: i64, end: u64, count: u32| {
+        let first = (start.max(0) as u64 / cell_width).saturating_sub(1);
+        let last = ((end.max(1) - 1) / cell_width + 2).min(count as u64);
+        (first as u32, (last - first) as u32)
+    };
+    let right = ((left + span as i64).max(0) as u64).min(width);
+    let bottom = ((top + span as i64).max(0) as u64).min(height);
+    let (mut x, mut crop_width) = cells(left, right, maze.width);
+    let (mut y, mut crop_height) = cells(top, bottom, maze.height);
+    // the hatching of excluded cells repeats every 4 pixels along both axes, it only lines up with
+    // the whole image if the columns and rows left out by the crop add up to a multiple of 4
+    let extra = (x + y) % 4;
+    let (dx, dy) = (extra.min(x), extra - extra.min(x));
+    (x, crop_width, y, crop_height) = (x - dx, crop_width + dx, y - dy, crop_height + dy);
+    let part = maze
+        .crop(Rect {
+            x,
+            y,
+            width: crop_width,
+            height: crop_height,
+        })
+        .unwrap();
+    let plain = ImageOptions {
+        solution: None,
+        labels: Vec::new(),
+        rulers: false,
+        ..opts.clone()
+    };
+    let pixels = draw_maze(&part, &plain, |_| 1)?;
+    let part_width = opts.layout().image_size(part.width, part.height).unwrap().0 as usize;
+    let (origin_x, origin_y) = (x as i64 * cell_width as i64, y as i64 * cell_width as i64);
+
+    // rows and columns of the tile inside the image are copied, the rest stays wall
+    let mut tile: Vec<u8> = vec![0; span as usize * span as usize];
+    let (from, to) = (left.max(0), right as i64);
+    for ty in 0..span as i64 {
+        let py = top + ty;
+        if py < 0 || py >= bottom as i64 || from >= to {
+            continue;
+        }
+        let src = (py - origin_y) as usize * part_width;
+        let dst = ty as usize * span as usize;
+        tile[dst + (from - left) as usize..dst + (to - left) as usize].copy_from_slice(
+            &pixels[src + (from - origin_x) as usize..src + (to - origin_x) as usize],
+        );
+    }
+
+    write_png(
+        &tile,
+        span,
+        span,
+        &opts.palette(),
+        opts.transparent(),
+        maze.metadata().entries(),
+        out,
+    )
+}
+
+// writes every tile into the directory at opts.file_path as column_row.png, together with a
+// tiles.json manifest and an index.html that shows them in a leaflet map viewer
+pub fn generate_tiles(
+    maze: &Grid,
+    opts: &ImageOptions,
+    tile_opts: &TileOptions,
+) -> Result<(), LabgenError> {
+    let dir = Path::new(&opts.file_path);
+    fs::create_dir_all(dir).map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!("could not create directory {}: {}", dir.display(), e),
+        )
+    })?;
+    let create = |name: &str| -> Result<BufWriter<File>, LabgenError> {
+        let path = dir.join(name);
+        let file = File::create(&path).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("could not create {}: {}", path.display(), e),
+            )
+        })?;
+        Ok(BufWriter::new(file))
+    };
+
+    let (columns, rows) = tile_count(maze, opts, tile_opts)?;
+    let progress = if tile_opts.progress {
+        Progress::new("tiles", columns as u64 * rows as u64)
+    } else {
+        Progress::hidden()
+    };
+    (0..rows)
+        .into_par_iter()
+        .flat_map(|row| {
+            (0..columns)
+                .into_par_iter()
+                .map(move |column| (column, row))
+        })
+        .try_for_each(|(column, row)| -> Result<(), LabgenError> {
+            let mut out = create(&format!("{}_{}.png", column, row))?;
+            render_tile(maze, opts, tile_opts, column, row, &mut out)?;
+            out.flush()?;
+            progress.inc();
+            Ok(())
+        })?;
+    progress.finish();
+
+    let (width, height) = full_size(maze, opts)?;
+    let manifest = format!(
+        "{{\"width\":{},\"height\":{},\"tile_size\":{},\"overlap\":{},\"columns\":{},\
+        \"rows\":{},\"tiles\":\"{{x}}_{{y}}.png\"}}",
+        width, height, tile_opts.size, tile_opts.overlap, columns, rows
+    );
+    let mut out = create("tiles.json")?;
+    writeln!(out, "{}", manifest)?;
+    out.flush()?;
+
+    let mut out = create("index.html")?;
+    write!(
+        out,
+        "{}",
+        VIEWER
+            .replace(
+                "{title}",
+                &format!("{}x{} {} maze", maze.width, maze.height, maze.params.method)
+            )
+            .replace("{manifest}", &manifest)
+    )?;
+    out.flush()?;
+    Ok(())
+}
+
+// leaflet map showing the tiles at their native size at zoom 0. every tile is clipped to its
+// share of the image so overlapping tiles line up
+const VIEWER: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<link rel="stylesheet" href="https://unpkg.com/leaflet@1.9.4/dist/leaflet.css">
+<script src="https://unpkg.com/leaflet@1.9.4/dist/leaflet.js"></script>
+<style>html, body, #map { height: 100%; margin: 0; background: #000; }</style>
+</head>
+<body>
+<div id="map"></div>
+<script>
+const tiles = {manifest};
+const map = L.map("map", { crs: L.CRS.Simple, minZoom: -8, maxZoom: 6 });
+const bounds = [[-tiles.height, 0], [0, tiles.width]];
+const Tiles = L.GridLayer.extend({
+  createTile(coords) {
+    const tile = document.createElement("div");
+    tile.style.overflow = "hidden";
+    const img = document.createElement("img");
+    img.src = `${coords.x}_${coords.y}.png`;
+    img.style.position = "absolute";
+    img.style.left = `${-tiles.overlap}px`;
+    img.style.top = `${-tiles.overlap}px`;
+    img.style.imageRendering = "pixelated";
+    tile.appendChild(img);
+    return tile;
+  },
+});
+new Tiles({
+  tileSize: tiles.tile_size,
+  minNativeZoom: 0,
+  maxNativeZoom: 0,
+  noWrap: true,
+  bounds,
+}).addTo(map);
+map.fitBounds(bounds);
+</script>
+</body>
+</html>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        arbitrary::maze_with_exclusion,
+        image::render_png,
+        maze::{MazeType, MazeWrap},
+    };
+    use proptest::prelude::*;
+
+    #[test]
+    fn spans_include_the_overlap_on_both_sides() {
+        let tile_opts = TileOptions {
+            size: 10,
+            overlap: 3,
+            progress: false,
+        };
+        assert_eq!(tile_opts.span(), Some(16));
+        let tile_opts = TileOptions {
+            size: u16::MAX,
+            ..tile_opts
+        };
+        assert_eq!(tile_opts.span(), None);
+    }
+
+    #[test]
+    fn tiles_cover_the_whole_image() {
+        // cells of 5 pixels and the closing wall make an image of 26 by 11 pixels
+        let maze = Grid::new(5, 2, MazeWrap::None, 0, MazeType::default());
+        let tile_opts = TileOptions {
+            size: 10,
+            overlap: 0,
+            progress: false,
+        };
+        let opts = ImageOptions::default();
+        assert_eq!(tile_count(&maze, &opts, &tile_opts).unwrap(), (3, 2));
+        let tile_opts = TileOptions {
+            size: 26,
+            ..tile_opts
+        };
+        assert_eq!(tile_count(&maze, &opts, &tile_opts).unwrap(), (1, 1));
+    }
+
+    proptest! {
+        #[test]
+        fn tiles_cut_the_image_into_pieces(
+            (maze, _, _) in maze_with_exclusion(),
+            mut opts in any::<ImageOptions>(),
+            size in 8..=64u16,
+            overlap in 0..=6u16,
+        ) {
+            opts.rulers = false;
+            let decode = |bytes: &[u8]| {
+                let mut reader = png::Decoder::new(bytes).read_info().unwrap();
+                let mut pixels = vec![0; reader.output_buffer_size()];
+                let info = reader.next_frame(&mut pixels).unwrap();
+                (pixels, info.width as i64, info.height as i64)
+            };
+            let mut image = Vec::new();
+            render_png(&maze, &opts, &mut image).unwrap();
+            let (image, width, height) = decode(&image);
+
+            let tile_opts = TileOptions { size, overlap, progress: false };
+            let (columns, rows) = tile_count(&maze, &opts, &tile_opts).unwrap();
+            for (column, row) in (0..rows).flat_map(|r| (0..columns).map(move |c| (c, r))) {
+                let mut tile = Vec::new();
+                render_tile(&maze, &opts, &tile_opts, column, row, &mut tile).unwrap();
+                let (tile, span, _) = decode(&tile);
+                for (ty, tx) in (0..span).flat_map(|y| (0..span).map(move |x| (y, x))) {
+                    let x = column as i64 * size as i64 - overlap as i64 + tx;
+                    let y = row as i64 * size as i64 - overlap as i64 + ty;
+                    let inside = (0..width).contains(&x) && (0..height).contains(&y);
+                    let expected = if inside { image[(x + y * width) as usize] } else { 0 };
+                    prop_assert_eq!(
+                        tile[(tx + ty * span) as usize], expected, "pixel {},{}", x, y
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use crate::{
+    error::LabgenError,
+    image::{image_size, play_frames, write_output, AnimationOptions, ImageOptions},
+    maze::{Grid, MazeAction},
+};
+use std::{
+    fs,
+    io::{self, Write},
+    process::{Command, Stdio},
+    thread,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VideoFormat {
+    Mp4,
+    Webm,
+}
+
+impl VideoFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            VideoFormat::Mp4 => "mp4",
+            VideoFormat::Webm => "webm",
+        }
+    }
+
+    // encoder options of ffmpeg. mp4 is written in fragments so it can be streamed through a pipe
+    fn codec_args(self) -> &'static [&'static str] {
+        match self {
+            VideoFormat::Mp4 => &[
+                "-c:v",
+                "libx264",
+                "-movflags",
+                "frag_keyframe+empty_moov",
+                "-f",
+                "mp4",
+            ],
+            VideoFormat::Webm => &["-c:v", "libvpx-vp9", "-f", "webm"],
+        }
+    }
+}
+
+fn gcd(a: u16, b: u16) -> u16 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// encodes the frames of the uncompressed gif as a video by piping them through ffmpeg, which has
+// to be installed. videos have a fixed frame rate, so it is the highest one at which every delay
+// is a whole number of frames and each frame is repeated for its delay. delays of 0 last a single
+// frame instead of being skipped. the image is padded to even sizes for yuv 4:2:0 color
+pub fn render_video(
+    maze: &Grid,
+    history: &[MazeAction],
+    opts: &ImageOptions,
+    ani_opts: &AnimationOptions,
+    format: VideoFormat,
+    out: &mut dyn Write,
+) -> Result<(), LabgenError> {
+    let (width, height) = image_size(maze, &opts.layout())?;
+    let palette = opts.palette();
+    let tick = gcd(ani_opts.frame_time.max(1), ani_opts.pause_time);
+
+    let mut ffmpeg = Command::new("ffmpeg")
+        .args([
+            "-loglevel",
+            "error",
+            "-f",
+            "rawvideo",
+            "-pixel_format",
+            "rgb24",
+        ])
+        .args(["-video_size", &format!("{}x{}", width, height)])
+        .args(["-framerate", &format!("100/{}", tick)])
+        .args(["-i", "-", "-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2"])
+        .args(["-pix_fmt", "yuv420p"])
+        .args(format.codec_args())
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| io::Error::new(e.kind(), format!("could not run ffmpeg: {}", e)))?;
+    let mut input = ffmpeg.stdin.take().unwrap();
+    let mut output = ffmpeg.stdout.take().unwrap();
+
+    // ffmpeg stops taking frames while its output is not read, so they are fed from another
+    // thread. its input is closed once the feeder is done, letting it finish the video
+    let (fed, copied) = thread::scope(|s| {
+        let feeder = s.spawn(move || {
+            play_frames(maze, history, opts, ani_opts, |pixels, delay, _| {
+                let rgb: Vec<u8> = pixels
+                    .iter()
+                    .flat_map(|i| &palette[*i as usize * 3..*i as usize * 3 + 3])
+                    .copied()
+                    .collect();
+                for _ in 0..(delay / tick).max(1) {
+                    input.write_all(&rgb)?;
+                }
+                Ok(())
+            })
+        });
+        let copied = io::copy(&mut output, out);
+        (feeder.join().unwrap(), copied)
+    });
+
+    // a failing ffmpeg breaks the pipe, its own message explains more than that
+    let status = ffmpeg.wait()?;
+    if !status.success() {
+        return Err(LabgenError::Render(format!(
+            "ffmpeg failed with {}",
+            status
+        )));
+    }
+    fed?;
+    copied?;
+    Ok(())
+}
+
+pub fn generate_video(
+    maze: &Grid,
+    history: &[MazeAction],
+    opts: &ImageOptions,
+    ani_opts: &AnimationOptions,
+    format: VideoFormat,
+) -> Result<(), LabgenError> {
+    let written = write_output(&opts.file_path, format.extension(), |out| {
+        render_video(maze, history, opts, ani_opts, format, out)
+    });
+    // the file is created before ffmpeg runs, a video it could not finish is not left behind
+    if written.is_err() && opts.file_path != "-" {
+        let _ = fs::remove_file(format!("{}.{}", opts.file_path, format.extension()));
+    }
+    written
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{generate_maze, MazeType, MazeWrap};
+    use std::{env, path::Path};
+
+    #[test]
+    fn ticks_divide_both_delays() {
+        assert_eq!(gcd(4, 6), 2);
+        assert_eq!(gcd(2, 100), 2);
+        assert_eq!(gcd(3, 100), 1);
+        assert_eq!(gcd(5, 0), 5);
+    }
+
+    #[test]
+    fn containers_match_the_extension() {
+        for format in [VideoFormat::Mp4, VideoFormat::Webm] {
+            let args = format.codec_args();
+            let container = args.iter().position(|a| *a == "-f").unwrap() + 1;
+            assert_eq!(args[container], format.extension());
+        }
+    }
+
+    #[test]
+    fn failed_videos_leave_no_file() {
+        let (maze, history) = generate_maze(3, 3, MazeType::Wilson, MazeWrap::None, 0).unwrap();
+        let file_path = env::temp_dir().join(format!("labgen-video-{}", std::process::id()));
+        // cells too large to draw fail before any frame reaches ffmpeg, installed or not
+        let opts = ImageOptions {
+            file_path: file_path.to_str().unwrap().to_string(),
+            passage_width: u16::MAX,
+            ..ImageOptions::default()
+        };
+        let ani_opts = AnimationOptions::default();
+        let written = generate_video(&maze, &history.steps, &opts, &ani_opts, VideoFormat::Mp4);
+        assert!(written.is_err());
+        assert!(!Path::new(&format!("{}.mp4", opts.file_path)).exists());
+    }
+}
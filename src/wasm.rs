@@ -0,0 +1,34 @@
+use crate::{
+    image::{render_png, ImageOptions},
+    maze::{generate_maze, MazeType, MazeWrap},
+};
+use clap::ValueEnum;
+use wasm_bindgen::prelude::*;
+
+// png image of a maze generated with the method named like on the command line, rendered in
+// memory for web pages that show it as a blob. sizes the command line refuses are refused here
+// too, with the reason as the message of the error
+#[wasm_bindgen]
+pub fn generate_maze_to_png_bytes(
+    width: u32,
+    height: u32,
+    method: &str,
+    seed: u64,
+    passage_width: u16,
+    wall_width: u16,
+) -> Result<Vec<u8>, JsError> {
+    if passage_width == 0 {
+        return Err(JsError::new("passage width must be at least 1"));
+    }
+    let method = MazeType::from_str(method, true)
+        .map_err(|_| JsError::new(&format!("unknown method \"{}\"", method)))?;
+    let (maze, _) = generate_maze(width, height, method, MazeWrap::None, seed)?;
+    let opts = ImageOptions {
+        passage_width,
+        wall_width,
+        ..ImageOptions::default()
+    };
+    let mut png = Vec::new();
+    render_png(&maze, &opts, &mut png).map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(png)
+}